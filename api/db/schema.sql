@@ -22,4 +22,238 @@ CREATE TABLE IF NOT EXISTS Times (
     webhook_url TEXT NOT NULL,
     PRIMARY KEY (user_id, guild_id),
     FOREIGN KEY (guild_id) REFERENCES Guilds(guild_id)
-);
\ No newline at end of file
+);
+
+CREATE INDEX IF NOT EXISTS times_channel_id_idx ON Times (channel_id);
+
+-- チャンネルごとに共有するWebhook
+-- 同じチャンネルをTimesにしている人は，ここに記録したWebhookを使う
+CREATE TABLE IF NOT EXISTS ChannelWebhooks (
+    channel_id NUMERIC(20) NOT NULL,
+    guild_id NUMERIC(20) NOT NULL,
+    webhook_url TEXT NOT NULL,
+    PRIMARY KEY (channel_id),
+    FOREIGN KEY (guild_id) REFERENCES Guilds(guild_id)
+);
+
+-- 拡散したメッセージの記録
+-- 長いメッセージは分割して送信するため，partで何番目かを表す
+CREATE TABLE IF NOT EXISTS Deliveries (
+    mirror_message_id NUMERIC(20) NOT NULL,
+    source_message_id NUMERIC(20) NOT NULL,
+    source_guild_id NUMERIC(20) NOT NULL,
+    source_channel_id NUMERIC(20) NOT NULL,
+    user_id NUMERIC(20) NOT NULL,
+    target_guild_id NUMERIC(20) NOT NULL,
+    target_channel_id NUMERIC(20) NOT NULL,
+    part INTEGER NOT NULL,
+    PRIMARY KEY (mirror_message_id)
+);
+
+CREATE INDEX IF NOT EXISTS deliveries_source_message_id_idx ON Deliveries (source_message_id);
+CREATE INDEX IF NOT EXISTS deliveries_user_id_idx ON Deliveries (user_id);
+
+-- 拡散済みの発信元メッセージ．配信ログに何も残らなかった拡散も，二重に拡散しないために記録する
+CREATE TABLE IF NOT EXISTS ReleasedMessages (
+    source_message_id NUMERIC(20) NOT NULL,
+    user_id NUMERIC(20) NOT NULL,
+    PRIMARY KEY (source_message_id)
+);
+
+CREATE INDEX IF NOT EXISTS releasedmessages_user_id_idx ON ReleasedMessages (user_id);
+
+-- ギルドごとの，拡散されてくるメッセージの受け入れ方
+-- modeは open, allow_list, approval_queue のいずれか
+CREATE TABLE IF NOT EXISTS InboundPolicies (
+    guild_id NUMERIC(20) NOT NULL,
+    mode VARCHAR(32) NOT NULL,
+    approval_channel_id NUMERIC(20),
+    PRIMARY KEY (guild_id),
+    FOREIGN KEY (guild_id) REFERENCES Guilds(guild_id) ON DELETE CASCADE
+);
+
+-- 受け入れを許可した発信元のギルド
+CREATE TABLE IF NOT EXISTS InboundAllowedGuilds (
+    guild_id NUMERIC(20) NOT NULL,
+    origin_guild_id NUMERIC(20) NOT NULL,
+    PRIMARY KEY (guild_id, origin_guild_id),
+    FOREIGN KEY (guild_id) REFERENCES InboundPolicies(guild_id) ON DELETE CASCADE
+);
+
+-- 承認待ちのメッセージ
+-- 承認のボタンが付いたメッセージのidで探す
+CREATE TABLE IF NOT EXISTS PendingMirrors (
+    review_message_id NUMERIC(20) NOT NULL,
+    review_channel_id NUMERIC(20) NOT NULL,
+    source_message_id NUMERIC(20) NOT NULL,
+    source_guild_id NUMERIC(20) NOT NULL,
+    source_channel_id NUMERIC(20) NOT NULL,
+    user_id NUMERIC(20) NOT NULL,
+    target_guild_id NUMERIC(20) NOT NULL,
+    content TEXT NOT NULL,
+    PRIMARY KEY (review_message_id)
+);
+
+-- ギルドの管理者が決めた，他のギルドとの間の拡散の可否
+-- actionは block, allow のいずれか
+CREATE TABLE IF NOT EXISTS GuildPairPolicies (
+    guild_id NUMERIC(20) NOT NULL,
+    other_guild_id NUMERIC(20) NOT NULL,
+    action VARCHAR(32) NOT NULL,
+    PRIMARY KEY (guild_id, other_guild_id),
+    FOREIGN KEY (guild_id) REFERENCES Guilds(guild_id) ON DELETE CASCADE
+);
+
+-- 送信先のギルドが決めた，拡散されてくるメッセージのフィルタ
+-- kindは regex, word, max_attachment_size, allowed_mime_types, no_links のいずれか
+-- valueはkindごとに，正規表現，語，バイト数，カンマでつないだMIMEタイプ，空文字
+-- actionは block, redact, warn のいずれか
+CREATE TABLE IF NOT EXISTS ContentFilters (
+    filter_id BIGSERIAL NOT NULL,
+    guild_id NUMERIC(20) NOT NULL,
+    kind VARCHAR(32) NOT NULL,
+    value TEXT NOT NULL,
+    action VARCHAR(32) NOT NULL,
+    PRIMARY KEY (filter_id),
+    FOREIGN KEY (guild_id) REFERENCES Guilds(guild_id) ON DELETE CASCADE
+);
+
+-- ギルドの管理者が決めた，拡散の回数の上限
+-- user_*は1人のユーザーがこのギルドから拡散できる回数，guild_*はこのギルドが受け取る回数
+-- period_secsの間にcapacity回まで．使った分は少しずつ戻る
+CREATE TABLE IF NOT EXISTS ReleaseRateLimits (
+    guild_id NUMERIC(20) NOT NULL,
+    user_capacity INTEGER NOT NULL,
+    user_period_secs INTEGER NOT NULL,
+    guild_capacity INTEGER NOT NULL,
+    guild_period_secs INTEGER NOT NULL,
+    PRIMARY KEY (guild_id),
+    FOREIGN KEY (guild_id) REFERENCES Guilds(guild_id) ON DELETE CASCADE
+);
+
+-- ギルドのモデレーター向けのチャンネル
+-- 拡散先のモデレーターが，拡散されてきたメッセージを発信元のギルドに知らせるときに使う
+CREATE TABLE IF NOT EXISTS ModerationChannels (
+    guild_id NUMERIC(20) NOT NULL,
+    channel_id NUMERIC(20) NOT NULL,
+    PRIMARY KEY (guild_id),
+    FOREIGN KEY (guild_id) REFERENCES Guilds(guild_id) ON DELETE CASCADE
+);
+
+-- ギルドの管理者が決めた，ギルドを抜けたメンバーのTimesの扱い
+-- actionは keep, unregister のいずれか
+CREATE TABLE IF NOT EXISTS MemberLeavePolicies (
+    guild_id NUMERIC(20) NOT NULL,
+    action VARCHAR(32) NOT NULL,
+    PRIMARY KEY (guild_id),
+    FOREIGN KEY (guild_id) REFERENCES Guilds(guild_id) ON DELETE CASCADE
+);
+
+-- BANやギルドを抜けたことで，botがTimesの登録を削除した記録
+-- reasonは banned, left のいずれか
+-- 登録を削除した後も理由を伝えられるように，ギルドが消えても残す
+CREATE TABLE IF NOT EXISTS TimeRemovals (
+    user_id NUMERIC(20) NOT NULL,
+    guild_id NUMERIC(20) NOT NULL,
+    channel_id NUMERIC(20) NOT NULL,
+    reason VARCHAR(32) NOT NULL,
+    removed_at TEXT NOT NULL,
+    PRIMARY KEY (user_id, guild_id)
+);
+
+-- 登録と設定の変更の監査ログ．追記だけで，更新や削除はしない
+-- 後から確かめられるように，ギルドが消えても残す
+-- actionは guild_init, time_set, time_webhook_recreate, time_delete, time_remove, setting_change のいずれか
+-- actor_idはbotが自分で行った操作ではNULL
+CREATE TABLE IF NOT EXISTS AuditEntries (
+    entry_id BIGSERIAL NOT NULL,
+    guild_id NUMERIC(20) NOT NULL,
+    actor_id NUMERIC(20),
+    action VARCHAR(32) NOT NULL,
+    detail TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (entry_id)
+);
+
+CREATE INDEX IF NOT EXISTS auditentries_guild_id_idx ON AuditEntries (guild_id, entry_id);
+CREATE INDEX IF NOT EXISTS auditentries_actor_id_idx ON AuditEntries (actor_id);
+
+-- 監査ログの項目を流すチャンネル
+CREATE TABLE IF NOT EXISTS AuditLogChannels (
+    guild_id NUMERIC(20) NOT NULL,
+    channel_id NUMERIC(20) NOT NULL,
+    PRIMARY KEY (guild_id),
+    FOREIGN KEY (guild_id) REFERENCES Guilds(guild_id) ON DELETE CASCADE
+);
+
+-- 拡散されてくるスレッドへの書き込みを，このギルドでも対応するスレッドへ送るか
+-- 設定していないギルドには送る
+CREATE TABLE IF NOT EXISTS ThreadFollowSettings (
+    guild_id NUMERIC(20) NOT NULL,
+    follow_threads BOOLEAN NOT NULL,
+    PRIMARY KEY (guild_id),
+    FOREIGN KEY (guild_id) REFERENCES Guilds(guild_id) ON DELETE CASCADE
+);
+
+-- ギルドの管理者が決めた，このギルドから拡散したメッセージに付ける発信元の案内
+-- accessは open, closed のいずれか．closedのギルドの案内にはリンクを付けない
+CREATE TABLE IF NOT EXISTS GuildFooterSettings (
+    guild_id NUMERIC(20) NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    access VARCHAR(32) NOT NULL,
+    PRIMARY KEY (guild_id),
+    FOREIGN KEY (guild_id) REFERENCES Guilds(guild_id) ON DELETE CASCADE
+);
+
+-- ユーザーが決めた，拡散したメッセージに発信元の案内を付けるか．ギルドの設定より優先する
+CREATE TABLE IF NOT EXISTS UserFooterSettings (
+    user_id NUMERIC(20) NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    PRIMARY KEY (user_id)
+);
+
+-- 拡散先のメッセージに付いたリアクションの数
+-- 拡散先のメッセージを削除して配信ログから消えたら，一緒に消す
+CREATE TABLE IF NOT EXISTS MirrorReactions (
+    mirror_message_id NUMERIC(20) NOT NULL,
+    emoji VARCHAR(255) NOT NULL,
+    count INTEGER NOT NULL,
+    PRIMARY KEY (mirror_message_id, emoji),
+    FOREIGN KEY (mirror_message_id) REFERENCES Deliveries(mirror_message_id) ON DELETE CASCADE
+);
+
+-- 拡散先のメッセージへの返信
+CREATE TABLE IF NOT EXISTS MirrorReplies (
+    reply_message_id NUMERIC(20) NOT NULL,
+    mirror_message_id NUMERIC(20) NOT NULL,
+    guild_id NUMERIC(20) NOT NULL,
+    channel_id NUMERIC(20) NOT NULL,
+    PRIMARY KEY (reply_message_id),
+    FOREIGN KEY (mirror_message_id) REFERENCES Deliveries(mirror_message_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS mirrorreplies_mirror_message_id_idx ON MirrorReplies (mirror_message_id);
+
+-- 拡散先での返信を，発信元のTimesへ届けるかのギルドの同意
+-- 返信したギルドのsend_repliesと，発信元のギルドのreceive_repliesの両方が必要
+CREATE TABLE IF NOT EXISTS ReplyBridgeSettings (
+    guild_id NUMERIC(20) NOT NULL,
+    send_replies BOOLEAN NOT NULL,
+    receive_replies BOOLEAN NOT NULL,
+    PRIMARY KEY (guild_id),
+    FOREIGN KEY (guild_id) REFERENCES Guilds(guild_id) ON DELETE CASCADE
+);
+
+-- 発信元のTimesへ届けた返信．返信が削除されたら，届けたメッセージも削除する
+CREATE TABLE IF NOT EXISTS RelayedReplies (
+    reply_message_id NUMERIC(20) NOT NULL,
+    author_id NUMERIC(20) NOT NULL,
+    user_id NUMERIC(20) NOT NULL,
+    guild_id NUMERIC(20) NOT NULL,
+    channel_id NUMERIC(20) NOT NULL,
+    thread_id NUMERIC(20),
+    relayed_message_id NUMERIC(20) NOT NULL,
+    PRIMARY KEY (reply_message_id)
+);
+CREATE INDEX IF NOT EXISTS relayedreplies_author_id_idx ON RelayedReplies (author_id);
+CREATE INDEX IF NOT EXISTS relayedreplies_user_id_idx ON RelayedReplies (user_id);
@@ -0,0 +1,182 @@
+//! 登録と設定の変更，モデレーターの操作，ユーザーのデータの削除を，監査ログに追記する
+//!
+//! 管理者向けのコマンドを追加したら，変更を保存した後にaudit_by_authorで記録する
+//! ボタンのようにコマンドの外で受け取る操作は，record_auditで記録する
+//! ログを流すチャンネルが設定されていれば，そこにも投稿する
+//! 記録に失敗しても変更は済んでいるので，コマンドは失敗させずにログに残すだけにする
+
+use domain::models::{AuditAction, NewAuditEntry, UtAuditEntry};
+use domain::repository::{AuditLogChannelRepository, AuditLogRepository};
+use poise::serenity_prelude::{ChannelId, CreateAllowedMentions, CreateMessage, Http, Timestamp};
+use tracing::warn;
+
+use crate::models::{Context, Data};
+
+/// 1ページに表示する項目の数
+pub const AUDIT_PAGE_SIZE: usize = 10;
+
+// 1つの項目のdetailとして表示する文字数．正規表現のフィルタなどは長くなることがある
+const DETAIL_DISPLAY_CHARS: usize = 150;
+
+/// 監査ログに追記して，設定されていればチャンネルにも投稿する
+pub async fn record_audit(http: &Http, data: &Data, entry: NewAuditEntry) {
+    let guild_id = entry.guild_id;
+    let entry = match data.audit_log_repository.insert_audit_entry(entry).await {
+        Ok(entry) => entry,
+        Err(e) => {
+            warn!(
+                "failed to insert audit entry. guild_id: {}, error: {}",
+                guild_id, e
+            );
+            return;
+        }
+    };
+
+    let channel = match data
+        .audit_log_channel_repository
+        .get_audit_log_channel(guild_id)
+        .await
+    {
+        Ok(Some(channel)) => channel,
+        Ok(None) => return,
+        Err(e) => {
+            warn!(
+                "failed to get audit log channel. guild_id: {}, error: {}",
+                guild_id, e
+            );
+            return;
+        }
+    };
+    // 操作した人への通知はしない
+    let message = CreateMessage::new()
+        .content(audit_entry_text(&entry))
+        .allowed_mentions(CreateAllowedMentions::new());
+    if let Err(e) = ChannelId::new(channel.channel_id)
+        .send_message(http, message)
+        .await
+    {
+        warn!(
+            "failed to post audit entry. guild_id: {}, entry_id: {}, error: {}",
+            guild_id, entry.entry_id, e
+        );
+    }
+}
+
+/// コマンドを実行したユーザーによる変更を記録する
+pub async fn audit_by_author(
+    ctx: Context<'_>,
+    guild_id: u64,
+    action: AuditAction,
+    detail: impl Into<String>,
+) {
+    record_audit(
+        &ctx.serenity_context().http,
+        ctx.data(),
+        NewAuditEntry {
+            guild_id,
+            actor_id: Some(ctx.author().id.get()),
+            action,
+            detail: detail.into(),
+            created_at: Timestamp::now().to_string(),
+        },
+    )
+    .await;
+}
+
+fn action_label(action: AuditAction) -> &'static str {
+    match action {
+        AuditAction::GuildInit => "guild init",
+        AuditAction::TimeSet => "Times set",
+        AuditAction::TimeWebhookRecreate => "Times webhook re-created",
+        AuditAction::TimeDelete => "Times deleted",
+        AuditAction::TimeRemove => "Times removed",
+        AuditAction::SettingChange => "setting changed",
+        AuditAction::Takedown => "mirrored post taken down",
+        AuditAction::Retract => "mirrored post retracted",
+        AuditAction::ReviewApprove => "incoming post approved",
+        AuditAction::ReviewReject => "incoming post rejected",
+        AuditAction::ForgetMe => "user data erased",
+    }
+}
+
+/// 1つの項目を1行で表す
+pub fn audit_entry_text(entry: &UtAuditEntry) -> String {
+    // 操作したユーザーがないのは，botが登録を削除したときと，ut_c_forget_meでユーザーを消したとき
+    let actor = match (entry.actor_id, entry.action) {
+        (Some(actor_id), _) => format!("<@{}>", actor_id),
+        (None, AuditAction::TimeRemove) => "the bot".to_string(),
+        (None, _) => "a forgotten user".to_string(),
+    };
+    let mut detail: String = entry.detail.chars().take(DETAIL_DISPLAY_CHARS).collect();
+    if detail.len() < entry.detail.len() {
+        detail.push('…');
+    }
+    format!(
+        "`#{}` {} {} **{}**: {}",
+        entry.entry_id,
+        entry.created_at,
+        actor,
+        action_label(entry.action),
+        detail
+    )
+}
+
+/// ページの位置．新しい項目から順に表示する
+///
+/// 前のページに戻れるように，2ページ目以降の各ページのbeforeを積んでおく
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditPager {
+    befores: Vec<u64>,
+}
+
+impl AuditPager {
+    /// 表示しているページの番号．1から数える
+    pub fn page(&self) -> usize {
+        self.befores.len() + 1
+    }
+
+    /// 表示しているページの項目を取得するときのbefore
+    pub fn before(&self) -> Option<u64> {
+        self.befores.last().copied()
+    }
+
+    /// 表示している項目より前を，次のページにする
+    pub fn older(&mut self, shown: &[UtAuditEntry]) {
+        if let Some(last) = shown.last() {
+            self.befores.push(last.entry_id);
+        }
+    }
+
+    /// 1つ前のページに戻る
+    pub fn newer(&mut self) {
+        self.befores.pop();
+    }
+
+    pub fn has_newer(&self) -> bool {
+        !self.befores.is_empty()
+    }
+}
+
+/// AUDIT_PAGE_SIZEより1件多く取得した項目から，表示する項目と，さらに古い項目があるかを返す
+pub fn split_audit_page(mut entries: Vec<UtAuditEntry>) -> (Vec<UtAuditEntry>, bool) {
+    let has_older = entries.len() > AUDIT_PAGE_SIZE;
+    entries.truncate(AUDIT_PAGE_SIZE);
+    (entries, has_older)
+}
+
+/// 1ページ分の表示
+pub fn audit_page_text(entries: &[UtAuditEntry], page: usize) -> String {
+    if entries.is_empty() {
+        return if page == 1 {
+            "No audit entries yet.".to_string()
+        } else {
+            "No older audit entries.".to_string()
+        };
+    }
+    let mut lines = vec![format!("Audit log of this guild (page {})", page)];
+    lines.extend(entries.iter().map(audit_entry_text));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,117 @@
+use super::*;
+
+fn entry(entry_id: u64) -> UtAuditEntry {
+    UtAuditEntry {
+        entry_id,
+        guild_id: 1,
+        actor_id: Some(2),
+        action: AuditAction::SettingChange,
+        detail: format!("detail {}", entry_id),
+        created_at: "2024-01-01T00:00:00Z".to_string(),
+    }
+}
+
+#[test]
+/// 記録には操作したユーザーと操作を載せる
+fn test_entry_text_shows_actor_and_action() {
+    assert_eq!(
+        audit_entry_text(&entry(3)),
+        "`#3` 2024-01-01T00:00:00Z <@2> **setting changed**: detail 3"
+    );
+
+    let by_bot = UtAuditEntry {
+        actor_id: None,
+        action: AuditAction::TimeRemove,
+        ..entry(4)
+    };
+    assert!(audit_entry_text(&by_bot).contains("the bot **Times removed**"));
+
+    let forgotten = UtAuditEntry {
+        actor_id: None,
+        ..entry(6)
+    };
+    assert!(audit_entry_text(&forgotten).contains("a forgotten user **setting changed**"));
+
+    let takedown = UtAuditEntry {
+        action: AuditAction::Takedown,
+        ..entry(5)
+    };
+    assert!(audit_entry_text(&takedown).contains("<@2> **mirrored post taken down**"));
+}
+
+#[test]
+/// 長い詳細は切り詰める
+fn test_entry_text_truncates_long_detail() {
+    let long = UtAuditEntry {
+        detail: "a".repeat(DETAIL_DISPLAY_CHARS + 10),
+        ..entry(1)
+    };
+
+    let text = audit_entry_text(&long);
+    assert!(text.ends_with(&format!("{}…", "a".repeat(DETAIL_DISPLAY_CHARS))));
+}
+
+#[test]
+/// 1ページより多く取得したときは，古い記録があるとわかる
+fn test_split_page_tells_whether_older_entries_exist() {
+    let entries: Vec<_> = (0..AUDIT_PAGE_SIZE as u64 + 1).rev().map(entry).collect();
+
+    let (shown, has_older) = split_audit_page(entries.clone());
+    assert_eq!(shown.len(), AUDIT_PAGE_SIZE);
+    assert!(has_older);
+
+    let (shown, has_older) = split_audit_page(entries[..3].to_vec());
+    assert_eq!(shown.len(), 3);
+    assert!(!has_older);
+}
+
+#[test]
+/// 古いページと新しいページを行き来できる
+fn test_pager_goes_back_and_forth() {
+    let mut pager = AuditPager::default();
+    assert_eq!(pager.page(), 1);
+    assert_eq!(pager.before(), None);
+    assert!(!pager.has_newer());
+
+    pager.older(&[entry(30), entry(21)]);
+    assert_eq!(pager.page(), 2);
+    assert_eq!(pager.before(), Some(21));
+    assert!(pager.has_newer());
+
+    pager.older(&[entry(20), entry(11)]);
+    assert_eq!(pager.before(), Some(11));
+
+    pager.newer();
+    assert_eq!(pager.page(), 2);
+    assert_eq!(pager.before(), Some(21));
+
+    pager.newer();
+    assert_eq!(pager, AuditPager::default());
+}
+
+#[test]
+/// 表示する記録がなければページを進めない
+fn test_pager_stays_when_nothing_was_shown() {
+    let mut pager = AuditPager::default();
+    pager.older(&[]);
+
+    assert_eq!(pager.page(), 1);
+}
+
+#[test]
+/// 記録がないときはそのことを伝える
+fn test_page_text_for_empty_log() {
+    assert_eq!(audit_page_text(&[], 1), "No audit entries yet.");
+    assert_eq!(audit_page_text(&[], 2), "No older audit entries.");
+}
+
+#[test]
+/// ページには記録を並べる
+fn test_page_text_lists_entries() {
+    let text = audit_page_text(&[entry(2), entry(1)], 3);
+
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(lines[0], "Audit log of this guild (page 3)");
+    assert!(lines[1].starts_with("`#2`"));
+    assert!(lines[2].starts_with("`#1`"));
+}
@@ -18,18 +18,68 @@
 // 	- 保存されたTimes情報のchannel_idと一致しない場合，チャンネル不一致として弾く
 // 	- 実行したギルド以外の，Timesが登録されているすべてのギルドへ同じ内容を送信する
 
+use crate::audit_log::{
+    audit_by_author, audit_page_text, split_audit_page, AuditPager, AUDIT_PAGE_SIZE,
+};
+use crate::content_filter::{
+    apply_filters, build_filter, describe_filter, parse_attachment_size, parse_mime_types,
+    plan_filtered_delivery, send_filtered, FilterContent,
+};
+use crate::data_export::{UserExport, UserRecords, EXPORT_FILE_NAME, EXPORT_FORMAT_VERSION};
+use crate::delivery_report::delivery_report_message;
+use crate::forget_me::forget_me;
+use crate::guild_pair_policy::{load_and_filter_by_guild_pair_policy, skipped_by_policy_message};
+use crate::inbound_policy::{inbound_report_message, load_inbound_plan};
+use crate::inbound_review::queue_for_review;
+use crate::member_removal::removal_text;
+use crate::mirror_context::load_mirror_context;
+use crate::mirror_feedback::{aggregate_feedback, feedback_text};
+use crate::mirror_footer::mirror_footer;
 use crate::models::error::GuildNotFound;
-use crate::models::{Context, UbiquiTimesCardiacResult as Result};
+use crate::models::{
+    ApplicationContext, Context, PrefixContext, UbiquiTimesCardiacResult as Result,
+};
+use crate::prefix::{ADDITIONAL_PREFIXES, PREFIX};
+use crate::rate_limit::{
+    guild_rate_limited_message, load_rate_limit_settings, take_guild_tokens,
+    user_rate_limited_message,
+};
+use crate::release_content::{ReleaseContentError, ReleaseContentParser};
+use crate::takedown::{
+    flag_to_origin, locate_takedown, take_down, takedown_audit_detail, TakedownReport,
+    TakedownTarget,
+};
+use crate::times_unregister::unregister_time;
+use crate::times_webhook::register_time;
 use crate::ubiquitimes_user_name::ubiquitimes_user_name;
-use crate::webhook_name::webhook_name;
-use domain::models::UtTime;
+use crate::webhook_api::WebhookDeletion;
+use crate::webhook_health::check_webhook_health;
 use domain::{
-    message_sender::TimesMessageSender,
-    models::UtGuild,
-    repository::{GuildRepository, TimesRepository},
+    models::{
+        AuditAction, CommunityAccess, ContentFilterRule, DeliveryFailure, FilterAction,
+        GuildPairAction, InboundMode, MemberLeaveAction, MessageLocation, UtAuditEntry,
+        UtAuditLogChannel, UtContentFilter, UtDelivery, UtGuild, UtGuildFooterSettings,
+        UtGuildPairPolicy, UtInboundPolicy, UtMemberLeavePolicy, UtModerationChannel,
+        UtRateLimitSettings, UtReplyBridgeSettings, UtThreadFollowSetting, UtUserFooterSetting,
+    },
+    repository::{
+        AuditLogChannelRepository, AuditLogRepository, ContentFilterRepository, DeliveryRepository,
+        FooterSettingsRepository, GuildPairPolicyRepository, GuildRepository,
+        InboundPolicyRepository, MemberLeavePolicyRepository, MirrorFeedbackRepository,
+        ModerationChannelRepository, PendingMirrorRepository, RateLimitRepository,
+        ReplyBridgeRepository, ThreadFollowRepository, TimeRemovalRepository, TimesRepository,
+    },
 };
 
-use poise::serenity_prelude::{CreateWebhook, Webhook};
+use message_sender::content_splitter::{split_content, DISCORD_CONTENT_LIMIT};
+use poise::serenity_prelude::{
+    Attachment, AutocompleteChoice, ButtonStyle, ChannelId, ComponentInteractionCollector,
+    CreateActionRow, CreateAttachment, CreateButton, CreateInteractionResponse,
+    CreateInteractionResponseMessage, GuildChannel, Message, Timestamp,
+};
+use poise::CreateReply;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::{Duration, Instant};
 use tracing::info;
 
 /// Responds with "world!"
@@ -74,6 +124,13 @@ pub async fn ut_c_guild_init(ctx: Context<'_>) -> Result<()> {
     let guilds_repository = ctx.data().guild_repository.clone();
     let guild = UtGuild::new(guild_id, Some(guild_name.clone()));
     guilds_repository.upsert_guild(guild).await?;
+    audit_by_author(
+        ctx,
+        guild_id,
+        AuditAction::GuildInit,
+        format!("guild name: {}", guild_name),
+    )
+    .await;
 
     let reply_mesage = format!(
         "Success! Welcome {},  I learned this guild! {}",
@@ -84,155 +141,2476 @@ pub async fn ut_c_guild_init(ctx: Context<'_>) -> Result<()> {
     Ok(())
 }
 
-#[poise::command(prefix_command, track_edits, aliases("UtTimesSet"), slash_command)]
+/// スラッシュコマンドで選ぶ，他のギルドとの間の拡散の可否
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum GuildPairActionChoice {
+    #[name = "block"]
+    Block,
+    #[name = "allow"]
+    Allow,
+    /// 決めたものを取り消す
+    #[name = "clear"]
+    Clear,
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
 #[tracing::instrument(skip(ctx))]
-/// 実行したチャンネルをあなたのTimesとして登録します
+/// 他のギルドとの間で拡散するかを設定します
 ///
-/// ２度目以降の実行は情報を更新します
-pub async fn ut_c_times_set(
+/// block: そのギルドとの間では，どちらの向きにも拡散しません
+/// allow: 1つでも許可すると，許可したギルドとの間でだけ拡散します
+/// clear: 設定を取り消します
+pub async fn ut_c_guild_policy(
     ctx: Context<'_>,
-    #[description = "このギルドで使用する名前"] user_name: String,
+    #[description = "相手のギルド"]
+    #[autocomplete = "autocomplete_known_guild"]
+    guild: String,
+    #[description = "拡散するかどうか"] action: GuildPairActionChoice,
 ) -> Result<()> {
-    let user_id = ctx.author().id.get();
     let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
-    let channel_id = ctx.channel_id();
-    let channel_id_u64 = channel_id.get();
 
-    // Ubiquitimesから拡散だとわかるように，ユーザー名にプレフィックスを付加する
-    let user_name = ubiquitimes_user_name(user_name);
-    let webhook_name = webhook_name(ctx).await;
+    let Ok(other_guild_id) = guild.trim().parse::<u64>() else {
+        ctx.say("Please choose a guild from the list or enter a guild id.")
+            .await?;
+        return Ok(());
+    };
+    if other_guild_id == guild_id {
+        ctx.say("Please choose another guild.").await?;
+        return Ok(());
+    }
+
+    let guild_pair_policy_repository = ctx.data().guild_pair_policy_repository.clone();
+    let action = match action {
+        GuildPairActionChoice::Block => Some(GuildPairAction::Block),
+        GuildPairActionChoice::Allow => Some(GuildPairAction::Allow),
+        GuildPairActionChoice::Clear => None,
+    };
+    let saved = match action {
+        Some(action) => {
+            guild_pair_policy_repository
+                .upsert_guild_pair_policy(UtGuildPairPolicy {
+                    guild_id,
+                    other_guild_id,
+                    action,
+                })
+                .await
+        }
+        None => {
+            guild_pair_policy_repository
+                .delete_guild_pair_policy(guild_id, other_guild_id)
+                .await
+        }
+    };
+    if let Err(e) = saved {
+        info!("failed to save guild pair policy. error: {}", e);
+        ctx.say("Failed to save the policy. Please run ut_c_guild_init first.")
+            .await?;
+        return Ok(());
+    }
+    let action_name = match action {
+        Some(GuildPairAction::Block) => "block",
+        Some(GuildPairAction::Allow) => "allow",
+        None => "clear",
+    };
+    audit_by_author(
+        ctx,
+        guild_id,
+        AuditAction::SettingChange,
+        format!("guild policy with {}: {}", other_guild_id, action_name),
+    )
+    .await;
 
-    // コマンド実行のたびに新しいwebhookを作成し，古いwebhookを削除する
-    // 処理の複雑さを減らすためと，予期せぬWebhookの無効化で，Webhook再作成の条件にあわないのに無効化されて動作しなくなることを防ぐため
-    let builder = CreateWebhook::new(webhook_name);
-    let webhook = ctx.channel_id().create_webhook(&ctx, builder).await?;
+    let policies = guild_pair_policy_repository
+        .get_guild_pair_policies(vec![guild_id])
+        .await?;
+    ctx.say(format!(
+        "Saved.\n{}",
+        guild_pair_policies_text(ctx, &policies).await
+    ))
+    .await?;
+    Ok(())
+}
 
-    let webhook_url = webhook.url()?;
+#[poise::command(slash_command, guild_only, ephemeral)]
+#[tracing::instrument(skip(ctx))]
+/// このギルドが決めた，他のギルドとの間で拡散するかの設定を表示します
+pub async fn ut_c_guild_policy_list(ctx: Context<'_>) -> Result<()> {
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
 
-    let times_repository = ctx.data().times_repository.clone();
+    let guild_pair_policy_repository = ctx.data().guild_pair_policy_repository.clone();
+    let policies = guild_pair_policy_repository
+        .get_guild_pair_policies(vec![guild_id])
+        .await?;
 
-    let time = UtTime::new(
-        user_id,
+    let text = guild_pair_policies_text(ctx, &policies).await;
+    for chunk in split_content(&text, DISCORD_CONTENT_LIMIT) {
+        ctx.say(chunk).await?;
+    }
+    Ok(())
+}
+
+/// ギルドの間の拡散の設定を説明する文
+async fn guild_pair_policies_text(ctx: Context<'_>, policies: &[UtGuildPairPolicy]) -> String {
+    let mut blocked = Vec::new();
+    let mut allowed = Vec::new();
+    for policy in policies {
+        let name = format!(
+            "{} ({})",
+            guild_display_name(ctx, policy.other_guild_id).await,
+            policy.other_guild_id
+        );
+        match policy.action {
+            GuildPairAction::Block => blocked.push(name),
+            GuildPairAction::Allow => allowed.push(name),
+        }
+    }
+
+    let mut lines = Vec::new();
+    if allowed.is_empty() {
+        lines.push("Mirroring with every guild except blocked ones.".to_string());
+    } else {
+        lines.push("Mirroring only with allowed guilds.".to_string());
+        lines.push("Allowed guilds".to_string());
+        lines.extend(allowed.into_iter().map(|n| format!("- {}", n)));
+    }
+    if !blocked.is_empty() {
+        lines.push("Blocked guilds".to_string());
+        lines.extend(blocked.into_iter().map(|n| format!("- {}", n)));
+    }
+    lines.join("\n")
+}
+
+/// botが知っているギルドを候補にする．実行したギルドは除く
+async fn autocomplete_known_guild(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let own_guild_id = ctx.guild_id().map(|g| g.get());
+    let guild_repository = ctx.data().guild_repository.clone();
+    let Ok(guilds) = guild_repository
+        .search_guilds(partial.to_string(), MAX_AUTOCOMPLETE_CHOICES + 1)
+        .await
+    else {
+        return Vec::new();
+    };
+
+    guilds
+        .into_iter()
+        .filter(|g| Some(g.guild_id) != own_guild_id)
+        .take(MAX_AUTOCOMPLETE_CHOICES)
+        .map(|g| {
+            let name = format!("{} ({})", g.guild_name.unwrap_or_default(), g.guild_id);
+            // 候補の名前は100文字まで
+            AutocompleteChoice::new(
+                name.chars().take(100).collect::<String>(),
+                g.guild_id.to_string(),
+            )
+        })
+        .collect()
+}
+
+/// スラッシュコマンドで選ぶ，拡散されてくるメッセージの受け入れ方
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum InboundModeChoice {
+    #[name = "open"]
+    Open,
+    #[name = "allow list"]
+    AllowList,
+    #[name = "approval queue"]
+    ApprovalQueue,
+}
+
+impl From<InboundModeChoice> for InboundMode {
+    fn from(choice: InboundModeChoice) -> Self {
+        match choice {
+            InboundModeChoice::Open => InboundMode::Open,
+            InboundModeChoice::AllowList => InboundMode::AllowList,
+            InboundModeChoice::ApprovalQueue => InboundMode::ApprovalQueue,
+        }
+    }
+}
+
+/// 受け入れ方を説明する文
+fn inbound_mode_text(policy: &UtInboundPolicy) -> String {
+    match policy.mode {
+        InboundMode::Open => "open: posts from every guild are delivered".to_string(),
+        InboundMode::AllowList => "allow list: only posts from allowed guilds are delivered".to_string(),
+        InboundMode::ApprovalQueue => match policy.approval_channel_id {
+            Some(channel_id) => format!(
+                "approval queue: posts from other guilds wait for approval in <#{}>",
+                channel_id
+            ),
+            None => "approval queue: no approval channel is set, so posts from other guilds are not delivered".to_string(),
+        },
+    }
+}
+
+fn inbound_policy_text(policy: &UtInboundPolicy) -> String {
+    let mode = inbound_mode_text(policy);
+
+    let allowed = if policy.allowed_guild_ids.is_empty() {
+        "none".to_string()
+    } else {
+        policy
+            .allowed_guild_ids
+            .iter()
+            .map(|g| g.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    format!(
+        "Inbound policy: {}
+Allowed guilds: {}",
+        mode, allowed
+    )
+}
+
+/// このギルドの受け入れ方．設定していなければ，すべて受け入れる
+async fn current_inbound_policy(ctx: Context<'_>, guild_id: u64) -> Result<UtInboundPolicy> {
+    let inbound_policy_repository = ctx.data().inbound_policy_repository.clone();
+    Ok(inbound_policy_repository
+        .get_inbound_policy(guild_id)
+        .await?
+        .unwrap_or_else(|| UtInboundPolicy::open(guild_id)))
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
+#[tracing::instrument(skip(ctx))]
+/// 他のギルドから拡散されてくるメッセージの受け入れ方を設定します
+///
+/// open: すべて受け入れます
+/// allow list: 許可したギルドからだけ受け入れます
+/// approval queue: 許可したギルド以外からは，承認待ちのチャンネルで承認してから受け入れます
+pub async fn ut_c_inbound_policy(
+    ctx: Context<'_>,
+    #[description = "受け入れ方"] mode: InboundModeChoice,
+    #[description = "承認待ちのメッセージを投稿するチャンネル"] approval_channel: Option<
+        GuildChannel,
+    >,
+) -> Result<()> {
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
+
+    let mut policy = current_inbound_policy(ctx, guild_id).await?;
+    policy.mode = mode.into();
+    if let Some(channel) = approval_channel {
+        policy.approval_channel_id = Some(channel.id.get());
+    }
+    if policy.mode == InboundMode::ApprovalQueue && policy.approval_channel_id.is_none() {
+        ctx.say("Please choose an approval channel for the approval queue.")
+            .await?;
+        return Ok(());
+    }
+
+    let inbound_policy_repository = ctx.data().inbound_policy_repository.clone();
+    if let Err(e) = inbound_policy_repository
+        .upsert_inbound_policy(policy.clone())
+        .await
+    {
+        info!("failed to save inbound policy. error: {}", e);
+        ctx.say("Failed to save the policy. Please run ut_c_guild_init first.")
+            .await?;
+        return Ok(());
+    }
+    audit_by_author(
+        ctx,
         guild_id,
-        user_name.clone(),
-        channel_id.get(),
-        webhook_url.clone(),
-    );
+        AuditAction::SettingChange,
+        format!("inbound policy: {}", inbound_mode_text(&policy)),
+    )
+    .await;
+
+    ctx.say(format!(
+        "Saved.
+{}",
+        inbound_policy_text(&policy)
+    ))
+    .await?;
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
+#[tracing::instrument(skip(ctx))]
+/// 拡散を受け入れる発信元のギルドを許可リストに追加・削除します
+pub async fn ut_c_inbound_allow(
+    ctx: Context<'_>,
+    #[description = "発信元のギルドのid"] origin_guild: String,
+    #[description = "Falseで許可リストから削除します"] allow: Option<bool>,
+) -> Result<()> {
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
 
-    let old_time = times_repository.upsert_and_return_old_time(time).await?;
+    let Ok(origin_guild_id) = origin_guild.trim().parse::<u64>() else {
+        ctx.say("Please enter the guild id of the origin guild.")
+            .await?;
+        return Ok(());
+    };
 
-    // 古いwebhookを削除
-    if let Some(old_time) = old_time {
-        let old_webhook_url = old_time.webhook_url;
-        let webhook = Webhook::from_url(ctx, &old_webhook_url).await?;
-        webhook.delete(ctx).await?;
+    let mut policy = current_inbound_policy(ctx, guild_id).await?;
+    policy.allowed_guild_ids.retain(|g| *g != origin_guild_id);
+    if allow.unwrap_or(true) {
+        policy.allowed_guild_ids.push(origin_guild_id);
+        policy.allowed_guild_ids.sort();
+    }
 
-        info!("Webhook deleted: {}", old_webhook_url);
+    let inbound_policy_repository = ctx.data().inbound_policy_repository.clone();
+    if let Err(e) = inbound_policy_repository
+        .upsert_inbound_policy(policy.clone())
+        .await
+    {
+        info!("failed to save inbound policy. error: {}", e);
+        ctx.say("Failed to save the policy. Please run ut_c_guild_init first.")
+            .await?;
+        return Ok(());
     }
+    let change = if allow.unwrap_or(true) {
+        "added to"
+    } else {
+        "removed from"
+    };
+    audit_by_author(
+        ctx,
+        guild_id,
+        AuditAction::SettingChange,
+        format!("{} {} the inbound allow list", origin_guild_id, change),
+    )
+    .await;
 
-    info!(
-        "new times set complete. guild_id: {}, user_id: {}, channel_id: {}, webhook_url: {}",
-        guild_id, user_id, channel_id_u64, webhook_url
-    );
+    ctx.say(format!(
+        "Saved.
+{}",
+        inbound_policy_text(&policy)
+    ))
+    .await?;
+    Ok(())
+}
 
-    let reply_mesage = format!(
-        "Success! Hello {}, I learned that this channel is your Times!",
-        user_name
-    );
+#[poise::command(slash_command, guild_only, ephemeral)]
+#[tracing::instrument(skip(ctx))]
+/// このギルドの，拡散されてくるメッセージの受け入れ方を表示します
+pub async fn ut_c_inbound_show(ctx: Context<'_>) -> Result<()> {
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
 
-    ctx.say(reply_mesage).await?;
+    let policy = current_inbound_policy(ctx, guild_id).await?;
+    ctx.say(inbound_policy_text(&policy)).await?;
     Ok(())
 }
 
-#[poise::command(prefix_command, track_edits, aliases("UtTimesDelete"), slash_command)]
+fn thread_follow_text(follow_threads: bool) -> &'static str {
+    if follow_threads {
+        "Posts written in a thread go to the matching thread here when it exists."
+    } else {
+        "Posts written in a thread are sent to the channel here, not to a thread."
+    }
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
 #[tracing::instrument(skip(ctx))]
-/// あなたのTimes情報を削除します
-pub async fn ut_c_times_delete(ctx: Context<'_>) -> Result<()> {
-    let user_id = ctx.author().id.get();
+/// 拡散されてくるスレッドへの書き込みを，スレッドへ送るかを設定します
+///
+/// 何も指定しなければ，現在の設定を表示します
+/// 設定しなければ，対応するスレッドがあればそこへ送ります
+pub async fn ut_c_thread_follow(
+    ctx: Context<'_>,
+    #[description = "対応するスレッドへ送るか"] follow_threads: Option<bool>,
+) -> Result<()> {
     let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
 
-    let times_repository = ctx.data().times_repository.clone();
-    times_repository.delete_time(user_id, guild_id).await?;
+    let thread_follow_repository = ctx.data().thread_follow_repository.clone();
+    let Some(follow_threads) = follow_threads else {
+        let follow_threads = thread_follow_repository
+            .get_thread_follow_settings(vec![guild_id])
+            .await?
+            .first()
+            .is_none_or(|s| s.follow_threads);
+        ctx.say(thread_follow_text(follow_threads)).await?;
+        return Ok(());
+    };
+
+    if let Err(e) = thread_follow_repository
+        .upsert_thread_follow_setting(UtThreadFollowSetting {
+            guild_id,
+            follow_threads,
+        })
+        .await
+    {
+        info!("failed to save thread follow setting. error: {}", e);
+        ctx.say("Failed to save the setting. Please run ut_c_guild_init first.")
+            .await?;
+        return Ok(());
+    }
+    audit_by_author(
+        ctx,
+        guild_id,
+        AuditAction::SettingChange,
+        format!("thread follow: {}", follow_threads),
+    )
+    .await;
 
-    ctx.say("Success! I forgot your Times!").await?;
+    ctx.say(format!("Saved. {}", thread_follow_text(follow_threads)))
+        .await?;
     Ok(())
 }
 
-#[poise::command(prefix_command, track_edits, aliases("UT"), slash_command)]
+/// スラッシュコマンドで選ぶ，フィルタで調べるもの
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum ContentFilterKindChoice {
+    #[name = "regex"]
+    Regex,
+    #[name = "word"]
+    Word,
+    #[name = "max attachment size"]
+    MaxAttachmentSize,
+    #[name = "allowed MIME types"]
+    AllowedMimeTypes,
+    #[name = "no links"]
+    NoLinks,
+}
+
+/// スラッシュコマンドで選ぶ，フィルタに当てはまったときの扱い
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum FilterActionChoice {
+    #[name = "block"]
+    Block,
+    #[name = "redact"]
+    Redact,
+    #[name = "warn"]
+    Warn,
+}
+
+impl From<FilterActionChoice> for FilterAction {
+    fn from(choice: FilterActionChoice) -> Self {
+        match choice {
+            FilterActionChoice::Block => FilterAction::Block,
+            FilterActionChoice::Redact => FilterAction::Redact,
+            FilterActionChoice::Warn => FilterAction::Warn,
+        }
+    }
+}
+
+/// 選んだ種類と値から，フィルタの条件を作る
+/// 値が正しくなければ，ユーザーに伝える文を返す
+fn content_filter_rule(
+    kind: ContentFilterKindChoice,
+    value: Option<&str>,
+) -> std::result::Result<ContentFilterRule, String> {
+    let value = value.map(|v| v.trim()).filter(|v| !v.is_empty());
+    let rule = match (kind, value) {
+        (ContentFilterKindChoice::NoLinks, _) => ContentFilterRule::NoLinks,
+        (_, None) => return Err("Please enter a value for this filter.".to_string()),
+        (ContentFilterKindChoice::Regex, Some(pattern)) => {
+            let rule = ContentFilterRule::Regex(pattern.to_string());
+            build_filter(&rule).map_err(|e| format!("Invalid regex: {}", e))?;
+            rule
+        }
+        (ContentFilterKindChoice::Word, Some(word)) => ContentFilterRule::Word(word.to_string()),
+        (ContentFilterKindChoice::MaxAttachmentSize, Some(size)) => {
+            ContentFilterRule::MaxAttachmentSize(parse_attachment_size(size).ok_or_else(|| {
+                "Please enter a size such as `8MB`, `500KB` or `1024`.".to_string()
+            })?)
+        }
+        (ContentFilterKindChoice::AllowedMimeTypes, Some(types)) => {
+            ContentFilterRule::AllowedMimeTypes(parse_mime_types(types).ok_or_else(|| {
+                "Please enter MIME types separated by commas, such as `image/*, text/plain`."
+                    .to_string()
+            })?)
+        }
+    };
+    Ok(rule)
+}
+
+/// フィルタの一覧を説明する文
+fn content_filters_text(filters: &[UtContentFilter]) -> String {
+    if filters.is_empty() {
+        return "No content filters. Posts from other guilds are delivered as they are."
+            .to_string();
+    }
+
+    let mut lines = vec!["Content filters".to_string()];
+    for filter in filters {
+        let rule = match &filter.rule {
+            ContentFilterRule::Regex(pattern) => format!("regex `{}`", pattern),
+            ContentFilterRule::Word(word) => format!("word `{}`", word),
+            ContentFilterRule::MaxAttachmentSize(size) => {
+                format!("max attachment size {} bytes", size)
+            }
+            ContentFilterRule::AllowedMimeTypes(types) => {
+                format!("allowed MIME types {}", types.join(", "))
+            }
+            ContentFilterRule::NoLinks => "no links".to_string(),
+        };
+        let action = match filter.action {
+            FilterAction::Block => "block",
+            FilterAction::Redact => "redact",
+            FilterAction::Warn => "warn",
+        };
+        lines.push(format!("- #{} {}: {}", filter.filter_id, rule, action));
+    }
+    lines.join("\n")
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
 #[tracing::instrument(skip(ctx))]
-/// 代わりに~UTプレフィックスコマンドを使用してください
+/// 拡散されてくるメッセージのフィルタを追加します
 ///
-/// 書き込んだ内容を，他のギルドのあなたのTimesへ送信します
-/// ~UTプレフィックスコマンドを使用してください
-/// スラッシュコマンドで使用した場合，アプリケーションの応答がないと返ってきますが，
-/// 無視してください
-pub async fn ut_c_times_release(
+/// regex, word: 本文が一致するか
+/// max attachment size: 添付ファイルの大きさの上限．8MBのように書けます
+/// allowed MIME types: 許可する添付ファイルの種類．image/*, text/plainのようにカンマでつなぎます
+/// no links: 本文にリンクを含むか．valueは要りません
+///
+/// block: このギルドへは拡散しません
+/// redact: 当てはまった部分を取り除いて拡散します
+/// warn: そのまま拡散して，拡散したユーザーに知らせます
+pub async fn ut_c_filter_add(
     ctx: Context<'_>,
-    // 複数行のメッセージを受け取るためにVec<String>を使用
-    // 使用されないが，引数がないとエラーになるため，ダミーの引数を追加
-    #[description = "message"] _content: Vec<String>,
-) -> Result<()> {
-    let prefix_ctx = match &ctx {
-        poise::Context::Application(_) => {
-            // スラッシュコマンド非対応なのに，なぜスラッシュコマンドとして呼ぶことも許可しているのか？
-            // コマンドを探すときに，スラッシュを打ったら候補に出てくる方が探しやすいから
-            // その後．プレフィックスコマンドに誘導する方が良いと考えた
-            info!("slash command is not supported. please use the ~UT prefix command.");
-            let _ = ctx.say("Please use the ~UT prefix command").await;
+    #[description = "調べるもの"] kind: ContentFilterKindChoice,
+    #[description = "当てはまったときの扱い"] action: FilterActionChoice,
+    #[description = "正規表現，語，大きさ，MIMEタイプ"] value: Option<String>,
+) -> Result<()> {
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
+
+    let rule = match content_filter_rule(kind, value.as_deref()) {
+        Ok(rule) => rule,
+        Err(message) => {
+            ctx.say(message).await?;
             return Ok(());
         }
-        poise::Context::Prefix(prefix_ctx) => prefix_ctx,
     };
-    info!("prefix command");
-
-    // 最初の~UTを削除
-    let content = prefix_ctx
-        .msg
-        .content
-        .trim_start_matches("~UT\n")
-        .to_string();
-    info!("content: {:?}", content);
 
-    let user_id = ctx.author().id.get();
+    let content_filter_repository = ctx.data().content_filter_repository.clone();
+    let filter = match content_filter_repository
+        .insert_content_filter(guild_id, rule, action.into())
+        .await
+    {
+        Ok(filter) => filter,
+        Err(e) => {
+            info!("failed to save content filter. error: {}", e);
+            ctx.say("Failed to save the filter. Please run ut_c_guild_init first.")
+                .await?;
+            return Ok(());
+        }
+    };
+    // 語や正規表現は監査ログにも残さない
+    audit_by_author(
+        ctx,
+        guild_id,
+        AuditAction::SettingChange,
+        format!(
+            "content filter added: {} ({:?})",
+            describe_filter(&filter),
+            filter.action
+        ),
+    )
+    .await;
 
-    let times_repository = ctx.data().times_repository.clone();
-    let times = times_repository.get_times(user_id).await?;
+    let filters = content_filter_repository
+        .get_content_filters(vec![guild_id])
+        .await?;
+    ctx.say(format!("Saved.\n{}", content_filters_text(&filters)))
+        .await?;
+    Ok(())
+}
 
-    // Timesから，発信元のguild_idを持ったTimeを削除
+#[poise::command(
+    slash_command,
+    guild_only,
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
+#[tracing::instrument(skip(ctx))]
+/// 拡散されてくるメッセージのフィルタを削除します
+pub async fn ut_c_filter_remove(
+    ctx: Context<'_>,
+    #[description = "ut_c_filter_listで表示される番号"] filter_id: u64,
+) -> Result<()> {
     let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
-    let times = times
-        .into_iter()
-        .filter(|t| t.guild_id != guild_id)
-        .collect();
 
-    let message_sender = ctx.data().times_message_sender.clone();
-    message_sender
-        .send_all(prefix_ctx.msg, content, times)
+    let content_filter_repository = ctx.data().content_filter_repository.clone();
+    let deleted = content_filter_repository
+        .delete_content_filter(guild_id, filter_id)
+        .await?;
+    if !deleted {
+        ctx.say(format!(
+            "Filter #{} was not found in this guild.",
+            filter_id
+        ))
         .await?;
+        return Ok(());
+    }
+    audit_by_author(
+        ctx,
+        guild_id,
+        AuditAction::SettingChange,
+        format!("content filter removed: #{}", filter_id),
+    )
+    .await;
 
-    info!("times release complete. user_id: {}", user_id);
+    let filters = content_filter_repository
+        .get_content_filters(vec![guild_id])
+        .await?;
+    ctx.say(format!("Removed.\n{}", content_filters_text(&filters)))
+        .await?;
     Ok(())
 }
 
-#[poise::command(prefix_command, hide_in_help)]
+#[poise::command(slash_command, guild_only, ephemeral)]
 #[tracing::instrument(skip(ctx))]
-///  スラッシュコマンドの変更を即座に反映するためのコマンド
-///
-/// 主にデバッグ用
-pub async fn register(ctx: Context<'_>, #[flag] global: bool) -> Result<()> {
-    poise::builtins::register_application_commands(ctx, global).await?;
+/// このギルドの，拡散されてくるメッセージのフィルタを表示します
+pub async fn ut_c_filter_list(ctx: Context<'_>) -> Result<()> {
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
+
+    let content_filter_repository = ctx.data().content_filter_repository.clone();
+    let filters = content_filter_repository
+        .get_content_filters(vec![guild_id])
+        .await?;
+
+    let text = content_filters_text(&filters);
+    for chunk in split_content(&text, DISCORD_CONTENT_LIMIT) {
+        ctx.say(chunk).await?;
+    }
     Ok(())
 }
 
-/// テスト用のコマンド
-#[poise::command(prefix_command, track_edits, aliases("test"), slash_command)]
+#[poise::command(prefix_command, track_edits, aliases("UtTimesSet"), slash_command)]
 #[tracing::instrument(skip(ctx))]
-pub async fn ut_c_test(
+/// 実行したチャンネルをあなたのTimesとして登録します
+///
+/// ２度目以降の実行は情報を更新します
+pub async fn ut_c_times_set(
     ctx: Context<'_>,
-    #[description = "message"] content: Vec<String>,
+    #[description = "このギルドで使用する名前"] user_name: String,
 ) -> Result<()> {
-    let content = content.join("\n");
-    info!("message: {:?}", content);
-    ctx.say(content).await?;
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
+    let channel_id = ctx.channel_id();
+
+    // Ubiquitimesから拡散だとわかるように，ユーザー名にプレフィックスを付加する
+    let user_name = ubiquitimes_user_name(user_name);
+
+    let rotation = register_time(ctx, guild_id, channel_id, user_name.clone()).await?;
+    let mut detail = format!("<#{}> as `{}`", channel_id, user_name);
+    if rotation.old_webhook.is_some() {
+        detail.push_str(", webhook rotated");
+    }
+    audit_by_author(ctx, guild_id, AuditAction::TimeSet, detail).await;
+
+    let mut reply_mesage = format!(
+        "Success! Hello {}, I learned that this channel is your Times!",
+        user_name
+    );
+    // 登録はできているので，古いWebhookを削除できなかったことは伝えるだけにする
+    if let Some(WebhookDeletion::Failed(reason)) = rotation.old_webhook {
+        reply_mesage.push_str(&format!(
+            "\nI could not delete your old webhook, so please remove it manually: {}",
+            reason
+        ));
+    }
+
+    ctx.say(reply_mesage).await?;
+    Ok(())
+}
+
+#[poise::command(prefix_command, track_edits, aliases("UtTimesDelete"), slash_command)]
+#[tracing::instrument(skip(ctx))]
+/// あなたのTimes情報を削除します
+///
+/// Webhookを削除してから，登録を削除します
+pub async fn ut_c_times_delete(ctx: Context<'_>) -> Result<()> {
+    let user_id = ctx.author().id.get();
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
+
+    let times_repository = ctx.data().times_repository.clone();
+    let time = times_repository
+        .get_times(user_id)
+        .await?
+        .into_iter()
+        .find(|t| t.guild_id == guild_id);
+    let Some(time) = time else {
+        ctx.say("You have no Times registered in this guild.")
+            .await?;
+        return Ok(());
+    };
+
+    let guild_name = guild_display_name(ctx, guild_id).await;
+    let report = unregister_time(ctx, &time).await;
+
+    ctx.say(report.message(&guild_name)).await?;
     Ok(())
 }
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(skip(ctx))]
+/// 任意のギルドに登録した，あなたのTimes情報を削除します
+///
+/// 退出したギルドやBANされたギルドの登録も削除できます
+/// DMからも実行できます．Webhookも削除します
+pub async fn ut_c_times_unregister(
+    ctx: Context<'_>,
+    #[description = "登録を削除するギルド"]
+    #[autocomplete = "autocomplete_registered_guild"]
+    guild: String,
+) -> Result<()> {
+    let user_id = ctx.author().id.get();
+
+    let Ok(guild_id) = guild.parse::<u64>() else {
+        ctx.say("Please choose a guild from the list.").await?;
+        return Ok(());
+    };
+
+    let times_repository = ctx.data().times_repository.clone();
+    let time = times_repository
+        .get_times(user_id)
+        .await?
+        .into_iter()
+        .find(|t| t.guild_id == guild_id);
+    let Some(time) = time else {
+        ctx.say("You have no Times registered in that guild.")
+            .await?;
+        return Ok(());
+    };
+
+    let guild_name = guild_display_name(ctx, guild_id).await;
+    let report = unregister_time(ctx, &time).await;
+
+    ctx.say(report.message(&guild_name)).await?;
+    Ok(())
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(skip(ctx))]
+/// botが保存している，あなたについてのデータをJSONファイルで受け取ります
+///
+/// WebhookのURLのトークンは伏せます
+/// DMからも実行できます
+pub async fn ut_c_export(ctx: Context<'_>) -> Result<()> {
+    let user_id = ctx.author().id.get();
+    let data = ctx.data();
+
+    let mut times = Vec::new();
+    for time in data.times_repository.get_times(user_id).await? {
+        let guild_name = guild_display_name(ctx, time.guild_id).await;
+        times.push((time, guild_name));
+    }
+    let records = UserRecords {
+        times,
+        deliveries: data
+            .delivery_repository
+            .get_deliveries_by_user(user_id)
+            .await?,
+        released_message_ids: data
+            .delivery_repository
+            .get_released_by_user(user_id)
+            .await?,
+        time_removals: data
+            .time_removal_repository
+            .get_time_removals(user_id)
+            .await?,
+        pending_mirrors: data
+            .pending_mirror_repository
+            .get_pending_mirrors_by_user(user_id)
+            .await?,
+        relayed_replies: data
+            .reply_bridge_repository
+            .get_relayed_replies_by_user(user_id)
+            .await?,
+        audit_entries: data
+            .audit_log_repository
+            .get_audit_entries_by_actor(user_id)
+            .await?,
+        footer: data
+            .footer_settings_repository
+            .get_user_footer_setting(user_id)
+            .await?,
+    };
+
+    let export = UserExport::new(user_id, Timestamp::now().to_string(), records);
+    let json = export.to_json().map_err(|e| Box::new(e) as Box<_>)?;
+    info!(
+        "export created. user_id: {}, times: {}, deliveries: {}",
+        user_id,
+        export.times.len(),
+        export.deliveries.len()
+    );
+
+    let file = CreateAttachment::bytes(json.into_bytes(), EXPORT_FILE_NAME);
+    let reply = CreateReply::default()
+        .content(format!(
+            "Here is everything I store about you (format version {}).",
+            EXPORT_FORMAT_VERSION
+        ))
+        .attachment(file);
+    ctx.send(reply).await?;
+    Ok(())
+}
+
+// ut_c_forget_meの確認を待つ時間
+const FORGET_ME_CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(skip(ctx))]
+/// すべてのギルドから，あなたの登録と拡散の記録を削除します
+///
+/// Timesの登録とWebhook，拡散の記録を削除します
+/// 実行前にボタンで確認します
+/// 途中で失敗しても，もう一度実行すれば続きから削除します
+pub async fn ut_c_forget_me(
+    ctx: Context<'_>,
+    #[description = "拡散したメッセージも削除する"] delete_mirrors: Option<bool>,
+) -> Result<()> {
+    let delete_mirrors = delete_mirrors.unwrap_or(false);
+
+    // 他の実行中のコマンドのボタンと区別するため，コマンドの実行ごとのidを付ける
+    let confirm_id = format!("{}:forget_me:confirm", ctx.id());
+    let cancel_id = format!("{}:forget_me:cancel", ctx.id());
+
+    let target = if delete_mirrors {
+        "all your Times registrations, their webhooks, your delivery history and every mirrored message"
+    } else {
+        "all your Times registrations, their webhooks and your delivery history"
+    };
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new(&confirm_id)
+            .label("Forget me")
+            .style(ButtonStyle::Danger),
+        CreateButton::new(&cancel_id)
+            .label("Cancel")
+            .style(ButtonStyle::Secondary),
+    ]);
+    let handle = ctx
+        .send(
+            CreateReply::default()
+                .content(format!(
+                    "This will delete {} in every guild. Are you sure?",
+                    target
+                ))
+                .components(vec![buttons])
+                .ephemeral(true),
+        )
+        .await?;
+
+    let (filter_confirm, filter_cancel) = (confirm_id.clone(), cancel_id.clone());
+    let Some(mci) = ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .channel_id(ctx.channel_id())
+        .timeout(FORGET_ME_CONFIRM_TIMEOUT)
+        .filter(move |mci| {
+            mci.data.custom_id == filter_confirm || mci.data.custom_id == filter_cancel
+        })
+        .await
+    else {
+        handle
+            .edit(
+                ctx,
+                CreateReply::default()
+                    .content("Timed out. Nothing was deleted.")
+                    .components(vec![]),
+            )
+            .await?;
+        return Ok(());
+    };
+
+    let confirmed = mci.data.custom_id == confirm_id;
+    let content = if confirmed {
+        "Forgetting you..."
+    } else {
+        "Cancelled. Nothing was deleted."
+    };
+    mci.create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .components(vec![]),
+        ),
+    )
+    .await?;
+    if !confirmed {
+        return Ok(());
+    }
+
+    let report = forget_me(ctx, delete_mirrors).await?;
+
+    let mut guild_names = HashMap::new();
+    for guild_id in report.guilds.keys() {
+        guild_names.insert(*guild_id, guild_display_name(ctx, *guild_id).await);
+    }
+    for chunk in split_content(&report.message(&guild_names), DISCORD_CONTENT_LIMIT) {
+        ctx.say(chunk).await?;
+    }
+    Ok(())
+}
+
+// Discordのオートコンプリートで返せる候補の上限
+const MAX_AUTOCOMPLETE_CHOICES: usize = 25;
+
+/// あなたがTimesを登録しているギルドを候補にする
+async fn autocomplete_registered_guild(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    let user_id = ctx.author().id.get();
+    let times_repository = ctx.data().times_repository.clone();
+    let Ok(times) = times_repository.get_times(user_id).await else {
+        return Vec::new();
+    };
+
+    let partial = partial.to_lowercase();
+    let mut choices = Vec::new();
+    for time in times {
+        let guild_name = guild_display_name(ctx, time.guild_id).await;
+        if guild_name.to_lowercase().contains(&partial)
+            || time.guild_id.to_string().starts_with(&partial)
+        {
+            choices.push(AutocompleteChoice::new(
+                guild_name,
+                time.guild_id.to_string(),
+            ));
+        }
+    }
+    choices.truncate(MAX_AUTOCOMPLETE_CHOICES);
+    choices
+}
+
+/// Guildsテーブルに保存されているギルド名
+/// 保存されていない場合はguild_idを使う
+async fn guild_display_name(ctx: Context<'_>, guild_id: u64) -> String {
+    let guild_repository = ctx.data().guild_repository.clone();
+    guild_repository
+        .get_guild(guild_id)
+        .await
+        .ok()
+        .and_then(|g| g.guild_name)
+        .unwrap_or_else(|| guild_id.to_string())
+}
+
+// 一覧のボタンを押せる時間
+const TIMES_LIST_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(skip(ctx))]
+/// あなたが登録しているTimesの一覧を表示します
+///
+/// 各ギルドのチャンネル，名前，Webhookの状態を表示します
+/// ボタンから，登録の削除やWebhookの作り直しができます
+pub async fn ut_c_times_list(ctx: Context<'_>) -> Result<()> {
+    ctx.defer_ephemeral().await?;
+
+    let user_id = ctx.author().id.get();
+    let times_repository = ctx.data().times_repository.clone();
+
+    // 他の実行中のコマンドのボタンと区別するため，コマンドの実行ごとのidを付ける
+    let button_prefix = format!("{}:", ctx.id());
+
+    let (content, components) = times_list(ctx, &button_prefix, None).await?;
+    ctx.send(
+        CreateReply::default()
+            .content(content)
+            .components(components)
+            .ephemeral(true),
+    )
+    .await?;
+
+    loop {
+        let filter_prefix = button_prefix.clone();
+        let Some(mci) = ComponentInteractionCollector::new(ctx)
+            .author_id(ctx.author().id)
+            .channel_id(ctx.channel_id())
+            .timeout(TIMES_LIST_TIMEOUT)
+            .filter(move |mci| mci.data.custom_id.starts_with(&filter_prefix))
+            .await
+        else {
+            break;
+        };
+
+        let Some((action, guild_id)) = parse_times_list_button(&mci.data.custom_id, &button_prefix)
+        else {
+            continue;
+        };
+
+        // 二重に押したときや，ut_c_forget_meの後に古い一覧のボタンを押したときは，登録がもうない
+        let time = times_repository
+            .get_times(user_id)
+            .await?
+            .into_iter()
+            .find(|t| t.guild_id == guild_id);
+        let status = match (action, time) {
+            (_, None) => {
+                let guild_name = guild_display_name(ctx, guild_id).await;
+                format!("**{}**: already unregistered.", guild_name)
+            }
+            (TimesListAction::Unregister, Some(time)) => {
+                let guild_name = guild_display_name(ctx, guild_id).await;
+                unregister_time(ctx, &time).await.message(&guild_name)
+            }
+            (TimesListAction::Recreate, Some(time)) => {
+                match register_time(
+                    ctx,
+                    guild_id,
+                    ChannelId::new(time.channel_id),
+                    time.user_name,
+                )
+                .await
+                {
+                    Ok(_) => {
+                        audit_by_author(
+                            ctx,
+                            guild_id,
+                            AuditAction::TimeWebhookRecreate,
+                            format!("<#{}>", time.channel_id),
+                        )
+                        .await;
+                        "Webhook re-created.".to_string()
+                    }
+                    Err(e) => format!("Failed to re-create the webhook: {}", e),
+                }
+            }
+        };
+
+        let (content, components) = times_list(ctx, &button_prefix, Some(status)).await?;
+        mci.create_response(
+            ctx,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(components),
+            ),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimesListAction {
+    Unregister,
+    Recreate,
+}
+
+impl TimesListAction {
+    fn id(&self) -> &'static str {
+        match self {
+            TimesListAction::Unregister => "unregister",
+            TimesListAction::Recreate => "recreate",
+        }
+    }
+}
+
+/// ボタンのidは {button_prefix}{action}:{guild_id}
+fn parse_times_list_button(custom_id: &str, button_prefix: &str) -> Option<(TimesListAction, u64)> {
+    let (action, guild_id) = custom_id.strip_prefix(button_prefix)?.split_once(':')?;
+    let action = match action {
+        "unregister" => TimesListAction::Unregister,
+        "recreate" => TimesListAction::Recreate,
+        _ => return None,
+    };
+    Some((action, guild_id.parse().ok()?))
+}
+
+// 1つのメッセージに付けられるボタンの行数の上限
+const MAX_ACTION_ROWS: usize = 5;
+
+/// Timesの一覧の本文とボタンを作る
+async fn times_list(
+    ctx: Context<'_>,
+    button_prefix: &str,
+    status: Option<String>,
+) -> Result<(String, Vec<CreateActionRow>)> {
+    let user_id = ctx.author().id.get();
+    let times_repository = ctx.data().times_repository.clone();
+
+    let mut times = times_repository.get_times(user_id).await?;
+    times.sort_by_key(|t| t.guild_id);
+
+    // BANやギルドを抜けたことでbotが削除した登録．登録し直したギルドのものは表示しない
+    let mut removal_lines = Vec::new();
+    for removal in ctx
+        .data()
+        .time_removal_repository
+        .get_time_removals(user_id)
+        .await?
+        .iter()
+        .filter(|r| times.iter().all(|t| t.guild_id != r.guild_id))
+    {
+        if removal_lines.is_empty() {
+            removal_lines.push(String::new());
+            removal_lines.push("Times removed by the bot".to_string());
+        }
+        let guild_name = guild_display_name(ctx, removal.guild_id).await;
+        removal_lines.push(removal_text(removal, &guild_name));
+    }
+
+    let mut lines = Vec::new();
+    if let Some(status) = status {
+        lines.push(status);
+        lines.push(String::new());
+    }
+
+    if times.is_empty() {
+        lines.push(
+            "You have no Times registered. Run ut_c_times_set in your Times channel.".to_string(),
+        );
+        lines.extend(removal_lines);
+        return Ok((lines.join("\n"), Vec::new()));
+    }
+
+    lines.push("Your Times".to_string());
+    let mut components = Vec::new();
+    for time in times.iter() {
+        let guild_name = guild_display_name(ctx, time.guild_id).await;
+        let health = check_webhook_health(ctx, time).await;
+
+        lines.push(format!(
+            "- **{}** <#{}> as `{}`: {}",
+            guild_name,
+            time.channel_id,
+            time.user_name,
+            health.label()
+        ));
+
+        if components.len() < MAX_ACTION_ROWS {
+            let button = |action: TimesListAction, label: String, style: ButtonStyle| {
+                CreateButton::new(format!(
+                    "{}{}:{}",
+                    button_prefix,
+                    action.id(),
+                    time.guild_id
+                ))
+                // ボタンのラベルは80文字まで
+                .label(label.chars().take(80).collect::<String>())
+                .style(style)
+            };
+            components.push(CreateActionRow::Buttons(vec![
+                button(
+                    TimesListAction::Recreate,
+                    format!("Re-create in {}", guild_name),
+                    ButtonStyle::Secondary,
+                ),
+                button(
+                    TimesListAction::Unregister,
+                    format!("Unregister from {}", guild_name),
+                    ButtonStyle::Danger,
+                ),
+            ]));
+        }
+    }
+
+    if times.len() > MAX_ACTION_ROWS {
+        lines.push(format!(
+            "Buttons are shown for the first {} guilds only.",
+            MAX_ACTION_ROWS
+        ));
+    }
+    lines.extend(removal_lines);
+
+    Ok((lines.join("\n"), components))
+}
+
+/// スラッシュコマンドで開くモーダル
+#[derive(Debug, poise::Modal)]
+#[name = "Release to your Times"]
+struct ReleaseModal {
+    #[name = "Content"]
+    #[placeholder = "拡散したい内容"]
+    #[paragraph]
+    // 発信元のTimesへ1つのメッセージとして投稿するため，Discordの上限に合わせる
+    #[max_length = 2000]
+    content: String,
+}
+
+// モーダルへの入力を待つ時間
+const RELEASE_MODAL_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// 回数の上限を説明する文
+fn rate_limit_settings_text(settings: &UtRateLimitSettings) -> String {
+    format!(
+        "Each user can release up to {} posts per {} s from this guild.\nThis guild receives up to {} posts per {} s from other guilds.",
+        settings.user_limit.capacity,
+        settings.user_limit.period_secs,
+        settings.guild_limit.capacity,
+        settings.guild_limit.period_secs
+    )
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
+#[tracing::instrument(skip(ctx))]
+/// 拡散する回数の上限を設定します
+///
+/// 何も指定しなければ，現在の設定を表示します
+/// user_limit, user_period: このギルドから，1人のユーザーがuser_period秒にuser_limit回まで拡散できます
+/// guild_limit, guild_period: このギルドは，他のギルドからguild_period秒にguild_limit回まで受け取ります
+pub async fn ut_c_rate_limit(
+    ctx: Context<'_>,
+    #[description = "1人のユーザーが拡散できる回数"]
+    #[min = 1]
+    #[max = 1000]
+    user_limit: Option<u32>,
+    #[description = "user_limitの期間(秒)"]
+    #[min = 1]
+    #[max = 86400]
+    user_period: Option<u32>,
+    #[description = "このギルドが受け取る回数"]
+    #[min = 1]
+    #[max = 10000]
+    guild_limit: Option<u32>,
+    #[description = "guild_limitの期間(秒)"]
+    #[min = 1]
+    #[max = 86400]
+    guild_period: Option<u32>,
+) -> Result<()> {
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
+
+    let rate_limit_repository = ctx.data().rate_limit_repository.clone();
+    let mut settings = load_rate_limit_settings(rate_limit_repository.as_ref(), guild_id).await?;
+    if user_limit.is_none()
+        && user_period.is_none()
+        && guild_limit.is_none()
+        && guild_period.is_none()
+    {
+        ctx.say(rate_limit_settings_text(&settings)).await?;
+        return Ok(());
+    }
+
+    if let Some(capacity) = user_limit {
+        settings.user_limit.capacity = capacity;
+    }
+    if let Some(period_secs) = user_period {
+        settings.user_limit.period_secs = period_secs;
+    }
+    if let Some(capacity) = guild_limit {
+        settings.guild_limit.capacity = capacity;
+    }
+    if let Some(period_secs) = guild_period {
+        settings.guild_limit.period_secs = period_secs;
+    }
+
+    if let Err(e) = rate_limit_repository
+        .upsert_rate_limit_settings(settings)
+        .await
+    {
+        info!("failed to save rate limit settings. error: {}", e);
+        ctx.say("Failed to save the limits. Please run ut_c_guild_init first.")
+            .await?;
+        return Ok(());
+    }
+    audit_by_author(
+        ctx,
+        guild_id,
+        AuditAction::SettingChange,
+        format!(
+            "rate limits: {} per {} s per user, {} per {} s for the guild",
+            settings.user_limit.capacity,
+            settings.user_limit.period_secs,
+            settings.guild_limit.capacity,
+            settings.guild_limit.period_secs
+        ),
+    )
+    .await;
+
+    ctx.say(format!("Saved.\n{}", rate_limit_settings_text(&settings)))
+        .await?;
+    Ok(())
+}
+
+/// スラッシュコマンドで選ぶ，ギルドを抜けたメンバーのTimesの扱い
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum MemberLeaveActionChoice {
+    #[name = "keep"]
+    Keep,
+    #[name = "unregister"]
+    Unregister,
+}
+
+impl From<MemberLeaveActionChoice> for MemberLeaveAction {
+    fn from(choice: MemberLeaveActionChoice) -> Self {
+        match choice {
+            MemberLeaveActionChoice::Keep => MemberLeaveAction::Keep,
+            MemberLeaveActionChoice::Unregister => MemberLeaveAction::Unregister,
+        }
+    }
+}
+
+fn member_leave_action_text(action: MemberLeaveAction) -> &'static str {
+    match action {
+        MemberLeaveAction::Keep => {
+            "Members who leave keep their Times here and keep receiving mirrored posts."
+        }
+        MemberLeaveAction::Unregister => {
+            "Times of members who leave are unregistered and their webhooks are deleted."
+        }
+    }
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
+#[tracing::instrument(skip(ctx))]
+/// ギルドを抜けたメンバーのTimesの扱いを設定します
+///
+/// 何も指定しなければ，現在の設定を表示します
+/// keep: 登録を残して，拡散されてくるメッセージを受け取り続けます(初期設定)
+/// unregister: 登録とWebhookを削除します
+/// BANされたメンバーのTimesは，この設定によらず削除します
+pub async fn ut_c_member_leave_policy(
+    ctx: Context<'_>,
+    #[description = "ギルドを抜けたメンバーのTimesの扱い"] action: Option<MemberLeaveActionChoice>,
+) -> Result<()> {
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
+
+    let member_leave_policy_repository = ctx.data().member_leave_policy_repository.clone();
+    let Some(action) = action else {
+        let action = member_leave_policy_repository
+            .get_member_leave_policy(guild_id)
+            .await?
+            .map_or(MemberLeaveAction::Keep, |p| p.action);
+        ctx.say(format!(
+            "{}\nBanned members' Times are always unregistered.",
+            member_leave_action_text(action)
+        ))
+        .await?;
+        return Ok(());
+    };
+
+    let action = MemberLeaveAction::from(action);
+    if let Err(e) = member_leave_policy_repository
+        .upsert_member_leave_policy(UtMemberLeavePolicy { guild_id, action })
+        .await
+    {
+        info!("failed to save member leave policy. error: {}", e);
+        ctx.say("Failed to save the policy. Please run ut_c_guild_init first.")
+            .await?;
+        return Ok(());
+    }
+    audit_by_author(
+        ctx,
+        guild_id,
+        AuditAction::SettingChange,
+        format!("member leave policy: {:?}", action),
+    )
+    .await;
+
+    ctx.say(format!("Saved. {}", member_leave_action_text(action)))
+        .await?;
+    Ok(())
+}
+
+/// スラッシュコマンドで選ぶ，ギルドに誰でも参加できるか
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum CommunityAccessChoice {
+    #[name = "open"]
+    Open,
+    #[name = "closed"]
+    Closed,
+}
+
+impl From<CommunityAccessChoice> for CommunityAccess {
+    fn from(choice: CommunityAccessChoice) -> Self {
+        match choice {
+            CommunityAccessChoice::Open => CommunityAccess::Open,
+            CommunityAccessChoice::Closed => CommunityAccess::Closed,
+        }
+    }
+}
+
+fn guild_footer_settings_text(settings: &UtGuildFooterSettings) -> String {
+    let enabled = if settings.enabled {
+        "Posts released from this guild get a footer unless the author turned it off."
+    } else {
+        "Posts released from this guild get a footer only if the author turned it on."
+    };
+    let access = match settings.access {
+        CommunityAccess::Open => "open: the footer links to the original post",
+        CommunityAccess::Closed => "closed: the footer shows the guild name without a link",
+    };
+    format!("{}\nCommunity: {}", enabled, access)
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
+#[tracing::instrument(skip(ctx))]
+/// このギルドから拡散したメッセージに付ける案内を設定します
+///
+/// 何も指定しなければ，現在の設定を表示します
+/// enabled: ユーザーが決めていなければ，案内を付けるか
+/// access: open なら発信元のメッセージへのリンクを付け，closed ならギルドの名前だけにします
+pub async fn ut_c_guild_footer(
+    ctx: Context<'_>,
+    #[description = "ユーザーが決めていなければ，案内を付けるか"] enabled: Option<bool>,
+    #[description = "誰でも参加できるギルドか"] access: Option<CommunityAccessChoice>,
+) -> Result<()> {
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
+
+    let footer_settings_repository = ctx.data().footer_settings_repository.clone();
+    let mut settings = footer_settings_repository
+        .get_guild_footer_settings(guild_id)
+        .await?
+        .unwrap_or(UtGuildFooterSettings {
+            guild_id,
+            enabled: false,
+            access: CommunityAccess::default(),
+        });
+    if enabled.is_none() && access.is_none() {
+        ctx.say(guild_footer_settings_text(&settings)).await?;
+        return Ok(());
+    }
+
+    if let Some(enabled) = enabled {
+        settings.enabled = enabled;
+    }
+    if let Some(access) = access {
+        settings.access = access.into();
+    }
+    if let Err(e) = footer_settings_repository
+        .upsert_guild_footer_settings(settings)
+        .await
+    {
+        info!("failed to save guild footer settings. error: {}", e);
+        ctx.say("Failed to save the settings. Please run ut_c_guild_init first.")
+            .await?;
+        return Ok(());
+    }
+    audit_by_author(
+        ctx,
+        guild_id,
+        AuditAction::SettingChange,
+        format!(
+            "footer: enabled {}, community {:?}",
+            settings.enabled, settings.access
+        ),
+    )
+    .await;
+
+    ctx.say(format!("Saved.\n{}", guild_footer_settings_text(&settings)))
+        .await?;
+    Ok(())
+}
+
+fn reply_bridge_settings_text(settings: &UtReplyBridgeSettings) -> String {
+    let send = if settings.send_replies {
+        "Replies here to mirrored posts are relayed to the origin guild if it accepts them."
+    } else {
+        "Replies here to mirrored posts stay in this guild."
+    };
+    let receive = if settings.receive_replies {
+        "Replies in other guilds to posts released from here are relayed into the author's Times."
+    } else {
+        "Replies in other guilds to posts released from here are not relayed."
+    };
+    format!("{}\n{}", send, receive)
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
+#[tracing::instrument(skip(ctx))]
+/// 拡散先での返信を，発信元のTimesへ届けるかを設定します
+///
+/// 何も指定しなければ，現在の設定を表示します
+/// send_replies: このギルドのメンバーの返信を，発信元のギルドへ届けてよいか
+/// receive_replies: 他のギルドでの返信を，このギルドのTimesへ届けてよいか
+/// 両方のギルドが同意したときだけ届けます
+pub async fn ut_c_reply_bridge(
+    ctx: Context<'_>,
+    #[description = "このギルドでの返信を発信元へ届けてよいか"] send_replies: Option<bool>,
+    #[description = "他のギルドでの返信をこのギルドのTimesへ届けてよいか"] receive_replies: Option<
+        bool,
+    >,
+) -> Result<()> {
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
+
+    let reply_bridge_repository = ctx.data().reply_bridge_repository.clone();
+    let mut settings = reply_bridge_repository
+        .get_reply_bridge_settings(guild_id)
+        .await?
+        .unwrap_or(UtReplyBridgeSettings {
+            guild_id,
+            send_replies: false,
+            receive_replies: false,
+        });
+    if send_replies.is_none() && receive_replies.is_none() {
+        ctx.say(reply_bridge_settings_text(&settings)).await?;
+        return Ok(());
+    }
+
+    if let Some(send_replies) = send_replies {
+        settings.send_replies = send_replies;
+    }
+    if let Some(receive_replies) = receive_replies {
+        settings.receive_replies = receive_replies;
+    }
+    if let Err(e) = reply_bridge_repository
+        .upsert_reply_bridge_settings(settings)
+        .await
+    {
+        info!("failed to save reply bridge settings. error: {}", e);
+        ctx.say("Failed to save the settings. Please run ut_c_guild_init first.")
+            .await?;
+        return Ok(());
+    }
+    audit_by_author(
+        ctx,
+        guild_id,
+        AuditAction::SettingChange,
+        format!(
+            "reply bridge: send {}, receive {}",
+            settings.send_replies, settings.receive_replies
+        ),
+    )
+    .await;
+
+    ctx.say(format!("Saved.\n{}", reply_bridge_settings_text(&settings)))
+        .await?;
+    Ok(())
+}
+
+/// スラッシュコマンドで選ぶ，拡散したメッセージに発信元の案内を付けるか
+#[derive(Debug, Clone, Copy, poise::ChoiceParameter)]
+pub enum UserFooterChoice {
+    #[name = "on"]
+    On,
+    #[name = "off"]
+    Off,
+    /// 自分では決めずに，発信元のギルドの設定に従う
+    #[name = "guild default"]
+    GuildDefault,
+}
+
+fn user_footer_setting_text(setting: Option<&UtUserFooterSetting>) -> &'static str {
+    match setting.map(|s| s.enabled) {
+        Some(true) => "Your released posts get a footer linking back to the origin guild.",
+        Some(false) => "Your released posts get no footer.",
+        None => "Your released posts follow each origin guild's footer setting.",
+    }
+}
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(skip(ctx))]
+/// 拡散したメッセージに発信元の案内を付けるかを設定します
+///
+/// 案内には発信元のギルドの名前と，発信元のメッセージへのリンクが入ります
+/// 何も指定しなければ，現在の設定を表示します
+/// 決めていなければ，発信元のギルドの設定に従います
+/// DMからも実行できます
+pub async fn ut_c_footer(
+    ctx: Context<'_>,
+    #[description = "発信元の案内を付けるか"] footer: Option<UserFooterChoice>,
+) -> Result<()> {
+    let user_id = ctx.author().id.get();
+
+    let footer_settings_repository = ctx.data().footer_settings_repository.clone();
+    let setting = match footer {
+        None => {
+            let setting = footer_settings_repository
+                .get_user_footer_setting(user_id)
+                .await?;
+            ctx.say(user_footer_setting_text(setting.as_ref())).await?;
+            return Ok(());
+        }
+        Some(UserFooterChoice::GuildDefault) => {
+            footer_settings_repository
+                .delete_user_footer_setting(user_id)
+                .await?;
+            None
+        }
+        Some(choice) => {
+            let setting = UtUserFooterSetting {
+                user_id,
+                enabled: matches!(choice, UserFooterChoice::On),
+            };
+            footer_settings_repository
+                .upsert_user_footer_setting(setting)
+                .await?;
+            Some(setting)
+        }
+    };
+
+    ctx.say(format!(
+        "Saved. {}",
+        user_footer_setting_text(setting.as_ref())
+    ))
+    .await?;
+    Ok(())
+}
+
+// ut_c_reactionsで表示するメッセージの数
+const DEFAULT_FEEDBACK_POSTS: u32 = 5;
+
+#[poise::command(slash_command, ephemeral)]
+#[tracing::instrument(skip(ctx))]
+/// 拡散したメッセージへの，各ギルドのリアクションと返信を表示します
+///
+/// 新しいメッセージから順に表示します
+/// botが止まっている間のリアクションと返信は数えません
+/// DMからも実行できます
+pub async fn ut_c_reactions(
+    ctx: Context<'_>,
+    #[description = "表示するメッセージの数"]
+    #[min = 1]
+    #[max = 25]
+    posts: Option<u32>,
+) -> Result<()> {
+    let user_id = ctx.author().id.get();
+    let posts = posts.unwrap_or(DEFAULT_FEEDBACK_POSTS) as usize;
+
+    let deliveries = ctx
+        .data()
+        .delivery_repository
+        .get_deliveries_by_user(user_id)
+        .await?;
+    let mirror_message_ids = deliveries
+        .iter()
+        .map(|d| d.mirror_message_id)
+        .collect::<Vec<_>>();
+    let mirror_feedback_repository = ctx.data().mirror_feedback_repository.clone();
+    let reactions = mirror_feedback_repository
+        .get_mirror_reactions(mirror_message_ids.clone())
+        .await?;
+    let replies = mirror_feedback_repository
+        .get_mirror_replies(mirror_message_ids)
+        .await?;
+
+    let feedback = aggregate_feedback(&deliveries, &reactions, &replies, posts);
+    let guild_ids = feedback
+        .iter()
+        .flat_map(|p| p.guilds.iter().map(|g| g.guild_id))
+        .collect::<BTreeSet<_>>();
+    let mut guild_names = BTreeMap::new();
+    for guild_id in guild_ids {
+        guild_names.insert(guild_id, guild_display_name(ctx, guild_id).await);
+    }
+
+    let text = feedback_text(&feedback, &guild_names);
+    for chunk in split_content(&text, DISCORD_CONTENT_LIMIT) {
+        ctx.say(chunk).await?;
+    }
+    Ok(())
+}
+
+const EMPTY_RELEASE_MESSAGE: &str = "Nothing to release. Write your post after the command, for example:\n```\n~UT\nHello from my Times!\n```";
+
+#[poise::command(prefix_command, track_edits, aliases("UT"), slash_command)]
+#[tracing::instrument(skip(ctx, attachment))]
+/// 書き込んだ内容を，他のギルドのあなたのTimesへ送信します
+///
+/// ~UTプレフィックスコマンドでは，コマンドの後に書いた内容を送信します
+/// スラッシュコマンドでは，入力欄が開きます
+/// 入力した内容は，このギルドのあなたのTimesに投稿してから送信します
+pub async fn ut_c_times_release(
+    ctx: Context<'_>,
+    #[description = "一緒に送信するファイル"] attachment: Option<Attachment>,
+    // プレフィックスコマンドでは使用しない
+    // 本文は，先頭の空白や改行を残すためにメッセージから直接取り出す
+    #[description = "入力欄にあらかじめ入れておく内容"]
+    #[rest]
+    content: Option<String>,
+) -> Result<()> {
+    match ctx {
+        poise::Context::Application(app_ctx) => {
+            info!("slash command");
+            release_from_modal(app_ctx, attachment, content).await
+        }
+        poise::Context::Prefix(prefix_ctx) => {
+            info!("prefix command");
+            release_from_prefix(prefix_ctx).await
+        }
+    }
+}
+
+/// ~UTなどのプレフィックスコマンドのメッセージを拡散する
+async fn release_from_prefix(prefix_ctx: PrefixContext<'_>) -> Result<()> {
+    let ctx = poise::Context::Prefix(prefix_ctx);
+
+    // メッセージの編集でも再度実行されるので，拡散済みのメッセージは二重に拡散しない
+    let delivery_repository = ctx.data().delivery_repository.clone();
+    if prefix_ctx.trigger == poise::MessageDispatchTrigger::MessageEdit {
+        let released = delivery_repository
+            .is_released(prefix_ctx.msg.id.get())
+            .await?;
+        if released {
+            info!("message already released. edits are not mirrored.");
+            ctx.say("This message has already been released. Edits are not mirrored.")
+                .await?;
+            return Ok(());
+        }
+    }
+
+    // プレフィックスとコマンド名を取り除く
+    let parser = release_content_parser(Some(prefix_ctx.prefix));
+    let content = match parser.parse(&prefix_ctx.msg.content) {
+        Ok(content) => content,
+        Err(e) => {
+            info!("invalid release content: {}", e);
+            ctx.say(EMPTY_RELEASE_MESSAGE).await?;
+            return Ok(());
+        }
+    };
+    info!("content: {:?}", content);
+
+    release(ctx, prefix_ctx.msg, content).await?;
+    Ok(())
+}
+
+/// ~UTなどのコマンドとして書かれたメッセージから，本文を取り出すパーサー
+/// used_prefixには，実行に使われたプレフィックスがあれば渡す
+fn release_content_parser(used_prefix: Option<&str>) -> ReleaseContentParser {
+    let command = ut_c_times_release();
+    let prefixes = used_prefix
+        .into_iter()
+        .chain(std::iter::once(PREFIX))
+        .chain(ADDITIONAL_PREFIXES);
+    let command_names = std::iter::once(command.name).chain(command.aliases);
+    ReleaseContentParser::new(prefixes, command_names)
+}
+
+/// モーダルに入力された内容を，発信元のTimesへ投稿してから拡散する
+async fn release_from_modal(
+    app_ctx: ApplicationContext<'_>,
+    attachment: Option<Attachment>,
+    content: Option<String>,
+) -> Result<()> {
+    let ctx = poise::Context::Application(app_ctx);
+    let user_id = ctx.author().id.get();
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
+
+    let times_repository = ctx.data().times_repository.clone();
+    let origin_time = times_repository
+        .get_times(user_id)
+        .await?
+        .into_iter()
+        .find(|t| t.guild_id == guild_id);
+    let Some(origin_time) = origin_time else {
+        ctx.send(
+            CreateReply::default()
+                .content("Your Times is not registered in this guild. Please run ut_c_times_set in your Times channel first.")
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    };
+
+    // 発信元のTimesへ投稿してから拡散するので，投稿した後に上限で止めないように先に調べる
+    if reply_if_user_rate_limited(ctx, guild_id, false).await? {
+        return Ok(());
+    }
+
+    let defaults = ReleaseModal {
+        content: content.unwrap_or_default(),
+    };
+    let Some(modal) =
+        poise::execute_modal(app_ctx, Some(defaults), Some(RELEASE_MODAL_TIMEOUT)).await?
+    else {
+        info!("release modal timed out");
+        return Ok(());
+    };
+
+    if modal.content.trim().is_empty() && attachment.is_none() {
+        ctx.send(
+            CreateReply::default()
+                .content(EMPTY_RELEASE_MESSAGE)
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    // 入力している間に，他の拡散で回数を使ったかもしれない
+    if reply_if_user_rate_limited(ctx, guild_id, false).await? {
+        return Ok(());
+    }
+
+    let message_sender = ctx.data().times_message_sender.clone();
+    let origin = message_sender
+        .post_origin(
+            &origin_time,
+            ctx.author(),
+            &modal.content,
+            attachment.as_ref(),
+        )
+        .await?;
+
+    // 投稿はWebhookによるものなので，「Release to my Times」からは拡散できない．投稿が残ることだけを伝える
+    if !release(ctx, &origin, modal.content).await? {
+        ctx.send(
+            CreateReply::default()
+                .content("Your post was published in this guild's Times, but it was not released to your other Times.")
+                .ephemeral(true),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    ctx.send(
+        CreateReply::default()
+            .content("Success! I released your post.")
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(())
+}
+
+#[poise::command(context_menu_command = "Release to my Times", guild_only)]
+#[tracing::instrument(skip(ctx, message))]
+/// 投稿済みのあなたのメッセージを，他のギルドのあなたのTimesへ送信します
+///
+/// 添付ファイルなども一緒に送信します
+/// 一度送信したメッセージは，もう一度送信することはできません
+pub async fn ut_c_release_message(ctx: Context<'_>, message: Message) -> Result<()> {
+    // 送信に時間がかかることがあるので，先に応答しておく
+    ctx.defer_ephemeral().await?;
+
+    if message.author.id != ctx.author().id {
+        ctx.say("You can only release your own messages.").await?;
+        return Ok(());
+    }
+
+    let delivery_repository = ctx.data().delivery_repository.clone();
+    if delivery_repository.is_released(message.id.get()).await? {
+        info!("message already released. message_id: {}", message.id);
+        ctx.say("This message has already been released.").await?;
+        return Ok(());
+    }
+
+    // ~UT 本文 のように書かれたメッセージは，プレフィックスとコマンド名を取り除く
+    let content = match release_content_parser(None).parse(&message.content) {
+        Ok(content) => content,
+        Err(ReleaseContentError::Empty) => String::new(),
+        Err(ReleaseContentError::NotACommand) => message.content.clone(),
+    };
+
+    let has_content = !content.trim().is_empty()
+        || !message.attachments.is_empty()
+        || !message.embeds.is_empty()
+        || !message.sticker_items.is_empty()
+        || message.poll.is_some();
+    if !has_content {
+        ctx.say("This message has nothing to release.").await?;
+        return Ok(());
+    }
+
+    // コンテキストメニューから渡されるメッセージには，ギルドが含まれないことがある
+    let mut message = message;
+    if message.guild_id.is_none() {
+        message.guild_id = ctx.guild_id();
+    }
+
+    if !release(ctx, &message, content).await? {
+        return Ok(());
+    }
+
+    ctx.say("Success! I released your message.").await?;
+    Ok(())
+}
+
+/// 発信元のメッセージを，発信元のギルド以外のあなたのTimesへ拡散する
+///
+/// プレフィックスコマンド，スラッシュコマンド，コンテキストメニューのどれでも，ここから先は同じ処理を行う
+/// ユーザーごとの回数の上限を超えていれば，待つ時間を返信して拡散せずにfalseを返す
+async fn release(ctx: Context<'_>, message: &Message, content: String) -> Result<bool> {
+    let user_id = ctx.author().id.get();
+
+    let times_repository = ctx.data().times_repository.clone();
+    let times = times_repository.get_times(user_id).await?;
+
+    // Timesから，発信元のguild_idを持ったTimeを削除
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
+    let times = times
+        .into_iter()
+        .filter(|t| t.guild_id != guild_id)
+        .collect();
+
+    // ギルドの管理者が拡散しないと決めた送信先を除く
+    let guild_pair_policy_repository = ctx.data().guild_pair_policy_repository.clone();
+    let (times, skipped) = load_and_filter_by_guild_pair_policy(
+        guild_pair_policy_repository.as_ref(),
+        guild_id,
+        times,
+    )
+    .await?;
+
+    // 送信先のギルドの受け入れ方に従って，すぐに拡散するか，承認を待つかを決める
+    let inbound_policy_repository = ctx.data().inbound_policy_repository.clone();
+    let plan = load_inbound_plan(inbound_policy_repository.as_ref(), guild_id, times).await?;
+
+    // 返信やスレッドへの書き込みであれば，拡散先でも対応するメッセージを探す
+    let mut context = load_mirror_context(ctx.data(), message).await?;
+    context.footer = mirror_footer(ctx.data(), message).await?;
+
+    // 送る先があるときだけ，ユーザーごとの回数を使う
+    // 入力の誤りや拡散済みで弾いたときに回数を使わないように，ここで数える
+    if (!plan.deliver.is_empty() || !plan.queue.is_empty())
+        && reply_if_user_rate_limited(ctx, guild_id, true).await?
+    {
+        return Ok(false);
+    }
+
+    // 送信先のギルドが決めたフィルタを当てはめて，送信先ごとに送る内容を決める
+    let content_filter_repository = ctx.data().content_filter_repository.clone();
+    let target_guild_ids = plan
+        .deliver
+        .iter()
+        .chain(plan.queue.iter().map(|(t, _)| t))
+        .map(|t| t.guild_id)
+        .collect();
+    let filters = content_filter_repository
+        .get_content_filters(target_guild_ids)
+        .await?;
+    let filter_content = FilterContent::from_message(message, content.clone());
+    let mut delivery = plan_filtered_delivery(&filter_content, plan.deliver, &filters);
+    // blockされる送信先は承認待ちにもしない．取り除く部分は，承認したときに取り除く
+    let mut queue = Vec::new();
+    for (time, approval_channel_id) in plan.queue {
+        let result = apply_filters(time.guild_id, &filter_content, &filters);
+        if result.content.is_none() {
+            delivery.outcomes.extend(result.outcomes);
+            continue;
+        }
+        queue.push((time, approval_channel_id));
+    }
+
+    // 送信先のギルドごとの，受け取る回数の上限を超えた送信先には送らない
+    // フィルタに止められた送信先の回数を使わないように，フィルタの後で数える
+    let target_guild_ids = delivery
+        .groups
+        .iter()
+        .flat_map(|(_, times)| times.iter())
+        .chain(queue.iter().map(|(t, _)| t))
+        .map(|t| t.guild_id)
+        .collect();
+    let rate_limit_settings = ctx
+        .data()
+        .rate_limit_repository
+        .get_rate_limit_settings(target_guild_ids)
+        .await?;
+    let limiter = &ctx.data().release_rate_limiter.guilds;
+    let now = Instant::now();
+    let mut rate_limited = Vec::new();
+    let mut groups = Vec::new();
+    for (group_content, times) in delivery.groups {
+        let (times, limited) = take_guild_tokens(limiter, times, |t| t, &rate_limit_settings, now);
+        rate_limited.extend(limited);
+        if !times.is_empty() {
+            groups.push((group_content, times));
+        }
+    }
+    delivery.groups = groups;
+    let (queue, queue_rate_limited) =
+        take_guild_tokens(limiter, queue, |(t, _)| t, &rate_limit_settings, now);
+    rate_limited.extend(queue_rate_limited);
+
+    let message_sender = ctx.data().times_message_sender.clone();
+    let mut report = send_filtered(message_sender.as_ref(), message, delivery, context).await?;
+
+    // 分割して送った場合も含めて，拡散したメッセージを配信ログに記録する
+    ctx.data()
+        .delivery_repository
+        .insert_deliveries(report.deliveries.clone())
+        .await?;
+
+    let mut queued = Vec::new();
+    for (time, approval_channel_id) in queue {
+        match queue_for_review(
+            ctx.serenity_context(),
+            ctx.data(),
+            message,
+            &content,
+            &time,
+            approval_channel_id,
+        )
+        .await
+        {
+            Ok(()) => queued.push(time.guild_id),
+            Err(e) => report.failures.push(DeliveryFailure {
+                guild_id: time.guild_id,
+                reason: format!("could not queue for approval: {}", e),
+            }),
+        }
+    }
+
+    // すべての送信先でフィルタに止められても，二重に拡散しないように記録する
+    // すべての送信先で失敗した場合は，やり直せるように記録しない
+    if !report.deliveries.is_empty() || !queued.is_empty() || !report.filtered.is_empty() {
+        ctx.data()
+            .delivery_repository
+            .mark_released(message.id.get(), user_id)
+            .await?;
+    }
+
+    // 失敗した送信先や拡散できなかった内容，設定により拡散しなかった送信先があれば伝える
+    let reply_message = [
+        delivery_report_message(&report),
+        skipped_by_policy_message(&skipped),
+        inbound_report_message(&queued, &plan.refused),
+        guild_rate_limited_message(&rate_limited),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
+    if !reply_message.is_empty() {
+        ctx.say(reply_message.join("\n")).await?;
+    }
+
+    info!("times release complete. user_id: {}", user_id);
+    Ok(true)
+}
+
+/// ユーザーごとの回数の上限に達していれば，待つ時間を返信してtrueを返す
+///
+/// takeなら1回分を使う．takeでなければ回数を使わずに調べるだけにする
+async fn reply_if_user_rate_limited(ctx: Context<'_>, guild_id: u64, take: bool) -> Result<bool> {
+    let user_id = ctx.author().id.get();
+    let rate_limit_repository = ctx.data().rate_limit_repository.clone();
+    let settings = load_rate_limit_settings(rate_limit_repository.as_ref(), guild_id).await?;
+    let users = &ctx.data().release_rate_limiter.users;
+    let now = Instant::now();
+    let result = if take {
+        users.try_acquire(user_id, settings.user_limit, now)
+    } else {
+        users.check(user_id, settings.user_limit, now)
+    };
+    let Err(retry_after) = result else {
+        return Ok(false);
+    };
+
+    info!(
+        "release rate limited. user_id: {}, retry_after: {:?}",
+        user_id, retry_after
+    );
+    ctx.send(
+        CreateReply::default()
+            .content(user_rate_limited_message(retry_after))
+            .ephemeral(true),
+    )
+    .await?;
+    Ok(true)
+}
+
+// 発信元のギルドに知らせるかを選ぶボタンを待つ時間
+const TAKEDOWN_FLAG_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[poise::command(
+    context_menu_command = "Take down mirrored post",
+    guild_only,
+    required_permissions = "MANAGE_MESSAGES"
+)]
+#[tracing::instrument(skip(ctx, message))]
+/// 拡散されたメッセージを取り下げます
+///
+/// 発信元のメッセージでは，すべての拡散先から取り下げます
+/// 拡散されてきたメッセージでは，このギルドのものだけを削除して，発信元のギルドに知らせるかを選べます
+pub async fn ut_c_takedown(ctx: Context<'_>, message: Message) -> Result<()> {
+    // 削除に時間がかかることがあるので，先に応答しておく
+    ctx.defer_ephemeral().await?;
+
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
+    let delivery_repository = ctx.data().delivery_repository.clone();
+    let by_mirror = delivery_repository
+        .get_delivery_by_mirror(message.id.get())
+        .await?;
+    let by_source = if by_mirror.is_none() {
+        delivery_repository
+            .get_deliveries_by_source(message.id.get())
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    let http = ctx.serenity_context().http.clone();
+    match locate_takedown(guild_id, message.id.get(), by_mirror, &by_source) {
+        None => {
+            ctx.say("This message is not a mirrored post, or it has no mirrored copies left.")
+                .await?;
+        }
+        Some(TakedownTarget::Origin { source_message_id }) => {
+            let report = take_down(&http, ctx.data(), source_message_id, None).await?;
+            let source =
+                MessageLocation::new(guild_id, message.channel_id.get(), source_message_id);
+            audit_by_author(
+                ctx,
+                guild_id,
+                AuditAction::Takedown,
+                takedown_audit_detail(&source, &report),
+            )
+            .await;
+            ctx.say(format!(
+                "Retracted this post from every guild.\n{}",
+                report.message()
+            ))
+            .await?;
+        }
+        Some(TakedownTarget::Mirror(delivery)) => {
+            let report = take_down(
+                &http,
+                ctx.data(),
+                delivery.source_message_id,
+                Some(guild_id),
+            )
+            .await?;
+            let source = MessageLocation::new(
+                delivery.source_guild_id,
+                delivery.source_channel_id,
+                delivery.source_message_id,
+            );
+            audit_by_author(
+                ctx,
+                guild_id,
+                AuditAction::Takedown,
+                takedown_audit_detail(&source, &report),
+            )
+            .await;
+            ask_to_flag_takedown(ctx, &delivery, &report).await?;
+        }
+    }
+    Ok(())
+}
+
+/// このギルドの拡散先のメッセージを削除した後に，発信元のギルドに知らせるかをボタンで選んでもらう
+async fn ask_to_flag_takedown(
+    ctx: Context<'_>,
+    delivery: &UtDelivery,
+    report: &TakedownReport,
+) -> Result<()> {
+    // 他の実行中のコマンドのボタンと区別するため，コマンドの実行ごとのidを付ける
+    let flag_id = format!("{}:takedown:flag", ctx.id());
+    let skip_id = format!("{}:takedown:skip", ctx.id());
+
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new(&flag_id)
+            .label("Flag for origin moderators")
+            .style(ButtonStyle::Danger),
+        CreateButton::new(&skip_id)
+            .label("Done")
+            .style(ButtonStyle::Secondary),
+    ]);
+    let handle = ctx
+        .send(
+            CreateReply::default()
+                .content(format!(
+                    "Removed the copy in this guild.\n{}\nDo you want to flag this post for the moderators of the origin guild?",
+                    report.message()
+                ))
+                .components(vec![buttons])
+                .ephemeral(true),
+        )
+        .await?;
+
+    let (filter_flag, filter_skip) = (flag_id.clone(), skip_id.clone());
+    let Some(mci) = ComponentInteractionCollector::new(ctx)
+        .author_id(ctx.author().id)
+        .channel_id(ctx.channel_id())
+        .timeout(TAKEDOWN_FLAG_TIMEOUT)
+        .filter(move |mci| mci.data.custom_id == filter_flag || mci.data.custom_id == filter_skip)
+        .await
+    else {
+        handle
+            .edit(ctx, CreateReply::default().components(vec![]))
+            .await?;
+        return Ok(());
+    };
+
+    let content = if mci.data.custom_id != flag_id {
+        "Removed the copy in this guild.".to_string()
+    } else {
+        let guild_name = guild_display_name(ctx, delivery.target_guild_id).await;
+        let http = ctx.serenity_context().http.clone();
+        match flag_to_origin(&http, ctx.data(), delivery, &guild_name, ctx.author().id).await {
+            Ok(true) => "Removed the copy in this guild and flagged it for the origin guild.".to_string(),
+            Ok(false) => "Removed the copy in this guild. The origin guild has not set a moderation channel, so it could not be flagged.".to_string(),
+            Err(e) => {
+                info!("failed to flag takedown. error: {}", e);
+                format!("Removed the copy in this guild, but could not flag it: {}", e)
+            }
+        }
+    };
+    mci.create_response(
+        ctx,
+        CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .components(vec![]),
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
+#[tracing::instrument(skip(ctx))]
+/// モデレーター向けの知らせを受け取るチャンネルを設定します
+///
+/// 他のギルドのモデレーターが，このギルドから拡散されたメッセージを取り下げたときに知らせが届きます
+/// チャンネルを指定しなければ，設定を取り消します
+pub async fn ut_c_moderation_channel(
+    ctx: Context<'_>,
+    #[description = "知らせを受け取るチャンネル"] channel: Option<GuildChannel>,
+) -> Result<()> {
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
+
+    let moderation_channel_repository = ctx.data().moderation_channel_repository.clone();
+    let saved = match &channel {
+        Some(channel) => {
+            moderation_channel_repository
+                .upsert_moderation_channel(UtModerationChannel {
+                    guild_id,
+                    channel_id: channel.id.get(),
+                })
+                .await
+        }
+        None => {
+            moderation_channel_repository
+                .delete_moderation_channel(guild_id)
+                .await
+        }
+    };
+    if let Err(e) = saved {
+        info!("failed to save moderation channel. error: {}", e);
+        ctx.say("Failed to save the channel. Please run ut_c_guild_init first.")
+            .await?;
+        return Ok(());
+    }
+    let detail = match &channel {
+        Some(channel) => format!("moderation channel: <#{}>", channel.id),
+        None => "moderation channel cleared".to_string(),
+    };
+    audit_by_author(ctx, guild_id, AuditAction::SettingChange, detail).await;
+
+    match channel {
+        Some(channel) => {
+            ctx.say(format!(
+                "Saved. Flags from other guilds will be posted in <#{}>.",
+                channel.id
+            ))
+            .await?
+        }
+        None => {
+            ctx.say("Cleared. Other guilds can no longer flag posts for this guild.")
+                .await?
+        }
+    };
+    Ok(())
+}
+
+// 監査ログのページを送るボタンを押せる時間
+const AUDIT_LOG_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// 監査ログの1ページ分を取得して，表示とボタンを作る
+async fn audit_log_page(
+    ctx: Context<'_>,
+    guild_id: u64,
+    pager: &AuditPager,
+    button_prefix: &str,
+) -> Result<(String, Vec<CreateActionRow>, Vec<UtAuditEntry>)> {
+    // 1件多く取得して，さらに古い項目があるかを調べる
+    let entries = ctx
+        .data()
+        .audit_log_repository
+        .get_audit_entries(guild_id, pager.before(), AUDIT_PAGE_SIZE + 1)
+        .await?;
+    let (entries, has_older) = split_audit_page(entries);
+
+    let mut buttons = Vec::new();
+    if pager.has_newer() {
+        buttons.push(
+            CreateButton::new(format!("{}newer", button_prefix))
+                .label("Newer")
+                .style(ButtonStyle::Secondary),
+        );
+    }
+    if has_older {
+        buttons.push(
+            CreateButton::new(format!("{}older", button_prefix))
+                .label("Older")
+                .style(ButtonStyle::Secondary),
+        );
+    }
+    let components = if buttons.is_empty() {
+        Vec::new()
+    } else {
+        vec![CreateActionRow::Buttons(buttons)]
+    };
+    Ok((audit_page_text(&entries, pager.page()), components, entries))
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
+#[tracing::instrument(skip(ctx))]
+/// このギルドの登録と設定の変更の記録を，新しい順に表示します
+///
+/// ボタンで古い記録をたどれます
+pub async fn ut_c_audit_log(ctx: Context<'_>) -> Result<()> {
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
+
+    // 他の実行中のコマンドのボタンと区別するため，コマンドの実行ごとのidを付ける
+    let button_prefix = format!("{}:", ctx.id());
+
+    let mut pager = AuditPager::default();
+    let (content, components, mut shown) =
+        audit_log_page(ctx, guild_id, &pager, &button_prefix).await?;
+    ctx.send(
+        CreateReply::default()
+            .content(content)
+            .components(components)
+            .ephemeral(true),
+    )
+    .await?;
+
+    loop {
+        let filter_prefix = button_prefix.clone();
+        let Some(mci) = ComponentInteractionCollector::new(ctx)
+            .author_id(ctx.author().id)
+            .channel_id(ctx.channel_id())
+            .timeout(AUDIT_LOG_TIMEOUT)
+            .filter(move |mci| mci.data.custom_id.starts_with(&filter_prefix))
+            .await
+        else {
+            break;
+        };
+
+        match mci.data.custom_id.strip_prefix(&button_prefix) {
+            Some("older") => pager.older(&shown),
+            Some("newer") => pager.newer(),
+            _ => continue,
+        }
+
+        let (content, components, entries) =
+            audit_log_page(ctx, guild_id, &pager, &button_prefix).await?;
+        shown = entries;
+        mci.create_response(
+            ctx,
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(content)
+                    .components(components),
+            ),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+#[poise::command(
+    slash_command,
+    guild_only,
+    ephemeral,
+    required_permissions = "MANAGE_GUILD"
+)]
+#[tracing::instrument(skip(ctx))]
+/// 登録と設定の変更の記録を流すチャンネルを設定します
+///
+/// チャンネルを指定しなければ，設定を取り消します
+pub async fn ut_c_audit_log_channel(
+    ctx: Context<'_>,
+    #[description = "記録を流すチャンネル"] channel: Option<GuildChannel>,
+) -> Result<()> {
+    let guild_id = ctx.guild_id().ok_or(GuildNotFound)?.get();
+
+    let audit_log_channel_repository = ctx.data().audit_log_channel_repository.clone();
+    let saved = match &channel {
+        Some(channel) => {
+            audit_log_channel_repository
+                .upsert_audit_log_channel(UtAuditLogChannel {
+                    guild_id,
+                    channel_id: channel.id.get(),
+                })
+                .await
+        }
+        None => {
+            audit_log_channel_repository
+                .delete_audit_log_channel(guild_id)
+                .await
+        }
+    };
+    if let Err(e) = saved {
+        info!("failed to save audit log channel. error: {}", e);
+        ctx.say("Failed to save the channel. Please run ut_c_guild_init first.")
+            .await?;
+        return Ok(());
+    }
+    // 新しいチャンネルには，この変更から流れる
+    let detail = match &channel {
+        Some(channel) => format!("audit log channel: <#{}>", channel.id),
+        None => "audit log channel cleared".to_string(),
+    };
+    audit_by_author(ctx, guild_id, AuditAction::SettingChange, detail).await;
+
+    match channel {
+        Some(channel) => {
+            ctx.say(format!(
+                "Saved. Audit entries will be posted in <#{}>.",
+                channel.id
+            ))
+            .await?
+        }
+        None => {
+            ctx.say("Cleared. Audit entries are still kept; see ut_c_audit_log.")
+                .await?
+        }
+    };
+    Ok(())
+}
+
+#[poise::command(prefix_command, hide_in_help)]
+#[tracing::instrument(skip(ctx))]
+///  スラッシュコマンドの変更を即座に反映するためのコマンド
+///
+/// 主にデバッグ用
+pub async fn register(ctx: Context<'_>, #[flag] global: bool) -> Result<()> {
+    poise::builtins::register_application_commands(ctx, global).await?;
+    Ok(())
+}
+
+/// テスト用のコマンド
+#[poise::command(prefix_command, track_edits, aliases("test"), slash_command)]
+#[tracing::instrument(skip(ctx))]
+pub async fn ut_c_test(
+    ctx: Context<'_>,
+    #[description = "message"] content: Vec<String>,
+) -> Result<()> {
+    let content = content.join("\n");
+    info!("message: {:?}", content);
+    ctx.say(content).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
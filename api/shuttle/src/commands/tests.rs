@@ -0,0 +1,67 @@
+use super::*;
+
+const BUTTON_PREFIX: &str = "12345:";
+
+#[test]
+/// 正しいidから，操作とギルドのidを取り出せることを確認する
+fn test_parse_times_list_button() {
+    assert_eq!(
+        parse_times_list_button("12345:unregister:678", BUTTON_PREFIX),
+        Some((TimesListAction::Unregister, 678))
+    );
+    assert_eq!(
+        parse_times_list_button("12345:recreate:678", BUTTON_PREFIX),
+        Some((TimesListAction::Recreate, 678))
+    );
+}
+
+#[test]
+/// 作ったボタンのidを，そのまま読み取れることを確認する
+fn test_parse_times_list_button_round_trip() {
+    for action in [TimesListAction::Unregister, TimesListAction::Recreate] {
+        let custom_id = format!("{}{}:{}", BUTTON_PREFIX, action.id(), u64::MAX);
+        assert_eq!(
+            parse_times_list_button(&custom_id, BUTTON_PREFIX),
+            Some((action, u64::MAX))
+        );
+    }
+}
+
+#[test]
+/// 他のコマンドの実行のボタンは無視することを確認する
+fn test_parse_times_list_button_wrong_prefix() {
+    assert_eq!(
+        parse_times_list_button("99999:unregister:678", BUTTON_PREFIX),
+        None
+    );
+    assert_eq!(
+        parse_times_list_button("1234:unregister:678", BUTTON_PREFIX),
+        None
+    );
+}
+
+#[test]
+/// ギルドのidが数値でなければ無視することを確認する
+fn test_parse_times_list_button_non_numeric_id() {
+    for custom_id in [
+        "12345:unregister:abc",
+        "12345:unregister:",
+        "12345:unregister:-1",
+        "12345:unregister:678:9",
+    ] {
+        assert_eq!(parse_times_list_button(custom_id, BUTTON_PREFIX), None);
+    }
+}
+
+#[test]
+/// 知らない操作とidの欠けたボタンは無視することを確認する
+fn test_parse_times_list_button_unknown_action() {
+    for custom_id in [
+        "12345:delete:678",
+        "12345:UNREGISTER:678",
+        "12345:unregister",
+        "12345:",
+    ] {
+        assert_eq!(parse_times_list_button(custom_id, BUTTON_PREFIX), None);
+    }
+}
@@ -0,0 +1,498 @@
+//! 送信先のギルドが決めたフィルタを，拡散する前に当てはめる
+//!
+//! フィルタに当てはまったときは，その送信先へ送らない(block)，当てはまった部分を取り除いて送る(redact)，
+//! そのまま送って拡散したユーザーに知らせる(warn)のいずれかにする
+//! 当てはめた結果は，DeliveryReportのfilteredで拡散したユーザーに伝える
+
+use domain::message_sender::TimesMessageSender;
+use domain::models::{
+    ContentFilterRule, DeliveryReport, FilterAction, FilterOutcome, MirrorContext, UtContentFilter,
+    UtTime,
+};
+use poise::serenity_prelude::Message;
+use regex::{Regex, RegexBuilder};
+use tracing::warn;
+
+// 本文から取り除いた部分に入れる文字列
+const REDACTED_TEXT: &str = "[redacted]";
+const REDACTED_LINK: &str = "[link removed]";
+
+// 正規表現を組み立てるときの大きさの上限．ギルドの管理者が書いたものを使うため，大きすぎるものは受け付けない
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+// `<https://...>`のように埋め込みを抑えたリンクも，括弧ごと取り除く
+const LINK_PATTERN: &str = r"<?(?i:https?://|www\.)[^\s>]+>?";
+
+/// フィルタで調べる添付ファイル
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterAttachment {
+    pub id: u64,
+    pub filename: String,
+    pub size: u64,
+    pub content_type: Option<String>,
+}
+
+/// 本文の他に文字を含む，メッセージの部分
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterPart {
+    Embed,
+    Poll,
+    Sticker,
+}
+
+/// 本文の他に，文字を調べる部分
+///
+/// 一部だけを書き換えることはできないので，当てはまったらその部分ごと拡散しない
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterExtra {
+    pub part: FilterPart,
+    pub text: String,
+}
+
+/// フィルタで調べる，拡散する内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterContent {
+    pub text: String,
+    /// 埋め込みのタイトルと説明，投票の質問と選択肢，スタンプの名前
+    pub extras: Vec<FilterExtra>,
+    pub attachments: Vec<FilterAttachment>,
+    /// 埋め込みも拡散するか．リンクを取り除いたときは，リンクの埋め込みも拡散しない
+    pub keep_embeds: bool,
+    pub keep_poll: bool,
+    pub keep_stickers: bool,
+}
+
+impl FilterContent {
+    pub fn from_message(message: &Message, text: String) -> Self {
+        let attachments = message
+            .attachments
+            .iter()
+            .map(|a| FilterAttachment {
+                id: a.id.get(),
+                filename: a.filename.clone(),
+                size: a.size as u64,
+                content_type: a.content_type.clone(),
+            })
+            .collect();
+
+        let mut extras = Vec::new();
+        for embed in &message.embeds {
+            for text in [&embed.title, &embed.description].into_iter().flatten() {
+                extras.push(FilterExtra {
+                    part: FilterPart::Embed,
+                    text: text.clone(),
+                });
+            }
+        }
+        if let Some(poll) = &message.poll {
+            let media =
+                std::iter::once(&poll.question).chain(poll.answers.iter().map(|a| &a.poll_media));
+            for text in media.filter_map(|m| m.text.as_ref()) {
+                extras.push(FilterExtra {
+                    part: FilterPart::Poll,
+                    text: text.clone(),
+                });
+            }
+        }
+        for sticker in &message.sticker_items {
+            extras.push(FilterExtra {
+                part: FilterPart::Sticker,
+                text: sticker.name.clone(),
+            });
+        }
+
+        Self {
+            text,
+            extras,
+            attachments,
+            keep_embeds: true,
+            keep_poll: true,
+            keep_stickers: true,
+        }
+    }
+
+    /// 本文と，本文の他に文字を含む部分のどれかが正規表現に一致するか
+    fn is_match(&self, regex: &Regex) -> bool {
+        regex.is_match(&self.text) || self.extras.iter().any(|e| regex.is_match(&e.text))
+    }
+
+    /// 正規表現に一致した部分を，本文からは取り除き，本文の他の部分はまるごと拡散しない
+    fn redact_matches(&mut self, regex: &Regex, replacement: &str) {
+        self.text = regex.replace_all(&self.text, replacement).into_owned();
+
+        let dropped = self
+            .extras
+            .iter()
+            .filter(|e| regex.is_match(&e.text))
+            .map(|e| e.part)
+            .collect::<Vec<_>>();
+        for part in dropped {
+            match part {
+                FilterPart::Embed => self.keep_embeds = false,
+                FilterPart::Poll => self.keep_poll = false,
+                FilterPart::Sticker => self.keep_stickers = false,
+            }
+            self.extras.retain(|e| e.part != part);
+        }
+    }
+
+    /// フィルタを当てはめた内容に合わせて，発信元メッセージの添付ファイル，埋め込み，投票，スタンプを減らす
+    pub fn apply_to_message(&self, message: &Message) -> Message {
+        let mut message = message.clone();
+        message
+            .attachments
+            .retain(|a| self.attachments.iter().any(|f| f.id == a.id.get()));
+        if !self.keep_embeds {
+            message.embeds.clear();
+        }
+        if !self.keep_poll {
+            message.poll = None;
+        }
+        if !self.keep_stickers {
+            message.sticker_items.clear();
+        }
+        message
+    }
+}
+
+/// 拡散する内容のフィルタ
+///
+/// フィルタの種類を増やすときは，これを実装してbuild_filterで作る
+pub trait ContentFilter {
+    /// 内容がフィルタに当てはまるか
+    fn matches(&self, content: &FilterContent) -> bool;
+    /// 当てはまった部分を取り除く
+    fn redact(&self, content: &mut FilterContent);
+}
+
+/// 本文や埋め込み，投票，スタンプの名前が正規表現に一致するか
+struct PatternFilter {
+    regex: Regex,
+}
+
+impl ContentFilter for PatternFilter {
+    fn matches(&self, content: &FilterContent) -> bool {
+        content.is_match(&self.regex)
+    }
+
+    fn redact(&self, content: &mut FilterContent) {
+        content.redact_matches(&self.regex, REDACTED_TEXT);
+    }
+}
+
+/// 本文や埋め込み，投票，スタンプの名前にリンクを含むか
+struct LinkFilter {
+    regex: Regex,
+}
+
+impl ContentFilter for LinkFilter {
+    fn matches(&self, content: &FilterContent) -> bool {
+        content.is_match(&self.regex)
+    }
+
+    fn redact(&self, content: &mut FilterContent) {
+        content.redact_matches(&self.regex, REDACTED_LINK);
+        content.keep_embeds = false;
+    }
+}
+
+/// 添付ファイルの大きさが上限を超えるか
+struct AttachmentSizeFilter {
+    max_size: u64,
+}
+
+impl ContentFilter for AttachmentSizeFilter {
+    fn matches(&self, content: &FilterContent) -> bool {
+        content.attachments.iter().any(|a| a.size > self.max_size)
+    }
+
+    fn redact(&self, content: &mut FilterContent) {
+        content.attachments.retain(|a| a.size <= self.max_size);
+    }
+}
+
+/// 添付ファイルのMIMEタイプが許可したものでないか
+struct MimeTypeFilter {
+    allowed: Vec<String>,
+}
+
+impl MimeTypeFilter {
+    fn is_allowed(&self, attachment: &FilterAttachment) -> bool {
+        // MIMEタイプがわからないものは許可しない
+        let Some(content_type) = &attachment.content_type else {
+            return false;
+        };
+        // `text/plain; charset=utf-8`のような引数は見ない
+        let content_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        self.allowed
+            .iter()
+            .any(|allowed| match allowed.strip_suffix("/*") {
+                Some(top_level) => content_type
+                    .split_once('/')
+                    .is_some_and(|(t, _)| t == top_level),
+                None => *allowed == content_type,
+            })
+    }
+}
+
+impl ContentFilter for MimeTypeFilter {
+    fn matches(&self, content: &FilterContent) -> bool {
+        content.attachments.iter().any(|a| !self.is_allowed(a))
+    }
+
+    fn redact(&self, content: &mut FilterContent) {
+        content.attachments.retain(|a| self.is_allowed(a));
+    }
+}
+
+/// 語のフィルタの正規表現
+///
+/// 英数字で始まる(終わる)語は，前(後ろ)が語の区切りのときだけ当てはまる．`ass`は`class`に当てはまらない
+/// 日本語のように区切りのない文字は，語の途中にあっても当てはまる
+fn word_pattern(word: &str) -> String {
+    let is_word_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    // ASCIIの語の区切りにするため，`\b`をUnicodeの扱いにしない
+    let boundary = |c: Option<char>| {
+        if c.is_some_and(is_word_char) {
+            r"(?-u:\b)"
+        } else {
+            ""
+        }
+    };
+    format!(
+        "(?i){}{}{}",
+        boundary(word.chars().next()),
+        regex::escape(word),
+        boundary(word.chars().last())
+    )
+}
+
+fn build_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+}
+
+/// 保存したフィルタの条件から，フィルタを作る
+///
+/// 正規表現として正しくなければエラーを返す
+pub fn build_filter(
+    rule: &ContentFilterRule,
+) -> Result<Box<dyn ContentFilter + Send + Sync>, regex::Error> {
+    let filter: Box<dyn ContentFilter + Send + Sync> = match rule {
+        ContentFilterRule::Regex(pattern) => Box::new(PatternFilter {
+            regex: build_regex(pattern)?,
+        }),
+        ContentFilterRule::Word(word) => Box::new(PatternFilter {
+            regex: build_regex(&word_pattern(word))?,
+        }),
+        ContentFilterRule::MaxAttachmentSize(max_size) => Box::new(AttachmentSizeFilter {
+            max_size: *max_size,
+        }),
+        ContentFilterRule::AllowedMimeTypes(allowed) => Box::new(MimeTypeFilter {
+            allowed: allowed.iter().map(|t| t.to_ascii_lowercase()).collect(),
+        }),
+        ContentFilterRule::NoLinks => Box::new(LinkFilter {
+            regex: build_regex(LINK_PATTERN)?,
+        }),
+    };
+    Ok(filter)
+}
+
+/// 拡散したユーザーに見せる，フィルタの説明
+///
+/// 語や正規表現はそのギルドの規則に関わるので，中身は見せない
+pub fn describe_filter(filter: &UtContentFilter) -> String {
+    match &filter.rule {
+        ContentFilterRule::Regex(_) => format!("pattern filter #{}", filter.filter_id),
+        ContentFilterRule::Word(_) => format!("word filter #{}", filter.filter_id),
+        ContentFilterRule::MaxAttachmentSize(max_size) => {
+            format!("attachments over {}", attachment_size_text(*max_size))
+        }
+        ContentFilterRule::AllowedMimeTypes(allowed) => {
+            format!("attachment types other than {}", allowed.join(", "))
+        }
+        ContentFilterRule::NoLinks => "links".to_string(),
+    }
+}
+
+/// 1つの送信先にフィルタを当てはめた結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterResult {
+    /// 送信する内容．blockされたらNone
+    pub content: Option<FilterContent>,
+    pub outcomes: Vec<FilterOutcome>,
+}
+
+/// 送信先のギルドのフィルタを当てはめる
+pub fn apply_filters(
+    guild_id: u64,
+    content: &FilterContent,
+    filters: &[UtContentFilter],
+) -> FilterResult {
+    let mut filters = filters
+        .iter()
+        .filter(|f| f.guild_id == guild_id)
+        .collect::<Vec<_>>();
+    // 取り除いた後の内容ではblockのフィルタに当てはまらなくなることがあるので，blockから順に調べる
+    filters.sort_by_key(|f| f.action);
+
+    let mut content = content.clone();
+    let mut outcomes = Vec::new();
+    for f in filters {
+        let filter = match build_filter(&f.rule) {
+            Ok(filter) => filter,
+            Err(e) => {
+                warn!(
+                    "invalid content filter. filter_id: {}, error: {}",
+                    f.filter_id, e
+                );
+                continue;
+            }
+        };
+        if !filter.matches(&content) {
+            continue;
+        }
+
+        outcomes.push(FilterOutcome {
+            guild_id,
+            filter: describe_filter(f),
+            action: f.action,
+        });
+        match f.action {
+            FilterAction::Block => {
+                return FilterResult {
+                    content: None,
+                    outcomes,
+                }
+            }
+            FilterAction::Redact => filter.redact(&mut content),
+            FilterAction::Warn => {}
+        }
+    }
+
+    FilterResult {
+        content: Some(content),
+        outcomes,
+    }
+}
+
+/// 送信先ごとにフィルタを当てはめて，同じ内容を送る送信先をまとめたもの
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilteredDelivery {
+    pub groups: Vec<(FilterContent, Vec<UtTime>)>,
+    pub outcomes: Vec<FilterOutcome>,
+}
+
+/// 各送信先のフィルタを当てはめる
+/// フィルタのない送信先には，元の内容をそのまま送る
+pub fn plan_filtered_delivery(
+    content: &FilterContent,
+    times: Vec<UtTime>,
+    filters: &[UtContentFilter],
+) -> FilteredDelivery {
+    let mut delivery = FilteredDelivery::default();
+    for time in times {
+        let result = apply_filters(time.guild_id, content, filters);
+        delivery.outcomes.extend(result.outcomes);
+        let Some(content) = result.content else {
+            continue;
+        };
+        match delivery.groups.iter_mut().find(|(c, _)| *c == content) {
+            Some((_, times)) => times.push(time),
+            None => delivery.groups.push((content, vec![time])),
+        }
+    }
+    delivery
+}
+
+/// フィルタを当てはめた内容ごとに送信して，結果を1つにまとめる
+pub async fn send_filtered<S>(
+    sender: &S,
+    message: &Message,
+    delivery: FilteredDelivery,
+    context: MirrorContext,
+) -> Result<DeliveryReport, S::Error>
+where
+    S: TimesMessageSender<Message = Message>,
+{
+    let mut report = DeliveryReport {
+        filtered: delivery.outcomes,
+        ..Default::default()
+    };
+    for (content, times) in delivery.groups {
+        let filtered_message = content.apply_to_message(message);
+        let group_report = sender
+            .send_all(&filtered_message, content.text, times, context.clone())
+            .await?;
+        report.deliveries.extend(group_report.deliveries);
+        report.failures.extend(group_report.failures);
+        for u in group_report.unmirrored {
+            if !report.unmirrored.contains(&u) {
+                report.unmirrored.push(u);
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// `8MB`や`500KB`，単位のない数値(バイト)を読む
+pub fn parse_attachment_size(text: &str) -> Option<u64> {
+    let text = text.trim().to_ascii_uppercase();
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => text.split_at(index),
+        None => (text.as_str(), ""),
+    };
+    let multiplier = match unit.trim() {
+        "" | "B" => 1,
+        "KB" => 1024,
+        "MB" => 1024 * 1024,
+        "GB" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+fn attachment_size_text(size: u64) -> String {
+    const UNITS: [(&str, u64); 3] = [
+        ("GB", 1024 * 1024 * 1024),
+        ("MB", 1024 * 1024),
+        ("KB", 1024),
+    ];
+    for (unit, bytes) in UNITS {
+        if size >= bytes && size.is_multiple_of(bytes) {
+            return format!("{} {}", size / bytes, unit);
+        }
+    }
+    format!("{} bytes", size)
+}
+
+/// カンマでつないだMIMEタイプを読む
+/// `image/png`か`image/*`の形でなければNoneを返す
+pub fn parse_mime_types(text: &str) -> Option<Vec<String>> {
+    let types = text
+        .split(',')
+        .map(|t| t.trim().to_ascii_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>();
+    let valid = types.iter().all(|t| {
+        t.split_once('/').is_some_and(|(top_level, sub_type)| {
+            !top_level.is_empty()
+                && !sub_type.is_empty()
+                && !top_level.contains('*')
+                && (sub_type == "*" || !sub_type.contains('*'))
+        })
+    });
+    if types.is_empty() || !valid {
+        return None;
+    }
+    Some(types)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,543 @@
+use std::sync::Mutex;
+
+use domain::models::{DeliveryFailure, UnmirroredContent};
+use poise::serenity_prelude::Attachment;
+use serde_json::json;
+
+use super::*;
+use crate::test_utils::time;
+
+const TARGET: u64 = 2;
+const OTHER: u64 = 3;
+
+fn filter(filter_id: u64, rule: ContentFilterRule, action: FilterAction) -> UtContentFilter {
+    UtContentFilter {
+        filter_id,
+        guild_id: TARGET,
+        rule,
+        action,
+    }
+}
+
+fn attachment(id: u64, size: u64, content_type: Option<&str>) -> FilterAttachment {
+    FilterAttachment {
+        id,
+        filename: format!("file{}", id),
+        size,
+        content_type: content_type.map(|t| t.to_string()),
+    }
+}
+
+fn content(text: &str, attachments: Vec<FilterAttachment>) -> FilterContent {
+    FilterContent {
+        text: text.to_string(),
+        extras: Vec::new(),
+        attachments,
+        keep_embeds: true,
+        keep_poll: true,
+        keep_stickers: true,
+    }
+}
+
+fn extra(part: FilterPart, text: &str) -> FilterExtra {
+    FilterExtra {
+        part,
+        text: text.to_string(),
+    }
+}
+
+#[test]
+/// フィルタがなければそのまま拡散する
+fn test_no_filters_keeps_content() {
+    let original = content("hello https://example.com", vec![attachment(1, 10, None)]);
+    let result = apply_filters(TARGET, &original, &[]);
+    assert_eq!(result.content, Some(original));
+    assert!(result.outcomes.is_empty());
+}
+
+#[test]
+/// 他のギルドのフィルタは当てはめない
+fn test_filters_of_other_guilds_are_ignored() {
+    let filters = [UtContentFilter {
+        guild_id: OTHER,
+        ..filter(1, ContentFilterRule::NoLinks, FilterAction::Block)
+    }];
+    let original = content("https://example.com", Vec::new());
+    let result = apply_filters(TARGET, &original, &filters);
+    assert_eq!(result.content, Some(original));
+}
+
+#[test]
+/// 語のフィルタは大文字と小文字を区別せずに取り除く
+fn test_word_filter_is_case_insensitive_and_redacts() {
+    let filters = [filter(
+        1,
+        ContentFilterRule::Word("Secret".to_string()),
+        FilterAction::Redact,
+    )];
+    let result = apply_filters(
+        TARGET,
+        &content("my SECRET and secret", Vec::new()),
+        &filters,
+    );
+    assert_eq!(result.content.unwrap().text, "my [redacted] and [redacted]");
+    assert_eq!(
+        result.outcomes,
+        vec![FilterOutcome {
+            guild_id: TARGET,
+            filter: "word filter #1".to_string(),
+            action: FilterAction::Redact,
+        }]
+    );
+}
+
+#[test]
+/// 語に含まれる正規表現の記号は，そのままの文字として扱う
+fn test_word_filter_escapes_regex() {
+    let filters = [filter(
+        1,
+        ContentFilterRule::Word("a.b".to_string()),
+        FilterAction::Block,
+    )];
+    assert!(apply_filters(TARGET, &content("axb", Vec::new()), &filters)
+        .content
+        .is_some());
+    assert!(apply_filters(TARGET, &content("a.b", Vec::new()), &filters)
+        .content
+        .is_none());
+}
+
+#[test]
+/// 正規表現に一致したらblockする
+fn test_regex_filter_blocks() {
+    let filters = [filter(
+        7,
+        ContentFilterRule::Regex(r"spam\d+".to_string()),
+        FilterAction::Block,
+    )];
+    let result = apply_filters(TARGET, &content("buy spam42 now", Vec::new()), &filters);
+    assert_eq!(result.content, None);
+    assert_eq!(result.outcomes[0].filter, "pattern filter #7");
+    assert_eq!(result.outcomes[0].action, FilterAction::Block);
+}
+
+#[test]
+/// warnはそのまま拡散して，当てはまったことを記録する
+fn test_warn_keeps_content() {
+    let filters = [filter(1, ContentFilterRule::NoLinks, FilterAction::Warn)];
+    let original = content("see https://example.com", Vec::new());
+    let result = apply_filters(TARGET, &original, &filters);
+    assert_eq!(result.content, Some(original));
+    assert_eq!(result.outcomes[0].action, FilterAction::Warn);
+}
+
+#[test]
+/// リンクを取り除いたときは埋め込みも拡散しない
+fn test_no_links_redacts_links_and_embeds() {
+    let filters = [filter(1, ContentFilterRule::NoLinks, FilterAction::Redact)];
+    let result = apply_filters(
+        TARGET,
+        &content(
+            "see https://example.com/a?b=c and <http://example.org> or www.example.net.",
+            Vec::new(),
+        ),
+        &filters,
+    );
+    let filtered = result.content.unwrap();
+    assert_eq!(
+        filtered.text,
+        "see [link removed] and [link removed] or [link removed]"
+    );
+    assert!(!filtered.keep_embeds);
+}
+
+#[test]
+/// 上限を超える添付ファイルを取り除く
+fn test_attachment_size_redacts_large_files() {
+    let filters = [filter(
+        1,
+        ContentFilterRule::MaxAttachmentSize(100),
+        FilterAction::Redact,
+    )];
+    let result = apply_filters(
+        TARGET,
+        &content(
+            "files",
+            vec![attachment(1, 100, None), attachment(2, 101, None)],
+        ),
+        &filters,
+    );
+    assert_eq!(
+        result.content.unwrap().attachments,
+        vec![attachment(1, 100, None)]
+    );
+}
+
+#[test]
+/// MIMEタイプの`*`と引数を扱える
+fn test_mime_types_allow_wildcards_and_parameters() {
+    let filters = [filter(
+        1,
+        ContentFilterRule::AllowedMimeTypes(vec!["image/*".to_string(), "text/plain".to_string()]),
+        FilterAction::Redact,
+    )];
+    let result = apply_filters(
+        TARGET,
+        &content(
+            "files",
+            vec![
+                attachment(1, 1, Some("image/png")),
+                attachment(2, 1, Some("text/plain; charset=utf-8")),
+                attachment(3, 1, Some("application/zip")),
+                attachment(4, 1, None),
+                attachment(5, 1, Some("imagex/png")),
+            ],
+        ),
+        &filters,
+    );
+    let ids = result
+        .content
+        .unwrap()
+        .attachments
+        .iter()
+        .map(|a| a.id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![1, 2]);
+}
+
+#[test]
+/// 取り除く前にblockのフィルタを調べる
+fn test_block_is_checked_before_redact() {
+    // 先に取り除くと，blockのフィルタに当てはまらなくなる
+    let filters = [
+        filter(
+            1,
+            ContentFilterRule::Word("bad".to_string()),
+            FilterAction::Redact,
+        ),
+        filter(
+            2,
+            ContentFilterRule::Regex("bad".to_string()),
+            FilterAction::Block,
+        ),
+    ];
+    let result = apply_filters(TARGET, &content("bad", Vec::new()), &filters);
+    assert_eq!(result.content, None);
+    assert_eq!(result.outcomes.len(), 1);
+    assert_eq!(result.outcomes[0].action, FilterAction::Block);
+}
+
+#[test]
+/// 正しくない正規表現のフィルタは当てはめない
+fn test_invalid_regex_is_skipped() {
+    let filters = [filter(
+        1,
+        ContentFilterRule::Regex("(".to_string()),
+        FilterAction::Block,
+    )];
+    let original = content("(", Vec::new());
+    assert_eq!(
+        apply_filters(TARGET, &original, &filters).content,
+        Some(original)
+    );
+}
+
+#[test]
+/// 同じ内容になる送信先をまとめる
+fn test_plan_groups_targets_by_content() {
+    let filters = [
+        filter(1, ContentFilterRule::NoLinks, FilterAction::Redact),
+        UtContentFilter {
+            guild_id: 5,
+            ..filter(2, ContentFilterRule::NoLinks, FilterAction::Block)
+        },
+    ];
+    let original = content("https://example.com", Vec::new());
+    let delivery = plan_filtered_delivery(
+        &original,
+        vec![time(TARGET), time(OTHER), time(4), time(5)],
+        &filters,
+    );
+
+    assert_eq!(delivery.groups.len(), 2);
+    assert_eq!(delivery.groups[0].0.text, "[link removed]");
+    assert_eq!(delivery.groups[0].1, vec![time(TARGET)]);
+    assert_eq!(delivery.groups[1].0, original);
+    assert_eq!(delivery.groups[1].1, vec![time(OTHER), time(4)]);
+    assert_eq!(
+        delivery
+            .outcomes
+            .iter()
+            .map(|o| (o.guild_id, o.action))
+            .collect::<Vec<_>>(),
+        vec![(TARGET, FilterAction::Redact), (5, FilterAction::Block)]
+    );
+}
+
+#[test]
+/// 英数字の語は，語の区切りにあるときだけ当てはまる
+fn test_word_filter_matches_on_word_boundaries() {
+    let filters = [filter(
+        1,
+        ContentFilterRule::Word("ass".to_string()),
+        FilterAction::Block,
+    )];
+    assert!(
+        apply_filters(TARGET, &content("first class", Vec::new()), &filters)
+            .content
+            .is_some()
+    );
+    assert!(
+        apply_filters(TARGET, &content("you ASS!", Vec::new()), &filters)
+            .content
+            .is_none()
+    );
+    // 日本語の文字は語の区切りとみなす
+    assert!(
+        apply_filters(TARGET, &content("これはassです", Vec::new()), &filters)
+            .content
+            .is_none()
+    );
+}
+
+#[test]
+/// 日本語の語は，語の途中にあっても当てはまる
+fn test_word_filter_matches_japanese_as_substring() {
+    let filters = [filter(
+        1,
+        ContentFilterRule::Word("秘密".to_string()),
+        FilterAction::Redact,
+    )];
+    let result = apply_filters(TARGET, &content("これは秘密です", Vec::new()), &filters);
+    assert_eq!(result.content.unwrap().text, "これは[redacted]です");
+}
+
+#[test]
+/// 埋め込み，投票，スタンプの名前もフィルタで調べ，当てはまった部分ごと拡散しない
+fn test_pattern_filter_redacts_extras() {
+    let filters = [filter(
+        1,
+        ContentFilterRule::Word("secret".to_string()),
+        FilterAction::Redact,
+    )];
+    let original = FilterContent {
+        extras: vec![
+            extra(FilterPart::Embed, "a secret title"),
+            extra(FilterPart::Poll, "question"),
+            extra(FilterPart::Poll, "secret answer"),
+            extra(FilterPart::Sticker, "cat"),
+        ],
+        ..content("hello", Vec::new())
+    };
+    let result = apply_filters(TARGET, &original, &filters);
+
+    let filtered = result.content.unwrap();
+    assert_eq!(filtered.text, "hello");
+    assert!(!filtered.keep_embeds);
+    assert!(!filtered.keep_poll);
+    assert!(filtered.keep_stickers);
+    assert_eq!(filtered.extras, vec![extra(FilterPart::Sticker, "cat")]);
+    assert_eq!(result.outcomes.len(), 1);
+}
+
+#[test]
+/// 本文の他の部分に当てはまったときも，blockする
+fn test_pattern_filter_blocks_on_extras() {
+    let filters = [filter(
+        1,
+        ContentFilterRule::Regex("forbidden".to_string()),
+        FilterAction::Block,
+    )];
+    let original = FilterContent {
+        extras: vec![extra(FilterPart::Sticker, "forbidden sticker")],
+        ..content("hello", Vec::new())
+    };
+    assert!(apply_filters(TARGET, &original, &filters).content.is_none());
+}
+
+fn message_attachment(id: u64) -> Attachment {
+    serde_json::from_value(json!({
+        "id": id.to_string(),
+        "filename": format!("file{}", id),
+        "size": 10,
+        "url": "https://cdn.discordapp.com/file",
+        "proxy_url": "https://media.discordapp.net/file",
+    }))
+    .unwrap()
+}
+
+#[test]
+/// 取り除いた添付ファイルは，発信元メッセージからも取り除く
+fn test_apply_to_message_drops_filtered_attachments() {
+    let mut message = Message::default();
+    message.attachments = vec![message_attachment(1), message_attachment(2)];
+
+    let filtered = FilterContent {
+        attachments: vec![attachment(2, 10, None)],
+        ..content("", Vec::new())
+    }
+    .apply_to_message(&message);
+    let ids = filtered
+        .attachments
+        .iter()
+        .map(|a| a.id.get())
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![2]);
+}
+
+#[test]
+/// 取り除いた投票とスタンプは，発信元メッセージからも取り除く
+fn test_apply_to_message_drops_poll_and_stickers() {
+    let mut message: Message = serde_json::from_value(json!({
+        "id": "1",
+        "channel_id": "2",
+        "author": {"id": "3", "username": "user", "discriminator": "0000", "avatar": null},
+        "content": "",
+        "timestamp": "2024-01-01T00:00:00Z",
+        "edited_timestamp": null,
+        "tts": false,
+        "mention_everyone": false,
+        "mentions": [],
+        "mention_roles": [],
+        "attachments": [],
+        "embeds": [],
+        "pinned": false,
+        "type": 0,
+        "sticker_items": [{"id": "4", "name": "cat", "format_type": 1}],
+        "poll": {
+            "question": {"text": "question"},
+            "answers": [{"answer_id": 1, "poll_media": {"text": "answer"}}],
+            "expiry": null,
+            "allow_multiselect": false,
+            "layout_type": 1
+        }
+    }))
+    .unwrap();
+    let extras = FilterContent::from_message(&message, String::new()).extras;
+    assert_eq!(
+        extras,
+        vec![
+            extra(FilterPart::Poll, "question"),
+            extra(FilterPart::Poll, "answer"),
+            extra(FilterPart::Sticker, "cat"),
+        ]
+    );
+
+    message = FilterContent {
+        keep_poll: false,
+        keep_stickers: false,
+        ..content("", Vec::new())
+    }
+    .apply_to_message(&message);
+    assert!(message.poll.is_none());
+    assert!(message.sticker_items.is_empty());
+}
+
+/// 送信した内容を記録する
+#[derive(Default)]
+struct RecordingSender {
+    sent: Mutex<Vec<(String, Vec<u64>)>>,
+}
+
+impl TimesMessageSender for RecordingSender {
+    type Error = std::convert::Infallible;
+    type Message = Message;
+
+    async fn send_all(
+        &self,
+        _message: &Message,
+        text: String,
+        times: Vec<UtTime>,
+        _context: MirrorContext,
+    ) -> Result<DeliveryReport, Self::Error> {
+        let guild_ids = times.iter().map(|t| t.guild_id).collect::<Vec<_>>();
+        self.sent.lock().unwrap().push((text, guild_ids.clone()));
+        Ok(DeliveryReport {
+            failures: guild_ids
+                .into_iter()
+                .map(|guild_id| DeliveryFailure {
+                    guild_id,
+                    reason: "failed".to_string(),
+                })
+                .collect(),
+            unmirrored: vec![UnmirroredContent::Sticker("sticker".to_string())],
+            ..Default::default()
+        })
+    }
+}
+
+#[tokio::test]
+/// まとめた送信先ごとに送り，結果を1つにまとめる
+async fn test_send_filtered_sends_each_group_and_merges_reports() {
+    let filters = [filter(1, ContentFilterRule::NoLinks, FilterAction::Redact)];
+    let delivery = plan_filtered_delivery(
+        &content("https://example.com", Vec::new()),
+        vec![time(TARGET), time(OTHER)],
+        &filters,
+    );
+    let outcomes = delivery.outcomes.clone();
+
+    let sender = RecordingSender::default();
+    let report = send_filtered(
+        &sender,
+        &Message::default(),
+        delivery,
+        MirrorContext::default(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        *sender.sent.lock().unwrap(),
+        vec![
+            ("[link removed]".to_string(), vec![TARGET]),
+            ("https://example.com".to_string(), vec![OTHER]),
+        ]
+    );
+    assert_eq!(report.failures.len(), 2);
+    // 発信元メッセージについてのものなので，まとめるときに重ねない
+    assert_eq!(report.unmirrored.len(), 1);
+    assert_eq!(report.filtered, outcomes);
+}
+
+#[test]
+/// 添付ファイルの大きさを読み取る
+fn test_parses_attachment_size() {
+    assert_eq!(parse_attachment_size("1024"), Some(1024));
+    assert_eq!(parse_attachment_size("8MB"), Some(8 * 1024 * 1024));
+    assert_eq!(parse_attachment_size("500 kb"), Some(500 * 1024));
+    assert_eq!(parse_attachment_size("1GB"), Some(1024 * 1024 * 1024));
+    assert_eq!(parse_attachment_size("MB"), None);
+    assert_eq!(parse_attachment_size("8TB"), None);
+    assert_eq!(parse_attachment_size("-1"), None);
+}
+
+#[test]
+/// 添付ファイルの大きさを単位をつけて表す
+fn test_describes_attachment_size() {
+    let f = filter(
+        1,
+        ContentFilterRule::MaxAttachmentSize(8 * 1024 * 1024),
+        FilterAction::Block,
+    );
+    assert_eq!(describe_filter(&f), "attachments over 8 MB");
+    let f = filter(
+        1,
+        ContentFilterRule::MaxAttachmentSize(1000),
+        FilterAction::Block,
+    );
+    assert_eq!(describe_filter(&f), "attachments over 1000 bytes");
+}
+
+#[test]
+/// カンマでつないだMIMEタイプを読み取る
+fn test_parses_mime_types() {
+    assert_eq!(
+        parse_mime_types("image/*, Text/Plain"),
+        Some(vec!["image/*".to_string(), "text/plain".to_string()])
+    );
+    assert_eq!(parse_mime_types(""), None);
+    assert_eq!(parse_mime_types("image"), None);
+    assert_eq!(parse_mime_types("*/*"), None);
+    assert_eq!(parse_mime_types("image/p*g"), None);
+}
@@ -0,0 +1,274 @@
+//! ut_c_exportで渡す，ユーザーについて保存しているデータ
+//!
+//! 形式はREADMEの「データのエクスポート」に書いてある
+//! 項目を変えたときは，EXPORT_FORMAT_VERSIONを上げてREADMEも直す
+
+use domain::models::{
+    AuditAction, TimeRemovalReason, UtAuditEntry, UtDelivery, UtPendingMirror, UtRelayedReply,
+    UtTime, UtTimeRemoval, UtUserFooterSetting,
+};
+use serde::Serialize;
+
+/// 形式の名前．受け取った側が何のファイルかわかるように入れる
+pub const EXPORT_FORMAT: &str = "ubiquitimes-cardiac-export";
+/// 形式のバージョン
+pub const EXPORT_FORMAT_VERSION: u32 = 4;
+/// 添付するファイルの名前
+pub const EXPORT_FILE_NAME: &str = "ubiquitimes_cardiac_export.json";
+
+/// idはJavaScriptなどで精度が落ちないように，文字列にする
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct UserExport {
+    pub format: &'static str,
+    pub format_version: u32,
+    /// RFC 3339
+    pub exported_at: String,
+    pub user_id: String,
+    pub times: Vec<ExportedTime>,
+    pub deliveries: Vec<ExportedDelivery>,
+    /// 拡散済みとして記録した発信元のメッセージ
+    pub released_message_ids: Vec<String>,
+    pub time_removals: Vec<ExportedTimeRemoval>,
+    pub pending_mirrors: Vec<ExportedPendingMirror>,
+    pub relayed_replies: Vec<ExportedRelayedReply>,
+    /// ユーザーが操作した監査ログの項目
+    pub audit_entries: Vec<ExportedAuditEntry>,
+    pub settings: ExportedSettings,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ExportedTime {
+    pub guild_id: String,
+    /// 名前がわからないギルドはguild_id
+    pub guild_name: String,
+    pub channel_id: String,
+    pub user_name: String,
+    /// トークンは伏せる
+    pub webhook_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ExportedDelivery {
+    pub source_guild_id: String,
+    pub source_channel_id: String,
+    pub source_message_id: String,
+    pub target_guild_id: String,
+    pub target_channel_id: String,
+    pub mirror_message_id: String,
+    pub part: u32,
+}
+
+/// botがTimesの登録を削除した記録
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ExportedTimeRemoval {
+    pub guild_id: String,
+    pub channel_id: String,
+    /// banned または left
+    pub reason: &'static str,
+    pub removed_at: String,
+}
+
+/// 承認待ちのメッセージ
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ExportedPendingMirror {
+    pub source_guild_id: String,
+    pub source_channel_id: String,
+    pub source_message_id: String,
+    pub target_guild_id: String,
+    pub content: String,
+}
+
+/// 発信元のTimesへ届けた返信．ユーザーが返信したものと，ユーザーのTimesへ届いたもの
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ExportedRelayedReply {
+    /// 返信したメッセージと返信したユーザー．他のユーザーの返信ではnull
+    pub reply_message_id: Option<String>,
+    pub author_id: Option<String>,
+    /// 届けた先のTimesの持ち主
+    pub user_id: String,
+    pub guild_id: String,
+    pub channel_id: String,
+    pub thread_id: Option<String>,
+    pub relayed_message_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ExportedAuditEntry {
+    pub guild_id: String,
+    pub action: &'static str,
+    pub detail: String,
+    pub created_at: String,
+}
+
+/// ユーザーごとの設定
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct ExportedSettings {
+    /// 拡散したメッセージに発信元の案内を付けるか．決めていなければnull
+    pub mirror_footer: Option<bool>,
+}
+
+impl From<UtDelivery> for ExportedDelivery {
+    fn from(d: UtDelivery) -> Self {
+        Self {
+            source_guild_id: d.source_guild_id.to_string(),
+            source_channel_id: d.source_channel_id.to_string(),
+            source_message_id: d.source_message_id.to_string(),
+            target_guild_id: d.target_guild_id.to_string(),
+            target_channel_id: d.target_channel_id.to_string(),
+            mirror_message_id: d.mirror_message_id.to_string(),
+            part: d.part,
+        }
+    }
+}
+
+impl From<UtTimeRemoval> for ExportedTimeRemoval {
+    fn from(r: UtTimeRemoval) -> Self {
+        Self {
+            guild_id: r.guild_id.to_string(),
+            channel_id: r.channel_id.to_string(),
+            reason: match r.reason {
+                TimeRemovalReason::Banned => "banned",
+                TimeRemovalReason::Left => "left",
+            },
+            removed_at: r.removed_at,
+        }
+    }
+}
+
+impl From<UtPendingMirror> for ExportedPendingMirror {
+    fn from(p: UtPendingMirror) -> Self {
+        Self {
+            source_guild_id: p.source_guild_id.to_string(),
+            source_channel_id: p.source_channel_id.to_string(),
+            source_message_id: p.source_message_id.to_string(),
+            target_guild_id: p.target_guild_id.to_string(),
+            content: p.content,
+        }
+    }
+}
+
+impl ExportedRelayedReply {
+    /// 他のユーザーがユーザーのTimesへ返信したものは，そのユーザーのidと返信したメッセージを含めない
+    fn new(r: UtRelayedReply, user_id: u64) -> Self {
+        let (reply_message_id, author_id) = if r.author_id == user_id {
+            (
+                Some(r.reply_message_id.to_string()),
+                Some(r.author_id.to_string()),
+            )
+        } else {
+            (None, None)
+        };
+        Self {
+            reply_message_id,
+            author_id,
+            user_id: r.user_id.to_string(),
+            guild_id: r.guild_id.to_string(),
+            channel_id: r.channel_id.to_string(),
+            thread_id: r.thread_id.map(|t| t.to_string()),
+            relayed_message_id: r.relayed_message_id.to_string(),
+        }
+    }
+}
+
+impl From<UtAuditEntry> for ExportedAuditEntry {
+    fn from(e: UtAuditEntry) -> Self {
+        Self {
+            guild_id: e.guild_id.to_string(),
+            action: audit_action_name(e.action),
+            detail: e.detail,
+            created_at: e.created_at,
+        }
+    }
+}
+
+// 監査ログの操作を，エクスポートの形式での名前に変換する
+
+fn audit_action_name(action: AuditAction) -> &'static str {
+    match action {
+        AuditAction::GuildInit => "guild_init",
+        AuditAction::TimeSet => "time_set",
+        AuditAction::TimeWebhookRecreate => "time_webhook_recreate",
+        AuditAction::TimeDelete => "time_delete",
+        AuditAction::TimeRemove => "time_remove",
+        AuditAction::SettingChange => "setting_change",
+        AuditAction::Takedown => "takedown",
+        AuditAction::Retract => "retract",
+        AuditAction::ReviewApprove => "review_approve",
+        AuditAction::ReviewReject => "review_reject",
+        AuditAction::ForgetMe => "forget_me",
+    }
+}
+
+/// エクスポートする，ユーザーについての記録
+#[derive(Debug, Clone, Default)]
+pub struct UserRecords {
+    /// 登録とそのギルドの名前の組
+    pub times: Vec<(UtTime, String)>,
+    pub deliveries: Vec<UtDelivery>,
+    pub released_message_ids: Vec<u64>,
+    pub time_removals: Vec<UtTimeRemoval>,
+    pub pending_mirrors: Vec<UtPendingMirror>,
+    pub relayed_replies: Vec<UtRelayedReply>,
+    pub audit_entries: Vec<UtAuditEntry>,
+    pub footer: Option<UtUserFooterSetting>,
+}
+
+impl UserExport {
+    pub fn new(user_id: u64, exported_at: String, records: UserRecords) -> Self {
+        let times = records
+            .times
+            .into_iter()
+            .map(|(time, guild_name)| ExportedTime {
+                guild_id: time.guild_id.to_string(),
+                guild_name,
+                channel_id: time.channel_id.to_string(),
+                user_name: time.user_name,
+                webhook_url: time.webhook_url.redacted(),
+            })
+            .collect();
+
+        Self {
+            format: EXPORT_FORMAT,
+            format_version: EXPORT_FORMAT_VERSION,
+            exported_at,
+            user_id: user_id.to_string(),
+            times,
+            deliveries: records.deliveries.into_iter().map(|d| d.into()).collect(),
+            released_message_ids: records
+                .released_message_ids
+                .iter()
+                .map(|id| id.to_string())
+                .collect(),
+            time_removals: records
+                .time_removals
+                .into_iter()
+                .map(|r| r.into())
+                .collect(),
+            pending_mirrors: records
+                .pending_mirrors
+                .into_iter()
+                .map(|p| p.into())
+                .collect(),
+            relayed_replies: records
+                .relayed_replies
+                .into_iter()
+                .map(|r| ExportedRelayedReply::new(r, user_id))
+                .collect(),
+            audit_entries: records
+                .audit_entries
+                .into_iter()
+                .map(|e| e.into())
+                .collect(),
+            settings: ExportedSettings {
+                mirror_footer: records.footer.map(|f| f.enabled),
+            },
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,172 @@
+use super::*;
+use crate::test_utils::time;
+
+fn delivery() -> UtDelivery {
+    UtDelivery {
+        source_message_id: 10,
+        source_guild_id: 11,
+        source_channel_id: 12,
+        user_id: 1,
+        target_guild_id: 13,
+        target_channel_id: 14,
+        mirror_message_id: 15,
+        part: 0,
+    }
+}
+
+fn records() -> UserRecords {
+    UserRecords {
+        times: vec![(time(2), "guild".to_string())],
+        deliveries: vec![delivery()],
+        released_message_ids: vec![10],
+        time_removals: vec![UtTimeRemoval {
+            user_id: 1,
+            // JavaScriptなどで精度が落ちる大きさのid
+            guild_id: 18446744073709551615,
+            channel_id: 21,
+            reason: TimeRemovalReason::Banned,
+            removed_at: "2023-12-01T00:00:00Z".to_string(),
+        }],
+        pending_mirrors: vec![UtPendingMirror {
+            review_message_id: 30,
+            review_channel_id: 31,
+            source_message_id: 32,
+            source_guild_id: 33,
+            source_channel_id: 34,
+            user_id: 1,
+            target_guild_id: 35,
+            content: "waiting".to_string(),
+        }],
+        relayed_replies: vec![
+            UtRelayedReply {
+                reply_message_id: 40,
+                author_id: 1,
+                user_id: 41,
+                guild_id: 42,
+                channel_id: 43,
+                thread_id: None,
+                relayed_message_id: 44,
+            },
+            // 他のユーザーがあなたのTimesへ返信したもの
+            UtRelayedReply {
+                reply_message_id: 45,
+                author_id: 46,
+                user_id: 1,
+                guild_id: 47,
+                channel_id: 48,
+                thread_id: Some(49),
+                relayed_message_id: 50,
+            },
+        ],
+        audit_entries: vec![UtAuditEntry {
+            entry_id: 50,
+            guild_id: 51,
+            actor_id: Some(1),
+            action: AuditAction::TimeSet,
+            detail: "registered".to_string(),
+            created_at: "2023-11-01T00:00:00Z".to_string(),
+        }],
+        footer: Some(UtUserFooterSetting {
+            user_id: 1,
+            enabled: true,
+        }),
+    }
+}
+
+#[test]
+/// WebhookのURLのトークンはエクスポートに含めない
+fn test_export_contains_no_webhook_token() {
+    let export = UserExport::new(1, "2024-01-01T00:00:00Z".to_string(), records());
+    let json = export.to_json().unwrap();
+
+    assert!(!json.contains("/token"));
+    assert!(json.contains("[redacted]"));
+}
+
+#[test]
+/// 何も記録していないユーザーは，空の一覧とnullの設定になる
+fn test_export_without_records() {
+    let export = UserExport::new(
+        1,
+        "2024-01-01T00:00:00Z".to_string(),
+        UserRecords::default(),
+    );
+    let json: serde_json::Value = serde_json::from_str(&export.to_json().unwrap()).unwrap();
+
+    assert_eq!(json["times"], serde_json::json!([]));
+    assert_eq!(json["audit_entries"], serde_json::json!([]));
+    assert_eq!(json["settings"]["mirror_footer"], serde_json::Value::Null);
+}
+
+#[test]
+/// READMEに書いた形式で出力する
+fn test_export_format() {
+    let export = UserExport::new(1, "2024-01-01T00:00:00Z".to_string(), records());
+    let json: serde_json::Value = serde_json::from_str(&export.to_json().unwrap()).unwrap();
+
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "format": "ubiquitimes-cardiac-export",
+            "format_version": 4,
+            "exported_at": "2024-01-01T00:00:00Z",
+            "user_id": "1",
+            "times": [{
+                "guild_id": "2",
+                "guild_name": "guild",
+                "channel_id": "20",
+                "user_name": "UT-c_user",
+                "webhook_url": "https://discord.com/api/webhooks/2/[redacted]",
+            }],
+            "deliveries": [{
+                "source_guild_id": "11",
+                "source_channel_id": "12",
+                "source_message_id": "10",
+                "target_guild_id": "13",
+                "target_channel_id": "14",
+                "mirror_message_id": "15",
+                "part": 0,
+            }],
+            "released_message_ids": ["10"],
+            "time_removals": [{
+                "guild_id": "18446744073709551615",
+                "channel_id": "21",
+                "reason": "banned",
+                "removed_at": "2023-12-01T00:00:00Z",
+            }],
+            "pending_mirrors": [{
+                "source_guild_id": "33",
+                "source_channel_id": "34",
+                "source_message_id": "32",
+                "target_guild_id": "35",
+                "content": "waiting",
+            }],
+            "relayed_replies": [{
+                "reply_message_id": "40",
+                "author_id": "1",
+                "user_id": "41",
+                "guild_id": "42",
+                "channel_id": "43",
+                "thread_id": null,
+                "relayed_message_id": "44",
+            }, {
+                "reply_message_id": null,
+                "author_id": null,
+                "user_id": "1",
+                "guild_id": "47",
+                "channel_id": "48",
+                "thread_id": "49",
+                "relayed_message_id": "50",
+            }],
+            "audit_entries": [{
+                "guild_id": "51",
+                "action": "time_set",
+                "detail": "registered",
+                "created_at": "2023-11-01T00:00:00Z",
+            }],
+            "settings": {
+                "mirror_footer": true,
+            },
+        })
+    );
+}
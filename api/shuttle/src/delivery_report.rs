@@ -0,0 +1,51 @@
+use domain::models::{DeliveryReport, FilterAction, FilterOutcome, UnmirroredContent};
+
+/// 拡散の結果をユーザーに伝えるためのメッセージを作る
+///
+/// すべて問題なく拡散できた場合はNoneを返す
+pub fn delivery_report_message(report: &DeliveryReport) -> Option<String> {
+    let mut lines = Vec::new();
+
+    if !report.failures.is_empty() {
+        lines.push("Failed to send to some guilds".to_string());
+        for f in report.failures.iter() {
+            lines.push(format!("- {}: {}", f.guild_id, f.reason));
+        }
+    }
+
+    if !report.unmirrored.is_empty() {
+        lines.push("Some content could not be mirrored".to_string());
+        for u in report.unmirrored.iter() {
+            lines.push(format!("- {}", unmirrored_content_text(u)));
+        }
+    }
+
+    if !report.filtered.is_empty() {
+        lines.push("Content filters of some guilds were applied".to_string());
+        for f in report.filtered.iter() {
+            lines.push(format!("- {}: {}", f.guild_id, filter_outcome_text(f)));
+        }
+    }
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+fn unmirrored_content_text(content: &UnmirroredContent) -> String {
+    match content {
+        UnmirroredContent::Embed(title) => format!("embed: {}", title),
+        UnmirroredContent::Sticker(name) => format!("sticker: {}", name),
+        UnmirroredContent::TooManyEmbeds(count) => format!("{} embeds over the limit", count),
+    }
+}
+
+fn filter_outcome_text(outcome: &FilterOutcome) -> String {
+    match outcome.action {
+        FilterAction::Block => format!("not sent because of {}", outcome.filter),
+        FilterAction::Redact => format!("removed {}", outcome.filter),
+        FilterAction::Warn => format!("sent, but matched {}", outcome.filter),
+    }
+}
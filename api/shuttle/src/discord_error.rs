@@ -0,0 +1,31 @@
+use poise::serenity_prelude::{self as serenity, HttpError};
+
+// Discordが返すJSONエラーコード
+// https://discord.com/developers/docs/topics/opcodes-and-status-codes#json-json-error-codes
+
+/// メッセージが存在しない
+pub const UNKNOWN_MESSAGE: isize = 10008;
+/// Webhookが存在しない
+pub const UNKNOWN_WEBHOOK: isize = 10015;
+/// botがチャンネルやギルドにアクセスできない
+pub const MISSING_ACCESS: isize = 50001;
+/// botに必要な権限がない
+pub const MISSING_PERMISSIONS: isize = 50013;
+
+/// serenityのエラーから，DiscordのJSONエラーコードを取り出す
+pub fn discord_error_code(e: &serenity::Error) -> Option<isize> {
+    match e {
+        serenity::Error::Http(HttpError::UnsuccessfulRequest(response)) => {
+            Some(response.error.code)
+        }
+        _ => None,
+    }
+}
+
+/// 権限が足りないために失敗したかどうか
+pub fn is_missing_permission(e: &serenity::Error) -> bool {
+    matches!(
+        discord_error_code(e),
+        Some(MISSING_ACCESS) | Some(MISSING_PERMISSIONS)
+    )
+}
@@ -0,0 +1,92 @@
+//! コマンド以外のDiscordのイベントを扱う
+
+use domain::models::TimeRemovalReason;
+use poise::serenity_prelude::{self as serenity, FullEvent, Interaction};
+
+use crate::inbound_review::handle_review;
+use crate::member_removal::remove_member_time;
+use crate::mirror_feedback::{
+    forget_replies, record_reaction_add, record_reaction_remove, record_reactions_cleared,
+    record_reply,
+};
+use crate::models::{Data, UbiquiTimesCardiacResult as Result};
+use crate::reply_bridge::{relay_reply, retract_relayed_replies};
+use crate::takedown::handle_retract;
+
+pub async fn event_handler(ctx: &serenity::Context, event: &FullEvent, data: &Data) -> Result<()> {
+    match event {
+        // コマンドのコレクターが待っているボタンもここに来るが，承認と取り下げのボタン以外は無視する
+        FullEvent::InteractionCreate {
+            interaction: Interaction::Component(interaction),
+        } => {
+            handle_review(ctx, data, interaction).await?;
+            handle_retract(ctx, data, interaction).await?;
+        }
+        // BANされるとギルドを抜けたイベントも来るが，どちらが先に来ても登録は削除される
+        FullEvent::GuildBanAddition {
+            guild_id,
+            banned_user,
+        } => {
+            remove_member_time(
+                ctx.http.clone(),
+                data,
+                guild_id.get(),
+                banned_user.id.get(),
+                TimeRemovalReason::Banned,
+            )
+            .await?;
+        }
+        FullEvent::GuildMemberRemoval { guild_id, user, .. } => {
+            remove_member_time(
+                ctx.http.clone(),
+                data,
+                guild_id.get(),
+                user.id.get(),
+                TimeRemovalReason::Left,
+            )
+            .await?;
+        }
+        // 拡散先のメッセージへの反応を，投稿者のために数える
+        FullEvent::ReactionAdd { add_reaction } => {
+            record_reaction_add(data, add_reaction).await?;
+        }
+        FullEvent::ReactionRemove { removed_reaction } => {
+            record_reaction_remove(data, removed_reaction).await?;
+        }
+        FullEvent::ReactionRemoveAll {
+            removed_from_message_id,
+            ..
+        } => {
+            record_reactions_cleared(data, *removed_from_message_id, None).await?;
+        }
+        FullEvent::ReactionRemoveEmoji { removed_reactions } => {
+            record_reactions_cleared(
+                data,
+                removed_reactions.message_id,
+                Some(removed_reactions.emoji.to_string()),
+            )
+            .await?;
+        }
+        // 返信は，同意があれば発信元のTimesへも届ける
+        FullEvent::Message { new_message } => {
+            if let Some(delivery) = record_reply(data, new_message).await? {
+                relay_reply(&ctx.http, data, new_message, &delivery).await?;
+            }
+        }
+        FullEvent::MessageDelete {
+            deleted_message_id, ..
+        } => {
+            forget_replies(data, &[*deleted_message_id]).await?;
+            retract_relayed_replies(&ctx.http, data, &[*deleted_message_id]).await?;
+        }
+        FullEvent::MessageDeleteBulk {
+            multiple_deleted_messages_ids,
+            ..
+        } => {
+            forget_replies(data, multiple_deleted_messages_ids).await?;
+            retract_relayed_replies(&ctx.http, data, multiple_deleted_messages_ids).await?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
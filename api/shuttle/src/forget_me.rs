@@ -0,0 +1,337 @@
+use std::collections::{BTreeMap, HashMap};
+
+use domain::models::{AuditAction, NewAuditEntry, UtDelivery};
+use domain::repository::{
+    AuditLogRepository, DeliveryRepository, FooterSettingsRepository, PendingMirrorRepository,
+    ReplyBridgeRepository, TimeRemovalRepository, TimesRepository,
+};
+use poise::serenity_prelude::{ChannelId, MessageId, Timestamp, Webhook};
+use tracing::{info, warn};
+
+use crate::audit_log::record_audit;
+use crate::mirror_deletion::delete_mirror;
+use crate::models::{Context, UbiquiTimesCardiacResult as Result};
+use crate::reply_bridge::delete_relayed_message;
+use crate::times_unregister::{unregister_time, UnregisterReport};
+
+/// ギルドごとの削除の結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GuildErasure {
+    /// 登録の削除の結果．そのギルドに登録がなければNone
+    pub unregister: Option<UnregisterReport>,
+    /// 削除した(すでに削除されていたものを含む)拡散先のメッセージの数
+    pub mirrors_deleted: usize,
+    pub mirrors_failed: usize,
+}
+
+/// ut_c_forget_meの結果
+///
+/// 失敗した分は記録を残すので，もう一度実行すれば続きから削除できる
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForgetMeReport {
+    pub guilds: BTreeMap<u64, GuildErasure>,
+    /// 拡散の記録の削除の結果．削除した数
+    pub history: Option<std::result::Result<usize, String>>,
+    /// 拡散先のメッセージを削除できなかったので残した記録の数
+    pub history_kept: usize,
+    /// 取り下げた承認待ちのメッセージの数
+    pub pending_withdrawn: usize,
+    /// 発信元のTimesへ届けた返信のうち，削除した(すでに削除されていたものを含む)数
+    pub replies_deleted: usize,
+    pub replies_failed: usize,
+}
+
+impl ForgetMeReport {
+    /// 各ギルドの結果をユーザーに伝えるためのメッセージ
+    /// guild_namesにないギルドはguild_idで表示する
+    pub fn message(&self, guild_names: &HashMap<u64, String>) -> String {
+        let mut lines = Vec::new();
+        if self.guilds.is_empty() {
+            lines.push("I had no Times registered for you.".to_string());
+        }
+
+        for (guild_id, erasure) in self.guilds.iter() {
+            let guild_name = guild_names
+                .get(guild_id)
+                .cloned()
+                .unwrap_or_else(|| guild_id.to_string());
+            match &erasure.unregister {
+                Some(report) => lines.push(report.steps(&guild_name)),
+                None => lines.push(format!("**{}**", guild_name)),
+            }
+            if erasure.mirrors_deleted > 0 {
+                lines.push(format!(
+                    "- ✅ {} mirrored messages deleted",
+                    erasure.mirrors_deleted
+                ));
+            }
+            if erasure.mirrors_failed > 0 {
+                lines.push(format!(
+                    "- ❌ {} mirrored messages could not be deleted",
+                    erasure.mirrors_failed
+                ));
+            }
+        }
+
+        match &self.history {
+            Some(Ok(count)) => lines.push(format!("✅ {} delivery records deleted", count)),
+            Some(Err(reason)) => {
+                lines.push(format!("❌ failed to delete delivery records: {}", reason))
+            }
+            None => {}
+        }
+        if self.replies_deleted > 0 {
+            lines.push(format!(
+                "✅ {} relayed replies deleted",
+                self.replies_deleted
+            ));
+        }
+        if self.replies_failed > 0 {
+            lines.push(format!(
+                "❌ {} relayed replies could not be deleted",
+                self.replies_failed
+            ));
+        }
+        if self.pending_withdrawn > 0 {
+            lines.push(format!(
+                "✅ {} posts waiting for approval withdrawn",
+                self.pending_withdrawn
+            ));
+        }
+        if self.history_kept > 0 {
+            lines.push(format!(
+                "⏸ {} delivery records kept because their messages could not be deleted",
+                self.history_kept
+            ));
+        }
+
+        if !self.is_complete() {
+            lines.push("Please run the command again to retry.".to_string());
+        }
+        lines.join("\n")
+    }
+
+    /// すべて削除できたか
+    pub fn is_complete(&self) -> bool {
+        let guilds_complete = self.guilds.values().all(|g| {
+            g.mirrors_failed == 0 && g.unregister.as_ref().is_none_or(|r| r.is_complete())
+        });
+        let history_complete = !matches!(self.history, Some(Err(_)));
+        guilds_complete && history_complete && self.history_kept == 0 && self.replies_failed == 0
+    }
+}
+
+/// 監査ログに残す，ギルドごとの削除の内容
+pub fn forget_me_audit_detail(erasure: &GuildErasure, pending_withdrawn: usize) -> String {
+    let mut parts = Vec::new();
+    match &erasure.unregister {
+        Some(report) if report.is_complete() => parts.push("Times unregistered".to_string()),
+        Some(_) => parts.push("Times could not be unregistered".to_string()),
+        None => {}
+    }
+    if erasure.mirrors_deleted > 0 {
+        parts.push(format!(
+            "{} mirrored messages deleted",
+            erasure.mirrors_deleted
+        ));
+    }
+    if erasure.mirrors_failed > 0 {
+        parts.push(format!(
+            "{} mirrored messages could not be deleted",
+            erasure.mirrors_failed
+        ));
+    }
+    if pending_withdrawn > 0 {
+        parts.push(format!(
+            "{} posts waiting for approval withdrawn",
+            pending_withdrawn
+        ));
+    }
+    if parts.is_empty() {
+        return "nothing to erase".to_string();
+    }
+    parts.join(", ")
+}
+
+/// すべてのギルドから，ユーザーの登録とWebhook，拡散の記録を削除する
+///
+/// 1. delete_mirrorsなら，拡散先のメッセージと，発信元のTimesへ届けた返信を削除する
+///    Webhookで削除するので，登録より先に行う
+/// 2. すべての登録とWebhookを削除する
+/// 3. 拡散と返信を届けた記録を削除する．メッセージを削除できなかった記録は，やり直せるように残す
+/// 4. 承認待ちのメッセージを取り下げる
+/// 5. 登録，拡散先のメッセージ，承認待ちのメッセージがあったギルドの監査ログに残す
+/// 6. 監査ログの項目から，このユーザーを消す．何が行われたかはギルドのために残す
+///
+/// 他のユーザーがこのユーザーのTimesへ届けた返信は，そのユーザーのメッセージなので削除せず，記録だけを消す
+pub async fn forget_me(ctx: Context<'_>, delete_mirrors: bool) -> Result<ForgetMeReport> {
+    let user_id = ctx.author().id.get();
+    let times_repository = ctx.data().times_repository.clone();
+    let delivery_repository = ctx.data().delivery_repository.clone();
+
+    let times = times_repository.get_times(user_id).await?;
+    let deliveries = delivery_repository.get_deliveries_by_user(user_id).await?;
+
+    let relayed_replies = ctx
+        .data()
+        .reply_bridge_repository
+        .get_relayed_replies_by_user(user_id)
+        .await?;
+
+    let mut report = ForgetMeReport::default();
+    let http = ctx.serenity_context().http.clone();
+
+    let mut purge = Vec::new();
+    if delete_mirrors {
+        let mut by_guild: BTreeMap<u64, Vec<&UtDelivery>> = BTreeMap::new();
+        for delivery in deliveries.iter() {
+            by_guild
+                .entry(delivery.target_guild_id)
+                .or_default()
+                .push(delivery);
+        }
+
+        for (guild_id, deliveries) in by_guild {
+            let webhook_url = times
+                .iter()
+                .find(|t| t.guild_id == guild_id)
+                .map(|t| t.webhook_url.expose_secret());
+            let webhook = match webhook_url {
+                Some(url) => Webhook::from_url(&*http, url).await.ok(),
+                None => None,
+            };
+
+            let erasure = report.guilds.entry(guild_id).or_default();
+            for delivery in deliveries {
+                if delete_mirror(&http, webhook.as_ref(), delivery)
+                    .await
+                    .is_gone()
+                {
+                    erasure.mirrors_deleted += 1;
+                    purge.push(delivery.mirror_message_id);
+                } else {
+                    erasure.mirrors_failed += 1;
+                }
+            }
+        }
+    } else {
+        purge = deliveries.iter().map(|d| d.mirror_message_id).collect();
+    }
+    report.history_kept = deliveries.len() - purge.len();
+
+    let mut relayed_purge = Vec::new();
+    for relayed in relayed_replies.iter() {
+        if delete_mirrors && relayed.author_id == user_id {
+            if delete_relayed_message(&http, ctx.data(), relayed)
+                .await?
+                .is_gone()
+            {
+                report.replies_deleted += 1;
+            } else {
+                report.replies_failed += 1;
+                continue;
+            }
+        }
+        relayed_purge.push(relayed.reply_message_id);
+    }
+
+    for time in times.iter() {
+        let unregister = unregister_time(ctx, time).await;
+        report.guilds.entry(time.guild_id).or_default().unregister = Some(unregister);
+    }
+
+    // 拡散済みの記録は，配信ログの記録を残す場合でも二重に拡散しないためのものなので削除してよい
+    delivery_repository.delete_released_by_user(user_id).await?;
+
+    if !purge.is_empty() {
+        let count = purge.len();
+        report.history = Some(
+            delivery_repository
+                .delete_deliveries(purge)
+                .await
+                .map(|()| count)
+                .map_err(|e| e.to_string()),
+        );
+    }
+
+    // 返信を届けた記録は外部キーで消えないので，ここで削除する
+    if !relayed_purge.is_empty() {
+        ctx.data()
+            .reply_bridge_repository
+            .delete_relayed_replies(relayed_purge)
+            .await?;
+    }
+
+    // botが登録を削除した記録とユーザーの設定も，ユーザーの情報なので削除する
+    ctx.data()
+        .time_removal_repository
+        .delete_time_removals(user_id)
+        .await?;
+    ctx.data()
+        .footer_settings_repository
+        .delete_user_footer_setting(user_id)
+        .await?;
+
+    // 承認待ちのメッセージも，承認待ちのチャンネルの投稿に本文が載っているので一緒に削除する
+    let pendings = ctx
+        .data()
+        .pending_mirror_repository
+        .delete_pending_mirrors_by_user(user_id)
+        .await?;
+    for pending in pendings.iter() {
+        // 記録はもう削除したので，投稿を削除できなくてもボタンは何もしない
+        if let Err(e) = ChannelId::new(pending.review_channel_id)
+            .delete_message(ctx, MessageId::new(pending.review_message_id))
+            .await
+        {
+            warn!(
+                "failed to delete review message. review_message_id: {}, error: {}",
+                pending.review_message_id, e
+            );
+        }
+    }
+    report.pending_withdrawn = pendings.len();
+
+    let mut withdrawn_by_guild: BTreeMap<u64, usize> = report
+        .guilds
+        .keys()
+        .map(|guild_id| (*guild_id, 0))
+        .collect();
+    for pending in pendings.iter() {
+        *withdrawn_by_guild
+            .entry(pending.target_guild_id)
+            .or_default() += 1;
+    }
+    for (guild_id, withdrawn) in withdrawn_by_guild {
+        let erasure = report.guilds.get(&guild_id).cloned().unwrap_or_default();
+        // 消したユーザーを監査ログに残さないように，操作したユーザーは記録しない
+        record_audit(
+            &http,
+            ctx.data(),
+            NewAuditEntry {
+                guild_id,
+                actor_id: None,
+                action: AuditAction::ForgetMe,
+                detail: forget_me_audit_detail(&erasure, withdrawn),
+                created_at: Timestamp::now().to_string(),
+            },
+        )
+        .await;
+    }
+
+    // 登録の削除のように，このコマンドの中で記録したものも含めて消す
+    ctx.data()
+        .audit_log_repository
+        .anonymize_audit_entries_by_actor(user_id)
+        .await?;
+
+    info!(
+        "forget me complete. user_id: {}, complete: {}",
+        user_id,
+        report.is_complete()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests;
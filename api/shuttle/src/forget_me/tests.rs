@@ -0,0 +1,192 @@
+use super::*;
+use crate::webhook_api::WebhookDeletion;
+
+fn unregistered(guild_id: u64) -> UnregisterReport {
+    UnregisterReport {
+        guild_id,
+        webhook: WebhookDeletion::Deleted,
+        time: Some(Ok(())),
+    }
+}
+
+fn names() -> HashMap<u64, String> {
+    HashMap::from([(1, "first".to_string())])
+}
+
+#[test]
+/// 消すものがなければそのことを伝える
+fn test_nothing_to_forget() {
+    let report = ForgetMeReport::default();
+
+    assert!(report.is_complete());
+    assert_eq!(
+        report.message(&names()),
+        "I had no Times registered for you."
+    );
+}
+
+#[test]
+/// すべて消せたときは，ギルドごとに結果を伝える
+fn test_complete_report_per_guild() {
+    let report = ForgetMeReport {
+        guilds: BTreeMap::from([
+            (
+                1,
+                GuildErasure {
+                    unregister: Some(unregistered(1)),
+                    mirrors_deleted: 2,
+                    mirrors_failed: 0,
+                },
+            ),
+            // 登録はないが，拡散先のメッセージがあったギルド
+            (
+                2,
+                GuildErasure {
+                    unregister: None,
+                    mirrors_deleted: 1,
+                    mirrors_failed: 0,
+                },
+            ),
+        ]),
+        history: Some(Ok(3)),
+        history_kept: 0,
+        ..Default::default()
+    };
+
+    assert!(report.is_complete());
+    assert_eq!(
+        report.message(&names()),
+        [
+            "**first**",
+            "- ✅ webhook deleted",
+            "- ✅ registration deleted",
+            "- ✅ 2 mirrored messages deleted",
+            "**2**",
+            "- ✅ 1 mirrored messages deleted",
+            "✅ 3 delivery records deleted",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+/// 消せなかった拡散先のメッセージがあれば，完了としない
+fn test_failed_mirrors_keep_records() {
+    let report = ForgetMeReport {
+        guilds: BTreeMap::from([(
+            1,
+            GuildErasure {
+                unregister: Some(unregistered(1)),
+                mirrors_deleted: 1,
+                mirrors_failed: 2,
+            },
+        )]),
+        history: Some(Ok(1)),
+        history_kept: 2,
+        ..Default::default()
+    };
+
+    assert!(!report.is_complete());
+    let message = report.message(&names());
+    assert!(message.contains("❌ 2 mirrored messages could not be deleted"));
+    assert!(message.contains("⏸ 2 delivery records kept"));
+    assert!(message.ends_with("Please run the command again to retry."));
+}
+
+#[test]
+/// 登録を解除できなかったら，完了としない
+fn test_failed_unregister_is_incomplete() {
+    let report = ForgetMeReport {
+        guilds: BTreeMap::from([(
+            1,
+            GuildErasure {
+                unregister: Some(UnregisterReport {
+                    guild_id: 1,
+                    webhook: WebhookDeletion::Failed("Missing Permissions".to_string()),
+                    time: None,
+                }),
+                ..Default::default()
+            },
+        )]),
+        history: None,
+        history_kept: 0,
+        ..Default::default()
+    };
+
+    assert!(!report.is_complete());
+}
+
+#[test]
+/// 履歴を消せなかったら，完了としない
+fn test_failed_history_is_incomplete() {
+    let report = ForgetMeReport {
+        history: Some(Err("connection closed".to_string())),
+        ..Default::default()
+    };
+
+    assert!(!report.is_complete());
+    assert!(report
+        .message(&names())
+        .contains("❌ failed to delete delivery records: connection closed"));
+}
+
+#[test]
+/// 取り下げた承認待ちのメッセージの数を伝える
+fn test_withdrawn_pending_mirrors() {
+    let report = ForgetMeReport {
+        pending_withdrawn: 2,
+        ..Default::default()
+    };
+
+    assert!(report.is_complete());
+    assert_eq!(
+        report.message(&names()),
+        [
+            "I had no Times registered for you.",
+            "✅ 2 posts waiting for approval withdrawn",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+/// 届けた返信を削除できなかったときは，もう一度実行するように伝える
+fn test_failed_relayed_replies_are_incomplete() {
+    let report = ForgetMeReport {
+        replies_deleted: 1,
+        replies_failed: 2,
+        ..Default::default()
+    };
+
+    assert!(!report.is_complete());
+    assert_eq!(
+        report.message(&names()),
+        [
+            "I had no Times registered for you.",
+            "✅ 1 relayed replies deleted",
+            "❌ 2 relayed replies could not be deleted",
+            "Please run the command again to retry.",
+        ]
+        .join("\n")
+    );
+}
+
+#[test]
+/// 監査ログには，そのギルドで削除したものを残す
+fn test_forget_me_audit_detail() {
+    let erasure = GuildErasure {
+        unregister: Some(unregistered(1)),
+        mirrors_deleted: 2,
+        mirrors_failed: 1,
+    };
+    assert_eq!(
+        forget_me_audit_detail(&erasure, 1),
+        "Times unregistered, 2 mirrored messages deleted, 1 mirrored messages could not be deleted, 1 posts waiting for approval withdrawn"
+    );
+
+    // 承認待ちのメッセージだけがあったギルド
+    assert_eq!(
+        forget_me_audit_detail(&GuildErasure::default(), 2),
+        "2 posts waiting for approval withdrawn"
+    );
+}
@@ -0,0 +1,89 @@
+//! ギルドの管理者が決めた，ギルドの間の拡散の可否に従って送信先を絞る
+//!
+//! 発信元と送信先のどちらかが相手をブロックしていれば拡散しない
+//! 許可したギルドが1つでもあるギルドは，許可したギルドとの間でだけ拡散する
+
+use domain::models::{GuildPairAction, UtGuildPairPolicy, UtTime};
+use domain::repository::GuildPairPolicyRepository;
+
+/// 発信元から送信先へ拡散してよいか
+pub fn is_pair_allowed(
+    policies: &[UtGuildPairPolicy],
+    origin_guild_id: u64,
+    target_guild_id: u64,
+) -> bool {
+    side_allows(policies, origin_guild_id, target_guild_id)
+        && side_allows(policies, target_guild_id, origin_guild_id)
+}
+
+/// guild_idのギルドが，other_guild_idのギルドとの間の拡散を認めているか
+fn side_allows(policies: &[UtGuildPairPolicy], guild_id: u64, other_guild_id: u64) -> bool {
+    let mut has_allow = false;
+    for policy in policies.iter().filter(|p| p.guild_id == guild_id) {
+        if policy.other_guild_id == other_guild_id {
+            return policy.action == GuildPairAction::Allow;
+        }
+        has_allow |= policy.action == GuildPairAction::Allow;
+    }
+    !has_allow
+}
+
+/// 拡散してよい送信先と，拡散しない送信先のギルドに分ける
+pub fn filter_by_guild_pair_policy(
+    origin_guild_id: u64,
+    times: Vec<UtTime>,
+    policies: &[UtGuildPairPolicy],
+) -> (Vec<UtTime>, Vec<u64>) {
+    let mut skipped = Vec::new();
+    let times = times
+        .into_iter()
+        .filter(|t| {
+            let allowed = is_pair_allowed(policies, origin_guild_id, t.guild_id);
+            if !allowed {
+                skipped.push(t.guild_id);
+            }
+            allowed
+        })
+        .collect();
+    (times, skipped)
+}
+
+/// 発信元と送信先の決めたものを取得して，送信先を絞る
+pub async fn load_and_filter_by_guild_pair_policy<R>(
+    guild_pair_policy_repository: &R,
+    origin_guild_id: u64,
+    times: Vec<UtTime>,
+) -> Result<(Vec<UtTime>, Vec<u64>), R::Error>
+where
+    R: GuildPairPolicyRepository,
+{
+    let guild_ids = std::iter::once(origin_guild_id)
+        .chain(times.iter().map(|t| t.guild_id))
+        .collect();
+    let policies = guild_pair_policy_repository
+        .get_guild_pair_policies(guild_ids)
+        .await?;
+    Ok(filter_by_guild_pair_policy(
+        origin_guild_id,
+        times,
+        &policies,
+    ))
+}
+
+/// 拡散しなかった送信先をユーザーに伝えるためのメッセージ
+///
+/// なければNoneを返す
+pub fn skipped_by_policy_message(skipped: &[u64]) -> Option<String> {
+    if skipped.is_empty() {
+        return None;
+    }
+
+    let mut lines = vec!["Skipped by guild policy".to_string()];
+    for guild_id in skipped.iter() {
+        lines.push(format!("- {}", guild_id));
+    }
+    Some(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests;
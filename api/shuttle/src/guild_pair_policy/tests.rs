@@ -0,0 +1,85 @@
+use super::*;
+use crate::test_utils::time;
+
+const ORIGIN: u64 = 1;
+const TARGET: u64 = 2;
+const OTHER: u64 = 3;
+
+fn policy(guild_id: u64, other_guild_id: u64, action: GuildPairAction) -> UtGuildPairPolicy {
+    UtGuildPairPolicy {
+        guild_id,
+        other_guild_id,
+        action,
+    }
+}
+
+#[test]
+/// ポリシーがなければ拡散する
+fn test_allowed_without_policies() {
+    assert!(is_pair_allowed(&[], ORIGIN, TARGET));
+}
+
+#[test]
+/// blockはどちらのギルドが決めたものでも当てはまる
+fn test_block_works_in_both_directions() {
+    // 送信先がブロックしている
+    let policies = [policy(TARGET, ORIGIN, GuildPairAction::Block)];
+    assert!(!is_pair_allowed(&policies, ORIGIN, TARGET));
+
+    // 発信元がブロックしている
+    let policies = [policy(ORIGIN, TARGET, GuildPairAction::Block)];
+    assert!(!is_pair_allowed(&policies, ORIGIN, TARGET));
+}
+
+#[test]
+/// 許可リストがあれば，許可したギルドとだけやりとりする
+fn test_allow_list_limits_to_allowed_guilds() {
+    let policies = [policy(TARGET, OTHER, GuildPairAction::Allow)];
+
+    assert!(!is_pair_allowed(&policies, ORIGIN, TARGET));
+    assert!(is_pair_allowed(&policies, OTHER, TARGET));
+}
+
+#[test]
+/// 許可リストにあるギルドへは拡散する
+fn test_allowed_guild_passes_allow_list() {
+    let policies = [
+        policy(TARGET, OTHER, GuildPairAction::Allow),
+        policy(TARGET, ORIGIN, GuildPairAction::Allow),
+    ];
+
+    assert!(is_pair_allowed(&policies, ORIGIN, TARGET));
+}
+
+#[test]
+/// blockしたギルドの他には影響しない
+fn test_block_only_affects_blocked_guild() {
+    let policies = [policy(TARGET, OTHER, GuildPairAction::Block)];
+
+    assert!(is_pair_allowed(&policies, ORIGIN, TARGET));
+}
+
+#[test]
+/// ポリシーで送らなかった送信先を返す
+fn test_filter_reports_skipped_targets() {
+    let policies = [
+        policy(TARGET, ORIGIN, GuildPairAction::Block),
+        policy(ORIGIN, 4, GuildPairAction::Block),
+    ];
+
+    let (times, skipped) =
+        filter_by_guild_pair_policy(ORIGIN, vec![time(TARGET), time(OTHER), time(4)], &policies);
+
+    assert_eq!(times, vec![time(OTHER)]);
+    assert_eq!(skipped, vec![TARGET, 4]);
+}
+
+#[test]
+/// 送らなかった送信先を拡散したユーザーに伝える
+fn test_skipped_message() {
+    assert_eq!(skipped_by_policy_message(&[]), None);
+    assert_eq!(
+        skipped_by_policy_message(&[2, 4]).unwrap(),
+        "Skipped by guild policy\n- 2\n- 4"
+    );
+}
@@ -0,0 +1,86 @@
+//! 送信先のギルドごとの受け入れ方に従って，拡散する送信先を振り分ける
+
+use domain::models::{InboundDecision, UtInboundPolicy, UtTime};
+use domain::repository::InboundPolicyRepository;
+
+/// 送信先を受け入れ方で振り分けた結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InboundPlan {
+    /// すぐに拡散する
+    pub deliver: Vec<UtTime>,
+    /// 承認待ちのチャンネルに投稿する．承認待ちのチャンネルのidとの組
+    pub queue: Vec<(UtTime, u64)>,
+    /// 受け入れられなかった送信先のギルド
+    pub refused: Vec<u64>,
+}
+
+/// 各送信先の受け入れ方に従って振り分ける
+/// 受け入れ方を設定していない送信先は，すべて受け入れる
+pub fn plan_inbound(
+    origin_guild_id: u64,
+    times: Vec<UtTime>,
+    policies: &[UtInboundPolicy],
+) -> InboundPlan {
+    let mut plan = InboundPlan::default();
+    for time in times {
+        let decision = policies
+            .iter()
+            .find(|p| p.guild_id == time.guild_id)
+            .map(|p| p.decide(origin_guild_id))
+            .unwrap_or(InboundDecision::Deliver);
+        match decision {
+            InboundDecision::Deliver => plan.deliver.push(time),
+            InboundDecision::Queue {
+                approval_channel_id,
+            } => plan.queue.push((time, approval_channel_id)),
+            InboundDecision::Refuse => plan.refused.push(time.guild_id),
+        }
+    }
+    plan
+}
+
+/// 送信先の受け入れ方を取得して振り分ける
+pub async fn load_inbound_plan<R>(
+    inbound_policy_repository: &R,
+    origin_guild_id: u64,
+    times: Vec<UtTime>,
+) -> Result<InboundPlan, R::Error>
+where
+    R: InboundPolicyRepository,
+{
+    let guild_ids = times.iter().map(|t| t.guild_id).collect();
+    let policies = inbound_policy_repository
+        .get_inbound_policies(guild_ids)
+        .await?;
+    Ok(plan_inbound(origin_guild_id, times, &policies))
+}
+
+/// 承認待ちになった送信先と，受け入れられなかった送信先をユーザーに伝えるためのメッセージ
+///
+/// どちらもなければNoneを返す
+pub fn inbound_report_message(queued: &[u64], refused: &[u64]) -> Option<String> {
+    let mut lines = Vec::new();
+
+    if !queued.is_empty() {
+        lines.push("Waiting for moderator approval in some guilds".to_string());
+        for guild_id in queued.iter() {
+            lines.push(format!("- {}", guild_id));
+        }
+    }
+
+    if !refused.is_empty() {
+        lines.push("Some guilds do not accept posts from this guild".to_string());
+        for guild_id in refused.iter() {
+            lines.push(format!("- {}", guild_id));
+        }
+    }
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,118 @@
+use super::*;
+use crate::test_utils::time;
+use domain::models::InboundMode;
+
+const ORIGIN_GUILD_ID: u64 = 1;
+
+fn policy(guild_id: u64, mode: InboundMode, allowed_guild_ids: Vec<u64>) -> UtInboundPolicy {
+    UtInboundPolicy {
+        guild_id,
+        mode,
+        approval_channel_id: Some(guild_id * 100),
+        allowed_guild_ids,
+    }
+}
+
+#[test]
+/// ポリシーのないギルドへは拡散する
+fn test_delivers_to_guilds_without_policy() {
+    let plan = plan_inbound(ORIGIN_GUILD_ID, vec![time(2), time(3)], &[]);
+
+    assert_eq!(plan.deliver, vec![time(2), time(3)]);
+    assert!(plan.queue.is_empty());
+    assert!(plan.refused.is_empty());
+}
+
+#[test]
+/// 送信先をポリシーに従って，拡散，承認待ち，拒否に分ける
+fn test_sorts_targets_by_policy() {
+    let policies = [
+        policy(2, InboundMode::AllowList, vec![]),
+        policy(3, InboundMode::ApprovalQueue, vec![]),
+        // 許可されていれば，承認を待たずに拡散する
+        policy(4, InboundMode::ApprovalQueue, vec![ORIGIN_GUILD_ID]),
+        policy(5, InboundMode::Open, vec![]),
+    ];
+
+    let plan = plan_inbound(
+        ORIGIN_GUILD_ID,
+        vec![time(2), time(3), time(4), time(5)],
+        &policies,
+    );
+
+    assert_eq!(plan.deliver, vec![time(4), time(5)]);
+    assert_eq!(plan.queue, vec![(time(3), 300)]);
+    assert_eq!(plan.refused, vec![2]);
+}
+
+#[test]
+/// すべて拡散したときは何も伝えない
+fn test_no_message_when_everything_delivered() {
+    assert_eq!(inbound_report_message(&[], &[]), None);
+}
+
+#[test]
+/// 承認待ちと拒否になったギルドを伝える
+fn test_reports_queued_and_refused() {
+    assert_eq!(
+        inbound_report_message(&[3], &[2]).unwrap(),
+        "Waiting for moderator approval in some guilds\n- 3\nSome guilds do not accept posts from this guild\n- 2"
+    );
+}
+
+/// 受け入れ方を取得した回数を数える
+#[derive(Default)]
+struct FakeInboundPolicyRepository {
+    policies: Vec<UtInboundPolicy>,
+    queries: std::sync::Mutex<usize>,
+}
+
+impl InboundPolicyRepository for FakeInboundPolicyRepository {
+    type Error = String;
+
+    async fn upsert_inbound_policy(&self, _policy: UtInboundPolicy) -> Result<(), String> {
+        unreachable!()
+    }
+
+    async fn get_inbound_policy(&self, _guild_id: u64) -> Result<Option<UtInboundPolicy>, String> {
+        unreachable!()
+    }
+
+    async fn get_inbound_policies(
+        &self,
+        guild_ids: Vec<u64>,
+    ) -> Result<Vec<UtInboundPolicy>, String> {
+        *self.queries.lock().unwrap() += 1;
+        Ok(self
+            .policies
+            .iter()
+            .filter(|p| guild_ids.contains(&p.guild_id))
+            .cloned()
+            .collect())
+    }
+}
+
+#[tokio::test]
+/// 送信先がいくつあっても，受け入れ方はまとめて1回で取得する
+async fn test_load_inbound_plan_fetches_policies_at_once() {
+    let repository = FakeInboundPolicyRepository {
+        policies: vec![
+            policy(2, InboundMode::AllowList, vec![]),
+            policy(3, InboundMode::ApprovalQueue, vec![]),
+        ],
+        ..Default::default()
+    };
+
+    let plan = load_inbound_plan(
+        &repository,
+        ORIGIN_GUILD_ID,
+        vec![time(2), time(3), time(4)],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(*repository.queries.lock().unwrap(), 1);
+    assert_eq!(plan.deliver, vec![time(4)]);
+    assert_eq!(plan.queue, vec![(time(3), 300)]);
+    assert_eq!(plan.refused, vec![2]);
+}
@@ -0,0 +1,430 @@
+//! 承認待ちのメッセージを，送信先のモデレーターが承認・却下する
+//!
+//! 承認待ちのチャンネルに，承認と却下のボタンを付けて投稿する
+//! ボタンはbotを再起動しても使えるように，コレクターではなくイベントで受け取る
+//! どのメッセージのボタンかは，ボタンが付いたメッセージのidで探す
+
+use domain::models::{
+    AuditAction, FilterAction, MessageLocation, NewAuditEntry, UtPendingMirror, UtTime,
+};
+use domain::repository::{
+    ContentFilterRepository, DeliveryRepository, GuildPairPolicyRepository, GuildRepository,
+    PendingMirrorRepository, TimesRepository,
+};
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, ChannelId, ComponentInteraction, CreateActionRow,
+    CreateAllowedMentions, CreateButton, CreateInteractionResponse,
+    CreateInteractionResponseFollowup, CreateInteractionResponseMessage, CreateMessage,
+    EditInteractionResponse, GuildId, Message, MessageId, Permissions, Timestamp, UserId,
+};
+use tracing::{info, warn};
+
+use crate::audit_log::record_audit;
+use crate::content_filter::{plan_filtered_delivery, send_filtered, FilterContent};
+use crate::guild_pair_policy::is_pair_allowed;
+use crate::mirror_context::load_mirror_context;
+use crate::mirror_footer::mirror_footer;
+use crate::models::{Data, UbiquiTimesCardiacResult as Result};
+
+const APPROVE_ID: &str = "ut_c_review:approve";
+const REJECT_ID: &str = "ut_c_review:reject";
+
+// 承認待ちのチャンネルに載せる本文の長さ．Discordの上限に収まるように，見出しの分を残す
+const PREVIEW_LIMIT: usize = 1500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReviewAction {
+    Approve,
+    Reject,
+}
+
+impl ReviewAction {
+    pub fn from_custom_id(custom_id: &str) -> Option<Self> {
+        match custom_id {
+            APPROVE_ID => Some(Self::Approve),
+            REJECT_ID => Some(Self::Reject),
+            _ => None,
+        }
+    }
+
+    fn audit_action(self) -> AuditAction {
+        match self {
+            Self::Approve => AuditAction::ReviewApprove,
+            Self::Reject => AuditAction::ReviewReject,
+        }
+    }
+}
+
+/// 監査ログに残す，審査したメッセージと結果
+///
+/// 承認しても拡散できなかったときは，not_deliveredに理由を入れる
+pub fn review_audit_detail(pending: &UtPendingMirror, not_delivered: Option<&str>) -> String {
+    let source = MessageLocation::new(
+        pending.source_guild_id,
+        pending.source_channel_id,
+        pending.source_message_id,
+    );
+    let detail = format!("post by <@{}> {}", pending.user_id, source.jump_url());
+    match not_delivered {
+        Some(reason) => format!("{} (not delivered: {})", detail, reason),
+        None => detail,
+    }
+}
+
+/// 承認と却下のボタン
+fn review_buttons() -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(APPROVE_ID)
+            .label("Approve")
+            .style(ButtonStyle::Success),
+        CreateButton::new(REJECT_ID)
+            .label("Reject")
+            .style(ButtonStyle::Danger),
+    ])]
+}
+
+/// 承認待ちのチャンネルに載せる内容
+pub fn review_content(
+    user_name: &str,
+    origin_guild_name: &str,
+    source: &MessageLocation,
+    content: &str,
+) -> String {
+    let mut preview = content.chars().take(PREVIEW_LIMIT).collect::<String>();
+    if preview.len() < content.len() {
+        preview.push('…');
+    }
+    if preview.trim().is_empty() {
+        preview = "(no text)".to_string();
+    }
+
+    format!(
+        "**Incoming post** from `{}` in **{}** is waiting for approval\n{}\n>>> {}",
+        user_name,
+        origin_guild_name,
+        source.jump_url(),
+        preview
+    )
+}
+
+/// 送信先の承認待ちのチャンネルに投稿して，承認を待つ
+pub async fn queue_for_review(
+    ctx: &serenity::Context,
+    data: &Data,
+    message: &Message,
+    content: &str,
+    time: &UtTime,
+    approval_channel_id: u64,
+) -> Result<()> {
+    let origin_guild_id = message.guild_id.map(|g| g.get()).unwrap_or_default();
+    let origin_guild_name = data
+        .guild_repository
+        .get_guild(origin_guild_id)
+        .await
+        .ok()
+        .and_then(|g| g.guild_name)
+        .unwrap_or_else(|| origin_guild_id.to_string());
+    let source = MessageLocation::new(origin_guild_id, message.channel_id.get(), message.id.get());
+
+    let review_channel = ChannelId::new(approval_channel_id);
+    let review_message = review_channel
+        .send_message(
+            ctx,
+            CreateMessage::new()
+                .content(review_content(
+                    &time.user_name,
+                    &origin_guild_name,
+                    &source,
+                    content,
+                ))
+                .components(review_buttons())
+                // 他のギルドからの本文なので，承認待ちのチャンネルで誰にも通知しない
+                .allowed_mentions(CreateAllowedMentions::new()),
+        )
+        .await?;
+
+    let pending = UtPendingMirror {
+        review_message_id: review_message.id.get(),
+        review_channel_id: approval_channel_id,
+        source_message_id: message.id.get(),
+        source_guild_id: origin_guild_id,
+        source_channel_id: message.channel_id.get(),
+        user_id: message.author.id.get(),
+        target_guild_id: time.guild_id,
+        content: content.to_string(),
+    };
+    if let Err(e) = data
+        .pending_mirror_repository
+        .insert_pending_mirror(pending)
+        .await
+    {
+        // 記録できなければボタンを押しても何も起きないので，投稿を取り消す
+        if let Err(e) = review_message.delete(ctx).await {
+            warn!("failed to delete review message. error: {}", e);
+        }
+        return Err(e.into());
+    }
+
+    info!(
+        "queued for review. target_guild_id: {}, review_message_id: {}",
+        time.guild_id, review_message.id
+    );
+    Ok(())
+}
+
+/// 承認・却下できるかどうか
+/// 送信先のギルドの，承認待ちのチャンネルで押されたボタンで，メッセージを管理できる人だけができる
+pub fn can_review(
+    pending: &UtPendingMirror,
+    guild_id: Option<u64>,
+    channel_id: u64,
+    permissions: Option<Permissions>,
+) -> bool {
+    guild_id == Some(pending.target_guild_id)
+        && channel_id == pending.review_channel_id
+        && permissions.is_some_and(|p| p.manage_messages())
+}
+
+/// 承認しても拡散できなかった理由
+enum ApprovalError {
+    /// もう拡散できない．承認待ちから外す
+    Gone(String),
+    /// もう一度承認すれば拡散できるかもしれない．承認待ちに戻す
+    Failed(String),
+}
+
+/// 承認と却下のボタンが押されたときの処理
+/// 承認・却下のボタンでなければ何もしない
+pub async fn handle_review(
+    ctx: &serenity::Context,
+    data: &Data,
+    interaction: &ComponentInteraction,
+) -> Result<()> {
+    let Some(action) = ReviewAction::from_custom_id(&interaction.data.custom_id) else {
+        return Ok(());
+    };
+
+    let review_message_id = interaction.message.id.get();
+    let Some(pending) = data
+        .pending_mirror_repository
+        .get_pending_mirror(review_message_id)
+        .await?
+    else {
+        interaction
+            .create_response(
+                ctx,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content("This post has already been reviewed.")
+                        .ephemeral(true),
+                ),
+            )
+            .await?;
+        return Ok(());
+    };
+
+    let permissions = interaction.member.as_ref().and_then(|m| m.permissions);
+    if !can_review(
+        &pending,
+        interaction.guild_id.map(|g| g.get()),
+        interaction.channel_id.get(),
+        permissions,
+    ) {
+        interaction
+            .create_response(
+                ctx,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(
+                            "Only moderators of the receiving guild who can manage messages can review incoming posts.",
+                        )
+                        .ephemeral(true),
+                ),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    // 送信に時間がかかることがあるので，先に応答しておく
+    interaction
+        .create_response(ctx, CreateInteractionResponse::Acknowledge)
+        .await?;
+
+    // 承認と却下が重なっても，片方だけが取り出せる
+    let Some(pending) = data
+        .pending_mirror_repository
+        .take_pending_mirror(review_message_id)
+        .await?
+    else {
+        interaction
+            .create_followup(
+                ctx,
+                CreateInteractionResponseFollowup::new()
+                    .content("This post has already been reviewed.")
+                    .ephemeral(true),
+            )
+            .await?;
+        return Ok(());
+    };
+
+    let reviewer = interaction.user.id;
+    // 承認待ちに戻したときは，まだ審査が済んでいないので記録しない
+    let mut audit_detail = Some(review_audit_detail(&pending, None));
+    let (status, components) = match action {
+        ReviewAction::Reject => (format!("Rejected by <@{}>.", reviewer), Vec::new()),
+        ReviewAction::Approve => match approve(ctx, data, &pending).await {
+            Ok(()) => (format!("Approved by <@{}>.", reviewer), Vec::new()),
+            Err(ApprovalError::Gone(reason)) => {
+                audit_detail = Some(review_audit_detail(&pending, Some(&reason)));
+                (
+                    format!("Could not deliver this post: {}", reason),
+                    Vec::new(),
+                )
+            }
+            Err(ApprovalError::Failed(reason)) => {
+                audit_detail = None;
+                // もう一度承認できるように戻す．送れた部分は配信ログに記録したので，やり直しでは送らない
+                data.pending_mirror_repository
+                    .insert_pending_mirror(pending.clone())
+                    .await?;
+                (
+                    format!(
+                        "Failed to deliver this post: {}\nPress Approve to try again.",
+                        reason
+                    ),
+                    review_buttons(),
+                )
+            }
+        },
+    };
+    info!(
+        "post reviewed. review_message_id: {}, reviewer_id: {}, action: {:?}, status: {}",
+        review_message_id, reviewer, action, status
+    );
+    if let Some(detail) = audit_detail {
+        record_audit(
+            &ctx.http,
+            data,
+            NewAuditEntry {
+                guild_id: pending.target_guild_id,
+                actor_id: Some(reviewer.get()),
+                action: action.audit_action(),
+                detail,
+                created_at: Timestamp::now().to_string(),
+            },
+        )
+        .await;
+    }
+
+    let content = format!("{}\n\n{}", interaction.message.content, status);
+    interaction
+        .edit_response(
+            ctx,
+            EditInteractionResponse::new()
+                .content(content)
+                .components(components),
+        )
+        .await?;
+    Ok(())
+}
+
+/// 発信元メッセージを取得しなおして，送信先へ拡散する
+async fn approve(
+    ctx: &serenity::Context,
+    data: &Data,
+    pending: &UtPendingMirror,
+) -> std::result::Result<(), ApprovalError> {
+    let time = data
+        .times_repository
+        .get_times(pending.user_id)
+        .await
+        .map_err(|e| ApprovalError::Failed(e.to_string()))?
+        .into_iter()
+        .find(|t| t.guild_id == pending.target_guild_id)
+        .ok_or_else(|| {
+            ApprovalError::Gone("the author no longer has a Times in this guild.".to_string())
+        })?;
+
+    // 承認を待つ間に，ギルドの間の拡散をやめたかもしれない
+    let policies = data
+        .guild_pair_policy_repository
+        .get_guild_pair_policies(vec![pending.source_guild_id, pending.target_guild_id])
+        .await
+        .map_err(|e| ApprovalError::Failed(e.to_string()))?;
+    if !is_pair_allowed(&policies, pending.source_guild_id, pending.target_guild_id) {
+        return Err(ApprovalError::Gone(
+            "mirroring between these guilds is blocked by guild policy.".to_string(),
+        ));
+    }
+
+    let mut message = ChannelId::new(pending.source_channel_id)
+        .message(ctx, MessageId::new(pending.source_message_id))
+        .await
+        .map_err(|e| ApprovalError::Gone(format!("the original post is not available: {}", e)))?;
+    // 拡散するときと同じように，発信元のギルドと投稿者を入れる
+    message.guild_id = Some(GuildId::new(pending.source_guild_id));
+    if let Ok(author) = UserId::new(pending.user_id).to_user(ctx).await {
+        message.author = author;
+    }
+
+    // 承認を待つ間にフィルタが変わったかもしれないので，承認したときのフィルタを当てはめる
+    let filters = data
+        .content_filter_repository
+        .get_content_filters(vec![pending.target_guild_id])
+        .await
+        .map_err(|e| ApprovalError::Failed(e.to_string()))?;
+    let filter_content = FilterContent::from_message(&message, pending.content.clone());
+    let delivery = plan_filtered_delivery(&filter_content, vec![time], &filters);
+    if let Some(blocked) = delivery
+        .outcomes
+        .iter()
+        .find(|o| o.action == FilterAction::Block)
+    {
+        return Err(ApprovalError::Gone(format!(
+            "blocked by the content filter: {}.",
+            blocked.filter
+        )));
+    }
+
+    let mut context = load_mirror_context(data, &message)
+        .await
+        .map_err(|e| ApprovalError::Failed(e.to_string()))?;
+    context.footer = mirror_footer(data, &message)
+        .await
+        .map_err(|e| ApprovalError::Failed(e.to_string()))?;
+    // 前の承認で途中まで送れていれば，送れた部分は送らない
+    context.sent_parts = data
+        .delivery_repository
+        .get_deliveries_by_source(pending.source_message_id)
+        .await
+        .map_err(|e| ApprovalError::Failed(e.to_string()))?
+        .into_iter()
+        .filter(|d| d.target_guild_id == pending.target_guild_id)
+        .map(|d| (d.target_guild_id, d.part))
+        .collect();
+    let report = send_filtered(
+        data.times_message_sender.as_ref(),
+        &message,
+        delivery,
+        context,
+    )
+    .await
+    .map_err(|e| ApprovalError::Failed(e.to_string()))?;
+
+    // 送信はできているので，記録できなくても承認は成功とする
+    if let Err(e) = data
+        .delivery_repository
+        .insert_deliveries(report.deliveries.clone())
+        .await
+    {
+        warn!("failed to record approved deliveries. error: {}", e);
+    }
+
+    match report.failures.first() {
+        Some(failure) => Err(ApprovalError::Failed(failure.reason.clone())),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests;
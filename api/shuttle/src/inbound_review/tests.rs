@@ -0,0 +1,117 @@
+use super::*;
+
+fn source() -> MessageLocation {
+    MessageLocation::new(1, 2, 3)
+}
+
+#[test]
+/// 承認と却下のボタンを読み取る
+fn test_parses_review_buttons() {
+    assert_eq!(
+        ReviewAction::from_custom_id(APPROVE_ID),
+        Some(ReviewAction::Approve)
+    );
+    assert_eq!(
+        ReviewAction::from_custom_id(REJECT_ID),
+        Some(ReviewAction::Reject)
+    );
+    assert_eq!(ReviewAction::from_custom_id("123:unregister:4"), None);
+}
+
+#[test]
+/// 承認を求めるメッセージには発信元へのリンクを載せる
+fn test_review_content_links_to_source() {
+    assert_eq!(
+        review_content("UT-c_user", "origin", &source(), "hello"),
+        "**Incoming post** from `UT-c_user` in **origin** is waiting for approval\nhttps://discord.com/channels/1/2/3\n>>> hello"
+    );
+}
+
+#[test]
+/// 長い投稿は切り詰めて載せる
+fn test_review_content_truncates_long_post() {
+    let content = "あ".repeat(PREVIEW_LIMIT + 10);
+
+    let review = review_content("UT-c_user", "origin", &source(), &content);
+
+    assert!(review.ends_with(&format!("{}…", "あ".repeat(PREVIEW_LIMIT))));
+    assert!(review.chars().count() < 2000);
+}
+
+#[test]
+/// 本文がないときは(no text)と載せる
+fn test_review_content_without_text() {
+    assert!(review_content("UT-c_user", "origin", &source(), " ").ends_with(">>> (no text)"));
+}
+
+fn pending() -> UtPendingMirror {
+    UtPendingMirror {
+        review_message_id: 10,
+        review_channel_id: 20,
+        source_message_id: 3,
+        source_guild_id: 1,
+        source_channel_id: 2,
+        user_id: 100,
+        target_guild_id: 30,
+        content: "hello".to_string(),
+    }
+}
+
+#[test]
+/// 送信先のギルドの承認待ちのチャンネルで，メッセージを管理できる人は承認できる
+fn test_can_review_in_approval_channel() {
+    assert!(can_review(
+        &pending(),
+        Some(30),
+        20,
+        Some(Permissions::MANAGE_MESSAGES)
+    ));
+}
+
+#[test]
+/// メッセージを管理できない人は承認できない
+fn test_can_review_requires_manage_messages() {
+    assert!(!can_review(
+        &pending(),
+        Some(30),
+        20,
+        Some(Permissions::SEND_MESSAGES)
+    ));
+    assert!(!can_review(&pending(), Some(30), 20, None));
+}
+
+#[test]
+/// 他のギルドやチャンネルで押されたボタンでは承認できない
+fn test_can_review_only_in_receiving_guild() {
+    let permissions = Some(Permissions::MANAGE_MESSAGES);
+
+    assert!(!can_review(&pending(), Some(1), 20, permissions));
+    assert!(!can_review(&pending(), None, 20, permissions));
+    assert!(!can_review(&pending(), Some(30), 21, permissions));
+}
+
+#[test]
+/// 監査ログには投稿者と発信元へのリンク，拡散できなかった理由を残す
+fn test_review_audit_detail() {
+    assert_eq!(
+        review_audit_detail(&pending(), None),
+        "post by <@100> https://discord.com/channels/1/2/3"
+    );
+    assert_eq!(
+        review_audit_detail(&pending(), Some("the original post is not available")),
+        "post by <@100> https://discord.com/channels/1/2/3 (not delivered: the original post is not available)"
+    );
+}
+
+#[test]
+/// 承認と却下を別の操作として記録する
+fn test_review_audit_action() {
+    assert_eq!(
+        ReviewAction::Approve.audit_action(),
+        AuditAction::ReviewApprove
+    );
+    assert_eq!(
+        ReviewAction::Reject.audit_action(),
+        AuditAction::ReviewReject
+    );
+}
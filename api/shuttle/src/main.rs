@@ -9,15 +9,60 @@ use shuttle_runtime::{CustomError, SecretStore};
 use shuttle_serenity::ShuttleSerenity;
 use sqlx::{Executor, PgPool};
 
+mod audit_log;
 mod commands;
+mod content_filter;
+mod data_export;
+mod delivery_report;
+mod discord_error;
+mod event_handler;
+mod forget_me;
+mod guild_pair_policy;
+mod inbound_policy;
+mod inbound_review;
+mod member_removal;
+mod mirror_context;
+mod mirror_deletion;
+mod mirror_feedback;
+mod mirror_footer;
 mod models;
+mod prefix;
+mod rate_limit;
+mod release_content;
+mod reply_bridge;
+mod takedown;
+mod times_unregister;
+mod times_webhook;
 mod ubiquitimes_user_name;
+mod webhook_api;
+mod webhook_health;
 mod webhook_name;
 
+#[cfg(test)]
+mod test_utils;
+
 use models::Data;
+use rate_limit::ReleaseRateLimiter;
 
+use repository::postgres_audit_log_channel_repository::PostgresAuditLogChannelRepository;
+use repository::postgres_audit_log_repository::PostgresAuditLogRepository;
+use repository::postgres_channel_webhook_repository::PostgresChannelWebhookRepository;
+use repository::postgres_content_filter_repository::PostgresContentFilterRepository;
+use repository::postgres_delivery_repository::PostgresDeliveryRepository;
+use repository::postgres_footer_settings_repository::PostgresFooterSettingsRepository;
+use repository::postgres_guild_pair_policy_repository::PostgresGuildPairPolicyRepository;
 use repository::postgres_guild_repository::PostgresGuildRepository;
+use repository::postgres_inbound_policy_repository::PostgresInboundPolicyRepository;
+use repository::postgres_member_leave_policy_repository::PostgresMemberLeavePolicyRepository;
+use repository::postgres_mirror_feedback_repository::PostgresMirrorFeedbackRepository;
+use repository::postgres_moderation_channel_repository::PostgresModerationChannelRepository;
+use repository::postgres_pending_mirror_repository::PostgresPendingMirrorRepository;
+use repository::postgres_rate_limit_repository::PostgresRateLimitRepository;
+use repository::postgres_reply_bridge_repository::PostgresReplyBridgeRepository;
+use repository::postgres_thread_follow_repository::PostgresThreadFollowRepository;
+use repository::postgres_time_removal_repository::PostgresTimeRemovalRepository;
 use repository::postgres_times_repository::PostgresTimesRepository;
+use repository::webhook_cipher::WebhookCipher;
 use tracing::info;
 
 #[shuttle_runtime::main]
@@ -37,9 +82,39 @@ async fn main(
         .get("DISCORD_TOKEN")
         .context("'DISCORD_TOKEN' was not found")?;
 
+    // WebhookのURLを暗号化する鍵
+    // {鍵のid}:{base64の32バイトの鍵}をカンマでつなぐ．最初の鍵で暗号化し，残りは古い鍵として復号に使う
+    let webhook_encryption_keys = secret_store
+        .get("WEBHOOK_ENCRYPTION_KEYS")
+        .context("'WEBHOOK_ENCRYPTION_KEYS' was not found")?;
+    let cipher =
+        Arc::new(WebhookCipher::from_config(&webhook_encryption_keys).map_err(CustomError::new)?);
+
+    // 暗号化する前の行と古い鍵で暗号化された行を，現在の鍵で暗号化しなおす
+    let times_repository = Arc::new(PostgresTimesRepository::new(pool.clone(), cipher.clone()));
+    let channel_webhook_repository =
+        Arc::new(PostgresChannelWebhookRepository::new(pool.clone(), cipher));
+    let encrypted = times_repository
+        .encrypt_webhook_urls()
+        .await
+        .map_err(CustomError::new)?
+        + channel_webhook_repository
+            .encrypt_webhook_urls()
+            .await
+            .map_err(CustomError::new)?;
+    info!(
+        "webhook urls encrypted with the current key. count: {}",
+        encrypted
+    );
+
     use commands::{
-        hello, help, register, ut_c_guild_init, ut_c_test, ut_c_times_delete, ut_c_times_release,
-        ut_c_times_set,
+        hello, help, register, ut_c_audit_log, ut_c_audit_log_channel, ut_c_export,
+        ut_c_filter_add, ut_c_filter_list, ut_c_filter_remove, ut_c_footer, ut_c_forget_me,
+        ut_c_guild_footer, ut_c_guild_init, ut_c_guild_policy, ut_c_guild_policy_list,
+        ut_c_inbound_allow, ut_c_inbound_policy, ut_c_inbound_show, ut_c_member_leave_policy,
+        ut_c_moderation_channel, ut_c_rate_limit, ut_c_reactions, ut_c_release_message,
+        ut_c_reply_bridge, ut_c_takedown, ut_c_test, ut_c_thread_follow, ut_c_times_delete,
+        ut_c_times_list, ut_c_times_release, ut_c_times_set, ut_c_times_unregister,
     };
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
@@ -47,22 +122,46 @@ async fn main(
                 hello(),
                 help(),
                 ut_c_guild_init(),
+                ut_c_guild_policy(),
+                ut_c_guild_policy_list(),
+                ut_c_inbound_policy(),
+                ut_c_inbound_allow(),
+                ut_c_inbound_show(),
+                ut_c_thread_follow(),
+                ut_c_filter_add(),
+                ut_c_filter_remove(),
+                ut_c_filter_list(),
+                ut_c_rate_limit(),
+                ut_c_member_leave_policy(),
+                ut_c_guild_footer(),
+                ut_c_reply_bridge(),
                 ut_c_times_set(),
                 ut_c_times_delete(),
+                ut_c_times_list(),
+                ut_c_times_unregister(),
+                ut_c_footer(),
+                ut_c_reactions(),
+                ut_c_export(),
+                ut_c_forget_me(),
                 ut_c_times_release(),
+                ut_c_release_message(),
+                ut_c_takedown(),
+                ut_c_moderation_channel(),
+                ut_c_audit_log(),
+                ut_c_audit_log_channel(),
                 register(),
                 ut_c_test(),
             ],
             // ここでprefixを設定する
             prefix_options: poise::PrefixFrameworkOptions {
-                prefix: Some("~".into()),
+                prefix: Some(prefix::PREFIX.into()),
                 edit_tracker: Some(Arc::new(poise::EditTracker::for_timespan(
                     Duration::from_secs(3600),
                 ))),
-                additional_prefixes: vec![
-                    poise::Prefix::Literal("hey bot"),
-                    poise::Prefix::Literal("hey bot,"),
-                ],
+                additional_prefixes: prefix::ADDITIONAL_PREFIXES
+                    .into_iter()
+                    .map(poise::Prefix::Literal)
+                    .collect(),
                 ..Default::default()
             },
             // This code is run before every command
@@ -79,6 +178,10 @@ async fn main(
                     info!("Executed command {}!", ctx.command().qualified_name);
                 })
             },
+            // 承認待ちのボタンなど，コマンド以外のイベントを扱う
+            event_handler: |ctx, event, _framework, data| {
+                Box::pin(event_handler::event_handler(ctx, event, data))
+            },
 
             ..Default::default()
         })
@@ -86,7 +189,33 @@ async fn main(
             // poolをcloneしてもよいのだろうか？
             // 不明である
             let guild_repository = Arc::new(PostgresGuildRepository::new(pool.clone()));
-            let times_repository = Arc::new(PostgresTimesRepository::new(pool));
+            let delivery_repository = Arc::new(PostgresDeliveryRepository::new(pool.clone()));
+            let inbound_policy_repository =
+                Arc::new(PostgresInboundPolicyRepository::new(pool.clone()));
+            let pending_mirror_repository =
+                Arc::new(PostgresPendingMirrorRepository::new(pool.clone()));
+            let guild_pair_policy_repository =
+                Arc::new(PostgresGuildPairPolicyRepository::new(pool.clone()));
+            let content_filter_repository =
+                Arc::new(PostgresContentFilterRepository::new(pool.clone()));
+            let rate_limit_repository = Arc::new(PostgresRateLimitRepository::new(pool.clone()));
+            let moderation_channel_repository =
+                Arc::new(PostgresModerationChannelRepository::new(pool.clone()));
+            let member_leave_policy_repository =
+                Arc::new(PostgresMemberLeavePolicyRepository::new(pool.clone()));
+            let time_removal_repository =
+                Arc::new(PostgresTimeRemovalRepository::new(pool.clone()));
+            let audit_log_repository = Arc::new(PostgresAuditLogRepository::new(pool.clone()));
+            let audit_log_channel_repository =
+                Arc::new(PostgresAuditLogChannelRepository::new(pool.clone()));
+            let footer_settings_repository =
+                Arc::new(PostgresFooterSettingsRepository::new(pool.clone()));
+            let mirror_feedback_repository =
+                Arc::new(PostgresMirrorFeedbackRepository::new(pool.clone()));
+            let reply_bridge_repository =
+                Arc::new(PostgresReplyBridgeRepository::new(pool.clone()));
+            let thread_follow_repository = Arc::new(PostgresThreadFollowRepository::new(pool));
+            let release_rate_limiter = Arc::new(ReleaseRateLimiter::default());
             let times_message_sender = Arc::new(PoiseWebhookMessageSender::new());
             Box::pin(async move {
                 poise::builtins::register_globally(ctx, &framework.options().commands).await?;
@@ -94,6 +223,23 @@ async fn main(
                     guild_repository,
                     times_repository,
                     times_message_sender,
+                    delivery_repository,
+                    channel_webhook_repository,
+                    inbound_policy_repository,
+                    pending_mirror_repository,
+                    guild_pair_policy_repository,
+                    content_filter_repository,
+                    rate_limit_repository,
+                    release_rate_limiter,
+                    moderation_channel_repository,
+                    member_leave_policy_repository,
+                    time_removal_repository,
+                    audit_log_repository,
+                    audit_log_channel_repository,
+                    footer_settings_repository,
+                    mirror_feedback_repository,
+                    reply_bridge_repository,
+                    thread_follow_repository,
                 })
             })
         })
@@ -103,7 +249,10 @@ async fn main(
         discord_token,
         GatewayIntents::non_privileged()
             | GatewayIntents::MESSAGE_CONTENT
-            | GatewayIntents::GUILD_WEBHOOKS,
+            | GatewayIntents::GUILD_WEBHOOKS
+            // BANされたメンバーとギルドを抜けたメンバーのTimesを削除するため
+            | GatewayIntents::GUILD_MODERATION
+            | GatewayIntents::GUILD_MEMBERS,
     )
     .framework(framework)
     .await
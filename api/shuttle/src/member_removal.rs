@@ -0,0 +1,127 @@
+//! BANされたメンバーと，ギルドを抜けたメンバーのTimesを削除する
+//!
+//! 登録が残っていると，保存したWebhookでそのギルドへ拡散し続けられてしまう
+//! BANされたら必ず削除し，抜けただけならギルドの設定に従う
+//! 削除した理由は記録して，ut_c_times_listで本人に伝える
+
+use std::sync::Arc;
+
+use domain::models::{
+    AuditAction, MemberLeaveAction, NewAuditEntry, TimeRemovalReason, UtMemberLeavePolicy,
+    UtTimeRemoval,
+};
+use domain::repository::{MemberLeavePolicyRepository, TimeRemovalRepository, TimesRepository};
+use poise::serenity_prelude::{Http, Timestamp};
+use tracing::{info, warn};
+
+use crate::audit_log::record_audit;
+use crate::models::{Data, UbiquiTimesCardiacResult as Result};
+use crate::times_unregister::{unregister_time_with, UnregisterReport};
+use crate::webhook_api::SerenityWebhookApi;
+
+/// 登録を削除するか
+/// 抜けたメンバーの登録は，設定していなければ残す
+pub fn should_unregister(reason: TimeRemovalReason, policy: Option<&UtMemberLeavePolicy>) -> bool {
+    match reason {
+        TimeRemovalReason::Banned => true,
+        TimeRemovalReason::Left => {
+            policy.is_some_and(|p| p.action == MemberLeaveAction::Unregister)
+        }
+    }
+}
+
+/// ギルドからいなくなったメンバーの，そのギルドのTimeの登録とWebhookを削除する
+///
+/// 登録がないか，設定で残す場合はNoneを返す
+/// Webhookの削除に失敗した場合は登録が残るので，記録もしない
+pub async fn remove_member_time(
+    http: Arc<Http>,
+    data: &Data,
+    guild_id: u64,
+    user_id: u64,
+    reason: TimeRemovalReason,
+) -> Result<Option<UnregisterReport>> {
+    let Some(time) = data
+        .times_repository
+        .get_times(user_id)
+        .await?
+        .into_iter()
+        .find(|t| t.guild_id == guild_id)
+    else {
+        return Ok(None);
+    };
+
+    let policy = match reason {
+        TimeRemovalReason::Banned => None,
+        TimeRemovalReason::Left => {
+            data.member_leave_policy_repository
+                .get_member_leave_policy(guild_id)
+                .await?
+        }
+    };
+    if !should_unregister(reason, policy.as_ref()) {
+        info!(
+            "member left but time kept by the guild policy. guild_id: {}, user_id: {}",
+            guild_id, user_id
+        );
+        return Ok(None);
+    }
+
+    let report =
+        unregister_time_with(&SerenityWebhookApi::from_http(http.clone()), data, &time).await;
+    if !report.is_complete() {
+        warn!(
+            "failed to remove time of member. guild_id: {}, user_id: {}, reason: {:?}, report: {:?}",
+            guild_id, user_id, reason, report
+        );
+        return Ok(Some(report));
+    }
+
+    let removed_at = Timestamp::now().to_string();
+    data.time_removal_repository
+        .upsert_time_removal(UtTimeRemoval {
+            user_id,
+            guild_id,
+            channel_id: time.channel_id,
+            reason,
+            removed_at: removed_at.clone(),
+        })
+        .await?;
+    let why = match reason {
+        TimeRemovalReason::Banned => "banned from the guild",
+        TimeRemovalReason::Left => "left the guild",
+    };
+    record_audit(
+        &http,
+        data,
+        NewAuditEntry {
+            guild_id,
+            actor_id: None,
+            action: AuditAction::TimeRemove,
+            detail: format!("<@{}> <#{}>: {}", user_id, time.channel_id, why),
+            created_at: removed_at,
+        },
+    )
+    .await;
+
+    info!(
+        "time of member removed. guild_id: {}, user_id: {}, reason: {:?}",
+        guild_id, user_id, reason
+    );
+    Ok(Some(report))
+}
+
+/// 登録を削除した記録を，本人に伝える文
+pub fn removal_text(removal: &UtTimeRemoval, guild_name: &str) -> String {
+    let reason = match removal.reason {
+        TimeRemovalReason::Banned => "you were banned from the guild",
+        TimeRemovalReason::Left => "you left the guild",
+    };
+    format!(
+        "- **{}** <#{}>: removed at {} because {}",
+        guild_name, removal.channel_id, removal.removed_at, reason
+    )
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,62 @@
+use super::*;
+
+fn policy(action: MemberLeaveAction) -> UtMemberLeavePolicy {
+    UtMemberLeavePolicy {
+        guild_id: 1,
+        action,
+    }
+}
+
+#[test]
+/// BANされたメンバーは，ポリシーに関わらず登録を解除する
+fn test_unregister_banned_member_regardless_of_policy() {
+    assert!(should_unregister(TimeRemovalReason::Banned, None));
+    assert!(should_unregister(
+        TimeRemovalReason::Banned,
+        Some(&policy(MemberLeaveAction::Keep))
+    ));
+}
+
+#[test]
+/// 退出したメンバーの登録は，初めは残す
+fn test_keep_member_who_left_by_default() {
+    assert!(!should_unregister(TimeRemovalReason::Left, None));
+}
+
+#[test]
+/// 退出したメンバーの登録は，ポリシーに従って扱う
+fn test_follow_policy_for_member_who_left() {
+    assert!(!should_unregister(
+        TimeRemovalReason::Left,
+        Some(&policy(MemberLeaveAction::Keep))
+    ));
+    assert!(should_unregister(
+        TimeRemovalReason::Left,
+        Some(&policy(MemberLeaveAction::Unregister))
+    ));
+}
+
+#[test]
+/// 解除した理由を伝える
+fn test_removal_text_tells_reason() {
+    let removal = UtTimeRemoval {
+        user_id: 1,
+        guild_id: 2,
+        channel_id: 3,
+        reason: TimeRemovalReason::Banned,
+        removed_at: "2024-01-01T00:00:00Z".to_string(),
+    };
+
+    assert_eq!(
+        removal_text(&removal, "guild"),
+        "- **guild** <#3>: removed at 2024-01-01T00:00:00Z because you were banned from the guild"
+    );
+    assert!(removal_text(
+        &UtTimeRemoval {
+            reason: TimeRemovalReason::Left,
+            ..removal
+        },
+        "guild"
+    )
+    .ends_with("because you left the guild"));
+}
@@ -0,0 +1,124 @@
+use domain::models::{MessageLocation, MirrorContext, UtDelivery, UtThreadFollowSetting};
+use domain::repository::{DeliveryRepository, ThreadFollowRepository};
+use poise::serenity_prelude::Message;
+
+use crate::models::{Data, UbiquiTimesCardiacResult as Result};
+
+/// 拡散先で対応するメッセージを探し，スレッドを追わないギルドのスレッドの起点を除く
+pub async fn load_mirror_context(data: &Data, message: &Message) -> Result<MirrorContext> {
+    let mut context = mirror_context(data.delivery_repository.as_ref(), message).await?;
+    if context.thread_starter.is_empty() {
+        return Ok(context);
+    }
+
+    let guild_ids = context
+        .thread_starter
+        .iter()
+        .map(|l| l.guild_id)
+        .collect::<Vec<_>>();
+    let settings = data
+        .thread_follow_repository
+        .get_thread_follow_settings(guild_ids)
+        .await?;
+    context.thread_starter = followed_threads(context.thread_starter, &settings);
+    Ok(context)
+}
+
+/// スレッドを追わないと設定したギルドのスレッドの起点を除く
+/// 設定していないギルドのものは残す
+pub fn followed_threads(
+    thread_starter: Vec<MessageLocation>,
+    settings: &[UtThreadFollowSetting],
+) -> Vec<MessageLocation> {
+    thread_starter
+        .into_iter()
+        .filter(|l| {
+            settings
+                .iter()
+                .find(|s| s.guild_id == l.guild_id)
+                .is_none_or(|s| s.follow_threads)
+        })
+        .collect()
+}
+
+/// 配信ログから，発信元メッセージの返信先とスレッドに対応する拡散先のメッセージを探す
+pub async fn mirror_context<R>(
+    delivery_repository: &R,
+    message: &Message,
+) -> std::result::Result<MirrorContext, R::Error>
+where
+    R: DeliveryRepository,
+{
+    let reply_to = match message
+        .message_reference
+        .as_ref()
+        .and_then(|r| r.message_id)
+    {
+        Some(parent_id) => parent_locations(delivery_repository, parent_id.get()).await?,
+        None => Vec::new(),
+    };
+
+    // メッセージから作られたスレッドのidは，起点のメッセージのidと同じ
+    // スレッドでないチャンネルのidで探しても，何も見つからない
+    let thread_starter = delivery_repository
+        .get_deliveries_by_source(message.channel_id.get())
+        .await?;
+    let thread_starter = first_parts(thread_starter);
+
+    Ok(MirrorContext {
+        reply_to,
+        thread_starter,
+        ..Default::default()
+    })
+}
+
+/// 返信先のメッセージに対応する，各ギルドのメッセージを探す
+///
+/// 返信先が拡散したメッセージの場合は，その拡散先を返す
+/// 返信先が他のギルドから拡散されてきたメッセージの場合は，その発信元と他の拡散先を返す
+async fn parent_locations<R>(
+    delivery_repository: &R,
+    parent_id: u64,
+) -> std::result::Result<Vec<MessageLocation>, R::Error>
+where
+    R: DeliveryRepository,
+{
+    let deliveries = delivery_repository
+        .get_deliveries_by_source(parent_id)
+        .await?;
+    if !deliveries.is_empty() {
+        return Ok(first_parts(deliveries));
+    }
+
+    let Some(mirrored) = delivery_repository
+        .get_delivery_by_mirror(parent_id)
+        .await?
+    else {
+        return Ok(Vec::new());
+    };
+
+    let source = MessageLocation::new(
+        mirrored.source_guild_id,
+        mirrored.source_channel_id,
+        mirrored.source_message_id,
+    );
+    let siblings = delivery_repository
+        .get_deliveries_by_source(mirrored.source_message_id)
+        .await?;
+
+    let mut locations = vec![source];
+    locations.extend(first_parts(siblings));
+    Ok(locations)
+}
+
+/// 分割して送ったものは，最初のメッセージを代表とする
+fn first_parts(deliveries: Vec<UtDelivery>) -> Vec<MessageLocation> {
+    deliveries
+        .into_iter()
+        .filter(|d| d.part == 0)
+        .map(|d| MessageLocation::new(d.target_guild_id, d.target_channel_id, d.mirror_message_id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,201 @@
+use super::*;
+use poise::serenity_prelude::{ChannelId, MessageId, MessageReference};
+
+/// 配信ログの代わりに，記録をベクタで持つ
+struct FakeDeliveryRepository {
+    deliveries: Vec<UtDelivery>,
+}
+
+impl DeliveryRepository for FakeDeliveryRepository {
+    type Error = String;
+
+    async fn insert_deliveries(
+        &self,
+        _deliveries: Vec<UtDelivery>,
+    ) -> std::result::Result<(), String> {
+        unreachable!()
+    }
+
+    async fn get_deliveries_by_source(
+        &self,
+        source_message_id: u64,
+    ) -> std::result::Result<Vec<UtDelivery>, String> {
+        Ok(self
+            .deliveries
+            .iter()
+            .filter(|d| d.source_message_id == source_message_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_deliveries_by_user(
+        &self,
+        _user_id: u64,
+    ) -> std::result::Result<Vec<UtDelivery>, String> {
+        unreachable!()
+    }
+
+    async fn delete_deliveries(
+        &self,
+        _mirror_message_ids: Vec<u64>,
+    ) -> std::result::Result<(), String> {
+        unreachable!()
+    }
+
+    async fn get_delivery_by_mirror(
+        &self,
+        mirror_message_id: u64,
+    ) -> std::result::Result<Option<UtDelivery>, String> {
+        Ok(self
+            .deliveries
+            .iter()
+            .find(|d| d.mirror_message_id == mirror_message_id)
+            .cloned())
+    }
+
+    async fn mark_released(
+        &self,
+        _source_message_id: u64,
+        _user_id: u64,
+    ) -> std::result::Result<(), String> {
+        unreachable!()
+    }
+
+    async fn is_released(&self, _source_message_id: u64) -> std::result::Result<bool, String> {
+        unreachable!()
+    }
+
+    async fn get_released_by_user(&self, _user_id: u64) -> std::result::Result<Vec<u64>, String> {
+        unreachable!()
+    }
+
+    async fn delete_released_by_user(&self, _user_id: u64) -> std::result::Result<(), String> {
+        unreachable!()
+    }
+}
+
+/// ギルド1のチャンネル2から拡散した記録
+fn delivery(
+    source_message_id: u64,
+    target_guild_id: u64,
+    mirror_message_id: u64,
+    part: u32,
+) -> UtDelivery {
+    UtDelivery {
+        source_message_id,
+        source_guild_id: 1,
+        source_channel_id: 2,
+        user_id: 3,
+        target_guild_id,
+        target_channel_id: target_guild_id * 10,
+        mirror_message_id,
+        part,
+    }
+}
+
+fn message(channel_id: u64, reply_to: Option<u64>) -> Message {
+    let mut message = Message::default();
+    message.channel_id = ChannelId::new(channel_id);
+    message.message_reference = reply_to
+        .map(|parent| MessageReference::from((ChannelId::new(channel_id), MessageId::new(parent))));
+    message
+}
+
+fn repository() -> FakeDeliveryRepository {
+    FakeDeliveryRepository {
+        deliveries: vec![
+            delivery(100, 7, 700, 0),
+            delivery(100, 7, 701, 1),
+            delivery(100, 8, 800, 0),
+        ],
+    }
+}
+
+#[tokio::test]
+/// 拡散したメッセージへの返信は，各ギルドの最初の部分を返信先にする
+async fn test_reply_to_released_message() {
+    let context = mirror_context(&repository(), &message(2, Some(100)))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        context.reply_to,
+        vec![
+            MessageLocation::new(7, 70, 700),
+            MessageLocation::new(8, 80, 800)
+        ]
+    );
+    assert!(context.thread_starter.is_empty());
+}
+
+#[tokio::test]
+/// 拡散されてきたメッセージへの返信は，その発信元と他の拡散先を返信先にする
+async fn test_reply_to_mirrored_message() {
+    let context = mirror_context(&repository(), &message(70, Some(701)))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        context.reply_to,
+        vec![
+            MessageLocation::new(1, 2, 100),
+            MessageLocation::new(7, 70, 700),
+            MessageLocation::new(8, 80, 800)
+        ]
+    );
+}
+
+#[tokio::test]
+/// 拡散したメッセージから作られたスレッドへの書き込みは，各ギルドのスレッドの起点を返す
+async fn test_thread_starter_from_delivery_log() {
+    let context = mirror_context(&repository(), &message(100, None))
+        .await
+        .unwrap();
+
+    assert!(context.reply_to.is_empty());
+    assert_eq!(
+        context.thread_starter,
+        vec![
+            MessageLocation::new(7, 70, 700),
+            MessageLocation::new(8, 80, 800)
+        ]
+    );
+}
+
+#[tokio::test]
+/// 配信ログにない返信先とチャンネルからは，何も見つからない
+async fn test_nothing_found_outside_delivery_log() {
+    let context = mirror_context(&repository(), &message(2, Some(999)))
+        .await
+        .unwrap();
+
+    assert_eq!(context, MirrorContext::default());
+}
+
+#[test]
+/// スレッドを追わないと設定したギルドだけ，スレッドの起点を除く
+fn test_followed_threads() {
+    let starters = vec![
+        MessageLocation::new(7, 70, 700),
+        MessageLocation::new(8, 80, 800),
+        MessageLocation::new(9, 90, 900),
+    ];
+    let settings = vec![
+        UtThreadFollowSetting {
+            guild_id: 7,
+            follow_threads: false,
+        },
+        UtThreadFollowSetting {
+            guild_id: 8,
+            follow_threads: true,
+        },
+    ];
+
+    assert_eq!(
+        followed_threads(starters, &settings),
+        vec![
+            MessageLocation::new(8, 80, 800),
+            MessageLocation::new(9, 90, 900)
+        ]
+    );
+}
@@ -0,0 +1,68 @@
+use domain::models::UtDelivery;
+use poise::serenity_prelude::{ChannelId, Http, MessageId, Webhook};
+
+use crate::discord_error::{discord_error_code, UNKNOWN_MESSAGE};
+
+/// 拡散先のメッセージの削除の結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MirrorDeletion {
+    Deleted,
+    /// すでに削除されていた
+    AlreadyDeleted,
+    Failed(String),
+}
+
+impl MirrorDeletion {
+    /// 拡散先にメッセージが残っていないか
+    pub fn is_gone(&self) -> bool {
+        matches!(
+            self,
+            MirrorDeletion::Deleted | MirrorDeletion::AlreadyDeleted
+        )
+    }
+}
+
+/// 拡散先のメッセージを削除する
+///
+/// 拡散に使ったWebhookがあれば，そのトークンで削除する．botに権限がなくても削除できる
+/// Webhookがない場合や，作り直す前のWebhookで送ったものは，botの権限で削除する
+pub async fn delete_mirror(
+    http: &Http,
+    webhook: Option<&Webhook>,
+    delivery: &UtDelivery,
+) -> MirrorDeletion {
+    delete_webhook_message(
+        http,
+        webhook,
+        ChannelId::new(delivery.target_channel_id),
+        MessageId::new(delivery.mirror_message_id),
+    )
+    .await
+}
+
+/// Webhookで送ったメッセージを削除する．channel_idはメッセージがあるチャンネルかスレッド
+pub async fn delete_webhook_message(
+    http: &Http,
+    webhook: Option<&Webhook>,
+    channel_id: ChannelId,
+    message_id: MessageId,
+) -> MirrorDeletion {
+    let result = match webhook {
+        Some(webhook) => {
+            // スレッドに送ったものは，スレッドを指定しないと削除できない
+            let thread_id = (webhook.channel_id != Some(channel_id)).then_some(channel_id);
+            match webhook.delete_message(http, thread_id, message_id).await {
+                Ok(()) => Ok(()),
+                // 別のWebhookで送ったものは見つからないので，削除済みかどうかはbotで確かめる
+                Err(_) => channel_id.delete_message(http, message_id).await,
+            }
+        }
+        None => channel_id.delete_message(http, message_id).await,
+    };
+
+    match result {
+        Ok(()) => MirrorDeletion::Deleted,
+        Err(e) if discord_error_code(&e) == Some(UNKNOWN_MESSAGE) => MirrorDeletion::AlreadyDeleted,
+        Err(e) => MirrorDeletion::Failed(e.to_string()),
+    }
+}
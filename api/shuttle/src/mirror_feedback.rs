@@ -0,0 +1,266 @@
+//! 拡散先のメッセージに付いたリアクションと返信を記録して，投稿者に知らせる
+//!
+//! 配信ログにある拡散先のメッセージだけを記録する
+//! リアクションは誰が付けたかを残さず，絵文字ごとの数だけを記録する
+//! botが止まっている間のリアクションと返信は数えない
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use domain::models::{MessageLocation, UtDelivery, UtMirrorReaction, UtMirrorReply};
+use domain::repository::{DeliveryRepository, MirrorFeedbackRepository};
+use poise::serenity_prelude::{Message, MessageId, Reaction};
+use tracing::info;
+
+use crate::models::{Data, UbiquiTimesCardiacResult as Result};
+
+/// リアクションが付いたときに，拡散先のメッセージなら数を増やす
+pub async fn record_reaction_add(data: &Data, reaction: &Reaction) -> Result<()> {
+    let mirror_message_id = reaction.message_id.get();
+    if !is_mirror(data, mirror_message_id).await? {
+        return Ok(());
+    }
+    data.mirror_feedback_repository
+        .add_mirror_reaction(mirror_message_id, reaction.emoji.to_string())
+        .await?;
+    Ok(())
+}
+
+/// リアクションが外されたときに，拡散先のメッセージなら数を減らす
+pub async fn record_reaction_remove(data: &Data, reaction: &Reaction) -> Result<()> {
+    let mirror_message_id = reaction.message_id.get();
+    if !is_mirror(data, mirror_message_id).await? {
+        return Ok(());
+    }
+    data.mirror_feedback_repository
+        .remove_mirror_reaction(mirror_message_id, reaction.emoji.to_string())
+        .await?;
+    Ok(())
+}
+
+/// リアクションがまとめて外されたときに，記録を消す
+/// emojiがNoneなら，すべての絵文字の記録を消す
+pub async fn record_reactions_cleared(
+    data: &Data,
+    message_id: MessageId,
+    emoji: Option<String>,
+) -> Result<()> {
+    data.mirror_feedback_repository
+        .clear_mirror_reactions(message_id.get(), emoji)
+        .await?;
+    Ok(())
+}
+
+/// 拡散先のメッセージへの返信を記録する
+/// 返信だった場合は，返信先の拡散先のメッセージの記録を返す
+///
+/// 拡散されたメッセージ(webhook)やbotの返信は数えない
+pub async fn record_reply(data: &Data, message: &Message) -> Result<Option<UtDelivery>> {
+    if message.webhook_id.is_some() || message.author.bot {
+        return Ok(None);
+    }
+    let (Some(guild_id), Some(parent_id)) = (
+        message.guild_id,
+        message
+            .message_reference
+            .as_ref()
+            .and_then(|r| r.message_id),
+    ) else {
+        return Ok(None);
+    };
+    let Some(delivery) = data
+        .delivery_repository
+        .get_delivery_by_mirror(parent_id.get())
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    data.mirror_feedback_repository
+        .insert_mirror_reply(UtMirrorReply {
+            reply_message_id: message.id.get(),
+            mirror_message_id: parent_id.get(),
+            guild_id: guild_id.get(),
+            channel_id: message.channel_id.get(),
+        })
+        .await?;
+    info!(
+        "mirror reply recorded. reply_message_id: {}, mirror_message_id: {}",
+        message.id, parent_id
+    );
+    Ok(Some(delivery))
+}
+
+/// 削除されたメッセージが返信として記録されていれば，その記録を消す
+pub async fn forget_replies(data: &Data, message_ids: &[MessageId]) -> Result<()> {
+    for message_id in message_ids {
+        data.mirror_feedback_repository
+            .delete_mirror_reply(message_id.get())
+            .await?;
+    }
+    Ok(())
+}
+
+async fn is_mirror(data: &Data, message_id: u64) -> Result<bool> {
+    Ok(data
+        .delivery_repository
+        .get_delivery_by_mirror(message_id)
+        .await?
+        .is_some())
+}
+
+/// 拡散先のギルドごとの反応
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuildFeedback {
+    pub guild_id: u64,
+    /// 絵文字と数．多い順
+    pub reactions: Vec<(String, u32)>,
+    /// 返信．古い順
+    pub replies: Vec<MessageLocation>,
+}
+
+/// 拡散したメッセージ1つへの反応
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostFeedback {
+    pub source: MessageLocation,
+    pub guilds: Vec<GuildFeedback>,
+}
+
+/// 配信ログとリアクション，返信の記録を，拡散したメッセージとギルドごとにまとめる
+///
+/// 新しい順にpost_count個のメッセージを返す
+/// 分割して送ったメッセージは，すべての部分の反応を合わせる
+pub fn aggregate_feedback(
+    deliveries: &[UtDelivery],
+    reactions: &[UtMirrorReaction],
+    replies: &[UtMirrorReply],
+    post_count: usize,
+) -> Vec<PostFeedback> {
+    // 拡散先のメッセージから，発信元のメッセージと拡散先のギルドを引く
+    let mirrors: BTreeMap<u64, (u64, u64)> = deliveries
+        .iter()
+        .map(|d| {
+            (
+                d.mirror_message_id,
+                (d.source_message_id, d.target_guild_id),
+            )
+        })
+        .collect();
+
+    let mut posts: BTreeMap<u64, (MessageLocation, BTreeSet<u64>)> = BTreeMap::new();
+    for d in deliveries {
+        posts
+            .entry(d.source_message_id)
+            .or_insert_with(|| {
+                (
+                    MessageLocation::new(
+                        d.source_guild_id,
+                        d.source_channel_id,
+                        d.source_message_id,
+                    ),
+                    BTreeSet::new(),
+                )
+            })
+            .1
+            .insert(d.target_guild_id);
+    }
+
+    let mut reaction_counts: BTreeMap<(u64, u64), BTreeMap<String, u32>> = BTreeMap::new();
+    for r in reactions {
+        if let Some(key) = mirrors.get(&r.mirror_message_id) {
+            *reaction_counts
+                .entry(*key)
+                .or_default()
+                .entry(r.emoji.clone())
+                .or_default() += r.count;
+        }
+    }
+
+    let mut reply_locations: BTreeMap<(u64, u64), Vec<MessageLocation>> = BTreeMap::new();
+    let mut sorted_replies = replies.iter().collect::<Vec<_>>();
+    sorted_replies.sort_by_key(|r| r.reply_message_id);
+    for r in sorted_replies {
+        if let Some(key) = mirrors.get(&r.mirror_message_id) {
+            reply_locations
+                .entry(*key)
+                .or_default()
+                .push(MessageLocation::new(
+                    r.guild_id,
+                    r.channel_id,
+                    r.reply_message_id,
+                ));
+        }
+    }
+
+    // メッセージのidは時刻順なので，大きいほど新しい
+    posts
+        .into_iter()
+        .rev()
+        .take(post_count)
+        .map(|(source_message_id, (source, guild_ids))| {
+            let guilds = guild_ids
+                .into_iter()
+                .map(|guild_id| {
+                    let key = (source_message_id, guild_id);
+                    let mut emoji_counts = reaction_counts
+                        .remove(&key)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    emoji_counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+                    GuildFeedback {
+                        guild_id,
+                        reactions: emoji_counts,
+                        replies: reply_locations.remove(&key).unwrap_or_default(),
+                    }
+                })
+                .collect();
+            PostFeedback { source, guilds }
+        })
+        .collect()
+}
+
+/// 投稿者に見せる反応のまとめ
+/// ギルドの名前が分からなければidで表示する
+pub fn feedback_text(posts: &[PostFeedback], guild_names: &BTreeMap<u64, String>) -> String {
+    if posts.is_empty() {
+        return "You have not released any posts yet.".to_string();
+    }
+
+    let mut lines = Vec::new();
+    for post in posts {
+        lines.push(format!("**Post** {}", post.source.jump_url()));
+        for guild in &post.guilds {
+            let name = guild_names
+                .get(&guild.guild_id)
+                .cloned()
+                .unwrap_or_else(|| guild.guild_id.to_string());
+            let mut parts = guild
+                .reactions
+                .iter()
+                .map(|(emoji, count)| format!("{} {}", emoji, count))
+                .collect::<Vec<_>>();
+            if !guild.replies.is_empty() {
+                let links = guild
+                    .replies
+                    .iter()
+                    .map(|r| r.jump_url())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let noun = if guild.replies.len() == 1 {
+                    "reply"
+                } else {
+                    "replies"
+                };
+                parts.push(format!("💬 {} {}: {}", guild.replies.len(), noun, links));
+            }
+            if parts.is_empty() {
+                parts.push("no reactions yet".to_string());
+            }
+            lines.push(format!("- **{}**: {}", name, parts.join(" · ")));
+        }
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,148 @@
+use super::*;
+
+fn delivery(source_message_id: u64, target_guild_id: u64, mirror_message_id: u64) -> UtDelivery {
+    UtDelivery {
+        source_message_id,
+        source_guild_id: 1,
+        source_channel_id: 2,
+        user_id: 3,
+        target_guild_id,
+        target_channel_id: target_guild_id * 10,
+        mirror_message_id,
+        part: 0,
+    }
+}
+
+fn reaction(mirror_message_id: u64, emoji: &str, count: u32) -> UtMirrorReaction {
+    UtMirrorReaction {
+        mirror_message_id,
+        emoji: emoji.to_string(),
+        count,
+    }
+}
+
+fn reply(reply_message_id: u64, mirror_message_id: u64, guild_id: u64) -> UtMirrorReply {
+    UtMirrorReply {
+        reply_message_id,
+        mirror_message_id,
+        guild_id,
+        channel_id: guild_id * 10,
+    }
+}
+
+#[test]
+/// リアクションと返信を投稿とギルドごとにまとめる
+fn test_feedback_is_grouped_by_post_and_guild() {
+    let deliveries = vec![delivery(100, 7, 700), delivery(100, 8, 800)];
+    let reactions = vec![
+        reaction(700, "🎉", 1),
+        reaction(700, "👍", 3),
+        reaction(800, "👀", 2),
+    ];
+    let replies = vec![reply(702, 700, 7), reply(701, 700, 7)];
+
+    let posts = aggregate_feedback(&deliveries, &reactions, &replies, 5);
+
+    assert_eq!(
+        posts,
+        vec![PostFeedback {
+            source: MessageLocation::new(1, 2, 100),
+            guilds: vec![
+                GuildFeedback {
+                    guild_id: 7,
+                    reactions: vec![("👍".to_string(), 3), ("🎉".to_string(), 1)],
+                    replies: vec![
+                        MessageLocation::new(7, 70, 701),
+                        MessageLocation::new(7, 70, 702)
+                    ],
+                },
+                GuildFeedback {
+                    guild_id: 8,
+                    reactions: vec![("👀".to_string(), 2)],
+                    replies: vec![],
+                },
+            ],
+        }]
+    );
+}
+
+#[test]
+/// 分割して送ったメッセージは1つの投稿として数える
+fn test_split_parts_are_counted_together() {
+    let mut second = delivery(100, 7, 701);
+    second.part = 1;
+    let deliveries = vec![delivery(100, 7, 700), second];
+    let reactions = vec![reaction(700, "👍", 1), reaction(701, "👍", 2)];
+
+    let posts = aggregate_feedback(&deliveries, &reactions, &[], 5);
+
+    assert_eq!(posts[0].guilds[0].reactions, vec![("👍".to_string(), 3)]);
+}
+
+#[test]
+/// 新しい投稿から順に，上限までを載せる
+fn test_newest_posts_come_first_and_are_limited() {
+    let deliveries = vec![
+        delivery(100, 7, 700),
+        delivery(300, 7, 702),
+        delivery(200, 7, 701),
+    ];
+
+    let posts = aggregate_feedback(&deliveries, &[], &[], 2);
+
+    let ids = posts
+        .iter()
+        .map(|p| p.source.message_id)
+        .collect::<Vec<_>>();
+    assert_eq!(ids, vec![300, 200]);
+}
+
+#[test]
+/// 記録のない拡散先メッセージへの反応は数えない
+fn test_feedback_for_unknown_mirrors_is_ignored() {
+    let deliveries = vec![delivery(100, 7, 700)];
+    let reactions = vec![reaction(999, "👍", 1)];
+    let replies = vec![reply(1000, 999, 7)];
+
+    let posts = aggregate_feedback(&deliveries, &reactions, &replies, 5);
+
+    assert!(posts[0].guilds[0].reactions.is_empty());
+    assert!(posts[0].guilds[0].replies.is_empty());
+}
+
+#[test]
+/// リアクションの数と返信へのリンクを載せる
+fn test_text_shows_reactions_and_reply_links() {
+    let posts = vec![PostFeedback {
+        source: MessageLocation::new(1, 2, 100),
+        guilds: vec![
+            GuildFeedback {
+                guild_id: 7,
+                reactions: vec![("👍".to_string(), 3)],
+                replies: vec![MessageLocation::new(7, 70, 701)],
+            },
+            GuildFeedback {
+                guild_id: 8,
+                reactions: vec![],
+                replies: vec![],
+            },
+        ],
+    }];
+    let guild_names = BTreeMap::from([(7, "seven".to_string())]);
+
+    assert_eq!(
+        feedback_text(&posts, &guild_names),
+        "**Post** https://discord.com/channels/1/2/100\n\
+         - **seven**: 👍 3 · 💬 1 reply: https://discord.com/channels/7/70/701\n\
+         - **8**: no reactions yet"
+    );
+}
+
+#[test]
+/// 投稿がないときはそのことを伝える
+fn test_text_without_posts() {
+    assert_eq!(
+        feedback_text(&[], &BTreeMap::new()),
+        "You have not released any posts yet."
+    );
+}
@@ -0,0 +1,74 @@
+//! 拡散したメッセージの最後に付ける，発信元の案内
+//!
+//! ユーザーが決めていればそれに従い，決めていなければ発信元のギルドの設定に従う
+//! 誰でも参加できるギルドの案内には，発信元のメッセージへのリンクを付ける
+//! メンバーだけのギルドのメッセージは拡散先の読み手が開けないので，ギルドの名前だけにする
+
+use domain::models::{
+    CommunityAccess, MessageLocation, UtGuildFooterSettings, UtUserFooterSetting,
+};
+use domain::repository::{FooterSettingsRepository, GuildRepository};
+use poise::serenity_prelude::Message;
+
+use crate::models::{Data, UbiquiTimesCardiacResult as Result};
+
+/// 案内を付けるか
+/// どちらも決めていなければ付けない
+pub fn footer_enabled(
+    user: Option<&UtUserFooterSetting>,
+    guild: Option<&UtGuildFooterSettings>,
+) -> bool {
+    match user {
+        Some(user) => user.enabled,
+        None => guild.is_some_and(|g| g.enabled),
+    }
+}
+
+/// 案内の文．Discordの小さい文字(-#)で表示する
+pub fn footer_text(guild_name: &str, source: &MessageLocation, access: CommunityAccess) -> String {
+    match access {
+        CommunityAccess::Open => format!(
+            "-# Mirrored from **{}** · [Jump to the original]({})",
+            guild_name,
+            source.jump_url()
+        ),
+        CommunityAccess::Closed => format!(
+            "-# Mirrored from **{}**, a members-only community",
+            guild_name
+        ),
+    }
+}
+
+/// 発信元のメッセージに付ける案内を，ユーザーとギルドの設定から作る
+/// 付けない場合はNone
+pub async fn mirror_footer(data: &Data, message: &Message) -> Result<Option<String>> {
+    let Some(guild_id) = message.guild_id.map(|g| g.get()) else {
+        return Ok(None);
+    };
+
+    let footer_settings_repository = data.footer_settings_repository.clone();
+    let user = footer_settings_repository
+        .get_user_footer_setting(message.author.id.get())
+        .await?;
+    let guild = footer_settings_repository
+        .get_guild_footer_settings(guild_id)
+        .await?;
+    if !footer_enabled(user.as_ref(), guild.as_ref()) {
+        return Ok(None);
+    }
+
+    // 登録したときの名前を使う．取得できなければidで表示する
+    let guild_name = data
+        .guild_repository
+        .get_guild(guild_id)
+        .await
+        .ok()
+        .and_then(|g| g.guild_name)
+        .unwrap_or_else(|| guild_id.to_string());
+    let source = MessageLocation::new(guild_id, message.channel_id.get(), message.id.get());
+    let access = guild.map(|g| g.access).unwrap_or_default();
+    Ok(Some(footer_text(&guild_name, &source, access)))
+}
+
+#[cfg(test)]
+mod tests;
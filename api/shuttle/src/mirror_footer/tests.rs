@@ -0,0 +1,61 @@
+use super::*;
+
+fn user(enabled: bool) -> UtUserFooterSetting {
+    UtUserFooterSetting {
+        user_id: 1,
+        enabled,
+    }
+}
+
+fn guild(enabled: bool) -> UtGuildFooterSettings {
+    UtGuildFooterSettings {
+        guild_id: 2,
+        enabled,
+        access: CommunityAccess::Open,
+    }
+}
+
+#[test]
+/// フッターは初めは付けない
+fn test_footer_is_off_by_default() {
+    assert!(!footer_enabled(None, None));
+}
+
+#[test]
+/// ユーザーが決めていなければギルドの設定に従う
+fn test_guild_setting_applies_when_user_has_not_decided() {
+    assert!(footer_enabled(None, Some(&guild(true))));
+    assert!(!footer_enabled(None, Some(&guild(false))));
+}
+
+#[test]
+/// ユーザーの設定をギルドの設定より優先する
+fn test_user_setting_overrides_guild_setting() {
+    assert!(footer_enabled(Some(&user(true)), Some(&guild(false))));
+    assert!(footer_enabled(Some(&user(true)), None));
+    assert!(!footer_enabled(Some(&user(false)), Some(&guild(true))));
+}
+
+#[test]
+/// 開かれたコミュニティでは，フッターに元の投稿へのリンクを載せる
+fn test_open_community_footer_links_to_the_original() {
+    let source = MessageLocation::new(2, 3, 4);
+
+    assert_eq!(
+        footer_text("guild", &source, CommunityAccess::Open),
+        "-# Mirrored from **guild** · [Jump to the original](https://discord.com/channels/2/3/4)"
+    );
+}
+
+#[test]
+/// 閉じたコミュニティでは，フッターにリンクを載せない
+fn test_closed_community_footer_hides_the_link() {
+    let source = MessageLocation::new(2, 3, 4);
+
+    let footer = footer_text("guild", &source, CommunityAccess::Closed);
+    assert_eq!(
+        footer,
+        "-# Mirrored from **guild**, a members-only community"
+    );
+    assert!(!footer.contains("discord.com"));
+}
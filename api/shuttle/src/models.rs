@@ -6,3 +6,6 @@ pub(crate) use error::UbiquiTimesCardiacError;
 pub(crate) use error::UbiquiTimesCardiacResult;
 
 pub(crate) type Context<'a> = poise::Context<'a, Data, UbiquiTimesCardiacError>;
+pub(crate) type ApplicationContext<'a> =
+    poise::ApplicationContext<'a, Data, UbiquiTimesCardiacError>;
+pub(crate) type PrefixContext<'a> = poise::PrefixContext<'a, Data, UbiquiTimesCardiacError>;
@@ -1,13 +1,49 @@
 use std::sync::Arc;
 
 use message_sender::poise_webhook_message_sender::PoiseWebhookMessageSender;
+use repository::postgres_audit_log_channel_repository::PostgresAuditLogChannelRepository;
+use repository::postgres_audit_log_repository::PostgresAuditLogRepository;
+use repository::postgres_channel_webhook_repository::PostgresChannelWebhookRepository;
+use repository::postgres_content_filter_repository::PostgresContentFilterRepository;
+use repository::postgres_delivery_repository::PostgresDeliveryRepository;
+use repository::postgres_footer_settings_repository::PostgresFooterSettingsRepository;
+use repository::postgres_guild_pair_policy_repository::PostgresGuildPairPolicyRepository;
 use repository::postgres_guild_repository::PostgresGuildRepository;
+use repository::postgres_inbound_policy_repository::PostgresInboundPolicyRepository;
+use repository::postgres_member_leave_policy_repository::PostgresMemberLeavePolicyRepository;
+use repository::postgres_mirror_feedback_repository::PostgresMirrorFeedbackRepository;
+use repository::postgres_moderation_channel_repository::PostgresModerationChannelRepository;
+use repository::postgres_pending_mirror_repository::PostgresPendingMirrorRepository;
+use repository::postgres_rate_limit_repository::PostgresRateLimitRepository;
+use repository::postgres_reply_bridge_repository::PostgresReplyBridgeRepository;
+use repository::postgres_thread_follow_repository::PostgresThreadFollowRepository;
+use repository::postgres_time_removal_repository::PostgresTimeRemovalRepository;
 use repository::postgres_times_repository::PostgresTimesRepository;
 
+use crate::rate_limit::ReleaseRateLimiter;
+
 // User data, which is stored and accessible in all command invocations
 // #[derive(Debug)]
 pub(crate) struct Data {
     pub guild_repository: Arc<PostgresGuildRepository>,
     pub times_repository: Arc<PostgresTimesRepository>,
     pub times_message_sender: Arc<PoiseWebhookMessageSender>,
+    pub delivery_repository: Arc<PostgresDeliveryRepository>,
+    pub channel_webhook_repository: Arc<PostgresChannelWebhookRepository>,
+    pub inbound_policy_repository: Arc<PostgresInboundPolicyRepository>,
+    pub pending_mirror_repository: Arc<PostgresPendingMirrorRepository>,
+    pub guild_pair_policy_repository: Arc<PostgresGuildPairPolicyRepository>,
+    pub content_filter_repository: Arc<PostgresContentFilterRepository>,
+    pub rate_limit_repository: Arc<PostgresRateLimitRepository>,
+    /// 拡散の回数の制限．メモリに置く
+    pub release_rate_limiter: Arc<ReleaseRateLimiter>,
+    pub moderation_channel_repository: Arc<PostgresModerationChannelRepository>,
+    pub member_leave_policy_repository: Arc<PostgresMemberLeavePolicyRepository>,
+    pub time_removal_repository: Arc<PostgresTimeRemovalRepository>,
+    pub audit_log_repository: Arc<PostgresAuditLogRepository>,
+    pub audit_log_channel_repository: Arc<PostgresAuditLogChannelRepository>,
+    pub footer_settings_repository: Arc<PostgresFooterSettingsRepository>,
+    pub mirror_feedback_repository: Arc<PostgresMirrorFeedbackRepository>,
+    pub reply_bridge_repository: Arc<PostgresReplyBridgeRepository>,
+    pub thread_follow_repository: Arc<PostgresThreadFollowRepository>,
 }
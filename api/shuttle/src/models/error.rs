@@ -2,7 +2,23 @@ use message_sender::poise_webhook_message_sender::PoiseWebhookMessageSenderError
 use poise::serenity_prelude::{self as serenity};
 
 use repository::{
+    postgres_audit_log_channel_repository::PostgresAuditLogChannelRepositoryError,
+    postgres_audit_log_repository::PostgresAuditLogRepositoryError,
+    postgres_channel_webhook_repository::PostgresChannelWebhookRepositoryError,
+    postgres_content_filter_repository::PostgresContentFilterRepositoryError,
+    postgres_delivery_repository::PostgresDeliveryRepositoryError,
+    postgres_footer_settings_repository::PostgresFooterSettingsRepositoryError,
+    postgres_guild_pair_policy_repository::PostgresGuildPairPolicyRepositoryError,
     postgres_guild_repository::PostgresGuildRepositoryError,
+    postgres_inbound_policy_repository::PostgresInboundPolicyRepositoryError,
+    postgres_member_leave_policy_repository::PostgresMemberLeavePolicyRepositoryError,
+    postgres_mirror_feedback_repository::PostgresMirrorFeedbackRepositoryError,
+    postgres_moderation_channel_repository::PostgresModerationChannelRepositoryError,
+    postgres_pending_mirror_repository::PostgresPendingMirrorRepositoryError,
+    postgres_rate_limit_repository::PostgresRateLimitRepositoryError,
+    postgres_reply_bridge_repository::PostgresReplyBridgeRepositoryError,
+    postgres_thread_follow_repository::PostgresThreadFollowRepositoryError,
+    postgres_time_removal_repository::PostgresTimeRemovalRepositoryError,
     postgres_times_repository::PostgresTimesRepositoryError,
 };
 use thiserror::Error;
@@ -19,6 +35,38 @@ pub enum UbiquiTimesCardiacError {
     GuildRepository(#[from] PostgresGuildRepositoryError),
     #[error("times repository error: {0}")]
     TimesRepository(#[from] PostgresTimesRepositoryError),
+    #[error("delivery repository error: {0}")]
+    DeliveryRepository(#[from] PostgresDeliveryRepositoryError),
+    #[error("channel webhook repository error: {0}")]
+    ChannelWebhookRepository(#[from] PostgresChannelWebhookRepositoryError),
+    #[error("inbound policy repository error: {0}")]
+    InboundPolicyRepository(#[from] PostgresInboundPolicyRepositoryError),
+    #[error("pending mirror repository error: {0}")]
+    PendingMirrorRepository(#[from] PostgresPendingMirrorRepositoryError),
+    #[error("guild pair policy repository error: {0}")]
+    GuildPairPolicyRepository(#[from] PostgresGuildPairPolicyRepositoryError),
+    #[error("content filter repository error: {0}")]
+    ContentFilterRepository(#[from] PostgresContentFilterRepositoryError),
+    #[error("rate limit repository error: {0}")]
+    RateLimitRepository(#[from] PostgresRateLimitRepositoryError),
+    #[error("moderation channel repository error: {0}")]
+    ModerationChannelRepository(#[from] PostgresModerationChannelRepositoryError),
+    #[error("member leave policy repository error: {0}")]
+    MemberLeavePolicyRepository(#[from] PostgresMemberLeavePolicyRepositoryError),
+    #[error("time removal repository error: {0}")]
+    TimeRemovalRepository(#[from] PostgresTimeRemovalRepositoryError),
+    #[error("audit log repository error: {0}")]
+    AuditLogRepository(#[from] PostgresAuditLogRepositoryError),
+    #[error("audit log channel repository error: {0}")]
+    AuditLogChannelRepository(#[from] PostgresAuditLogChannelRepositoryError),
+    #[error("footer settings repository error: {0}")]
+    FooterSettingsRepository(#[from] PostgresFooterSettingsRepositoryError),
+    #[error("mirror feedback repository error: {0}")]
+    MirrorFeedbackRepository(#[from] PostgresMirrorFeedbackRepositoryError),
+    #[error("reply bridge repository error: {0}")]
+    ReplyBridgeRepository(#[from] PostgresReplyBridgeRepositoryError),
+    #[error("thread follow repository error: {0}")]
+    ThreadFollowRepository(#[from] PostgresThreadFollowRepositoryError),
     #[error("guild get error: {0}")]
     GuildNotFound(#[from] GuildNotFound),
     #[error("user get error: {0}")]
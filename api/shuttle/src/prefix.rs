@@ -0,0 +1,5 @@
+/// プレフィックスコマンドのプレフィックス
+pub const PREFIX: &str = "~";
+
+/// PREFIXの他に使えるプレフィックス
+pub const ADDITIONAL_PREFIXES: [&str; 2] = ["hey bot", "hey bot,"];
@@ -0,0 +1,168 @@
+//! 拡散の回数をトークンバケットで制限する
+//!
+//! 拡散するユーザーごとと，送信先のギルドごとにバケットを持つ
+//! バケットはメモリに置くので，botを再起動すると満タンに戻る
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use domain::models::{RateLimit, UtRateLimitSettings, UtTime};
+use domain::repository::RateLimitRepository;
+
+// これより多くのバケットを持ったら，満タンに戻ったバケットを捨てる
+const PRUNE_THRESHOLD: usize = 10_000;
+
+// 使える回数を数える代わりに，次の1回分が戻る時刻を持つ(GCRA)．トークンバケットと同じように振る舞い，
+// 時間の計算をDurationのまま行えるので，待つ秒数がずれない
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    /// すべて戻って満タンになる時刻
+    full_at: Instant,
+}
+
+/// 1回分が戻るまでの時間と，期間
+fn interval_and_period(limit: RateLimit) -> (Duration, Duration) {
+    // 0回や0秒の設定では使えなくなるので，1以上にする
+    let period = Duration::from_secs(limit.period_secs.max(1) as u64);
+    (period / limit.capacity.max(1), period)
+}
+
+/// キーごとのトークンバケット
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<u64, Bucket>>,
+}
+
+/// 1回分を使った後に満タンになる時刻
+/// 使えなければ，次に使えるようになるまでの時間を返す
+fn next_full_at(
+    bucket: Option<&Bucket>,
+    limit: RateLimit,
+    now: Instant,
+) -> Result<Instant, Duration> {
+    let (interval, period) = interval_and_period(limit);
+    let full_at = bucket.map_or(now, |b| b.full_at.max(now)) + interval;
+    let limit_at = now + period;
+    if full_at > limit_at {
+        return Err(full_at - limit_at);
+    }
+    Ok(full_at)
+}
+
+impl RateLimiter {
+    /// 1回分を使う
+    /// 使えなければ，次に使えるようになるまでの時間を返す
+    pub fn try_acquire(&self, key: u64, limit: RateLimit, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() > PRUNE_THRESHOLD {
+            buckets.retain(|_, b| b.full_at > now);
+        }
+
+        let full_at = next_full_at(buckets.get(&key), limit, now)?;
+        buckets.insert(key, Bucket { full_at });
+        Ok(())
+    }
+
+    /// 1回分を使えるかを，使わずに調べる
+    /// 使えなければ，次に使えるようになるまでの時間を返す
+    pub fn check(&self, key: u64, limit: RateLimit, now: Instant) -> Result<(), Duration> {
+        let buckets = self.buckets.lock().unwrap();
+        next_full_at(buckets.get(&key), limit, now).map(|_| ())
+    }
+}
+
+/// 拡散の回数の制限
+#[derive(Debug, Default)]
+pub struct ReleaseRateLimiter {
+    /// 拡散するユーザーごと
+    pub users: RateLimiter,
+    /// 送信先のギルドごと
+    pub guilds: RateLimiter,
+}
+
+/// ギルドの設定．設定していなければ既定の上限を使う
+pub fn settings_for(settings: &[UtRateLimitSettings], guild_id: u64) -> UtRateLimitSettings {
+    settings
+        .iter()
+        .find(|s| s.guild_id == guild_id)
+        .copied()
+        .unwrap_or_else(|| UtRateLimitSettings::default_for(guild_id))
+}
+
+/// ギルドの設定を取得する．設定していなければ既定の上限を使う
+pub async fn load_rate_limit_settings<R>(
+    rate_limit_repository: &R,
+    guild_id: u64,
+) -> Result<UtRateLimitSettings, R::Error>
+where
+    R: RateLimitRepository,
+{
+    let settings = rate_limit_repository
+        .get_rate_limit_settings(vec![guild_id])
+        .await?;
+    Ok(settings_for(&settings, guild_id))
+}
+
+/// 送信先のギルドごとに1回分を使う
+/// 使えた送信先と，上限を超えた送信先のギルドと次に使えるようになるまでの時間に分ける
+/// 承認待ちの送信先のように，UtTimeに値を添えたものも扱えるように，time_ofでUtTimeを取り出す
+pub fn take_guild_tokens<T>(
+    limiter: &RateLimiter,
+    targets: Vec<T>,
+    time_of: impl Fn(&T) -> &UtTime,
+    settings: &[UtRateLimitSettings],
+    now: Instant,
+) -> (Vec<T>, Vec<(u64, Duration)>) {
+    let mut limited = Vec::new();
+    let targets = targets
+        .into_iter()
+        .filter(|t| {
+            let guild_id = time_of(t).guild_id;
+            let limit = settings_for(settings, guild_id).guild_limit;
+            match limiter.try_acquire(guild_id, limit, now) {
+                Ok(()) => true,
+                Err(retry_after) => {
+                    limited.push((guild_id, retry_after));
+                    false
+                }
+            }
+        })
+        .collect();
+    (targets, limited)
+}
+
+/// 待つ秒数．切り上げて，少なくとも1秒とする
+pub fn retry_secs(retry_after: Duration) -> u64 {
+    retry_after.as_secs_f64().ceil().max(1.0) as u64
+}
+
+/// ユーザーごとの上限を超えたときに返すメッセージ
+pub fn user_rate_limited_message(retry_after: Duration) -> String {
+    format!(
+        "You are releasing too fast. Please try again in {} s.",
+        retry_secs(retry_after)
+    )
+}
+
+/// 受け取る回数の上限を超えたので送らなかった送信先をユーザーに伝えるためのメッセージ
+///
+/// なければNoneを返す
+pub fn guild_rate_limited_message(limited: &[(u64, Duration)]) -> Option<String> {
+    if limited.is_empty() {
+        return None;
+    }
+
+    let mut lines = vec!["Some guilds are receiving too many posts right now".to_string()];
+    for (guild_id, retry_after) in limited.iter() {
+        lines.push(format!(
+            "- {}: try again in {} s",
+            guild_id,
+            retry_secs(*retry_after)
+        ));
+    }
+    Some(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,173 @@
+use super::*;
+use crate::test_utils::time;
+
+const PER_MINUTE: RateLimit = RateLimit {
+    capacity: 2,
+    period_secs: 60,
+};
+
+#[test]
+/// トークンがなくなるまで取り出せる
+fn test_acquire_until_empty() {
+    let limiter = RateLimiter::default();
+    let now = Instant::now();
+
+    assert_eq!(limiter.try_acquire(1, PER_MINUTE, now), Ok(()));
+    assert_eq!(limiter.try_acquire(1, PER_MINUTE, now), Ok(()));
+    // 2回で60秒なので，1回分戻るのは30秒後
+    assert_eq!(
+        limiter.try_acquire(1, PER_MINUTE, now),
+        Err(Duration::from_secs(30))
+    );
+}
+
+#[test]
+/// 調べるだけでは回数を使わない
+fn test_check_does_not_consume() {
+    let limiter = RateLimiter::default();
+    let now = Instant::now();
+
+    assert_eq!(limiter.check(1, PER_MINUTE, now), Ok(()));
+    assert_eq!(limiter.check(1, PER_MINUTE, now), Ok(()));
+    limiter.try_acquire(1, PER_MINUTE, now).unwrap();
+    limiter.try_acquire(1, PER_MINUTE, now).unwrap();
+    assert_eq!(
+        limiter.check(1, PER_MINUTE, now),
+        Err(Duration::from_secs(30))
+    );
+}
+
+#[test]
+/// キーごとに別々に数える
+fn test_keys_are_independent() {
+    let limiter = RateLimiter::default();
+    let now = Instant::now();
+
+    limiter.try_acquire(1, PER_MINUTE, now).unwrap();
+    limiter.try_acquire(1, PER_MINUTE, now).unwrap();
+    assert_eq!(limiter.try_acquire(2, PER_MINUTE, now), Ok(()));
+}
+
+#[test]
+/// 時間が経つとトークンが戻る
+fn test_tokens_refill_over_time() {
+    let limiter = RateLimiter::default();
+    let now = Instant::now();
+
+    limiter.try_acquire(1, PER_MINUTE, now).unwrap();
+    limiter.try_acquire(1, PER_MINUTE, now).unwrap();
+
+    let later = now + Duration::from_secs(20);
+    let retry_after = limiter.try_acquire(1, PER_MINUTE, later).unwrap_err();
+    assert_eq!(retry_secs(retry_after), 10);
+
+    let later = now + Duration::from_secs(30);
+    assert_eq!(limiter.try_acquire(1, PER_MINUTE, later), Ok(()));
+}
+
+#[test]
+/// トークンは上限を超えて貯まらない
+fn test_tokens_do_not_exceed_capacity() {
+    let limiter = RateLimiter::default();
+    let now = Instant::now();
+
+    limiter.try_acquire(1, PER_MINUTE, now).unwrap();
+    let later = now + Duration::from_secs(3600);
+    limiter.try_acquire(1, PER_MINUTE, later).unwrap();
+    limiter.try_acquire(1, PER_MINUTE, later).unwrap();
+    assert!(limiter.try_acquire(1, PER_MINUTE, later).is_err());
+}
+
+#[test]
+/// 上限を下げたら，すでにあるバケットにも当てはめる
+fn test_lowered_capacity_applies_to_existing_bucket() {
+    let limiter = RateLimiter::default();
+    let now = Instant::now();
+    let generous = RateLimit {
+        capacity: 10,
+        period_secs: 60,
+    };
+    let strict = RateLimit {
+        capacity: 1,
+        period_secs: 60,
+    };
+
+    limiter.try_acquire(1, generous, now).unwrap();
+    // 使った1回分が戻るまでは，厳しい上限では使えない
+    assert_eq!(
+        limiter.try_acquire(1, strict, now),
+        Err(Duration::from_secs(6))
+    );
+    assert_eq!(
+        limiter.try_acquire(1, strict, now + Duration::from_secs(6)),
+        Ok(())
+    );
+}
+
+#[test]
+/// 上限が0でも1回は拡散できる
+fn test_zero_limits_still_allow_one() {
+    let limiter = RateLimiter::default();
+    let zero = RateLimit {
+        capacity: 0,
+        period_secs: 0,
+    };
+    assert_eq!(limiter.try_acquire(1, zero, Instant::now()), Ok(()));
+}
+
+#[test]
+/// ギルドごとの設定に従ってトークンを取り出す
+fn test_take_guild_tokens_uses_guild_settings() {
+    let limiter = RateLimiter::default();
+    let now = Instant::now();
+    let settings = [UtRateLimitSettings {
+        guild_limit: RateLimit {
+            capacity: 1,
+            period_secs: 10,
+        },
+        ..UtRateLimitSettings::default_for(2)
+    }];
+
+    let (sent, limited) =
+        take_guild_tokens(&limiter, vec![time(2), time(3)], |t| t, &settings, now);
+    assert_eq!(sent, vec![time(2), time(3)]);
+    assert!(limited.is_empty());
+
+    let (sent, limited) = take_guild_tokens(
+        &limiter,
+        vec![(time(2), 20), (time(3), 30)],
+        |(t, _)| t,
+        &settings,
+        now,
+    );
+    assert_eq!(sent, vec![(time(3), 30)]);
+    assert_eq!(limited, vec![(2, Duration::from_secs(10))]);
+}
+
+#[test]
+/// 設定がなければ既定の上限を使う
+fn test_settings_default_when_not_configured() {
+    assert_eq!(settings_for(&[], 5), UtRateLimitSettings::default_for(5));
+}
+
+#[test]
+/// 待つ秒数は切り上げる
+fn test_retry_secs_rounds_up() {
+    assert_eq!(retry_secs(Duration::from_millis(100)), 1);
+    assert_eq!(retry_secs(Duration::from_millis(1500)), 2);
+    assert_eq!(retry_secs(Duration::from_secs(3)), 3);
+}
+
+#[test]
+/// 上限に達したことを伝えるメッセージ
+fn test_messages() {
+    assert_eq!(
+        user_rate_limited_message(Duration::from_millis(2500)),
+        "You are releasing too fast. Please try again in 3 s."
+    );
+    assert_eq!(guild_rate_limited_message(&[]), None);
+    assert_eq!(
+        guild_rate_limited_message(&[(2, Duration::from_secs(5))]).unwrap(),
+        "Some guilds are receiving too many posts right now\n- 2: try again in 5 s"
+    );
+}
@@ -0,0 +1,95 @@
+//! ~UTなどのプレフィックスコマンドのメッセージから，拡散する本文を取り出す
+
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ReleaseContentError {
+    #[error("message does not start with a release command")]
+    NotACommand,
+    #[error("message has no content to release")]
+    Empty,
+}
+
+/// プレフィックスとコマンド名を取り除いて，本文だけを取り出す
+///
+/// ```text
+/// ~UT
+///     インデントされた本文
+/// ```
+/// のように，コマンド名の後で改行した場合は，本文の先頭の空白をそのまま残す
+/// `~UT 本文` のように，同じ行に書いた場合はコマンド名との間の空白だけを取り除く
+#[derive(Debug, Clone)]
+pub struct ReleaseContentParser {
+    prefixes: Vec<String>,
+    command_names: Vec<String>,
+}
+
+impl ReleaseContentParser {
+    pub fn new<P, N>(prefixes: P, command_names: N) -> Self
+    where
+        P: IntoIterator,
+        P::Item: Into<String>,
+        N: IntoIterator,
+        N::Item: Into<String>,
+    {
+        // "hey bot"より先に"hey bot,"を試すように，長いものから順に並べる
+        let mut prefixes: Vec<String> = prefixes.into_iter().map(Into::into).collect();
+        prefixes.sort_by_key(|p| std::cmp::Reverse(p.len()));
+        let mut command_names: Vec<String> = command_names.into_iter().map(Into::into).collect();
+        command_names.sort_by_key(|n| std::cmp::Reverse(n.len()));
+
+        Self {
+            prefixes,
+            command_names,
+        }
+    }
+
+    pub fn parse(&self, content: &str) -> Result<String, ReleaseContentError> {
+        let rest = content.trim_start();
+        let rest = self
+            .prefixes
+            .iter()
+            .find_map(|p| rest.strip_prefix(p.as_str()))
+            .ok_or(ReleaseContentError::NotACommand)?;
+
+        let rest = rest.trim_start();
+        let rest = self
+            .command_names
+            .iter()
+            .find_map(|n| strip_command_name(rest, n))
+            .ok_or(ReleaseContentError::NotACommand)?;
+
+        // コマンド名と同じ行の空白を取り除き，改行があれば1つだけ取り除く
+        let rest = rest.trim_start_matches([' ', '\t']);
+        let rest = rest
+            .strip_prefix("\r\n")
+            .or_else(|| rest.strip_prefix('\n'))
+            .unwrap_or(rest);
+
+        let body = rest.replace("\r\n", "\n");
+        if body.trim().is_empty() {
+            return Err(ReleaseContentError::Empty);
+        }
+
+        Ok(body)
+    }
+}
+
+/// コマンド名を取り除く
+/// コマンド名の直後が空白か終端でなければ，別の単語とみなす
+fn strip_command_name<'a>(rest: &'a str, name: &str) -> Option<&'a str> {
+    let head = rest.get(..name.len())?;
+    if !head.eq_ignore_ascii_case(name) {
+        return None;
+    }
+
+    let tail = &rest[name.len()..];
+    match tail.chars().next() {
+        None => Some(tail),
+        Some(c) if c.is_whitespace() => Some(tail),
+        Some(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests;
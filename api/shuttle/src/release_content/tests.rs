@@ -0,0 +1,127 @@
+use super::*;
+use crate::prefix::{ADDITIONAL_PREFIXES, PREFIX};
+use proptest::prelude::*;
+
+const COMMAND_NAMES: [&str; 2] = ["ut_c_times_release", "UT"];
+
+fn parser() -> ReleaseContentParser {
+    let prefixes = std::iter::once(PREFIX).chain(ADDITIONAL_PREFIXES);
+    ReleaseContentParser::new(prefixes, COMMAND_NAMES)
+}
+
+fn prefix() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(vec![PREFIX, ADDITIONAL_PREFIXES[0], ADDITIONAL_PREFIXES[1]])
+}
+
+fn command_name() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(COMMAND_NAMES.to_vec())
+}
+
+fn line_break() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(vec!["\n", "\r\n", " \n", "\t\r\n"])
+}
+
+/// 空白以外の文字を含む本文
+/// 改行はLFのみとし，先頭の空白やコードブロックを含みうる
+fn body() -> impl Strategy<Value = String> {
+    "[ \t]{0,4}(```[a-z]{0,4}\n)?[^\r]{0,40}[^\\s][^\r]{0,40}"
+}
+
+/// 先頭が空白でない本文
+fn same_line_body() -> impl Strategy<Value = String> {
+    "[^\\s][^\r]{0,80}"
+}
+
+fn separator_before_command() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(vec!["", " ", "  "])
+}
+
+proptest! {
+    #[test]
+    /// コマンド名の後で改行した場合，本文はそのまま残る
+    fn test_body_after_line_break_is_preserved(
+        prefix in prefix(),
+        space in separator_before_command(),
+        name in command_name(),
+        line_break in line_break(),
+        body in body(),
+    ) {
+        let content = format!("{}{}{}{}{}", prefix, space, name, line_break, body);
+        prop_assert_eq!(parser().parse(&content), Ok(body));
+    }
+
+    #[test]
+    /// コマンド名と同じ行に書いた場合，間の空白だけが取り除かれる
+    fn test_body_on_same_line(
+        prefix in prefix(),
+        name in command_name(),
+        spaces in "[ \t]{1,3}",
+        body in same_line_body(),
+    ) {
+        let content = format!("{}{}{}{}", prefix, name, spaces, body);
+        prop_assert_eq!(parser().parse(&content), Ok(body));
+    }
+
+    #[test]
+    /// CRLFの改行はLFにそろえる
+    fn test_crlf_is_normalized(
+        prefix in prefix(),
+        name in command_name(),
+        body in body(),
+    ) {
+        let content = format!("{}{}\r\n{}", prefix, name, body.replace('\n', "\r\n"));
+        prop_assert_eq!(parser().parse(&content), Ok(body));
+    }
+
+    #[test]
+    /// 空白だけの投稿は拒否する
+    fn test_empty_body_is_rejected(
+        prefix in prefix(),
+        name in command_name(),
+        blank in "[ \t\r\n]{0,10}",
+    ) {
+        let content = format!("{}{}{}", prefix, name, blank);
+        prop_assert_eq!(parser().parse(&content), Err(ReleaseContentError::Empty));
+    }
+
+    #[test]
+    /// コマンド名の大文字小文字は区別しない
+    fn test_command_name_is_case_insensitive(
+        prefix in prefix(),
+        name in command_name(),
+        body in body(),
+    ) {
+        let content = format!("{}{}\n{}", prefix, name.to_lowercase(), body);
+        prop_assert_eq!(parser().parse(&content), Ok(body));
+    }
+
+    #[test]
+    /// どんな入力でもパニックしない
+    fn test_never_panics(content in "\\PC{0,100}") {
+        let _ = parser().parse(&content);
+    }
+}
+
+#[test]
+/// コマンド名の続きに文字がある場合は別のコマンドとみなす
+fn test_longer_word_is_not_a_command() {
+    assert_eq!(
+        parser().parse("~UTX\nbody"),
+        Err(ReleaseContentError::NotACommand)
+    );
+}
+
+#[test]
+/// プレフィックスがない場合はコマンドとみなさない
+fn test_missing_prefix() {
+    assert_eq!(
+        parser().parse("UT\nbody"),
+        Err(ReleaseContentError::NotACommand)
+    );
+}
+
+#[test]
+/// "hey bot,"のように，他のプレフィックスを含むものでも取り除ける
+fn test_overlapping_prefixes() {
+    assert_eq!(parser().parse("hey bot, UT body"), Ok("body".to_string()));
+}
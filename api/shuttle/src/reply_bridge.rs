@@ -0,0 +1,324 @@
+//! 拡散先での返信を，発信元のTimesへ届ける
+//!
+//! 返信したギルドと発信元のギルドの両方が同意したときだけ届ける
+//! 発信元のメッセージからスレッドを作り，そこへWebhookで投稿する
+//! 届けたメッセージはWebhookの投稿なので，返信として数えず，もう一度届けることもない
+//! 返信が削除されたら，届けたメッセージも削除する
+
+use domain::models::{
+    CommunityAccess, MessageLocation, UtDelivery, UtRelayedReply, UtReplyBridgeSettings,
+};
+use domain::repository::{
+    FooterSettingsRepository, GuildRepository, ReplyBridgeRepository, TimesRepository,
+};
+use message_sender::content_splitter::DISCORD_CONTENT_LIMIT;
+use poise::serenity_prelude::{ChannelId, CreateThread, Http, Message, MessageId, Webhook};
+use tracing::{info, warn};
+
+use crate::mirror_deletion::{delete_webhook_message, MirrorDeletion};
+use crate::models::{Data, UbiquiTimesCardiacResult as Result};
+use crate::ubiquitimes_user_name::ubiquitimes_user_name;
+
+// 発信元のメッセージから作るスレッドの名前
+const REPLY_THREAD_NAME: &str = "Replies from other communities";
+
+// Webhookの投稿者名の上限
+const WEBHOOK_USER_NAME_LIMIT: usize = 80;
+
+/// 返信を届けてよいか
+/// 返信したギルドが送ることと，発信元のギルドが受け取ることの両方に同意している必要がある
+pub fn bridge_allowed(
+    reply_guild: Option<&UtReplyBridgeSettings>,
+    origin_guild: Option<&UtReplyBridgeSettings>,
+) -> bool {
+    reply_guild.is_some_and(|s| s.send_replies) && origin_guild.is_some_and(|s| s.receive_replies)
+}
+
+/// 届ける返信の本文
+///
+/// スレッドへ届けられない場合は，発信元のメッセージへのリンクを先頭に付ける
+/// 返信したギルドがopenなら，返信へのリンクを付ける
+/// 長すぎる返信は，Discordの文字数制限に収まるように切り詰める
+pub fn relayed_reply_text(
+    body: &str,
+    guild_name: &str,
+    reply: &MessageLocation,
+    access: CommunityAccess,
+    reply_to: Option<&MessageLocation>,
+) -> String {
+    let header = match reply_to {
+        Some(source) => format!("> ↪ [Reply to]({})\n", source.jump_url()),
+        None => String::new(),
+    };
+    let footer = match access {
+        CommunityAccess::Open => format!(
+            "-# Reply from **{}** · [Jump to the reply]({})",
+            guild_name,
+            reply.jump_url()
+        ),
+        CommunityAccess::Closed => {
+            format!("-# Reply from **{}**, a members-only community", guild_name)
+        }
+    };
+
+    // 区切りの改行2つと，省略記号の分を残す
+    let room =
+        DISCORD_CONTENT_LIMIT.saturating_sub(header.chars().count() + footer.chars().count() + 3);
+    let body = if body.chars().count() > room {
+        format!("{}…", body.chars().take(room).collect::<String>())
+    } else {
+        body.to_string()
+    };
+
+    if body.is_empty() {
+        format!("{}{}", header, footer)
+    } else {
+        format!("{}{}\n\n{}", header, body, footer)
+    }
+}
+
+/// 届ける返信の投稿者名．拡散と同じプレフィックスを付ける
+pub fn relay_user_name(display_name: &str) -> String {
+    ubiquitimes_user_name(display_name.to_string())
+        .chars()
+        .take(WEBHOOK_USER_NAME_LIMIT)
+        .collect()
+}
+
+/// 返信の本文と，添付ファイルのURL
+fn reply_body(message: &Message) -> String {
+    let mut lines = Vec::new();
+    if !message.content.is_empty() {
+        lines.push(message.content.clone());
+    }
+    lines.extend(
+        message
+            .attachments
+            .iter()
+            .map(|a| format!("[{}]({})", a.filename, a.url)),
+    );
+    lines.join("\n")
+}
+
+/// 拡散先のメッセージへの返信を，発信元のTimesへ届ける
+///
+/// 同意がない場合や，発信元のTimesが登録されていない場合は何もしない
+/// Webhookで送れなかった場合は，ログに残して諦める
+pub async fn relay_reply(
+    http: &Http,
+    data: &Data,
+    message: &Message,
+    delivery: &UtDelivery,
+) -> Result<()> {
+    let Some(reply_guild_id) = message.guild_id.map(|g| g.get()) else {
+        return Ok(());
+    };
+    let origin_guild_id = delivery.source_guild_id;
+    if reply_guild_id == origin_guild_id {
+        return Ok(());
+    }
+
+    let reply_bridge_repository = data.reply_bridge_repository.clone();
+    let reply_settings = reply_bridge_repository
+        .get_reply_bridge_settings(reply_guild_id)
+        .await?;
+    let origin_settings = reply_bridge_repository
+        .get_reply_bridge_settings(origin_guild_id)
+        .await?;
+    if !bridge_allowed(reply_settings.as_ref(), origin_settings.as_ref()) {
+        return Ok(());
+    }
+
+    let Some(time) = data
+        .times_repository
+        .get_times(delivery.user_id)
+        .await?
+        .into_iter()
+        .find(|t| t.guild_id == origin_guild_id)
+    else {
+        info!(
+            "origin times not registered, reply not relayed. user_id: {}, guild_id: {}",
+            delivery.user_id, origin_guild_id
+        );
+        return Ok(());
+    };
+
+    // Webhookは登録したチャンネルのスレッドにしか投稿できない
+    let thread_id = if delivery.source_channel_id == time.channel_id {
+        reply_thread(http, delivery).await
+    } else {
+        None
+    };
+
+    let guild_name = data
+        .guild_repository
+        .get_guild(reply_guild_id)
+        .await
+        .ok()
+        .and_then(|g| g.guild_name)
+        .unwrap_or_else(|| reply_guild_id.to_string());
+    let access = data
+        .footer_settings_repository
+        .get_guild_footer_settings(reply_guild_id)
+        .await?
+        .map(|s| s.access)
+        .unwrap_or_default();
+    let reply = MessageLocation::new(reply_guild_id, message.channel_id.get(), message.id.get());
+    let source = MessageLocation::new(
+        origin_guild_id,
+        delivery.source_channel_id,
+        delivery.source_message_id,
+    );
+    let content = relayed_reply_text(
+        &reply_body(message),
+        &guild_name,
+        &reply,
+        access,
+        thread_id.is_none().then_some(&source),
+    );
+
+    let relayed = match data
+        .times_message_sender
+        .post_relayed_reply(
+            &time,
+            thread_id,
+            &relay_user_name(message.author.display_name()),
+            &message.author.face(),
+            &content,
+        )
+        .await
+    {
+        Ok(relayed) => relayed,
+        Err(e) => {
+            warn!(
+                "failed to relay reply. reply_message_id: {}, error: {}",
+                message.id, e
+            );
+            return Ok(());
+        }
+    };
+
+    let relayed = UtRelayedReply {
+        reply_message_id: message.id.get(),
+        author_id: message.author.id.get(),
+        user_id: time.user_id,
+        guild_id: time.guild_id,
+        channel_id: time.channel_id,
+        thread_id,
+        relayed_message_id: relayed.id.get(),
+    };
+
+    // 届けている間に登録が削除されていたら，記録を残さずに届けたメッセージを削除する
+    // 記録を残すと，ut_c_forget_meで消したあとのTimesを指す記録になってしまう
+    let still_registered = data
+        .times_repository
+        .get_times(time.user_id)
+        .await?
+        .iter()
+        .any(|t| t.guild_id == time.guild_id && t.channel_id == time.channel_id);
+    if !still_registered {
+        let deletion = delete_relayed_message(http, data, &relayed).await?;
+        info!(
+            "origin times unregistered while relaying, relayed reply removed. relayed_message_id: {}, result: {:?}",
+            relayed.relayed_message_id, deletion
+        );
+        return Ok(());
+    }
+
+    reply_bridge_repository
+        .insert_relayed_reply(relayed)
+        .await?;
+    Ok(())
+}
+
+/// 発信元のメッセージのスレッドを探し，なければ作る
+/// メッセージから作ったスレッドのidは，メッセージのidと同じ
+/// 作れなかった場合はNone
+async fn reply_thread(http: &Http, delivery: &UtDelivery) -> Option<u64> {
+    let thread_id = ChannelId::new(delivery.source_message_id);
+    if http.get_channel(thread_id).await.is_ok() {
+        return Some(thread_id.get());
+    }
+
+    match ChannelId::new(delivery.source_channel_id)
+        .create_thread_from_message(
+            http,
+            MessageId::new(delivery.source_message_id),
+            CreateThread::new(REPLY_THREAD_NAME),
+        )
+        .await
+    {
+        Ok(thread) => Some(thread.id.get()),
+        Err(e) => {
+            info!(
+                "could not create reply thread, relay to channel. source_message_id: {}, error: {}",
+                delivery.source_message_id, e
+            );
+            None
+        }
+    }
+}
+
+/// 届けた返信を削除する
+/// 発信元のTimesの登録が残っていればそのWebhookで，なければbotの権限で削除する
+pub async fn delete_relayed_message(
+    http: &Http,
+    data: &Data,
+    relayed: &UtRelayedReply,
+) -> Result<MirrorDeletion> {
+    let time = data
+        .times_repository
+        .get_times(relayed.user_id)
+        .await?
+        .into_iter()
+        .find(|t| t.guild_id == relayed.guild_id);
+    let webhook = match time {
+        Some(time) => Webhook::from_url(http, time.webhook_url.expose_secret())
+            .await
+            .ok(),
+        None => None,
+    };
+
+    let channel_id = ChannelId::new(relayed.thread_id.unwrap_or(relayed.channel_id));
+    Ok(delete_webhook_message(
+        http,
+        webhook.as_ref(),
+        channel_id,
+        MessageId::new(relayed.relayed_message_id),
+    )
+    .await)
+}
+
+/// 削除された返信を届けていれば，届けたメッセージも削除する
+/// 削除できなくても，記録は消す
+pub async fn retract_relayed_replies(
+    http: &Http,
+    data: &Data,
+    message_ids: &[MessageId],
+) -> Result<()> {
+    let reply_bridge_repository = data.reply_bridge_repository.clone();
+    for message_id in message_ids {
+        let Some(relayed) = reply_bridge_repository
+            .get_relayed_reply(message_id.get())
+            .await?
+        else {
+            continue;
+        };
+
+        if let MirrorDeletion::Failed(reason) = delete_relayed_message(http, data, &relayed).await?
+        {
+            warn!(
+                "failed to delete relayed reply. relayed_message_id: {}, error: {}",
+                relayed.relayed_message_id, reason
+            );
+        }
+
+        reply_bridge_repository
+            .delete_relayed_reply(message_id.get())
+            .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,91 @@
+use super::*;
+
+fn settings(send_replies: bool, receive_replies: bool) -> UtReplyBridgeSettings {
+    UtReplyBridgeSettings {
+        guild_id: 1,
+        send_replies,
+        receive_replies,
+    }
+}
+
+#[test]
+/// 返信の中継には両方のギルドの同意が必要
+fn test_bridge_needs_consent_from_both_guilds() {
+    assert!(bridge_allowed(
+        Some(&settings(true, false)),
+        Some(&settings(false, true))
+    ));
+    assert!(!bridge_allowed(
+        Some(&settings(false, true)),
+        Some(&settings(false, true))
+    ));
+    assert!(!bridge_allowed(
+        Some(&settings(true, false)),
+        Some(&settings(true, false))
+    ));
+}
+
+#[test]
+/// 返信の中継は初めは行わない
+fn test_bridge_is_off_by_default() {
+    assert!(!bridge_allowed(None, Some(&settings(true, true))));
+    assert!(!bridge_allowed(Some(&settings(true, true)), None));
+}
+
+#[test]
+/// 開かれたギルドからの返信には，返信へのリンクを載せる
+fn test_open_guild_reply_links_back_to_the_reply() {
+    let reply = MessageLocation::new(7, 70, 700);
+    assert_eq!(
+        relayed_reply_text("nice!", "seven", &reply, CommunityAccess::Open, None),
+        "nice!\n\n-# Reply from **seven** · [Jump to the reply](https://discord.com/channels/7/70/700)"
+    );
+}
+
+#[test]
+/// 閉じたギルドからの返信にはリンクを載せない
+fn test_closed_guild_reply_has_no_link() {
+    let reply = MessageLocation::new(7, 70, 700);
+    assert_eq!(
+        relayed_reply_text("nice!", "seven", &reply, CommunityAccess::Closed, None),
+        "nice!\n\n-# Reply from **seven**, a members-only community"
+    );
+}
+
+#[test]
+/// スレッドの外では元の投稿へのリンクを載せる
+fn test_reply_outside_thread_links_to_the_original() {
+    let reply = MessageLocation::new(7, 70, 700);
+    let source = MessageLocation::new(1, 2, 3);
+    assert_eq!(
+        relayed_reply_text(
+            "nice!",
+            "seven",
+            &reply,
+            CommunityAccess::Closed,
+            Some(&source)
+        ),
+        "> ↪ [Reply to](https://discord.com/channels/1/2/3)\nnice!\n\n-# Reply from **seven**, a members-only community"
+    );
+}
+
+#[test]
+/// 長い返信は上限まで切り詰める
+fn test_long_reply_is_truncated_to_the_limit() {
+    let reply = MessageLocation::new(7, 70, 700);
+    let body = "あ".repeat(DISCORD_CONTENT_LIMIT);
+    let text = relayed_reply_text(&body, "seven", &reply, CommunityAccess::Open, None);
+
+    assert!(text.chars().count() <= DISCORD_CONTENT_LIMIT);
+    assert!(text.contains("…\n\n-# Reply from **seven**"));
+}
+
+#[test]
+/// 中継するユーザー名には印を付け，長さを制限する
+fn test_relay_user_name_is_prefixed_and_limited() {
+    assert_eq!(relay_user_name("alice"), "UT-alice");
+    assert_eq!(
+        relay_user_name(&"a".repeat(100)).chars().count(),
+        WEBHOOK_USER_NAME_LIMIT
+    );
+}
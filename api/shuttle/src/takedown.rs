@@ -0,0 +1,325 @@
+//! モデレーターが，拡散されたメッセージを取り下げる
+//!
+//! 配信ログから発信元メッセージを探す
+//! 発信元のギルドのモデレーターは，すべての拡散先のメッセージを取り下げられる
+//! 拡散先のギルドのモデレーターは，そのギルドのメッセージだけを削除して，発信元のギルドに知らせられる
+//! 知らせたメッセージのボタンは，botを再起動しても使えるように，コレクターではなくイベントで受け取る
+
+use std::collections::BTreeMap;
+
+use domain::models::{
+    AuditAction, MessageLocation, NewAuditEntry, UtDelivery, UtModerationChannel,
+};
+use domain::repository::{DeliveryRepository, ModerationChannelRepository, TimesRepository};
+use poise::serenity_prelude::{
+    self as serenity, ButtonStyle, ChannelId, ComponentInteraction, CreateActionRow, CreateButton,
+    CreateInteractionResponse, CreateInteractionResponseFollowup, CreateInteractionResponseMessage,
+    CreateMessage, EditInteractionResponse, Http, Timestamp, UserId, Webhook,
+};
+use tracing::info;
+
+use crate::audit_log::record_audit;
+use crate::mirror_deletion::delete_mirror;
+use crate::models::{Data, UbiquiTimesCardiacResult as Result};
+
+// 発信元のメッセージのidを後ろに付ける
+const RETRACT_ID_PREFIX: &str = "ut_c_takedown:retract:";
+
+/// 取り下げるメッセージ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TakedownTarget {
+    /// 発信元のメッセージ．すべての拡散先から取り下げる
+    Origin { source_message_id: u64 },
+    /// 拡散されてきたメッセージ．このギルドのものだけを削除する
+    Mirror(UtDelivery),
+}
+
+/// 実行したギルドでのメッセージの立場を，配信ログから決める
+///
+/// by_mirrorは，メッセージを拡散先のメッセージとして探した記録
+/// by_sourceは，メッセージを発信元のメッセージとして探した記録
+pub fn locate_takedown(
+    guild_id: u64,
+    message_id: u64,
+    by_mirror: Option<UtDelivery>,
+    by_source: &[UtDelivery],
+) -> Option<TakedownTarget> {
+    if let Some(delivery) = by_mirror.filter(|d| d.target_guild_id == guild_id) {
+        return Some(TakedownTarget::Mirror(delivery));
+    }
+    by_source
+        .iter()
+        .any(|d| d.source_guild_id == guild_id && d.source_message_id == message_id)
+        .then_some(TakedownTarget::Origin {
+            source_message_id: message_id,
+        })
+}
+
+/// 取り下げの結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TakedownReport {
+    /// 削除した(すでに削除されていたものを含む)拡散先のメッセージの数
+    pub deleted: usize,
+    pub failed: usize,
+}
+
+impl TakedownReport {
+    pub fn message(&self) -> String {
+        let mut lines = Vec::new();
+        if self.deleted == 0 && self.failed == 0 {
+            lines.push("No mirrored copies were left.".to_string());
+        }
+        if self.deleted > 0 {
+            lines.push(format!("✅ {} mirrored messages deleted", self.deleted));
+        }
+        if self.failed > 0 {
+            lines.push(format!(
+                "❌ {} mirrored messages could not be deleted. Run this again to retry.",
+                self.failed
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// 監査ログに残す，取り下げた発信元メッセージと結果
+pub fn takedown_audit_detail(source: &MessageLocation, report: &TakedownReport) -> String {
+    format!(
+        "{} ({} deleted, {} failed)",
+        source.jump_url(),
+        report.deleted,
+        report.failed
+    )
+}
+
+/// 発信元メッセージの拡散先のメッセージを削除する
+/// target_guild_idを指定すれば，そのギルドのものだけを削除する
+///
+/// 削除できたものは配信ログからも消す．削除できなかったものは，やり直せるように残す
+pub async fn take_down(
+    http: &Http,
+    data: &Data,
+    source_message_id: u64,
+    target_guild_id: Option<u64>,
+) -> Result<TakedownReport> {
+    let deliveries = data
+        .delivery_repository
+        .get_deliveries_by_source(source_message_id)
+        .await?;
+
+    let mut by_guild: BTreeMap<u64, Vec<&UtDelivery>> = BTreeMap::new();
+    for delivery in deliveries
+        .iter()
+        .filter(|d| target_guild_id.is_none_or(|g| d.target_guild_id == g))
+    {
+        by_guild
+            .entry(delivery.target_guild_id)
+            .or_default()
+            .push(delivery);
+    }
+
+    // 拡散に使ったWebhookで削除すれば，botに権限がなくても削除できる
+    let times = match deliveries.first() {
+        Some(d) => data.times_repository.get_times(d.user_id).await?,
+        None => Vec::new(),
+    };
+
+    let mut report = TakedownReport::default();
+    let mut purge = Vec::new();
+    for (guild_id, deliveries) in by_guild {
+        let webhook_url = times
+            .iter()
+            .find(|t| t.guild_id == guild_id)
+            .map(|t| t.webhook_url.expose_secret());
+        let webhook = match webhook_url {
+            Some(url) => Webhook::from_url(http, url).await.ok(),
+            None => None,
+        };
+
+        for delivery in deliveries {
+            if delete_mirror(http, webhook.as_ref(), delivery)
+                .await
+                .is_gone()
+            {
+                report.deleted += 1;
+                purge.push(delivery.mirror_message_id);
+            } else {
+                report.failed += 1;
+            }
+        }
+    }
+
+    if !purge.is_empty() {
+        data.delivery_repository.delete_deliveries(purge).await?;
+    }
+
+    info!(
+        "mirrored messages taken down. source_message_id: {}, target_guild_id: {:?}, report: {:?}",
+        source_message_id, target_guild_id, report
+    );
+    Ok(report)
+}
+
+fn retract_id(source_message_id: u64) -> String {
+    format!("{}{}", RETRACT_ID_PREFIX, source_message_id)
+}
+
+/// 取り下げのボタンのidから，発信元のメッセージのidを取り出す
+pub fn parse_retract_id(custom_id: &str) -> Option<u64> {
+    custom_id.strip_prefix(RETRACT_ID_PREFIX)?.parse().ok()
+}
+
+/// 発信元のギルドのモデレーターに知らせる内容
+pub fn flag_content(
+    reporting_guild_name: &str,
+    moderator_id: u64,
+    source: &MessageLocation,
+) -> String {
+    format!(
+        "**Mirrored post flagged** by <@{}>, a moderator of **{}**\nThey removed their copy of this post:\n{}\nPress the button to retract every mirrored copy.",
+        moderator_id,
+        reporting_guild_name,
+        source.jump_url()
+    )
+}
+
+/// 拡散先のモデレーターが，発信元のギルドのモデレーター向けのチャンネルに知らせる
+///
+/// 発信元のギルドがチャンネルを設定していなければ，falseを返す
+pub async fn flag_to_origin(
+    http: &Http,
+    data: &Data,
+    delivery: &UtDelivery,
+    reporting_guild_name: &str,
+    moderator_id: UserId,
+) -> Result<bool> {
+    let Some(UtModerationChannel { channel_id, .. }) = data
+        .moderation_channel_repository
+        .get_moderation_channel(delivery.source_guild_id)
+        .await?
+    else {
+        return Ok(false);
+    };
+
+    let source = MessageLocation::new(
+        delivery.source_guild_id,
+        delivery.source_channel_id,
+        delivery.source_message_id,
+    );
+    ChannelId::new(channel_id)
+        .send_message(
+            http,
+            CreateMessage::new()
+                .content(flag_content(
+                    reporting_guild_name,
+                    moderator_id.get(),
+                    &source,
+                ))
+                .components(vec![CreateActionRow::Buttons(vec![CreateButton::new(
+                    retract_id(delivery.source_message_id),
+                )
+                .label("Retract all copies")
+                .style(ButtonStyle::Danger)])]),
+        )
+        .await?;
+
+    info!(
+        "mirrored post flagged to origin guild. source_message_id: {}, reporting_guild_id: {}",
+        delivery.source_message_id, delivery.target_guild_id
+    );
+    Ok(true)
+}
+
+/// 知らせたメッセージの取り下げのボタンが押されたときの処理
+/// 取り下げのボタンでなければ何もしない
+pub async fn handle_retract(
+    ctx: &serenity::Context,
+    data: &Data,
+    interaction: &ComponentInteraction,
+) -> Result<()> {
+    let Some(source_message_id) = parse_retract_id(&interaction.data.custom_id) else {
+        return Ok(());
+    };
+
+    // 発信元のギルドで，メッセージの管理権限を持つ人だけが取り下げられる
+    let can_retract = interaction
+        .member
+        .as_ref()
+        .and_then(|m| m.permissions)
+        .is_some_and(|p| p.manage_messages());
+    let deliveries = data
+        .delivery_repository
+        .get_deliveries_by_source(source_message_id)
+        .await?;
+    let in_origin_guild = deliveries
+        .first()
+        .is_none_or(|d| interaction.guild_id.map(|g| g.get()) == Some(d.source_guild_id));
+    if !can_retract || !in_origin_guild {
+        interaction
+            .create_response(
+                ctx,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content("Only moderators of the origin guild who can manage messages can retract this post.")
+                        .ephemeral(true),
+                ),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    // 削除に時間がかかることがあるので，先に応答しておく
+    interaction
+        .create_response(ctx, CreateInteractionResponse::Acknowledge)
+        .await?;
+
+    let report = take_down(&ctx.http, data, source_message_id, None).await?;
+    // 拡散先が残っていなければ何も取り下げていないので，記録しない
+    if let Some(d) = deliveries.first() {
+        let source =
+            MessageLocation::new(d.source_guild_id, d.source_channel_id, source_message_id);
+        record_audit(
+            &ctx.http,
+            data,
+            NewAuditEntry {
+                guild_id: d.source_guild_id,
+                actor_id: Some(interaction.user.id.get()),
+                action: AuditAction::Retract,
+                detail: takedown_audit_detail(&source, &report),
+                created_at: Timestamp::now().to_string(),
+            },
+        )
+        .await;
+    }
+    if report.failed > 0 {
+        // もう一度押せばやり直せるように，ボタンは残す
+        interaction
+            .create_followup(
+                ctx,
+                CreateInteractionResponseFollowup::new()
+                    .content(report.message())
+                    .ephemeral(true),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    let content = format!(
+        "{}\n\nRetracted by <@{}>.\n{}",
+        interaction.message.content,
+        interaction.user.id,
+        report.message()
+    );
+    interaction
+        .edit_response(
+            ctx,
+            EditInteractionResponse::new()
+                .content(content)
+                .components(Vec::new()),
+        )
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
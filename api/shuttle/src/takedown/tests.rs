@@ -0,0 +1,112 @@
+use super::*;
+
+const ORIGIN: u64 = 1;
+const TARGET: u64 = 2;
+const SOURCE_MESSAGE: u64 = 10;
+const MIRROR_MESSAGE: u64 = 20;
+
+fn delivery() -> UtDelivery {
+    UtDelivery {
+        source_message_id: SOURCE_MESSAGE,
+        source_guild_id: ORIGIN,
+        source_channel_id: 11,
+        user_id: 100,
+        target_guild_id: TARGET,
+        target_channel_id: 21,
+        mirror_message_id: MIRROR_MESSAGE,
+        part: 0,
+    }
+}
+
+#[test]
+/// 拡散先のギルドでは拡散先のメッセージを取り下げる
+fn test_mirror_in_receiving_guild() {
+    assert_eq!(
+        locate_takedown(TARGET, MIRROR_MESSAGE, Some(delivery()), &[]),
+        Some(TakedownTarget::Mirror(delivery()))
+    );
+}
+
+#[test]
+/// 発信元のギルドでは発信元のメッセージのすべての拡散先を取り下げる
+fn test_source_in_origin_guild() {
+    assert_eq!(
+        locate_takedown(ORIGIN, SOURCE_MESSAGE, None, &[delivery()]),
+        Some(TakedownTarget::Origin {
+            source_message_id: SOURCE_MESSAGE
+        })
+    );
+}
+
+#[test]
+/// 拡散に関わらないメッセージは取り下げない
+fn test_unrelated_message() {
+    assert_eq!(locate_takedown(ORIGIN, 99, None, &[]), None);
+}
+
+#[test]
+/// 他のギルドの記録は使わない
+fn test_records_of_other_guilds_are_ignored() {
+    assert_eq!(
+        locate_takedown(3, MIRROR_MESSAGE, Some(delivery()), &[]),
+        None
+    );
+    assert_eq!(
+        locate_takedown(3, SOURCE_MESSAGE, None, &[delivery()]),
+        None
+    );
+}
+
+#[test]
+/// 取り下げのボタンを読み取る
+fn test_parses_retract_button() {
+    assert_eq!(
+        parse_retract_id(&retract_id(18446744073709551615)),
+        Some(18446744073709551615)
+    );
+    assert_eq!(parse_retract_id("ut_c_review:approve"), None);
+    assert_eq!(parse_retract_id("ut_c_takedown:retract:x"), None);
+}
+
+#[test]
+/// 報告には報告したユーザー，ギルド，発信元へのリンクを載せる
+fn test_flag_content_links_to_source() {
+    let source = MessageLocation::new(ORIGIN, 11, SOURCE_MESSAGE);
+    let content = flag_content("receiving", 5, &source);
+    assert!(content.contains("<@5>"));
+    assert!(content.contains("**receiving**"));
+    assert!(content.contains("https://discord.com/channels/1/11/10"));
+}
+
+#[test]
+/// 取り下げた結果を伝える
+fn test_report_message() {
+    assert_eq!(
+        TakedownReport::default().message(),
+        "No mirrored copies were left."
+    );
+    assert_eq!(
+        TakedownReport {
+            deleted: 2,
+            failed: 1
+        }
+        .message(),
+        "✅ 2 mirrored messages deleted\n❌ 1 mirrored messages could not be deleted. Run this again to retry."
+    );
+}
+
+#[test]
+/// 監査ログには発信元へのリンクと取り下げた結果を残す
+fn test_takedown_audit_detail() {
+    let source = MessageLocation::new(ORIGIN, 11, SOURCE_MESSAGE);
+    assert_eq!(
+        takedown_audit_detail(
+            &source,
+            &TakedownReport {
+                deleted: 2,
+                failed: 1
+            }
+        ),
+        "https://discord.com/channels/1/11/10 (2 deleted, 1 failed)"
+    );
+}
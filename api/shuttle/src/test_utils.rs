@@ -0,0 +1,13 @@
+use domain::models::UtTime;
+
+// テストで使うTimesの登録
+// チャンネルとWebhookは，ギルドごとに異なるようにguild_idから作る
+pub(crate) fn time(guild_id: u64) -> UtTime {
+    UtTime::new(
+        100,
+        guild_id,
+        "UT-c_user".to_string(),
+        guild_id * 10,
+        format!("https://discord.com/api/webhooks/{}/token", guild_id).into(),
+    )
+}
@@ -0,0 +1,121 @@
+use domain::models::{AuditAction, UtTime};
+use domain::repository::TimesRepository;
+use tracing::{info, warn};
+
+use crate::audit_log::audit_by_author;
+use crate::models::{Context, Data};
+use crate::times_webhook::release_webhook;
+use crate::webhook_api::{SerenityWebhookApi, WebhookApi, WebhookDeletion};
+
+/// Timeの登録を削除した結果
+///
+/// Webhookの削除に失敗した場合は，DBの登録を残す
+/// 登録を残しておけば，後からもう一度削除を試せるため
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnregisterReport {
+    pub guild_id: u64,
+    pub webhook: WebhookDeletion,
+    /// DBの登録の削除の結果．Webhookの削除に失敗した場合は試さないのでNone
+    pub time: Option<Result<(), String>>,
+}
+
+impl UnregisterReport {
+    /// 各手順の結果をユーザーに伝えるためのメッセージ
+    pub fn steps(&self, guild_name: &str) -> String {
+        let webhook = match &self.webhook {
+            WebhookDeletion::Deleted => "✅ webhook deleted".to_string(),
+            WebhookDeletion::AlreadyDeleted => "✅ webhook was already deleted".to_string(),
+            WebhookDeletion::Failed(reason) => format!("❌ failed to delete webhook: {}", reason),
+            WebhookDeletion::InUse => {
+                "✅ webhook kept because other Times in the channel use it".to_string()
+            }
+        };
+        let time = match &self.time {
+            Some(Ok(())) => "✅ registration deleted".to_string(),
+            Some(Err(reason)) => format!("❌ failed to delete registration: {}", reason),
+            None => "⏸ registration kept so you can retry".to_string(),
+        };
+        format!("**{}**\n- {}\n- {}", guild_name, webhook, time)
+    }
+
+    /// stepsに，やり直しが必要な場合の案内を付けたもの
+    pub fn message(&self, guild_name: &str) -> String {
+        let mut message = self.steps(guild_name);
+        if !self.is_complete() {
+            message.push_str("\nPlease run the command again to retry.");
+        }
+        message
+    }
+
+    /// Webhookと登録の両方を削除できたか
+    /// Webhookだけ削除できた場合も，もう一度実行すればすでに削除済みとして扱われて登録を削除できる
+    pub fn is_complete(&self) -> bool {
+        matches!(self.time, Some(Ok(())))
+    }
+}
+
+/// Timeの登録を削除する
+///
+/// 先に保存されているURLを使ってWebhookを削除し，その後DBの登録を削除する
+/// WebhookのURLにはトークンが含まれるので，botがそのギルドにいなくても削除できる
+/// 同じチャンネルの他の登録が使っているWebhookは残す
+pub async fn unregister_time(ctx: Context<'_>, time: &UtTime) -> UnregisterReport {
+    let report = unregister_time_with(&SerenityWebhookApi::new(ctx), ctx.data(), time).await;
+    if report.is_complete() {
+        audit_by_author(
+            ctx,
+            time.guild_id,
+            AuditAction::TimeDelete,
+            format!("<#{}>", time.channel_id),
+        )
+        .await;
+    }
+    report
+}
+
+/// コマンドの外のイベントから，Timeの登録を削除する
+pub async fn unregister_time_with(
+    api: &impl WebhookApi,
+    data: &Data,
+    time: &UtTime,
+) -> UnregisterReport {
+    let times_repository = data.times_repository.clone();
+    let channel_webhook_repository = data.channel_webhook_repository.clone();
+    let webhook = release_webhook(
+        api,
+        times_repository.as_ref(),
+        channel_webhook_repository.as_ref(),
+        time,
+    )
+    .await;
+    if let WebhookDeletion::Failed(reason) = &webhook {
+        warn!(
+            "failed to delete webhook. keep the time. guild_id: {}, reason: {}",
+            time.guild_id, reason
+        );
+        return UnregisterReport {
+            guild_id: time.guild_id,
+            webhook,
+            time: None,
+        };
+    }
+
+    let deleted = times_repository
+        .delete_time(time.user_id, time.guild_id)
+        .await
+        .map_err(|e| e.to_string());
+
+    info!(
+        "time unregistered. guild_id: {}, user_id: {}, webhook: {:?}, time: {:?}",
+        time.guild_id, time.user_id, webhook, deleted
+    );
+
+    UnregisterReport {
+        guild_id: time.guild_id,
+        webhook,
+        time: Some(deleted),
+    }
+}
+
+#[cfg(test)]
+mod tests;
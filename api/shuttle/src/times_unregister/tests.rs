@@ -0,0 +1,65 @@
+use super::*;
+
+fn report(webhook: WebhookDeletion, time: Option<Result<(), String>>) -> UnregisterReport {
+    UnregisterReport {
+        guild_id: 1,
+        webhook,
+        time,
+    }
+}
+
+#[test]
+/// Webhookと登録を消せたら完了する
+fn test_complete_when_webhook_and_time_deleted() {
+    let report = report(WebhookDeletion::Deleted, Some(Ok(())));
+
+    assert!(report.is_complete());
+    assert_eq!(
+        report.message("guild"),
+        "**guild**\n- ✅ webhook deleted\n- ✅ registration deleted"
+    );
+}
+
+#[test]
+/// Webhookがすでに削除されていても完了する
+fn test_complete_when_webhook_already_deleted() {
+    let report = report(WebhookDeletion::AlreadyDeleted, Some(Ok(())));
+
+    assert!(report.is_complete());
+    assert!(report.message("guild").contains("already deleted"));
+}
+
+#[test]
+/// Webhookを削除できなかったら登録を残す
+fn test_keep_time_when_webhook_deletion_failed() {
+    let report = report(
+        WebhookDeletion::Failed("Missing Permissions".to_string()),
+        None,
+    );
+
+    assert!(!report.is_complete());
+    let message = report.message("guild");
+    assert!(message.contains("❌ failed to delete webhook: Missing Permissions"));
+    assert!(message.contains("registration kept"));
+    assert!(message.ends_with("run the command again to retry."));
+}
+
+#[test]
+/// 登録を消せなかったらやり直せるようにする
+fn test_retry_when_time_deletion_failed() {
+    let report = report(WebhookDeletion::Deleted, Some(Err("timeout".to_string())));
+
+    assert!(!report.is_complete());
+    let message = report.message("guild");
+    assert!(message.contains("❌ failed to delete registration: timeout"));
+    assert!(message.ends_with("run the command again to retry."));
+}
+
+#[test]
+/// 他の登録が使っているWebhookは削除せずに完了する
+fn test_complete_when_webhook_in_use() {
+    let report = report(WebhookDeletion::InUse, Some(Ok(())));
+
+    assert!(report.is_complete());
+    assert!(report.message("guild").contains("webhook kept"));
+}
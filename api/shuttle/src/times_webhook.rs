@@ -0,0 +1,301 @@
+use domain::models::{UtChannelWebhook, UtTime, WebhookUrl};
+use domain::repository::{ChannelWebhookRepository, TimesRepository};
+use poise::serenity_prelude::{self as serenity, ChannelId};
+use thiserror::Error;
+use tracing::{error, info, warn};
+
+use crate::models::{Context, UbiquiTimesCardiacResult as Result};
+use crate::webhook_api::{SerenityWebhookApi, WebhookApi, WebhookDeletion};
+use crate::webhook_name::{is_times_webhook_name, SHARED_WEBHOOK_NAME};
+
+/// Timesとして登録する内容
+/// WebhookのURLは登録の途中で決める
+#[derive(Debug, Clone)]
+pub struct NewTime {
+    pub user_id: u64,
+    pub guild_id: u64,
+    pub channel_id: ChannelId,
+    /// プレフィックスを付加済みのもの
+    pub user_name: String,
+}
+
+/// Webhookを用意して登録した結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rotation {
+    pub time: UtTime,
+    /// チャンネルにあったWebhookを使ったかどうか
+    pub reused: bool,
+    /// 古いWebhookの削除の結果．初めての登録か，同じWebhookを使い続ける場合はNone
+    pub old_webhook: Option<WebhookDeletion>,
+}
+
+#[derive(Debug, Error)]
+pub enum RotateWebhookError<E> {
+    #[error("failed to prepare webhook: {0}")]
+    PrepareWebhook(serenity::Error),
+    /// 作成したWebhookは削除済み．削除にも失敗した場合はそのまま残っている
+    #[error("failed to save time: {0}")]
+    SaveTime(E),
+}
+
+/// 登録に使うWebhook
+enum PreparedWebhook {
+    /// チャンネルにあったものを使う
+    Reused(WebhookUrl),
+    /// 新しく作成した
+    Created(WebhookUrl),
+}
+
+/// チャンネルで共有するWebhookを用意して登録し，古いWebhookを片付ける
+///
+/// 1. チャンネルにbotのWebhookがあれば使い，なければ作成する
+/// 2. 登録を保存する．失敗したら，作成したWebhookを削除して元に戻す
+/// 3. チャンネルとWebhookの対応を記録する
+/// 4. 古いWebhookを片付ける．登録は保存できているので，失敗しても登録は成功とする
+pub async fn rotate_webhook<A, R, C>(
+    api: &A,
+    times_repository: &R,
+    channel_webhook_repository: &C,
+    new_time: NewTime,
+) -> std::result::Result<Rotation, RotateWebhookError<R::Error>>
+where
+    A: WebhookApi,
+    R: TimesRepository,
+    R::Error: std::fmt::Display,
+    C: ChannelWebhookRepository,
+    C::Error: std::fmt::Display,
+{
+    let NewTime {
+        user_id,
+        guild_id,
+        channel_id,
+        user_name,
+    } = new_time;
+
+    let prepared = prepare_webhook(api, channel_webhook_repository, channel_id)
+        .await
+        .map_err(RotateWebhookError::PrepareWebhook)?;
+    let (webhook_url, reused) = match &prepared {
+        PreparedWebhook::Reused(url) => (url.clone(), true),
+        PreparedWebhook::Created(url) => (url.clone(), false),
+    };
+
+    let time = UtTime::new(
+        user_id,
+        guild_id,
+        user_name,
+        channel_id.get(),
+        webhook_url.clone(),
+    );
+
+    let old_time = match times_repository
+        .upsert_and_return_old_time(time.clone())
+        .await
+    {
+        Ok(old_time) => old_time,
+        Err(e) => {
+            warn!("failed to save time. guild_id: {}, error: {}", guild_id, e);
+            // 登録できなかったWebhookが残らないように削除する
+            // チャンネルにあったWebhookは，他の登録が使っているかもしれないので残す
+            if let PreparedWebhook::Created(url) = &prepared {
+                if let WebhookDeletion::Failed(reason) = api.delete_webhook(url).await {
+                    error!(
+                        "failed to delete the new webhook. it is leaked. guild_id: {}, channel_id: {}, reason: {}",
+                        guild_id, channel_id, reason
+                    );
+                }
+            }
+            return Err(RotateWebhookError::SaveTime(e));
+        }
+    };
+
+    // 対応の記録は次の登録でWebhookを探すときに使うだけなので，失敗しても登録は成功とする
+    let channel_webhook = UtChannelWebhook {
+        channel_id: channel_id.get(),
+        guild_id,
+        webhook_url: webhook_url.clone(),
+    };
+    if let Err(e) = channel_webhook_repository
+        .upsert_channel_webhook(channel_webhook)
+        .await
+    {
+        warn!(
+            "failed to save channel webhook. channel_id: {}, error: {}",
+            channel_id, e
+        );
+    }
+
+    // 作り直すのはWebhookが削除されてしまった場合が多いので，すでに存在しなければそのままでよい
+    let old_webhook = match old_time {
+        Some(old_time) if old_time.webhook_url != webhook_url => Some(
+            release_webhook(api, times_repository, channel_webhook_repository, &old_time).await,
+        ),
+        _ => None,
+    };
+
+    info!(
+        "new times set complete. guild_id: {}, user_id: {}, channel_id: {}, reused: {}, old_webhook: {:?}",
+        guild_id, user_id, channel_id, reused, old_webhook
+    );
+
+    Ok(Rotation {
+        time,
+        reused,
+        old_webhook,
+    })
+}
+
+/// チャンネルにあるbotのWebhookを探し，なければ作成する
+/// 記録してあるWebhookがまだあればそれを使い，なければUT-c_から始まるWebhookを使う
+async fn prepare_webhook<A, C>(
+    api: &A,
+    channel_webhook_repository: &C,
+    channel_id: ChannelId,
+) -> serenity::Result<PreparedWebhook>
+where
+    A: WebhookApi,
+    C: ChannelWebhookRepository,
+    C::Error: std::fmt::Display,
+{
+    let webhooks = api.channel_webhooks(channel_id).await?;
+
+    let recorded = match channel_webhook_repository
+        .get_channel_webhook(channel_id.get())
+        .await
+    {
+        Ok(recorded) => recorded.map(|w| w.webhook_url),
+        Err(e) => {
+            warn!(
+                "failed to get channel webhook. channel_id: {}, error: {}",
+                channel_id, e
+            );
+            None
+        }
+    };
+
+    let existing = webhooks
+        .iter()
+        .find(|w| Some(&w.url) == recorded.as_ref())
+        .or_else(|| webhooks.iter().find(|w| is_times_webhook_name(&w.name)));
+    if let Some(webhook) = existing {
+        return Ok(PreparedWebhook::Reused(webhook.url.clone()));
+    }
+
+    let url = api
+        .create_webhook(channel_id, SHARED_WEBHOOK_NAME.to_string())
+        .await?;
+    Ok(PreparedWebhook::Created(url))
+}
+
+/// 使わなくなる登録のWebhookを片付ける
+///
+/// 同じチャンネルの他の登録が同じWebhookを使っていれば残す
+/// 削除した場合は，チャンネルとWebhookの対応の記録も消す
+pub async fn release_webhook<A, R, C>(
+    api: &A,
+    times_repository: &R,
+    channel_webhook_repository: &C,
+    time: &UtTime,
+) -> WebhookDeletion
+where
+    A: WebhookApi,
+    R: TimesRepository,
+    R::Error: std::fmt::Display,
+    C: ChannelWebhookRepository,
+    C::Error: std::fmt::Display,
+{
+    let times = match times_repository.get_times_by_channel(time.channel_id).await {
+        Ok(times) => times,
+        // 他の登録が使っているかわからないまま削除すると，その登録が動かなくなるので残す
+        Err(e) => return WebhookDeletion::Failed(e.to_string()),
+    };
+    let in_use = times.iter().any(|t| {
+        (t.user_id, t.guild_id) != (time.user_id, time.guild_id)
+            && t.webhook_url == time.webhook_url
+    });
+    if in_use {
+        info!(
+            "webhook is in use by other times. channel_id: {}",
+            time.channel_id
+        );
+        return WebhookDeletion::InUse;
+    }
+
+    let deletion = api.delete_webhook(&time.webhook_url).await;
+    match &deletion {
+        WebhookDeletion::Deleted | WebhookDeletion::AlreadyDeleted => {
+            forget_channel_webhook(channel_webhook_repository, time).await
+        }
+        WebhookDeletion::Failed(reason) => warn!(
+            "failed to delete webhook. guild_id: {}, channel_id: {}, reason: {}",
+            time.guild_id, time.channel_id, reason
+        ),
+        WebhookDeletion::InUse => {}
+    }
+    deletion
+}
+
+/// 削除したWebhookがチャンネルの共有Webhookとして記録されていれば，その記録を消す
+async fn forget_channel_webhook<C>(channel_webhook_repository: &C, time: &UtTime)
+where
+    C: ChannelWebhookRepository,
+    C::Error: std::fmt::Display,
+{
+    let recorded = channel_webhook_repository
+        .get_channel_webhook(time.channel_id)
+        .await;
+    let result = match recorded {
+        Ok(Some(recorded)) if recorded.webhook_url == time.webhook_url => {
+            channel_webhook_repository
+                .delete_channel_webhook(time.channel_id)
+                .await
+        }
+        Ok(_) => Ok(()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        warn!(
+            "failed to forget channel webhook. channel_id: {}, error: {}",
+            time.channel_id, e
+        );
+    }
+}
+
+/// 指定したチャンネルをTimesとして登録する
+///
+/// コマンド実行のたびにWebhookを用意しなおし，使わなくなったWebhookを削除する
+/// 処理の複雑さを減らすためと，予期せぬWebhookの無効化で，Webhook再作成の条件にあわないのに無効化されて動作しなくなることを防ぐため
+/// user_nameはプレフィックスを付加済みのもの
+pub async fn register_time(
+    ctx: Context<'_>,
+    guild_id: u64,
+    channel_id: ChannelId,
+    user_name: String,
+) -> Result<Rotation> {
+    let new_time = NewTime {
+        user_id: ctx.author().id.get(),
+        guild_id,
+        channel_id,
+        user_name,
+    };
+
+    let api = SerenityWebhookApi::new(ctx);
+    let times_repository = ctx.data().times_repository.clone();
+    let channel_webhook_repository = ctx.data().channel_webhook_repository.clone();
+
+    match rotate_webhook(
+        &api,
+        times_repository.as_ref(),
+        channel_webhook_repository.as_ref(),
+        new_time,
+    )
+    .await
+    {
+        Ok(rotation) => Ok(rotation),
+        Err(RotateWebhookError::PrepareWebhook(e)) => Err(e.into()),
+        Err(RotateWebhookError::SaveTime(e)) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,687 @@
+use super::*;
+use crate::webhook_api::ChannelWebhook;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Discordの代わりに，チャンネルにあるWebhookと作成・削除の記録だけを持つ
+/// 実際のHTTPのリクエストとエラーの分類は，webhook_api/tests.rsで確かめている
+#[derive(Default)]
+struct FakeWebhookApi {
+    fail_list: bool,
+    fail_create: bool,
+    /// チャンネルにある，botが作成したWebhook
+    existing: Vec<ChannelWebhook>,
+    /// 削除のリクエストに返す結果．指定がなければDeleted
+    deletions: HashMap<String, WebhookDeletion>,
+    created: Mutex<Vec<String>>,
+    deleted: Mutex<Vec<String>>,
+}
+
+impl FakeWebhookApi {
+    fn with_existing(existing: Vec<ChannelWebhook>) -> Self {
+        Self {
+            existing,
+            ..Default::default()
+        }
+    }
+
+    fn created(&self) -> Vec<String> {
+        self.created.lock().unwrap().clone()
+    }
+
+    fn deleted(&self) -> Vec<String> {
+        self.deleted.lock().unwrap().clone()
+    }
+}
+
+impl WebhookApi for FakeWebhookApi {
+    async fn create_webhook(
+        &self,
+        channel_id: ChannelId,
+        name: String,
+    ) -> serenity::Result<WebhookUrl> {
+        if self.fail_create {
+            return Err(serenity::Error::Other("Missing Permissions"));
+        }
+        let mut created = self.created.lock().unwrap();
+        let url = format!(
+            "https://discord.com/api/webhooks/{}/{}-{}",
+            channel_id,
+            name,
+            created.len()
+        );
+        created.push(url.clone());
+        Ok(url.into())
+    }
+
+    async fn channel_webhooks(
+        &self,
+        _channel_id: ChannelId,
+    ) -> serenity::Result<Vec<ChannelWebhook>> {
+        if self.fail_list {
+            return Err(serenity::Error::Other("Missing Permissions"));
+        }
+        Ok(self.existing.clone())
+    }
+
+    async fn delete_webhook(&self, webhook_url: &WebhookUrl) -> WebhookDeletion {
+        let webhook_url = webhook_url.expose_secret();
+        self.deleted.lock().unwrap().push(webhook_url.to_string());
+        self.deletions
+            .get(webhook_url)
+            .cloned()
+            .unwrap_or(WebhookDeletion::Deleted)
+    }
+}
+
+#[derive(Default)]
+struct FakeTimesRepository {
+    fail_upsert: bool,
+    times: Mutex<HashMap<(u64, u64), UtTime>>,
+}
+
+impl FakeTimesRepository {
+    fn with_times(times: Vec<UtTime>) -> Self {
+        let repository = Self::default();
+        for time in times {
+            repository
+                .times
+                .lock()
+                .unwrap()
+                .insert((time.user_id, time.guild_id), time);
+        }
+        repository
+    }
+
+    fn time(&self, user_id: u64, guild_id: u64) -> Option<UtTime> {
+        self.times
+            .lock()
+            .unwrap()
+            .get(&(user_id, guild_id))
+            .cloned()
+    }
+}
+
+impl TimesRepository for FakeTimesRepository {
+    type Error = String;
+
+    async fn upsert_and_return_old_time(
+        &self,
+        time: UtTime,
+    ) -> std::result::Result<Option<UtTime>, String> {
+        if self.fail_upsert {
+            return Err("connection closed".to_string());
+        }
+        let mut times = self.times.lock().unwrap();
+        Ok(times.insert((time.user_id, time.guild_id), time))
+    }
+
+    async fn get_time(&self, user_id: u64, guild_id: u64) -> std::result::Result<UtTime, String> {
+        self.time(user_id, guild_id).ok_or("not found".to_string())
+    }
+
+    async fn get_times(&self, user_id: u64) -> std::result::Result<Vec<UtTime>, String> {
+        let times = self.times.lock().unwrap();
+        Ok(times
+            .values()
+            .filter(|t| t.user_id == user_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn get_times_by_channel(
+        &self,
+        channel_id: u64,
+    ) -> std::result::Result<Vec<UtTime>, String> {
+        let times = self.times.lock().unwrap();
+        Ok(times
+            .values()
+            .filter(|t| t.channel_id == channel_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn delete_time(&self, user_id: u64, guild_id: u64) -> std::result::Result<(), String> {
+        self.times.lock().unwrap().remove(&(user_id, guild_id));
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct FakeChannelWebhookRepository {
+    channel_webhooks: Mutex<HashMap<u64, UtChannelWebhook>>,
+}
+
+impl FakeChannelWebhookRepository {
+    fn with_webhook(channel_id: u64, webhook_url: &str) -> Self {
+        let repository = Self::default();
+        repository.channel_webhooks.lock().unwrap().insert(
+            channel_id,
+            UtChannelWebhook {
+                channel_id,
+                guild_id: GUILD_ID,
+                webhook_url: webhook_url.into(),
+            },
+        );
+        repository
+    }
+
+    fn webhook_url(&self, channel_id: u64) -> Option<String> {
+        self.channel_webhooks
+            .lock()
+            .unwrap()
+            .get(&channel_id)
+            .map(|w| w.webhook_url.expose_secret().to_string())
+    }
+}
+
+impl ChannelWebhookRepository for FakeChannelWebhookRepository {
+    type Error = String;
+
+    async fn upsert_channel_webhook(
+        &self,
+        channel_webhook: UtChannelWebhook,
+    ) -> std::result::Result<(), String> {
+        self.channel_webhooks
+            .lock()
+            .unwrap()
+            .insert(channel_webhook.channel_id, channel_webhook);
+        Ok(())
+    }
+
+    async fn get_channel_webhook(
+        &self,
+        channel_id: u64,
+    ) -> std::result::Result<Option<UtChannelWebhook>, String> {
+        Ok(self
+            .channel_webhooks
+            .lock()
+            .unwrap()
+            .get(&channel_id)
+            .cloned())
+    }
+
+    async fn delete_channel_webhook(&self, channel_id: u64) -> std::result::Result<(), String> {
+        self.channel_webhooks.lock().unwrap().remove(&channel_id);
+        Ok(())
+    }
+}
+
+const USER_ID: u64 = 1;
+const GUILD_ID: u64 = 2;
+const CHANNEL_ID: u64 = 3;
+const OLD_CHANNEL_ID: u64 = 4;
+const OLD_WEBHOOK_URL: &str = "https://discord.com/api/webhooks/1/old";
+const SHARED_WEBHOOK_URL: &str = "https://discord.com/api/webhooks/2/shared";
+
+fn new_time() -> NewTime {
+    NewTime {
+        user_id: USER_ID,
+        guild_id: GUILD_ID,
+        channel_id: ChannelId::new(CHANNEL_ID),
+        user_name: "UT-c_user".to_string(),
+    }
+}
+
+fn old_time() -> UtTime {
+    UtTime::new(
+        USER_ID,
+        GUILD_ID,
+        "UT-c_old".to_string(),
+        OLD_CHANNEL_ID,
+        OLD_WEBHOOK_URL.into(),
+    )
+}
+
+/// 同じギルドの別のユーザーが，同じチャンネルを同じWebhookで登録している
+fn other_user_time(channel_id: u64, webhook_url: &str) -> UtTime {
+    UtTime::new(
+        USER_ID + 100,
+        GUILD_ID,
+        "UT-c_other".to_string(),
+        channel_id,
+        webhook_url.into(),
+    )
+}
+
+fn channel_webhook(name: &str, url: &str) -> ChannelWebhook {
+    ChannelWebhook {
+        name: name.to_string(),
+        url: url.into(),
+    }
+}
+
+#[tokio::test]
+/// 初めての登録では，共有するWebhookを作成する
+async fn test_first_registration_creates_shared_webhook() {
+    let api = FakeWebhookApi::default();
+    let repository = FakeTimesRepository::default();
+    let channel_webhooks = FakeChannelWebhookRepository::default();
+
+    let rotation = rotate_webhook(&api, &repository, &channel_webhooks, new_time())
+        .await
+        .unwrap();
+
+    assert!(!rotation.reused);
+    assert_eq!(rotation.old_webhook, None);
+    assert_eq!(
+        api.created(),
+        vec![rotation.time.webhook_url.expose_secret().to_string()]
+    );
+    assert!(rotation
+        .time
+        .webhook_url
+        .expose_secret()
+        .contains(SHARED_WEBHOOK_NAME));
+    assert!(api.deleted().is_empty());
+    assert_eq!(
+        repository.time(USER_ID, GUILD_ID),
+        Some(rotation.time.clone())
+    );
+    assert_eq!(
+        channel_webhooks.webhook_url(CHANNEL_ID),
+        Some(rotation.time.webhook_url.expose_secret().to_string())
+    );
+}
+
+#[tokio::test]
+/// チャンネルにあるWebhookを使い回す
+async fn test_reuses_existing_times_webhook() {
+    let api = FakeWebhookApi::with_existing(vec![
+        channel_webhook("other bot", "https://discord.com/api/webhooks/9/other"),
+        channel_webhook("UT-c_100", SHARED_WEBHOOK_URL),
+    ]);
+    let repository = FakeTimesRepository::default();
+    let channel_webhooks = FakeChannelWebhookRepository::default();
+
+    let rotation = rotate_webhook(&api, &repository, &channel_webhooks, new_time())
+        .await
+        .unwrap();
+
+    assert!(rotation.reused);
+    assert_eq!(
+        rotation.time.webhook_url.expose_secret(),
+        SHARED_WEBHOOK_URL
+    );
+    assert!(api.created().is_empty());
+    assert_eq!(
+        channel_webhooks.webhook_url(CHANNEL_ID),
+        Some(SHARED_WEBHOOK_URL.to_string())
+    );
+}
+
+#[tokio::test]
+/// 記録したチャンネルのWebhookを優先して使う
+async fn test_prefers_recorded_channel_webhook() {
+    let api = FakeWebhookApi::with_existing(vec![
+        channel_webhook("UT-c_100", "https://discord.com/api/webhooks/3/legacy"),
+        channel_webhook(SHARED_WEBHOOK_NAME, SHARED_WEBHOOK_URL),
+    ]);
+    let repository = FakeTimesRepository::default();
+    let channel_webhooks =
+        FakeChannelWebhookRepository::with_webhook(CHANNEL_ID, SHARED_WEBHOOK_URL);
+
+    let rotation = rotate_webhook(&api, &repository, &channel_webhooks, new_time())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        rotation.time.webhook_url.expose_secret(),
+        SHARED_WEBHOOK_URL
+    );
+}
+
+#[tokio::test]
+/// 記録したWebhookがなくなっていたら作り直す
+async fn test_recreates_when_recorded_webhook_is_gone() {
+    // 記録はあるが，チャンネルからは削除されている
+    let api = FakeWebhookApi::default();
+    let repository = FakeTimesRepository::default();
+    let channel_webhooks =
+        FakeChannelWebhookRepository::with_webhook(CHANNEL_ID, SHARED_WEBHOOK_URL);
+
+    let rotation = rotate_webhook(&api, &repository, &channel_webhooks, new_time())
+        .await
+        .unwrap();
+
+    assert!(!rotation.reused);
+    assert_eq!(
+        channel_webhooks.webhook_url(CHANNEL_ID),
+        Some(rotation.time.webhook_url.expose_secret().to_string())
+    );
+}
+
+#[tokio::test]
+/// Webhookを作り直したら古いものを削除する
+async fn test_rotation_deletes_old_webhook() {
+    let api = FakeWebhookApi::default();
+    let repository = FakeTimesRepository::with_times(vec![old_time()]);
+    let channel_webhooks =
+        FakeChannelWebhookRepository::with_webhook(OLD_CHANNEL_ID, OLD_WEBHOOK_URL);
+
+    let rotation = rotate_webhook(&api, &repository, &channel_webhooks, new_time())
+        .await
+        .unwrap();
+
+    assert_eq!(rotation.old_webhook, Some(WebhookDeletion::Deleted));
+    assert_eq!(api.deleted(), vec![OLD_WEBHOOK_URL.to_string()]);
+    assert_eq!(
+        repository.time(USER_ID, GUILD_ID).unwrap().channel_id,
+        CHANNEL_ID
+    );
+    // 削除したWebhookの記録も消える
+    assert_eq!(channel_webhooks.webhook_url(OLD_CHANNEL_ID), None);
+}
+
+#[tokio::test]
+/// 他の登録が使っている古いWebhookは削除しない
+async fn test_keeps_old_webhook_used_by_others() {
+    let api = FakeWebhookApi::default();
+    let repository = FakeTimesRepository::with_times(vec![
+        old_time(),
+        other_user_time(OLD_CHANNEL_ID, OLD_WEBHOOK_URL),
+    ]);
+    let channel_webhooks =
+        FakeChannelWebhookRepository::with_webhook(OLD_CHANNEL_ID, OLD_WEBHOOK_URL);
+
+    let rotation = rotate_webhook(&api, &repository, &channel_webhooks, new_time())
+        .await
+        .unwrap();
+
+    assert_eq!(rotation.old_webhook, Some(WebhookDeletion::InUse));
+    assert!(api.deleted().is_empty());
+    assert_eq!(
+        channel_webhooks.webhook_url(OLD_CHANNEL_ID),
+        Some(OLD_WEBHOOK_URL.to_string())
+    );
+}
+
+#[tokio::test]
+/// 同じWebhookのままなら削除しない
+async fn test_same_webhook_is_not_deleted() {
+    let api = FakeWebhookApi::with_existing(vec![channel_webhook(
+        SHARED_WEBHOOK_NAME,
+        SHARED_WEBHOOK_URL,
+    )]);
+    let time = UtTime::new(
+        USER_ID,
+        GUILD_ID,
+        "UT-c_old".to_string(),
+        CHANNEL_ID,
+        SHARED_WEBHOOK_URL.into(),
+    );
+    let repository = FakeTimesRepository::with_times(vec![time]);
+    let channel_webhooks =
+        FakeChannelWebhookRepository::with_webhook(CHANNEL_ID, SHARED_WEBHOOK_URL);
+
+    let rotation = rotate_webhook(&api, &repository, &channel_webhooks, new_time())
+        .await
+        .unwrap();
+
+    assert_eq!(rotation.old_webhook, None);
+    assert!(api.deleted().is_empty());
+    assert_eq!(
+        repository.time(USER_ID, GUILD_ID).unwrap().user_name,
+        "UT-c_user"
+    );
+}
+
+#[tokio::test]
+/// 古いWebhookがすでに削除されていても成功とする
+async fn test_old_webhook_already_deleted_is_success() {
+    let api = FakeWebhookApi {
+        deletions: HashMap::from([(OLD_WEBHOOK_URL.to_string(), WebhookDeletion::AlreadyDeleted)]),
+        ..Default::default()
+    };
+    let repository = FakeTimesRepository::with_times(vec![old_time()]);
+    let channel_webhooks = FakeChannelWebhookRepository::default();
+
+    let rotation = rotate_webhook(&api, &repository, &channel_webhooks, new_time())
+        .await
+        .unwrap();
+
+    assert_eq!(rotation.old_webhook, Some(WebhookDeletion::AlreadyDeleted));
+    assert_eq!(repository.time(USER_ID, GUILD_ID), Some(rotation.time));
+}
+
+#[tokio::test]
+/// 古いWebhookを削除できなくても，新しい登録は残す
+async fn test_old_webhook_deletion_failure_keeps_new_time() {
+    let api = FakeWebhookApi {
+        deletions: HashMap::from([(
+            OLD_WEBHOOK_URL.to_string(),
+            WebhookDeletion::Failed("Missing Permissions".to_string()),
+        )]),
+        ..Default::default()
+    };
+    let repository = FakeTimesRepository::with_times(vec![old_time()]);
+    let channel_webhooks =
+        FakeChannelWebhookRepository::with_webhook(OLD_CHANNEL_ID, OLD_WEBHOOK_URL);
+
+    let rotation = rotate_webhook(&api, &repository, &channel_webhooks, new_time())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        rotation.old_webhook,
+        Some(WebhookDeletion::Failed("Missing Permissions".to_string()))
+    );
+    assert_eq!(repository.time(USER_ID, GUILD_ID), Some(rotation.time));
+    // 削除できなかったWebhookの記録は残す
+    assert_eq!(
+        channel_webhooks.webhook_url(OLD_CHANNEL_ID),
+        Some(OLD_WEBHOOK_URL.to_string())
+    );
+}
+
+#[tokio::test]
+/// 保存できなかったら作成したWebhookを削除する
+async fn test_save_failure_deletes_new_webhook() {
+    let api = FakeWebhookApi::default();
+    let repository = FakeTimesRepository {
+        fail_upsert: true,
+        ..Default::default()
+    };
+    let channel_webhooks = FakeChannelWebhookRepository::default();
+
+    let result = rotate_webhook(&api, &repository, &channel_webhooks, new_time()).await;
+
+    assert!(matches!(result, Err(RotateWebhookError::SaveTime(_))));
+    assert_eq!(api.created().len(), 1);
+    assert_eq!(api.deleted(), api.created());
+    assert_eq!(channel_webhooks.webhook_url(CHANNEL_ID), None);
+}
+
+#[tokio::test]
+/// 保存できなくても，使い回したWebhookは削除しない
+async fn test_save_failure_keeps_reused_webhook() {
+    let api = FakeWebhookApi::with_existing(vec![channel_webhook(
+        SHARED_WEBHOOK_NAME,
+        SHARED_WEBHOOK_URL,
+    )]);
+    let repository = FakeTimesRepository {
+        fail_upsert: true,
+        ..Default::default()
+    };
+    let channel_webhooks = FakeChannelWebhookRepository::default();
+
+    let result = rotate_webhook(&api, &repository, &channel_webhooks, new_time()).await;
+
+    assert!(matches!(result, Err(RotateWebhookError::SaveTime(_))));
+    assert!(api.deleted().is_empty());
+}
+
+#[tokio::test]
+/// 保存できなかったら，前の登録とWebhookを残す
+async fn test_save_failure_keeps_old_time_and_webhook() {
+    let api = FakeWebhookApi::default();
+    let repository = FakeTimesRepository {
+        fail_upsert: true,
+        ..FakeTimesRepository::with_times(vec![old_time()])
+    };
+    let channel_webhooks = FakeChannelWebhookRepository::default();
+
+    let result = rotate_webhook(&api, &repository, &channel_webhooks, new_time()).await;
+
+    assert!(result.is_err());
+    assert!(!api.deleted().contains(&OLD_WEBHOOK_URL.to_string()));
+    assert_eq!(repository.time(USER_ID, GUILD_ID), Some(old_time()));
+}
+
+#[tokio::test]
+/// Webhookを用意できなかったら何も変えない
+async fn test_prepare_failure_changes_nothing() {
+    for api in [
+        FakeWebhookApi {
+            fail_create: true,
+            ..Default::default()
+        },
+        FakeWebhookApi {
+            fail_list: true,
+            ..Default::default()
+        },
+    ] {
+        let repository = FakeTimesRepository::with_times(vec![old_time()]);
+        let channel_webhooks = FakeChannelWebhookRepository::default();
+
+        let result = rotate_webhook(&api, &repository, &channel_webhooks, new_time()).await;
+
+        assert!(matches!(result, Err(RotateWebhookError::PrepareWebhook(_))));
+        assert!(api.deleted().is_empty());
+        assert_eq!(repository.time(USER_ID, GUILD_ID), Some(old_time()));
+    }
+}
+
+#[tokio::test]
+/// 手放すとき，他の登録が使っているWebhookは削除しない
+async fn test_release_keeps_webhook_used_by_others() {
+    let time = old_time();
+    let api = FakeWebhookApi::default();
+    let repository = FakeTimesRepository::with_times(vec![
+        time.clone(),
+        other_user_time(OLD_CHANNEL_ID, OLD_WEBHOOK_URL),
+    ]);
+    let channel_webhooks = FakeChannelWebhookRepository::default();
+
+    let deletion = release_webhook(&api, &repository, &channel_webhooks, &time).await;
+
+    assert_eq!(deletion, WebhookDeletion::InUse);
+    assert!(api.deleted().is_empty());
+}
+
+#[tokio::test]
+/// 手放すとき，自分だけが使っているWebhookは削除する
+async fn test_release_deletes_webhook_used_only_by_itself() {
+    let time = old_time();
+    let api = FakeWebhookApi::default();
+    // 同じチャンネルでも，別のWebhookを使っている登録は関係ない
+    let repository = FakeTimesRepository::with_times(vec![
+        time.clone(),
+        other_user_time(OLD_CHANNEL_ID, SHARED_WEBHOOK_URL),
+    ]);
+    let channel_webhooks =
+        FakeChannelWebhookRepository::with_webhook(OLD_CHANNEL_ID, OLD_WEBHOOK_URL);
+
+    let deletion = release_webhook(&api, &repository, &channel_webhooks, &time).await;
+
+    assert_eq!(deletion, WebhookDeletion::Deleted);
+    assert_eq!(api.deleted(), vec![OLD_WEBHOOK_URL.to_string()]);
+    assert_eq!(channel_webhooks.webhook_url(OLD_CHANNEL_ID), None);
+}
+
+/// tracingの出力を集める
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl CapturedLogs {
+    fn text(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+#[tokio::test]
+/// Webhookのトークンはログに出さない
+async fn test_webhook_token_never_appears_in_logs() {
+    const OLD_TOKEN: &str = "old-secret-token";
+    let old_webhook_url = format!("https://discord.com/api/webhooks/1/{}", OLD_TOKEN);
+
+    let logs = CapturedLogs::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::TRACE)
+        .with_ansi(false)
+        .with_writer({
+            let logs = logs.clone();
+            move || logs.clone()
+        })
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    // 古いWebhookの削除に失敗して，警告を出す
+    let time = UtTime::new(
+        USER_ID,
+        GUILD_ID,
+        "UT-c_old".to_string(),
+        OLD_CHANNEL_ID,
+        old_webhook_url.clone().into(),
+    );
+    let api = FakeWebhookApi {
+        deletions: HashMap::from([(
+            old_webhook_url.clone(),
+            WebhookDeletion::Failed("Missing Permissions".to_string()),
+        )]),
+        ..Default::default()
+    };
+    let repository = FakeTimesRepository::with_times(vec![time]);
+    let channel_webhooks =
+        FakeChannelWebhookRepository::with_webhook(OLD_CHANNEL_ID, &old_webhook_url);
+    let rotation = rotate_webhook(&api, &repository, &channel_webhooks, new_time())
+        .await
+        .unwrap();
+    let new_token = rotation
+        .time
+        .webhook_url
+        .expose_secret()
+        .rsplit('/')
+        .next()
+        .unwrap()
+        .to_string();
+    tracing::info!(
+        "rotation: {:?}, time: {}",
+        rotation,
+        rotation.time.webhook_url
+    );
+
+    // 保存に失敗して，作成したWebhookを片付ける
+    let api = FakeWebhookApi {
+        deletions: HashMap::from([(
+            format!(
+                "https://discord.com/api/webhooks/{}/{}",
+                CHANNEL_ID, new_token
+            ),
+            WebhookDeletion::Failed("Missing Permissions".to_string()),
+        )]),
+        ..Default::default()
+    };
+    let repository = FakeTimesRepository {
+        fail_upsert: true,
+        ..Default::default()
+    };
+    let result = rotate_webhook(&api, &repository, &channel_webhooks, new_time()).await;
+    assert!(result.is_err());
+
+    let logs = logs.text();
+    assert!(logs.contains("[redacted]"), "{}", logs);
+    assert!(!logs.contains(OLD_TOKEN), "{}", logs);
+    assert!(!logs.contains(&new_token), "{}", logs);
+}
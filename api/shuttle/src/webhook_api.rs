@@ -0,0 +1,117 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use domain::models::WebhookUrl;
+use poise::serenity_prelude::{self as serenity, ChannelId, CreateWebhook, Http, Webhook};
+
+use crate::discord_error::{discord_error_code, UNKNOWN_WEBHOOK};
+use crate::models::Context;
+
+/// Webhookの削除の結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebhookDeletion {
+    Deleted,
+    /// すでに削除されていた
+    AlreadyDeleted,
+    Failed(String),
+    /// 他の登録が使っているので残した
+    InUse,
+}
+
+/// チャンネルにある，botが作成したWebhook
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelWebhook {
+    pub name: String,
+    pub url: WebhookUrl,
+}
+
+/// TimesのWebhookを扱うDiscordのAPI
+///
+/// Webhookの作り直しや削除の手順を，Discordに接続せずにテストできるように切り出している
+pub trait WebhookApi {
+    /// Webhookを作成して，そのURLを返す
+    fn create_webhook(
+        &self,
+        channel_id: ChannelId,
+        name: String,
+    ) -> impl Future<Output = serenity::Result<WebhookUrl>> + Send;
+    /// チャンネルにある，botが作成したWebhookを取得する
+    /// 他のbotや人が作成したWebhookはトークンが得られず，使えないので含めない
+    fn channel_webhooks(
+        &self,
+        channel_id: ChannelId,
+    ) -> impl Future<Output = serenity::Result<Vec<ChannelWebhook>>> + Send;
+    /// URLで指定したWebhookを削除する
+    fn delete_webhook(
+        &self,
+        webhook_url: &WebhookUrl,
+    ) -> impl Future<Output = WebhookDeletion> + Send;
+}
+
+/// serenityを使って，Discordへ実際にリクエストする
+pub struct SerenityWebhookApi {
+    http: Arc<Http>,
+}
+
+impl SerenityWebhookApi {
+    pub fn new(ctx: Context<'_>) -> Self {
+        Self {
+            http: ctx.serenity_context().http.clone(),
+        }
+    }
+
+    /// コマンドの外のイベントから使うとき
+    pub fn from_http(http: Arc<Http>) -> Self {
+        Self { http }
+    }
+}
+
+impl WebhookApi for SerenityWebhookApi {
+    async fn create_webhook(
+        &self,
+        channel_id: ChannelId,
+        name: String,
+    ) -> serenity::Result<WebhookUrl> {
+        let webhook = channel_id
+            .create_webhook(&*self.http, CreateWebhook::new(name))
+            .await?;
+        webhook.url().map(WebhookUrl::from)
+    }
+
+    async fn channel_webhooks(
+        &self,
+        channel_id: ChannelId,
+    ) -> serenity::Result<Vec<ChannelWebhook>> {
+        let webhooks = channel_id.webhooks(&*self.http).await?;
+        let webhooks = webhooks
+            .into_iter()
+            .filter_map(|webhook| {
+                let url = webhook.url().ok()?;
+                Some(ChannelWebhook {
+                    name: webhook.name.unwrap_or_default(),
+                    url: url.into(),
+                })
+            })
+            .collect();
+        Ok(webhooks)
+    }
+
+    async fn delete_webhook(&self, webhook_url: &WebhookUrl) -> WebhookDeletion {
+        // WebhookのURLにはトークンが含まれるので，botがそのギルドにいなくても削除できる
+        let result = match Webhook::from_url(&*self.http, webhook_url.expose_secret()).await {
+            Ok(webhook) => webhook.delete(&*self.http).await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(()) => WebhookDeletion::Deleted,
+            Err(e) if discord_error_code(&e) == Some(UNKNOWN_WEBHOOK) => {
+                WebhookDeletion::AlreadyDeleted
+            }
+            Err(e) => WebhookDeletion::Failed(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,321 @@
+use super::*;
+use crate::discord_error::{is_missing_permission, MISSING_PERMISSIONS};
+use poise::serenity_prelude::HttpBuilder;
+use std::sync::Mutex;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+// serenityはWebhookのURLのIDとトークンの長さを確かめるので，実際のものと同じ長さにしておく
+const WEBHOOK_ID: u64 = 123456789012345678;
+const WEBHOOK_TOKEN: &str = "0123456789abcdefghijklmnopqrstuvwxyz0123456789abcdefghijklmnopqrstuv";
+
+fn webhook_url() -> WebhookUrl {
+    WebhookUrl::from(format!(
+        "https://discord.com/api/webhooks/{WEBHOOK_ID}/{WEBHOOK_TOKEN}"
+    ))
+}
+
+fn webhook_suffix() -> String {
+    format!("/webhooks/{WEBHOOK_ID}/{WEBHOOK_TOKEN}")
+}
+
+/// DiscordのAPIの代わりに，決めておいた応答を返すHTTPサーバー
+///
+/// serenityのproxyの設定で，`https://discord.com`へのリクエストをこのサーバーへ向ける
+struct StubDiscord {
+    address: String,
+    /// 受け取ったリクエストの「メソッド パス」
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+/// パスの末尾がsuffixのリクエストに，statusとbodyで応答する
+struct StubRoute {
+    method: &'static str,
+    suffix: String,
+    status: u16,
+    body: String,
+}
+
+impl StubDiscord {
+    async fn start(routes: Vec<StubRoute>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let routes = Arc::new(routes);
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    return;
+                };
+                respond(stream, &routes, &recorded).await;
+            }
+        });
+
+        Self { address, requests }
+    }
+
+    fn http(&self) -> Arc<Http> {
+        Arc::new(
+            HttpBuilder::new("Bot token")
+                .proxy(self.address.clone())
+                .ratelimiter_disabled(true)
+                .build(),
+        )
+    }
+
+    fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn respond(mut stream: TcpStream, routes: &[StubRoute], requests: &Mutex<Vec<String>>) {
+    let Some(request_line) = read_request(&mut stream).await else {
+        return;
+    };
+    let mut parts = request_line.split(' ');
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+    requests.lock().unwrap().push(format!("{method} {path}"));
+
+    let (status, body) = routes
+        .iter()
+        .find(|route| route.method == method && path.ends_with(&route.suffix))
+        .map(|route| (route.status, route.body.as_str()))
+        .unwrap_or((404, r#"{"code":0,"message":"404: Not Found"}"#));
+    let response = format!(
+        "HTTP/1.1 {status} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// ヘッダーと本文を読み切って，リクエスト行を返す
+async fn read_request(stream: &mut TcpStream) -> Option<String> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 1024];
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(position) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break position + 4;
+        }
+    };
+
+    let header = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let content_length = header
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    while buffer.len() < header_end + content_length {
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+
+    header.lines().next().map(str::to_string)
+}
+
+fn webhook_json(id: u64, name: &str, token: Option<&str>) -> String {
+    let token = token.map_or("null".to_string(), |token| format!("\"{token}\""));
+    format!(
+        r#"{{"id":"{id}","type":1,"channel_id":"100","name":"{name}","avatar":null,"token":{token}}}"#
+    )
+}
+
+#[tokio::test]
+/// 削除できたときはDeletedになる
+async fn test_delete_webhook_deleted() {
+    let stub = StubDiscord::start(vec![
+        StubRoute {
+            method: "GET",
+            suffix: webhook_suffix(),
+            status: 200,
+            body: webhook_json(WEBHOOK_ID, "UT-c", Some(WEBHOOK_TOKEN)),
+        },
+        StubRoute {
+            method: "DELETE",
+            suffix: webhook_suffix(),
+            status: 204,
+            body: String::new(),
+        },
+    ])
+    .await;
+    let api = SerenityWebhookApi::from_http(stub.http());
+
+    let deletion = api.delete_webhook(&webhook_url()).await;
+
+    assert_eq!(deletion, WebhookDeletion::Deleted);
+    let requests = stub.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[0].starts_with("GET ") && requests[0].ends_with(&webhook_suffix()));
+    assert!(requests[1].starts_with("DELETE ") && requests[1].ends_with(&webhook_suffix()));
+}
+
+#[tokio::test]
+/// Unknown Webhookが返ってきたときは，すでに削除されていたとみなす
+async fn test_delete_webhook_unknown_webhook_is_already_deleted() {
+    let stub = StubDiscord::start(vec![StubRoute {
+        method: "GET",
+        suffix: webhook_suffix(),
+        status: 404,
+        body: r#"{"code":10015,"message":"Unknown Webhook"}"#.to_string(),
+    }])
+    .await;
+    let api = SerenityWebhookApi::from_http(stub.http());
+
+    let deletion = api.delete_webhook(&webhook_url()).await;
+
+    assert_eq!(deletion, WebhookDeletion::AlreadyDeleted);
+    // 取得で失敗したので，削除のリクエストは送らない
+    assert_eq!(stub.requests().len(), 1);
+}
+
+#[tokio::test]
+/// 削除のリクエストでUnknown Webhookが返ってきたときも，すでに削除されていたとみなす
+async fn test_delete_webhook_deleted_meanwhile_is_already_deleted() {
+    let stub = StubDiscord::start(vec![
+        StubRoute {
+            method: "GET",
+            suffix: webhook_suffix(),
+            status: 200,
+            body: webhook_json(WEBHOOK_ID, "UT-c", Some(WEBHOOK_TOKEN)),
+        },
+        StubRoute {
+            method: "DELETE",
+            suffix: webhook_suffix(),
+            status: 404,
+            body: r#"{"code":10015,"message":"Unknown Webhook"}"#.to_string(),
+        },
+    ])
+    .await;
+    let api = SerenityWebhookApi::from_http(stub.http());
+
+    let deletion = api.delete_webhook(&webhook_url()).await;
+
+    assert_eq!(deletion, WebhookDeletion::AlreadyDeleted);
+}
+
+#[tokio::test]
+/// それ以外のエラーはFailedになる
+async fn test_delete_webhook_other_error_is_failed() {
+    let stub = StubDiscord::start(vec![
+        StubRoute {
+            method: "GET",
+            suffix: webhook_suffix(),
+            status: 200,
+            body: webhook_json(WEBHOOK_ID, "UT-c", Some(WEBHOOK_TOKEN)),
+        },
+        StubRoute {
+            method: "DELETE",
+            suffix: webhook_suffix(),
+            status: 500,
+            body: r#"{"code":0,"message":"500: Internal Server Error"}"#.to_string(),
+        },
+    ])
+    .await;
+    let api = SerenityWebhookApi::from_http(stub.http());
+
+    let deletion = api.delete_webhook(&webhook_url()).await;
+
+    assert!(matches!(deletion, WebhookDeletion::Failed(_)));
+}
+
+#[tokio::test]
+/// 作成したWebhookのURLを返す
+async fn test_create_webhook_returns_url() {
+    let stub = StubDiscord::start(vec![StubRoute {
+        method: "POST",
+        suffix: "/channels/100/webhooks".to_string(),
+        status: 200,
+        body: webhook_json(300, "UT-c", Some("created")),
+    }])
+    .await;
+    let api = SerenityWebhookApi::from_http(stub.http());
+
+    let url = api
+        .create_webhook(ChannelId::new(100), "UT-c".to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        url.expose_secret(),
+        "https://discord.com/api/webhooks/300/created"
+    );
+    assert_eq!(stub.requests().len(), 1);
+}
+
+#[tokio::test]
+/// 権限が足りずに作成できなかったときは，権限不足のエラーとして分類できる
+async fn test_create_webhook_missing_permission() {
+    let stub = StubDiscord::start(vec![StubRoute {
+        method: "POST",
+        suffix: "/channels/100/webhooks".to_string(),
+        status: 403,
+        body: r#"{"code":50013,"message":"Missing Permissions"}"#.to_string(),
+    }])
+    .await;
+    let api = SerenityWebhookApi::from_http(stub.http());
+
+    let error = api
+        .create_webhook(ChannelId::new(100), "UT-c".to_string())
+        .await
+        .unwrap_err();
+
+    assert_eq!(discord_error_code(&error), Some(MISSING_PERMISSIONS));
+    assert!(is_missing_permission(&error));
+}
+
+#[tokio::test]
+/// トークンが得られないWebhook(他のbotや人が作成したもの)は含めない
+async fn test_channel_webhooks_skips_webhooks_without_token() {
+    let body = format!(
+        "[{},{}]",
+        webhook_json(400, "other bot", None),
+        webhook_json(401, "UT-c", Some("mine"))
+    );
+    let stub = StubDiscord::start(vec![StubRoute {
+        method: "GET",
+        suffix: "/channels/100/webhooks".to_string(),
+        status: 200,
+        body,
+    }])
+    .await;
+    let api = SerenityWebhookApi::from_http(stub.http());
+
+    let webhooks = api.channel_webhooks(ChannelId::new(100)).await.unwrap();
+
+    assert_eq!(
+        webhooks,
+        vec![ChannelWebhook {
+            name: "UT-c".to_string(),
+            url: WebhookUrl::from("https://discord.com/api/webhooks/401/mine"),
+        }]
+    );
+}
+
+#[tokio::test]
+/// 一覧の取得に失敗したときはエラーを返す
+async fn test_channel_webhooks_error() {
+    let stub = StubDiscord::start(vec![StubRoute {
+        method: "GET",
+        suffix: "/channels/100/webhooks".to_string(),
+        status: 403,
+        body: r#"{"code":50001,"message":"Missing Access"}"#.to_string(),
+    }])
+    .await;
+    let api = SerenityWebhookApi::from_http(stub.http());
+
+    let error = api.channel_webhooks(ChannelId::new(100)).await.unwrap_err();
+
+    assert!(is_missing_permission(&error));
+}
@@ -0,0 +1,56 @@
+use domain::models::UtTime;
+use poise::serenity_prelude::{ChannelId, GuildId, Webhook};
+
+use crate::discord_error::{discord_error_code, is_missing_permission, UNKNOWN_WEBHOOK};
+use crate::models::Context;
+
+/// 登録されているWebhookの状態
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebhookHealth {
+    /// 送信できる
+    Valid,
+    /// Webhookが削除されている
+    Deleted,
+    /// botにWebhookを管理する権限がない
+    MissingPermission,
+    /// それ以外の理由で確認できなかった
+    Unknown(String),
+}
+
+impl WebhookHealth {
+    pub fn label(&self) -> String {
+        match self {
+            WebhookHealth::Valid => "✅ valid".to_string(),
+            WebhookHealth::Deleted => "❌ webhook deleted".to_string(),
+            WebhookHealth::MissingPermission => "⚠️ missing permission".to_string(),
+            WebhookHealth::Unknown(reason) => format!("❓ unknown ({})", reason),
+        }
+    }
+}
+
+/// Timeに登録されているWebhookが使えるかどうかを確認する
+///
+/// Webhookが存在していても，botにWebhookを管理する権限がなければ，
+/// Webhookの作り直しや削除ができないので，MissingPermissionとする
+pub async fn check_webhook_health(ctx: Context<'_>, time: &UtTime) -> WebhookHealth {
+    if let Err(e) = Webhook::from_url(ctx, time.webhook_url.expose_secret()).await {
+        return match discord_error_code(&e) {
+            Some(UNKNOWN_WEBHOOK) => WebhookHealth::Deleted,
+            _ if is_missing_permission(&e) => WebhookHealth::MissingPermission,
+            _ => WebhookHealth::Unknown(e.to_string()),
+        };
+    }
+
+    match bot_can_manage_webhooks(ctx, time.guild_id, time.channel_id) {
+        Some(false) => WebhookHealth::MissingPermission,
+        // キャッシュから確認できない場合は，Webhookが使えることだけで判断する
+        _ => WebhookHealth::Valid,
+    }
+}
+
+fn bot_can_manage_webhooks(ctx: Context<'_>, guild_id: u64, channel_id: u64) -> Option<bool> {
+    let guild = ctx.cache().guild(GuildId::new(guild_id))?;
+    let channel = guild.channels.get(&ChannelId::new(channel_id))?;
+    let member = guild.members.get(&ctx.framework().bot_id)?;
+    Some(guild.user_permissions_in(channel, member).manage_webhooks())
+}
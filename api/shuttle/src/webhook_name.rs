@@ -1,11 +1,13 @@
-use crate::models::Context;
-
-/// Weebhook名はUT-c_{user_id}とする
+/// Weebhook名はUT-c_から始める
+/// この名前のWebhookは，botが拡散のために作成したものとして扱う
 pub const WEBHOOK_NAME_PREFIX: &str = "UT-c_";
 
-pub async fn webhook_name(ctx: Context<'_>) -> String {
-    let user_id = ctx.author().id.get();
-    let webhook_name = format!("{}{}", WEBHOOK_NAME_PREFIX, user_id);
+/// チャンネルで共有するWebhookの名前
+/// 投稿者名とアイコンはメッセージごとに上書きするので，Webhook自体は誰のものでもない
+pub const SHARED_WEBHOOK_NAME: &str = "UT-c_shared";
 
-    webhook_name
+/// botが拡散のために作成したWebhookかどうか
+/// 以前はユーザーごとにUT-c_{user_id}という名前で作成していたので，それも含む
+pub fn is_times_webhook_name(name: &str) -> bool {
+    name.starts_with(WEBHOOK_NAME_PREFIX)
 }
@@ -1,14 +1,17 @@
-use crate::models::UtTime;
+use crate::models::{DeliveryReport, MirrorContext, UtTime};
 
 pub trait TimesMessageSender {
     type Error;
     type Message;
     // テキストは別途用意する
     // コマンドの引数としてわたってくるから，それを使う
+    // 送信先ごとの成否はDeliveryReportで返す
+    // 返信やスレッドの対応はMirrorContextで渡す
     fn send_all(
         &self,
         message: &Self::Message,
         text: String,
         times: Vec<UtTime>,
-    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+        context: MirrorContext,
+    ) -> impl std::future::Future<Output = Result<DeliveryReport, Self::Error>> + Send;
 }
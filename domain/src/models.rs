@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct UtGuild {
     pub guild_id: u64,
@@ -22,7 +24,7 @@ pub struct UtTime {
     pub guild_id: u64,
     pub user_name: String,
     pub channel_id: u64,
-    pub webhook_url: String,
+    pub webhook_url: WebhookUrl,
 }
 
 impl UtTime {
@@ -31,7 +33,7 @@ impl UtTime {
         guild_id: u64,
         user_name: String,
         channel_id: u64,
-        webhook_url: String,
+        webhook_url: WebhookUrl,
     ) -> Self {
         Self {
             user_id,
@@ -43,8 +45,562 @@ impl UtTime {
     }
 }
 
+/// チャンネルごとに共有するWebhook
+/// 同じチャンネルをTimesにしている人は，このWebhookを使って拡散を受け取る
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtChannelWebhook {
+    pub channel_id: u64,
+    pub guild_id: u64,
+    pub webhook_url: WebhookUrl,
+}
+
+// WebhookのURLのうち，トークンの代わりに入れる文字列
+const REDACTED: &str = "[redacted]";
+
+/// WebhookのURL
+///
+/// URLにはトークンが含まれ，それだけで投稿できてしまう
+/// ログに出さないように，Debug/Displayではトークンを伏せる
+/// 本当の値が必要なときはexpose_secretで取り出す
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct WebhookUrl(String);
+
+impl WebhookUrl {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self(url.into())
+    }
+
+    /// トークンを含む本当の値
+    /// Discordへのリクエストと保存にだけ使い，ログには出さない
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+
+    /// トークンを伏せたURL
+    ///
+    /// https://discord.com/api/webhooks/{id}/{token} の{token}を置き換える
+    /// 形が違う場合は，トークンを含むかもしれないのですべて伏せる
+    pub fn redacted(&self) -> String {
+        const WEBHOOKS_PATH: &str = "/webhooks/";
+
+        let url = &self.0;
+        let Some(start) = url.find(WEBHOOKS_PATH) else {
+            return REDACTED.to_string();
+        };
+        let id_start = start + WEBHOOKS_PATH.len();
+        let Some(id_len) = url[id_start..].find('/') else {
+            return REDACTED.to_string();
+        };
+        let id = &url[id_start..id_start + id_len];
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_digit()) {
+            return REDACTED.to_string();
+        }
+
+        format!("{}/{}", &url[..id_start + id_len], REDACTED)
+    }
+}
+
+impl From<String> for WebhookUrl {
+    fn from(url: String) -> Self {
+        Self(url)
+    }
+}
+
+impl From<&str> for WebhookUrl {
+    fn from(url: &str) -> Self {
+        Self(url.to_string())
+    }
+}
+
+impl fmt::Debug for WebhookUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WebhookUrl").field(&self.redacted()).finish()
+    }
+}
+
+impl fmt::Display for WebhookUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.redacted())
+    }
+}
+
+/// 他のギルドから拡散されてくるメッセージの受け入れ方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InboundMode {
+    /// すべて受け入れる
+    #[default]
+    Open,
+    /// 許可したギルドからだけ受け入れる
+    AllowList,
+    /// 許可したギルド以外からは，モデレーターが承認してから受け入れる
+    ApprovalQueue,
+}
+
+/// ギルドごとの，拡散されてくるメッセージの受け入れ方
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtInboundPolicy {
+    pub guild_id: u64,
+    pub mode: InboundMode,
+    /// 承認待ちのメッセージを投稿するチャンネル．ApprovalQueueでは必須
+    pub approval_channel_id: Option<u64>,
+    /// 許可した発信元のギルド
+    pub allowed_guild_ids: Vec<u64>,
+}
+
+/// 拡散されてきたメッセージをどう扱うか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InboundDecision {
+    Deliver,
+    /// 受け入れない
+    Refuse,
+    /// 承認待ちのチャンネルに投稿して，承認を待つ
+    Queue {
+        approval_channel_id: u64,
+    },
+}
+
+impl UtInboundPolicy {
+    /// 設定していないギルドは，すべて受け入れる
+    pub fn open(guild_id: u64) -> Self {
+        Self {
+            guild_id,
+            mode: InboundMode::Open,
+            approval_channel_id: None,
+            allowed_guild_ids: Vec::new(),
+        }
+    }
+
+    pub fn decide(&self, origin_guild_id: u64) -> InboundDecision {
+        if self.allowed_guild_ids.contains(&origin_guild_id) {
+            return InboundDecision::Deliver;
+        }
+        match (self.mode, self.approval_channel_id) {
+            (InboundMode::Open, _) => InboundDecision::Deliver,
+            (InboundMode::AllowList, _) => InboundDecision::Refuse,
+            (InboundMode::ApprovalQueue, Some(approval_channel_id)) => InboundDecision::Queue {
+                approval_channel_id,
+            },
+            // 承認するチャンネルがなければ，承認できないので受け入れない
+            (InboundMode::ApprovalQueue, None) => InboundDecision::Refuse,
+        }
+    }
+}
+
+/// 承認待ちのメッセージ
+///
+/// 承認されたら，発信元メッセージを取得しなおして送信先へ拡散する
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtPendingMirror {
+    /// 承認待ちのチャンネルに投稿した，承認のボタンが付いたメッセージ
+    pub review_message_id: u64,
+    pub review_channel_id: u64,
+    pub source_message_id: u64,
+    pub source_guild_id: u64,
+    pub source_channel_id: u64,
+    pub user_id: u64,
+    pub target_guild_id: u64,
+    /// 拡散する本文．プレフィックスコマンドではコマンドを取り除いたもの
+    pub content: String,
+}
+
+/// 他のギルドとの間の拡散を許可するか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuildPairAction {
+    /// このギルドとの間では拡散しない
+    Block,
+    /// このギルドとの間で拡散する
+    /// 1つでも許可したギルドがあれば，許可したギルドとの間でだけ拡散する
+    Allow,
+}
+
+/// ギルドの管理者が決めた，他のギルドとの間の拡散の可否
+/// 拡散してくる向きと，拡散していく向きの両方に効く
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtGuildPairPolicy {
+    pub guild_id: u64,
+    pub other_guild_id: u64,
+    pub action: GuildPairAction,
+}
+
+/// 拡散されてくるメッセージの内容のうち，フィルタで調べるもの
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentFilterRule {
+    /// 本文が正規表現に一致する
+    Regex(String),
+    /// 本文に語を含む．大文字と小文字は区別しない
+    Word(String),
+    /// 添付ファイルの大きさ(バイト)が上限を超える
+    MaxAttachmentSize(u64),
+    /// 添付ファイルのMIMEタイプが許可したものでない．`image/*`のようにも書ける
+    AllowedMimeTypes(Vec<String>),
+    /// 本文にリンクを含む
+    NoLinks,
+}
+
+/// フィルタに当てはまったときの扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FilterAction {
+    /// その送信先へは拡散しない
+    Block,
+    /// 当てはまった部分を取り除いて拡散する
+    Redact,
+    /// そのまま拡散して，拡散したユーザーに知らせる
+    Warn,
+}
+
+/// 送信先のギルドが決めた，拡散されてくるメッセージのフィルタ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtContentFilter {
+    pub filter_id: u64,
+    pub guild_id: u64,
+    pub rule: ContentFilterRule,
+    pub action: FilterAction,
+}
+
+/// 送信先でフィルタに当てはまったこと
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterOutcome {
+    pub guild_id: u64,
+    /// 当てはまったフィルタの説明
+    pub filter: String,
+    pub action: FilterAction,
+}
+
+/// 一定の期間に使える回数
+/// 使った分は期間をかけて少しずつ戻る
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub capacity: u32,
+    pub period_secs: u32,
+}
+
+/// 設定していないギルドで，1人のユーザーが拡散できる回数
+pub const DEFAULT_USER_RATE_LIMIT: RateLimit = RateLimit {
+    capacity: 5,
+    period_secs: 60,
+};
+
+/// 設定していないギルドが，拡散されてくるメッセージを受け取る回数
+pub const DEFAULT_GUILD_RATE_LIMIT: RateLimit = RateLimit {
+    capacity: 30,
+    period_secs: 60,
+};
+
+/// ギルドの管理者が決めた，拡散の回数の上限
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UtRateLimitSettings {
+    pub guild_id: u64,
+    /// このギルドから，1人のユーザーが拡散できる回数
+    pub user_limit: RateLimit,
+    /// このギルドが，他のギルドから拡散されてくるメッセージを受け取る回数
+    pub guild_limit: RateLimit,
+}
+
+impl UtRateLimitSettings {
+    pub fn default_for(guild_id: u64) -> Self {
+        Self {
+            guild_id,
+            user_limit: DEFAULT_USER_RATE_LIMIT,
+            guild_limit: DEFAULT_GUILD_RATE_LIMIT,
+        }
+    }
+}
+
+/// ギルドのモデレーター向けのチャンネル
+/// 拡散先のモデレーターが，拡散されてきたメッセージを発信元のギルドに知らせるときに使う
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UtModerationChannel {
+    pub guild_id: u64,
+    pub channel_id: u64,
+}
+
+/// ギルドを抜けたメンバーのTimesの扱い
+/// BANされたメンバーのTimesは，この設定によらず登録を削除する
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberLeaveAction {
+    /// 登録を残して，拡散されてくるメッセージを受け取り続ける
+    Keep,
+    /// 登録とWebhookを削除する
+    Unregister,
+}
+
+/// ギルドの管理者が決めた，ギルドを抜けたメンバーのTimesの扱い
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UtMemberLeavePolicy {
+    pub guild_id: u64,
+    pub action: MemberLeaveAction,
+}
+
+/// botがTimesの登録を削除した理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeRemovalReason {
+    /// ギルドからBANされた
+    Banned,
+    /// ギルドを抜けた
+    Left,
+}
+
+/// ギルドの側の出来事で，botがTimesの登録を削除した記録
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtTimeRemoval {
+    pub user_id: u64,
+    pub guild_id: u64,
+    pub channel_id: u64,
+    pub reason: TimeRemovalReason,
+    /// RFC 3339の日時
+    pub removed_at: String,
+}
+
+/// 監査ログに記録する操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    /// ギルドの情報を登録した
+    GuildInit,
+    /// Timesを登録した，または登録を更新した
+    TimeSet,
+    /// TimesのWebhookを作り直した
+    TimeWebhookRecreate,
+    /// 本人がTimesの登録を削除した
+    TimeDelete,
+    /// BANやギルドを抜けたことで，botがTimesの登録を削除した
+    TimeRemove,
+    /// 管理者がギルドの設定を変えた
+    SettingChange,
+    /// モデレーターが拡散されたメッセージを取り下げた
+    Takedown,
+    /// 発信元のギルドのモデレーターが，拡散先から知らされたメッセージをすべての拡散先から取り下げた
+    Retract,
+    /// モデレーターが承認待ちのメッセージを承認した
+    ReviewApprove,
+    /// モデレーターが承認待ちのメッセージを却下した
+    ReviewReject,
+    /// ユーザーがut_c_forget_meで自分のデータを削除した
+    ForgetMe,
+}
+
+/// 監査ログに追記する項目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewAuditEntry {
+    pub guild_id: u64,
+    /// 操作したユーザー．botが自分で行った操作と，ut_c_forget_meで消したユーザーの操作はNone
+    pub actor_id: Option<u64>,
+    pub action: AuditAction,
+    /// 何を変えたか．ユーザーに見せる文
+    pub detail: String,
+    /// RFC 3339の日時
+    pub created_at: String,
+}
+
+/// 監査ログの項目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtAuditEntry {
+    /// 追記した順に増える
+    pub entry_id: u64,
+    pub guild_id: u64,
+    pub actor_id: Option<u64>,
+    pub action: AuditAction,
+    pub detail: String,
+    pub created_at: String,
+}
+
+/// 監査ログの項目を流すチャンネル
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UtAuditLogChannel {
+    pub guild_id: u64,
+    pub channel_id: u64,
+}
+
+/// ギルドに誰でも参加できるか
+/// 拡散先の読み手が発信元のメッセージを開けるかどうかで，発信元の案内を変える
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CommunityAccess {
+    /// 誰でも参加できる．発信元のメッセージへのリンクを付ける
+    Open,
+    /// メンバーだけが参加できる．開けないリンクは付けない
+    #[default]
+    Closed,
+}
+
+/// スレッドへの書き込みを，このギルドでも対応するスレッドへ送るか
+/// 設定していないギルドには送る
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UtThreadFollowSetting {
+    pub guild_id: u64,
+    pub follow_threads: bool,
+}
+
+/// ギルドの管理者が決めた，このギルドから拡散したメッセージに付ける発信元の案内
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UtGuildFooterSettings {
+    pub guild_id: u64,
+    /// ユーザーが決めていなければ，案内を付けるか
+    pub enabled: bool,
+    pub access: CommunityAccess,
+}
+
+/// ユーザーが決めた，拡散したメッセージに発信元の案内を付けるか
+/// ギルドの設定より優先する
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UtUserFooterSetting {
+    pub user_id: u64,
+    pub enabled: bool,
+}
+
+/// 拡散先のメッセージに付いたリアクションの数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtMirrorReaction {
+    pub mirror_message_id: u64,
+    /// 絵文字そのもの，またはカスタム絵文字の`<:name:id>`
+    pub emoji: String,
+    pub count: u32,
+}
+
+/// 拡散先のメッセージへの返信
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtMirrorReply {
+    pub reply_message_id: u64,
+    pub mirror_message_id: u64,
+    pub guild_id: u64,
+    pub channel_id: u64,
+}
+
+/// 拡散先での返信を，発信元のTimesへ届けるかのギルドの同意
+/// 返信したギルドのsend_repliesと，発信元のギルドのreceive_repliesの両方が必要
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UtReplyBridgeSettings {
+    pub guild_id: u64,
+    /// このギルドのメンバーの返信を，発信元のギルドへ届けてよいか
+    pub send_replies: bool,
+    /// 他のギルドでの返信を，このギルドのTimesへ届けてよいか
+    pub receive_replies: bool,
+}
+
+/// 発信元のTimesへ届けた返信
+/// 返信が削除されたときに，届けたメッセージも削除するために記録する
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtRelayedReply {
+    pub reply_message_id: u64,
+    /// 返信したユーザー
+    pub author_id: u64,
+    /// 届けた先のTimesの持ち主とギルド．Webhookを探すために使う
+    pub user_id: u64,
+    pub guild_id: u64,
+    /// 届けた先のTimesのチャンネル．Timesの登録が消えても，botの権限で削除できるように記録する
+    pub channel_id: u64,
+    /// スレッドへ届けた場合はそのid
+    pub thread_id: Option<u64>,
+    pub relayed_message_id: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct TimesMessage {
     pub avater_url: String,
     pub content: String,
 }
+
+/// 拡散したメッセージ１件分の記録
+///
+/// 長いメッセージは分割して送信するため，１つの発信元メッセージに対して
+/// 送信先ギルドごとに複数の記録ができる．partはその何番目かを表す
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtDelivery {
+    pub source_message_id: u64,
+    pub source_guild_id: u64,
+    pub source_channel_id: u64,
+    pub user_id: u64,
+    pub target_guild_id: u64,
+    pub target_channel_id: u64,
+    pub mirror_message_id: u64,
+    pub part: u32,
+}
+
+/// Discord上のメッセージの位置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageLocation {
+    pub guild_id: u64,
+    pub channel_id: u64,
+    pub message_id: u64,
+}
+
+impl MessageLocation {
+    pub fn new(guild_id: u64, channel_id: u64, message_id: u64) -> Self {
+        Self {
+            guild_id,
+            channel_id,
+            message_id,
+        }
+    }
+
+    /// メッセージへのリンク
+    pub fn jump_url(&self) -> String {
+        format!(
+            "https://discord.com/channels/{}/{}/{}",
+            self.guild_id, self.channel_id, self.message_id
+        )
+    }
+}
+
+/// 拡散先で，返信先やスレッドを発信元と対応させるための情報
+///
+/// 返信先とスレッドの起点は，送信先ギルドごとに対応するメッセージの位置を持つ
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MirrorContext {
+    /// 発信元メッセージの返信先に対応するメッセージ
+    pub reply_to: Vec<MessageLocation>,
+    /// 発信元メッセージのスレッドの起点に対応するメッセージ
+    /// メッセージから作られたスレッドのidは，起点のメッセージのidと同じになる
+    pub thread_starter: Vec<MessageLocation>,
+    /// すべての送信先で，本文の最後に付ける発信元の案内
+    pub footer: Option<String>,
+    /// 前に送信できた，送信先のギルドと分割したメッセージの何番目かの組
+    /// 途中で失敗した送信をやり直すときに，同じ部分を二重に送らない
+    pub sent_parts: Vec<(u64, u32)>,
+}
+
+impl MirrorContext {
+    pub fn is_sent(&self, guild_id: u64, part: u32) -> bool {
+        self.sent_parts.contains(&(guild_id, part))
+    }
+
+    pub fn reply_to_in(&self, guild_id: u64) -> Option<&MessageLocation> {
+        self.reply_to.iter().find(|l| l.guild_id == guild_id)
+    }
+
+    pub fn thread_starter_in(&self, guild_id: u64) -> Option<&MessageLocation> {
+        self.thread_starter.iter().find(|l| l.guild_id == guild_id)
+    }
+}
+
+/// 送信に失敗した送信先とその理由
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeliveryFailure {
+    pub guild_id: u64,
+    pub reason: String,
+}
+
+/// 拡散できなかった内容
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnmirroredContent {
+    /// Webhookでは再現できない埋め込み．中身はタイトル
+    Embed(String),
+    /// 画像として表示できないスタンプ．中身はスタンプの名前
+    Sticker(String),
+    /// 1つのメッセージに付けられる上限を超えた埋め込みの数
+    TooManyEmbeds(usize),
+}
+
+/// send_allの結果
+///
+/// 一部の送信先で失敗しても，他の送信先への送信は続ける
+/// 成功したものと失敗したもの，拡散できなかった内容をまとめて返す
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeliveryReport {
+    pub deliveries: Vec<UtDelivery>,
+    pub failures: Vec<DeliveryFailure>,
+    pub unmirrored: Vec<UnmirroredContent>,
+    /// 送信先のフィルタに当てはまったもの
+    pub filtered: Vec<FilterOutcome>,
+}
+
+#[cfg(test)]
+mod tests;
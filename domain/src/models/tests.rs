@@ -0,0 +1,122 @@
+use super::*;
+
+const WEBHOOK_URL: &str = "https://discord.com/api/webhooks/1234567890/secret-token_abc";
+const TOKEN: &str = "secret-token_abc";
+
+#[test]
+/// WebhookのURLからトークンを取り除く
+fn test_redacts_webhook_token() {
+    assert_eq!(
+        WebhookUrl::new(WEBHOOK_URL).redacted(),
+        "https://discord.com/api/webhooks/1234567890/[redacted]"
+    );
+}
+
+#[test]
+/// 形のわからないURLはすべて隠す
+fn test_redacts_unknown_url_entirely() {
+    for url in [
+        "https://example.com/secret",
+        "https://discord.com/api/webhooks/secret",
+        "https://discord.com/api/webhooks/abc/secret",
+        "enc:v1:key:wrapped:data",
+        "",
+    ] {
+        assert_eq!(WebhookUrl::new(url).redacted(), "[redacted]", "{}", url);
+    }
+}
+
+#[test]
+/// DebugとDisplayではトークンを見せない
+fn test_debug_and_display_hide_token() {
+    let url = WebhookUrl::new(WEBHOOK_URL);
+
+    assert_eq!(
+        format!("{:?}", url),
+        r#"WebhookUrl("https://discord.com/api/webhooks/1234567890/[redacted]")"#
+    );
+    assert_eq!(
+        url.to_string(),
+        "https://discord.com/api/webhooks/1234567890/[redacted]"
+    );
+    assert_eq!(url.expose_secret(), WEBHOOK_URL);
+}
+
+#[test]
+/// WebhookのURLを持つモデルのDebugでもトークンを見せない
+fn test_models_debug_hides_token() {
+    let time = UtTime::new(1, 2, "UT-c_user".to_string(), 3, WEBHOOK_URL.into());
+    let channel_webhook = UtChannelWebhook {
+        channel_id: 3,
+        guild_id: 2,
+        webhook_url: WEBHOOK_URL.into(),
+    };
+
+    assert!(!format!("{:?}", time).contains(TOKEN));
+    assert!(!format!("{:#?}", channel_webhook).contains(TOKEN));
+}
+
+const ORIGIN: u64 = 10;
+const ALLOWED: u64 = 11;
+
+fn policy(mode: InboundMode, approval_channel_id: Option<u64>) -> UtInboundPolicy {
+    UtInboundPolicy {
+        guild_id: 1,
+        mode,
+        approval_channel_id,
+        allowed_guild_ids: vec![ALLOWED],
+    }
+}
+
+#[test]
+/// 制限のないポリシーではすべて拡散する
+fn test_open_policy_delivers_everything() {
+    assert_eq!(
+        UtInboundPolicy::open(1).decide(ORIGIN),
+        InboundDecision::Deliver
+    );
+}
+
+#[test]
+/// 許可リストにない発信元は拒否する
+fn test_allow_list_refuses_unknown_origin() {
+    let policy = policy(InboundMode::AllowList, None);
+
+    assert_eq!(policy.decide(ALLOWED), InboundDecision::Deliver);
+    assert_eq!(policy.decide(ORIGIN), InboundDecision::Refuse);
+}
+
+#[test]
+/// 承認待ちにするポリシーでは，許可リストにない発信元を承認待ちにする
+fn test_approval_queue_holds_unknown_origin() {
+    let policy = policy(InboundMode::ApprovalQueue, Some(5));
+
+    assert_eq!(policy.decide(ALLOWED), InboundDecision::Deliver);
+    assert_eq!(
+        policy.decide(ORIGIN),
+        InboundDecision::Queue {
+            approval_channel_id: 5
+        }
+    );
+}
+
+#[test]
+/// 承認するチャンネルがなければ拒否する
+fn test_approval_queue_without_channel_refuses() {
+    let policy = policy(InboundMode::ApprovalQueue, None);
+
+    assert_eq!(policy.decide(ORIGIN), InboundDecision::Refuse);
+}
+
+#[test]
+/// 前に送信できた部分は，送信先のギルドごとに調べる
+fn test_mirror_context_is_sent() {
+    let context = MirrorContext {
+        sent_parts: vec![(2, 0)],
+        ..Default::default()
+    };
+
+    assert!(context.is_sent(2, 0));
+    assert!(!context.is_sent(2, 1));
+    assert!(!context.is_sent(3, 0));
+}
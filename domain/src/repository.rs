@@ -1,4 +1,10 @@
-use crate::models::{UtGuild, UtTime};
+use crate::models::{
+    ContentFilterRule, FilterAction, NewAuditEntry, UtAuditEntry, UtAuditLogChannel,
+    UtChannelWebhook, UtContentFilter, UtDelivery, UtGuild, UtGuildFooterSettings,
+    UtGuildPairPolicy, UtInboundPolicy, UtMemberLeavePolicy, UtMirrorReaction, UtMirrorReply,
+    UtModerationChannel, UtPendingMirror, UtRateLimitSettings, UtRelayedReply,
+    UtReplyBridgeSettings, UtThreadFollowSetting, UtTime, UtTimeRemoval, UtUserFooterSetting,
+};
 
 pub trait TimesRepository {
     type Error;
@@ -15,6 +21,11 @@ pub trait TimesRepository {
         &self,
         user_id: u64,
     ) -> impl std::future::Future<Output = Result<Vec<UtTime>, Self::Error>> + Send;
+    /// 同じチャンネルをTimesにしている登録をすべて取得する
+    fn get_times_by_channel(
+        &self,
+        channel_id: u64,
+    ) -> impl std::future::Future<Output = Result<Vec<UtTime>, Self::Error>> + Send;
     fn delete_time(
         &self,
         user_id: u64,
@@ -37,4 +48,393 @@ pub trait GuildRepository {
         &self,
         guild_id: u64,
     ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 名前かidで探す．オートコンプリートに使う
+    /// 名前は大文字と小文字を区別せず部分一致，idは前方一致
+    fn search_guilds(
+        &self,
+        partial: String,
+        limit: usize,
+    ) -> impl std::future::Future<Output = Result<Vec<UtGuild>, Self::Error>> + Send;
+}
+
+/// 拡散したメッセージの記録(配信ログ)を扱う
+pub trait DeliveryRepository {
+    type Error;
+    fn insert_deliveries(
+        &self,
+        deliveries: Vec<UtDelivery>,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 発信元メッセージから，拡散先のメッセージをすべて取得する
+    fn get_deliveries_by_source(
+        &self,
+        source_message_id: u64,
+    ) -> impl std::future::Future<Output = Result<Vec<UtDelivery>, Self::Error>> + Send;
+    /// そのユーザーが拡散したメッセージの記録をすべて取得する
+    fn get_deliveries_by_user(
+        &self,
+        user_id: u64,
+    ) -> impl std::future::Future<Output = Result<Vec<UtDelivery>, Self::Error>> + Send;
+    /// 拡散先のメッセージを指定して，その記録を削除する
+    fn delete_deliveries(
+        &self,
+        mirror_message_ids: Vec<u64>,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 拡散先のメッセージから，その記録を取得する
+    fn get_delivery_by_mirror(
+        &self,
+        mirror_message_id: u64,
+    ) -> impl std::future::Future<Output = Result<Option<UtDelivery>, Self::Error>> + Send;
+    /// 発信元メッセージを拡散済みとして記録する
+    /// すべての送信先でフィルタに止められて配信ログに何も残らなくても，二重に拡散しないために使う
+    fn mark_released(
+        &self,
+        source_message_id: u64,
+        user_id: u64,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 拡散済みとして記録されているか，配信ログにあれば拡散済み
+    fn is_released(
+        &self,
+        source_message_id: u64,
+    ) -> impl std::future::Future<Output = Result<bool, Self::Error>> + Send;
+    /// そのユーザーが拡散済みとして記録したメッセージのidを取得する
+    fn get_released_by_user(
+        &self,
+        user_id: u64,
+    ) -> impl std::future::Future<Output = Result<Vec<u64>, Self::Error>> + Send;
+    /// そのユーザーの拡散済みの記録をすべて削除する
+    fn delete_released_by_user(
+        &self,
+        user_id: u64,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+}
+
+/// チャンネルごとに共有するWebhookを扱う
+pub trait ChannelWebhookRepository {
+    type Error;
+    fn upsert_channel_webhook(
+        &self,
+        channel_webhook: UtChannelWebhook,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    fn get_channel_webhook(
+        &self,
+        channel_id: u64,
+    ) -> impl std::future::Future<Output = Result<Option<UtChannelWebhook>, Self::Error>> + Send;
+    fn delete_channel_webhook(
+        &self,
+        channel_id: u64,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+}
+
+/// ギルドごとの，拡散されてくるメッセージの受け入れ方を扱う
+pub trait InboundPolicyRepository {
+    type Error;
+    /// 許可したギルドも含めて，まとめて置き換える
+    fn upsert_inbound_policy(
+        &self,
+        policy: UtInboundPolicy,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 設定していないギルドはNone
+    fn get_inbound_policy(
+        &self,
+        guild_id: u64,
+    ) -> impl std::future::Future<Output = Result<Option<UtInboundPolicy>, Self::Error>> + Send;
+    /// 複数のギルドの受け入れ方をまとめて取得する．設定していないギルドは含めない
+    fn get_inbound_policies(
+        &self,
+        guild_ids: Vec<u64>,
+    ) -> impl std::future::Future<Output = Result<Vec<UtInboundPolicy>, Self::Error>> + Send;
+}
+
+/// 承認待ちのメッセージを扱う
+pub trait PendingMirrorRepository {
+    type Error;
+    fn insert_pending_mirror(
+        &self,
+        pending: UtPendingMirror,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 取り出して削除する．承認と却下が重なっても，片方だけが取り出せる
+    fn take_pending_mirror(
+        &self,
+        review_message_id: u64,
+    ) -> impl std::future::Future<Output = Result<Option<UtPendingMirror>, Self::Error>> + Send;
+    /// 取り出さずに取得する
+    fn get_pending_mirror(
+        &self,
+        review_message_id: u64,
+    ) -> impl std::future::Future<Output = Result<Option<UtPendingMirror>, Self::Error>> + Send;
+    fn get_pending_mirrors_by_user(
+        &self,
+        user_id: u64,
+    ) -> impl std::future::Future<Output = Result<Vec<UtPendingMirror>, Self::Error>> + Send;
+    /// そのユーザーの承認待ちをすべて削除して，削除したものを返す
+    fn delete_pending_mirrors_by_user(
+        &self,
+        user_id: u64,
+    ) -> impl std::future::Future<Output = Result<Vec<UtPendingMirror>, Self::Error>> + Send;
+}
+
+/// ギルドの間の拡散の可否を扱う
+pub trait GuildPairPolicyRepository {
+    type Error;
+    fn upsert_guild_pair_policy(
+        &self,
+        policy: UtGuildPairPolicy,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    fn delete_guild_pair_policy(
+        &self,
+        guild_id: u64,
+        other_guild_id: u64,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 指定したギルドが決めたものをすべて取得する
+    fn get_guild_pair_policies(
+        &self,
+        guild_ids: Vec<u64>,
+    ) -> impl std::future::Future<Output = Result<Vec<UtGuildPairPolicy>, Self::Error>> + Send;
+}
+
+pub trait ContentFilterRepository {
+    type Error;
+    /// 追加したフィルタを返す．filter_idはDBで決める
+    fn insert_content_filter(
+        &self,
+        guild_id: u64,
+        rule: ContentFilterRule,
+        action: FilterAction,
+    ) -> impl std::future::Future<Output = Result<UtContentFilter, Self::Error>> + Send;
+    /// 削除したらtrueを返す．そのギルドのフィルタでなければ削除しない
+    fn delete_content_filter(
+        &self,
+        guild_id: u64,
+        filter_id: u64,
+    ) -> impl std::future::Future<Output = Result<bool, Self::Error>> + Send;
+    /// 指定したギルドのフィルタを，追加した順にすべて取得する
+    fn get_content_filters(
+        &self,
+        guild_ids: Vec<u64>,
+    ) -> impl std::future::Future<Output = Result<Vec<UtContentFilter>, Self::Error>> + Send;
+}
+
+pub trait RateLimitRepository {
+    type Error;
+    fn upsert_rate_limit_settings(
+        &self,
+        settings: UtRateLimitSettings,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 設定したギルドのものだけを返す
+    fn get_rate_limit_settings(
+        &self,
+        guild_ids: Vec<u64>,
+    ) -> impl std::future::Future<Output = Result<Vec<UtRateLimitSettings>, Self::Error>> + Send;
+}
+
+pub trait ModerationChannelRepository {
+    type Error;
+    fn upsert_moderation_channel(
+        &self,
+        moderation_channel: UtModerationChannel,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    fn delete_moderation_channel(
+        &self,
+        guild_id: u64,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    fn get_moderation_channel(
+        &self,
+        guild_id: u64,
+    ) -> impl std::future::Future<Output = Result<Option<UtModerationChannel>, Self::Error>> + Send;
+}
+
+/// ギルドごとの，ギルドを抜けたメンバーのTimesの扱いを扱う
+pub trait MemberLeavePolicyRepository {
+    type Error;
+    fn upsert_member_leave_policy(
+        &self,
+        policy: UtMemberLeavePolicy,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 設定していないギルドはNone
+    fn get_member_leave_policy(
+        &self,
+        guild_id: u64,
+    ) -> impl std::future::Future<Output = Result<Option<UtMemberLeavePolicy>, Self::Error>> + Send;
+}
+
+/// botがTimesの登録を削除した記録を扱う
+pub trait TimeRemovalRepository {
+    type Error;
+    /// 同じユーザーとギルドの記録は，新しいもので置き換える
+    fn upsert_time_removal(
+        &self,
+        removal: UtTimeRemoval,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    fn get_time_removals(
+        &self,
+        user_id: u64,
+    ) -> impl std::future::Future<Output = Result<Vec<UtTimeRemoval>, Self::Error>> + Send;
+    /// そのユーザーの記録をすべて削除する
+    fn delete_time_removals(
+        &self,
+        user_id: u64,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+}
+
+/// 監査ログを扱う．追記だけで，更新や削除はしない
+pub trait AuditLogRepository {
+    type Error;
+    /// 追記した項目を返す．entry_idはDBで決める
+    fn insert_audit_entry(
+        &self,
+        entry: NewAuditEntry,
+    ) -> impl std::future::Future<Output = Result<UtAuditEntry, Self::Error>> + Send;
+    /// 新しい順にlimit件まで取得する．beforeを指定すれば，そのentry_idより前の項目だけを取得する
+    fn get_audit_entries(
+        &self,
+        guild_id: u64,
+        before: Option<u64>,
+        limit: usize,
+    ) -> impl std::future::Future<Output = Result<Vec<UtAuditEntry>, Self::Error>> + Send;
+    /// そのユーザーが操作した項目を，すべてのギルドから古い順に取得する
+    fn get_audit_entries_by_actor(
+        &self,
+        actor_id: u64,
+    ) -> impl std::future::Future<Output = Result<Vec<UtAuditEntry>, Self::Error>> + Send;
+    /// そのユーザーが操作した項目から，操作したユーザーを消す．項目そのものは残す
+    fn anonymize_audit_entries_by_actor(
+        &self,
+        actor_id: u64,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+}
+
+pub trait AuditLogChannelRepository {
+    type Error;
+    fn upsert_audit_log_channel(
+        &self,
+        audit_log_channel: UtAuditLogChannel,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    fn delete_audit_log_channel(
+        &self,
+        guild_id: u64,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    fn get_audit_log_channel(
+        &self,
+        guild_id: u64,
+    ) -> impl std::future::Future<Output = Result<Option<UtAuditLogChannel>, Self::Error>> + Send;
+}
+
+/// 拡散されてくるスレッドへの書き込みを，対応するスレッドへ送るかのギルドの設定を扱う
+pub trait ThreadFollowRepository {
+    type Error;
+    fn upsert_thread_follow_setting(
+        &self,
+        setting: UtThreadFollowSetting,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 設定しているギルドの分だけ返す
+    fn get_thread_follow_settings(
+        &self,
+        guild_ids: Vec<u64>,
+    ) -> impl std::future::Future<Output = Result<Vec<UtThreadFollowSetting>, Self::Error>> + Send;
+}
+
+/// 拡散したメッセージに付ける発信元の案内の，ギルドとユーザーの設定を扱う
+pub trait FooterSettingsRepository {
+    type Error;
+    fn upsert_guild_footer_settings(
+        &self,
+        settings: UtGuildFooterSettings,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 設定していないギルドはNone
+    fn get_guild_footer_settings(
+        &self,
+        guild_id: u64,
+    ) -> impl std::future::Future<Output = Result<Option<UtGuildFooterSettings>, Self::Error>> + Send;
+    fn upsert_user_footer_setting(
+        &self,
+        setting: UtUserFooterSetting,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 削除すると，ギルドの設定に従う
+    fn delete_user_footer_setting(
+        &self,
+        user_id: u64,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 決めていないユーザーはNone
+    fn get_user_footer_setting(
+        &self,
+        user_id: u64,
+    ) -> impl std::future::Future<Output = Result<Option<UtUserFooterSetting>, Self::Error>> + Send;
+}
+
+/// 拡散先のメッセージへの反応(リアクションと返信)を扱う
+/// 配信ログにある拡散先のメッセージのものだけを記録する
+pub trait MirrorFeedbackRepository {
+    type Error;
+    /// リアクションの数を1つ増やす
+    fn add_mirror_reaction(
+        &self,
+        mirror_message_id: u64,
+        emoji: String,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// リアクションの数を1つ減らす．0になれば記録を消す
+    fn remove_mirror_reaction(
+        &self,
+        mirror_message_id: u64,
+        emoji: String,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// リアクションの記録を消す．emojiを指定しなければ，すべての絵文字を消す
+    fn clear_mirror_reactions(
+        &self,
+        mirror_message_id: u64,
+        emoji: Option<String>,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    fn get_mirror_reactions(
+        &self,
+        mirror_message_ids: Vec<u64>,
+    ) -> impl std::future::Future<Output = Result<Vec<UtMirrorReaction>, Self::Error>> + Send;
+    fn insert_mirror_reply(
+        &self,
+        reply: UtMirrorReply,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 返信が削除されたときに消す．返信でなければ何もしない
+    fn delete_mirror_reply(
+        &self,
+        reply_message_id: u64,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 返信を，古い順に取得する
+    fn get_mirror_replies(
+        &self,
+        mirror_message_ids: Vec<u64>,
+    ) -> impl std::future::Future<Output = Result<Vec<UtMirrorReply>, Self::Error>> + Send;
+}
+
+/// 拡散先での返信を発信元のTimesへ届ける設定と，届けた記録を扱う
+pub trait ReplyBridgeRepository {
+    type Error;
+    fn upsert_reply_bridge_settings(
+        &self,
+        settings: UtReplyBridgeSettings,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 設定していないギルドはNone
+    fn get_reply_bridge_settings(
+        &self,
+        guild_id: u64,
+    ) -> impl std::future::Future<Output = Result<Option<UtReplyBridgeSettings>, Self::Error>> + Send;
+    fn insert_relayed_reply(
+        &self,
+        relayed: UtRelayedReply,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// 届けていない返信はNone
+    fn get_relayed_reply(
+        &self,
+        reply_message_id: u64,
+    ) -> impl std::future::Future<Output = Result<Option<UtRelayedReply>, Self::Error>> + Send;
+    fn delete_relayed_reply(
+        &self,
+        reply_message_id: u64,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
+    /// そのユーザーが返信したものと，そのユーザーのTimesへ届けたものを取得する
+    fn get_relayed_replies_by_user(
+        &self,
+        user_id: u64,
+    ) -> impl std::future::Future<Output = Result<Vec<UtRelayedReply>, Self::Error>> + Send;
+    fn delete_relayed_replies(
+        &self,
+        reply_message_ids: Vec<u64>,
+    ) -> impl std::future::Future<Output = Result<(), Self::Error>> + Send;
 }
@@ -0,0 +1,145 @@
+//! Discordのメッセージ本文の文字数制限に収まるように，本文を分割する
+
+/// Discordのメッセージ本文の上限文字数
+pub const DISCORD_CONTENT_LIMIT: usize = 2000;
+
+const CODE_FENCE: &str = "```";
+
+// コードブロックの途中で分割するときに，閉じるために付け足す分の文字数 "\n```"
+const CLOSE_FENCE_LEN: usize = 4;
+
+// これより長いコードブロックの開始行は，言語指定を引き継がずに ``` だけで開き直す
+const MAX_REOPEN_FENCE_LEN: usize = 32;
+
+/// 本文をlimit文字以下のかたまりに分割する
+///
+/// できるだけ行の境目で分割する
+/// コードブロックの途中で分割する場合は，いったん閉じて次のかたまりで開き直す
+/// 1行がlimitを超える場合のみ，行の途中で分割する
+pub fn split_content(text: &str, limit: usize) -> Vec<String> {
+    if text.chars().count() <= limit {
+        return vec![text.to_string()];
+    }
+
+    let mut splitter = Splitter::new(limit);
+    for line in text.split_inclusive('\n') {
+        splitter.push_line(line);
+    }
+    splitter.finish()
+}
+
+struct Splitter {
+    limit: usize,
+    chunks: Vec<String>,
+    current: String,
+    current_len: usize,
+    // 開いているコードブロックの開始行 (```rust など)
+    open_fence: Option<String>,
+}
+
+impl Splitter {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            chunks: Vec::new(),
+            current: String::new(),
+            current_len: 0,
+            open_fence: None,
+        }
+    }
+
+    fn push_line(&mut self, line: &str) {
+        let is_fence = line.trim_start().starts_with(CODE_FENCE);
+        // コードブロックを閉じる行は，それ自体が閉じる役割を果たすので余白はいらない
+        let reserve = if self.open_fence.is_some() && !is_fence {
+            CLOSE_FENCE_LEN
+        } else {
+            0
+        };
+
+        let line_len = line.chars().count();
+        if self.current_len + line_len + reserve > self.limit && !self.is_current_blank() {
+            self.flush();
+        }
+
+        if self.current_len + line_len + reserve > self.limit {
+            // 1行だけで上限を超えるので，行の途中で分割する
+            self.push_long_line(line, reserve);
+        } else {
+            self.push_str(line);
+        }
+
+        if is_fence {
+            self.open_fence = match self.open_fence {
+                Some(_) => None,
+                None => Some(reopen_fence(line)),
+            };
+        }
+    }
+
+    fn push_long_line(&mut self, line: &str, reserve: usize) {
+        let mut rest = line;
+        while !rest.is_empty() {
+            let space = self.limit.saturating_sub(self.current_len + reserve).max(1);
+            let split_at = rest
+                .char_indices()
+                .nth(space)
+                .map(|(i, _)| i)
+                .unwrap_or(rest.len());
+            let (head, tail) = rest.split_at(split_at);
+            self.push_str(head);
+            rest = tail;
+            if !rest.is_empty() {
+                self.flush();
+            }
+        }
+    }
+
+    fn push_str(&mut self, s: &str) {
+        self.current.push_str(s);
+        self.current_len += s.chars().count();
+    }
+
+    /// 開き直したコードブロック以外に何も書かれていないかどうか
+    fn is_current_blank(&self) -> bool {
+        match &self.open_fence {
+            Some(fence) => self.current_len <= fence.chars().count() + 1,
+            None => self.current.is_empty(),
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.open_fence.is_some() {
+            if !self.current.ends_with('\n') {
+                self.current.push('\n');
+            }
+            self.current.push_str(CODE_FENCE);
+        }
+        self.chunks.push(std::mem::take(&mut self.current));
+        self.current_len = 0;
+
+        if let Some(fence) = self.open_fence.clone() {
+            self.push_str(&fence);
+            self.push_str("\n");
+        }
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        if !self.current.is_empty() {
+            self.chunks.push(self.current);
+        }
+        self.chunks
+    }
+}
+
+fn reopen_fence(line: &str) -> String {
+    let fence = line.trim();
+    if fence.chars().count() > MAX_REOPEN_FENCE_LEN {
+        CODE_FENCE.to_string()
+    } else {
+        fence.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests;
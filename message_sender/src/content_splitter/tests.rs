@@ -0,0 +1,75 @@
+use super::*;
+
+fn assert_within_limit(chunks: &[String], limit: usize) {
+    for chunk in chunks {
+        assert!(
+            chunk.chars().count() <= limit,
+            "chunk is too long: {}",
+            chunk.chars().count()
+        );
+    }
+}
+
+#[test]
+/// 上限以下ならそのまま返す
+fn test_short_text_is_not_split() {
+    let chunks = split_content("hello\nworld", DISCORD_CONTENT_LIMIT);
+    assert_eq!(chunks, vec!["hello\nworld".to_string()]);
+}
+
+#[test]
+/// 行の境目で分割され，つなげると元に戻る
+fn test_split_on_line_boundary() {
+    let text = "aaaa\nbbbb\ncccc\n";
+    let chunks = split_content(text, 10);
+
+    assert_within_limit(&chunks, 10);
+    assert_eq!(chunks, vec!["aaaa\nbbbb\n", "cccc\n"]);
+    assert_eq!(chunks.concat(), text);
+}
+
+#[test]
+/// 1行が上限を超える場合は行の途中で分割する
+fn test_split_long_line() {
+    let text = "あ".repeat(25);
+    let chunks = split_content(&text, 10);
+
+    assert_within_limit(&chunks, 10);
+    assert_eq!(chunks.len(), 3);
+    assert_eq!(chunks.concat(), text);
+}
+
+#[test]
+/// コードブロックの途中で分割した場合，閉じて開き直す
+fn test_split_inside_code_block() {
+    let text = "intro\n```rust\nlet a = 1;\nlet b = 2;\nlet c = 3;\n```\noutro\n";
+    let chunks = split_content(text, 30);
+
+    assert_within_limit(&chunks, 30);
+    for chunk in &chunks {
+        let fences = chunk
+            .lines()
+            .filter(|l| l.trim_start().starts_with("```"))
+            .count();
+        assert_eq!(fences % 2, 0, "unbalanced code block: {:?}", chunk);
+    }
+    // 開き直したコードブロックは言語指定を引き継ぐ
+    assert!(chunks[1].starts_with("```rust\n"));
+}
+
+#[test]
+/// コードブロックの中身を取り出すと，元の中身と一致する
+fn test_code_block_content_is_preserved() {
+    let body = (0..50).map(|i| format!("line {}\n", i)).collect::<String>();
+    let text = format!("```\n{}```\n", body);
+    let chunks = split_content(&text, 100);
+
+    assert_within_limit(&chunks, 100);
+    let restored = chunks
+        .iter()
+        .flat_map(|c| c.lines())
+        .filter(|l| !l.starts_with("```"))
+        .map(|l| format!("{}\n", l))
+        .collect::<String>();
+    assert_eq!(restored, body);
+}
@@ -1 +1,3 @@
+pub mod content_splitter;
 pub mod poise_webhook_message_sender;
+pub mod rich_content;
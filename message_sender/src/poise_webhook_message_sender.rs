@@ -1,16 +1,42 @@
-use domain::{message_sender::TimesMessageSender, models::UtTime};
-use poise::serenity_prelude::{ExecuteWebhook, Http, Message, Webhook};
+use domain::{
+    message_sender::TimesMessageSender,
+    models::{DeliveryFailure, DeliveryReport, MirrorContext, UtDelivery, UtTime},
+};
+use poise::serenity_prelude::{
+    Attachment, ChannelId, CreateAllowedMentions, CreateAttachment, CreateEmbed, ExecuteWebhook,
+    GuildId, Http, Message, User, Webhook,
+};
 use thiserror::Error;
-use tracing::info;
+use tracing::{info, warn};
+
+use crate::content_splitter::{split_content, DISCORD_CONTENT_LIMIT};
+use crate::rich_content::rich_content;
 
 #[derive(Debug, Error)]
 pub enum PoiseWebhookMessageSenderError {
     #[error("Webhook error: {0}")]
     WebhookError(#[from] poise::serenity_prelude::Error),
+    #[error("Webhook did not return the posted message")]
+    MessageNotReturned,
+}
+
+/// 本文がDiscordの文字数制限を超えたときの扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowMode {
+    /// 複数のメッセージに分割して，順番に送信する
+    #[default]
+    Split,
+    /// 収まる分だけを本文にして，全文を.txtファイルとして添付する
+    TextFile,
 }
 
+// TextFileモードで添付するファイルの名前
+const OVERFLOW_FILE_NAME: &str = "message.txt";
+
 #[derive(Debug)]
-pub struct PoiseWebhookMessageSender;
+pub struct PoiseWebhookMessageSender {
+    overflow_mode: OverflowMode,
+}
 
 impl Default for PoiseWebhookMessageSender {
     fn default() -> Self {
@@ -20,7 +46,224 @@ impl Default for PoiseWebhookMessageSender {
 
 impl PoiseWebhookMessageSender {
     pub fn new() -> Self {
-        Self
+        Self {
+            overflow_mode: OverflowMode::default(),
+        }
+    }
+
+    pub fn with_overflow_mode(overflow_mode: OverflowMode) -> Self {
+        Self { overflow_mode }
+    }
+
+    /// 発信元のTimesへ，ユーザーの代わりにWebhookで投稿する
+    ///
+    /// スラッシュコマンドから拡散するときに，拡散元になるメッセージを作るために使う
+    /// 返すメッセージは，そのユーザーが発信元のギルドで投稿したものとして扱えるように，
+    /// 投稿者とギルドを書き換えてある
+    pub async fn post_origin(
+        &self,
+        time: &UtTime,
+        author: &User,
+        content: &str,
+        attachment: Option<&Attachment>,
+    ) -> Result<Message, PoiseWebhookMessageSenderError> {
+        let http = Http::new("");
+        let webhook = Webhook::from_url(&http, time.webhook_url.expose_secret()).await?;
+
+        let mut builder = ExecuteWebhook::new()
+            .content(content)
+            .username(author.display_name())
+            .avatar_url(author.face());
+        if let Some(attachment) = attachment {
+            // 添付ファイルは発信元のTimesへ投稿しなおし，拡散はそのURLを使う
+            let file = CreateAttachment::url(&http, &attachment.url).await?;
+            builder = builder.add_file(file);
+        }
+
+        let mut origin = webhook
+            .execute(&http, true, builder)
+            .await?
+            .ok_or(PoiseWebhookMessageSenderError::MessageNotReturned)?;
+        origin.author = author.clone();
+        origin.guild_id = Some(GuildId::new(time.guild_id));
+
+        info!(
+            "origin posted. guild_id: {}, message_id: {}",
+            time.guild_id, origin.id
+        );
+        Ok(origin)
+    }
+
+    /// 拡散先での返信を，発信元のTimesへWebhookで届ける
+    ///
+    /// thread_idを指定すれば，発信元のメッセージから作ったスレッドへ投稿する
+    /// 他のギルドのメンバーへの通知が飛ばないように，メンションは無効にする
+    pub async fn post_relayed_reply(
+        &self,
+        time: &UtTime,
+        thread_id: Option<u64>,
+        user_name: &str,
+        avatar_url: &str,
+        content: &str,
+    ) -> Result<Message, PoiseWebhookMessageSenderError> {
+        let http = Http::new("");
+        let webhook = Webhook::from_url(&http, time.webhook_url.expose_secret()).await?;
+
+        let mut builder = ExecuteWebhook::new()
+            .content(content)
+            .username(user_name)
+            .avatar_url(avatar_url)
+            .allowed_mentions(CreateAllowedMentions::new());
+        if let Some(thread_id) = thread_id {
+            builder = builder.in_thread(ChannelId::new(thread_id));
+        }
+
+        let relayed = webhook
+            .execute(&http, true, builder)
+            .await?
+            .ok_or(PoiseWebhookMessageSenderError::MessageNotReturned)?;
+
+        info!(
+            "reply relayed. guild_id: {}, message_id: {}",
+            time.guild_id, relayed.id
+        );
+        Ok(relayed)
+    }
+
+    /// 1つの送信先へ送るメッセージを組み立てる
+    /// 分割した場合は，送る順に並べて返す
+    /// 埋め込みは本文の後ろに来るように，最後のメッセージに付ける
+    fn build_messages(&self, text: &str, embeds: &[CreateEmbed]) -> Vec<ExecuteWebhook> {
+        let mut builders = self.build_contents(text);
+        if let Some(last) = builders.pop() {
+            builders.push(last.embeds(embeds.to_vec()));
+        }
+        builders
+    }
+
+    fn build_contents(&self, text: &str) -> Vec<ExecuteWebhook> {
+        let chunks = split_content(text, DISCORD_CONTENT_LIMIT);
+        if chunks.len() <= 1 {
+            return vec![ExecuteWebhook::new().content(text)];
+        }
+
+        match self.overflow_mode {
+            OverflowMode::Split => chunks
+                .into_iter()
+                .map(|chunk| ExecuteWebhook::new().content(chunk))
+                .collect(),
+            OverflowMode::TextFile => {
+                let file = CreateAttachment::bytes(text.as_bytes().to_vec(), OVERFLOW_FILE_NAME);
+                vec![ExecuteWebhook::new()
+                    .content(chunks[0].clone())
+                    .add_file(file)]
+            }
+        }
+    }
+
+    /// 1つの送信先へ，分割したメッセージを順番に送信する
+    /// 途中で失敗した場合でも，それまでに送信できたものは返す
+    async fn send_to(
+        &self,
+        http: &Http,
+        outgoing: &OutgoingMessage<'_>,
+        time: &UtTime,
+    ) -> (Vec<UtDelivery>, Option<DeliveryFailure>) {
+        let OutgoingMessage {
+            message,
+            text,
+            embeds,
+            avater_url,
+            context,
+        } = outgoing;
+        let mut deliveries = Vec::new();
+
+        let webhook = match Webhook::from_url(http, time.webhook_url.expose_secret()).await {
+            Ok(webhook) => webhook,
+            Err(e) => return (deliveries, Some(failure(time, e))),
+        };
+
+        // 返信先がこのギルドにも拡散されていれば，そこへのリンクを先頭に付ける
+        let text = match context.reply_to_in(time.guild_id) {
+            Some(reply_to) => format!("> ↪ [Reply to]({})\n{}", reply_to.jump_url(), text),
+            None => text.to_string(),
+        };
+        // 発信元の案内は，分割しても最後のメッセージに来るように本文の後ろに付ける
+        let text = match &context.footer {
+            Some(footer) if text.is_empty() => footer.clone(),
+            Some(footer) => format!("{}\n\n{}", text, footer),
+            None => text,
+        };
+
+        // スレッドの起点がこのギルドにも拡散されていれば，対応するスレッドへ送信する
+        // 拡散先でスレッドが作られていない場合は，チャンネルへ送信する
+        let mut thread_id = context
+            .thread_starter_in(time.guild_id)
+            .map(|starter| ChannelId::new(starter.message_id));
+
+        for (part, builder) in self.build_messages(&text, embeds).into_iter().enumerate() {
+            if context.is_sent(time.guild_id, part as u32) {
+                continue;
+            }
+            let builder = builder
+                .username(time.user_name.clone())
+                .avatar_url(avater_url.as_str());
+            // 配信ログに記録するため，送信したメッセージを受け取る
+            let result = match thread_id {
+                Some(thread) => {
+                    let result = webhook
+                        .execute(http, true, builder.clone().in_thread(thread))
+                        .await;
+                    if let Err(e) = &result {
+                        info!(
+                            "thread not available, send to channel. thread_id: {}, error: {}",
+                            thread, e
+                        );
+                        thread_id = None;
+                        webhook.execute(http, true, builder).await
+                    } else {
+                        result
+                    }
+                }
+                None => webhook.execute(http, true, builder).await,
+            };
+            let mirror = match result {
+                Ok(mirror) => mirror,
+                Err(e) => return (deliveries, Some(failure(time, e))),
+            };
+
+            if let Some(mirror) = mirror {
+                deliveries.push(UtDelivery {
+                    source_message_id: message.id.get(),
+                    source_guild_id: message.guild_id.map(|g| g.get()).unwrap_or_default(),
+                    source_channel_id: message.channel_id.get(),
+                    user_id: message.author.id.get(),
+                    target_guild_id: time.guild_id,
+                    target_channel_id: mirror.channel_id.get(),
+                    mirror_message_id: mirror.id.get(),
+                    part: part as u32,
+                });
+            }
+        }
+
+        (deliveries, None)
+    }
+}
+
+/// すべての送信先に共通する，送信する内容
+struct OutgoingMessage<'a> {
+    message: &'a Message,
+    text: String,
+    embeds: Vec<CreateEmbed>,
+    avater_url: String,
+    context: MirrorContext,
+}
+
+fn failure(time: &UtTime, e: poise::serenity_prelude::Error) -> DeliveryFailure {
+    warn!("failed to send. guild_id: {}, error: {}", time.guild_id, e);
+    DeliveryFailure {
+        guild_id: time.guild_id,
+        reason: e.to_string(),
     }
 }
 
@@ -28,13 +271,14 @@ impl TimesMessageSender for PoiseWebhookMessageSender {
     type Error = PoiseWebhookMessageSenderError;
     type Message = Message;
 
-    #[tracing::instrument(skip(self, message, text, times))]
+    #[tracing::instrument(skip(self, message, text, times, context))]
     async fn send_all(
         &self,
         message: &Self::Message,
         text: String,
         times: Vec<UtTime>,
-    ) -> Result<(), Self::Error> {
+        context: MirrorContext,
+    ) -> Result<DeliveryReport, Self::Error> {
         // Webhookを送るだけなら，トークンとやらはなしでもいいらしい
         let http = Http::new("");
         let avater_url = message.author.avatar_url().unwrap_or_default();
@@ -63,20 +307,41 @@ impl TimesMessageSender for PoiseWebhookMessageSender {
 
         let text = format!("{}\n{}", text, files_name_and_url);
 
-        for time in times.into_iter() {
+        // 埋め込み，スタンプ，投票も拡散する
+        let rich = rich_content(message, &text);
+        let text = format!("{}{}", text, rich.text);
+        for u in rich.unmirrored.iter() {
+            info!("unmirrored content: {:?}", u);
+        }
+
+        // 一部の送信先で失敗しても，残りの送信先には送信する
+        let outgoing = OutgoingMessage {
+            message,
+            text,
+            embeds: rich.embeds,
+            avater_url,
+            context,
+        };
+        let mut report = DeliveryReport {
+            unmirrored: rich.unmirrored,
+            ..Default::default()
+        };
+        for time in times.iter() {
+            info!(
+                "will send guild_id {}, webhook_url {}",
+                time.guild_id, time.webhook_url
+            );
+            let (deliveries, failure) = self.send_to(&http, &outgoing, time).await;
             info!(
-                "will send guild_id {}, webhook_url {:?}",
-                time.guild_id, &time.webhook_url
+                "sent guild_id {}, parts: {}",
+                time.guild_id,
+                deliveries.len()
             );
-            let webhook = Webhook::from_url(&http, &time.webhook_url).await?;
-            let builder = ExecuteWebhook::new()
-                .content(&text)
-                .username(time.user_name)
-                .avatar_url(&avater_url);
-            webhook.execute(&http, false, builder).await?;
+            report.deliveries.extend(deliveries);
+            report.failures.extend(failure);
         }
 
         info!("send_all complete");
-        Ok(())
+        Ok(report)
     }
 }
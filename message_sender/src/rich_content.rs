@@ -0,0 +1,117 @@
+//! 本文と添付ファイル以外の内容(埋め込み，スタンプ，投票)を，Webhookで送れる形に変換する
+
+use domain::models::UnmirroredContent;
+use poise::serenity_prelude::{
+    CreateEmbed, Embed, Message, Poll, PollMedia, PollMediaEmoji, StickerFormatType, StickerItem,
+};
+
+/// 1つのメッセージに付けられる埋め込みの上限
+pub const DISCORD_EMBED_LIMIT: usize = 10;
+
+// Webhookでは動画を埋め込めないため，URLを本文に付けてDiscordに展開してもらう
+const VIDEO_EMBED_KINDS: [&str; 2] = ["video", "gifv"];
+
+/// 発信元メッセージの本文以外の内容を変換したもの
+#[derive(Debug, Default)]
+pub struct RichContent {
+    /// 本文の末尾に付け足すテキスト
+    pub text: String,
+    pub embeds: Vec<CreateEmbed>,
+    pub unmirrored: Vec<UnmirroredContent>,
+}
+
+/// 埋め込み，スタンプ，投票を拡散できる形に変換する
+///
+/// textは拡散する本文
+/// 本文に含まれるURLの埋め込みは，Discordが拡散先で再び展開するので作り直さない
+pub fn rich_content(message: &Message, text: &str) -> RichContent {
+    let mut content = RichContent::default();
+
+    mirror_embeds(&mut content, &message.embeds, text);
+    mirror_stickers(&mut content, &message.sticker_items);
+    if let Some(poll) = &message.poll {
+        content.text.push_str(&summarize_poll(poll));
+    }
+
+    if content.embeds.len() > DISCORD_EMBED_LIMIT {
+        let overflow = content.embeds.split_off(DISCORD_EMBED_LIMIT);
+        content
+            .unmirrored
+            .push(UnmirroredContent::TooManyEmbeds(overflow.len()));
+    }
+
+    content
+}
+
+fn mirror_embeds(content: &mut RichContent, embeds: &[Embed], text: &str) {
+    for embed in embeds {
+        if let Some(url) = &embed.url {
+            if text.contains(url.as_str()) {
+                continue;
+            }
+        }
+
+        let is_video = embed
+            .kind
+            .as_deref()
+            .is_some_and(|kind| VIDEO_EMBED_KINDS.contains(&kind));
+        if is_video {
+            match &embed.url {
+                Some(url) => content.text.push_str(&format!("\n{}", url)),
+                None => content.unmirrored.push(UnmirroredContent::Embed(
+                    embed.title.clone().unwrap_or_default(),
+                )),
+            }
+            continue;
+        }
+
+        content.embeds.push(CreateEmbed::from(embed.clone()));
+    }
+}
+
+fn mirror_stickers(content: &mut RichContent, stickers: &[StickerItem]) {
+    for sticker in stickers {
+        // Lottie形式は画像として表示できない
+        let image_url = match sticker.format_type {
+            StickerFormatType::Lottie => None,
+            _ => sticker.image_url(),
+        };
+
+        match image_url {
+            Some(url) => content
+                .embeds
+                .push(CreateEmbed::new().title(&sticker.name).image(url)),
+            None => content
+                .unmirrored
+                .push(UnmirroredContent::Sticker(sticker.name.clone())),
+        }
+    }
+}
+
+/// 投票をテキストにまとめる
+/// 拡散先で投票することはできないので，質問と選択肢だけを載せる
+pub fn summarize_poll(poll: &Poll) -> String {
+    let answers = poll
+        .answers
+        .iter()
+        .map(|a| format!("- {}", poll_media_text(&a.poll_media)))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    format!(
+        "\n📊 **{}**\n{}\n",
+        poll_media_text(&poll.question),
+        answers
+    )
+}
+
+fn poll_media_text(media: &PollMedia) -> String {
+    let text = media.text.clone().unwrap_or_default();
+    match &media.emoji {
+        Some(PollMediaEmoji::Name(emoji)) => format!("{} {}", emoji, text),
+        _ => text,
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,97 @@
+use super::*;
+use serde_json::json;
+
+fn embed(kind: &str, url: Option<&str>) -> Embed {
+    let mut embed = Embed::default();
+    embed.kind = Some(kind.to_string());
+    embed.title = Some("title".to_string());
+    embed.url = url.map(|u| u.to_string());
+    embed
+}
+
+fn sticker(name: &str, format_type: u8) -> StickerItem {
+    serde_json::from_value(json!({
+        "id": "1",
+        "name": name,
+        "format_type": format_type,
+    }))
+    .unwrap()
+}
+
+#[test]
+/// 本文に含まれるURLの埋め込みは作り直さない
+fn test_embed_with_url_in_text_is_skipped() {
+    let mut content = RichContent::default();
+    let embeds = vec![embed("link", Some("https://example.com"))];
+
+    mirror_embeds(&mut content, &embeds, "see https://example.com");
+
+    assert!(content.embeds.is_empty());
+    assert!(content.unmirrored.is_empty());
+}
+
+#[test]
+/// 本文に含まれない埋め込みは作り直す
+fn test_rich_embed_is_recreated() {
+    let mut content = RichContent::default();
+    let embeds = vec![embed("rich", None)];
+
+    mirror_embeds(&mut content, &embeds, "text");
+
+    assert_eq!(content.embeds.len(), 1);
+}
+
+#[test]
+/// 動画の埋め込みはURLを本文に付ける．URLがなければ拡散できない
+fn test_video_embed() {
+    let mut content = RichContent::default();
+    let embeds = vec![
+        embed("video", Some("https://example.com/video")),
+        embed("video", None),
+    ];
+
+    mirror_embeds(&mut content, &embeds, "text");
+
+    assert!(content.embeds.is_empty());
+    assert_eq!(content.text, "\nhttps://example.com/video");
+    assert_eq!(
+        content.unmirrored,
+        vec![UnmirroredContent::Embed("title".to_string())]
+    );
+}
+
+#[test]
+/// スタンプは画像の埋め込みにする．Lottie形式は拡散できない
+fn test_stickers() {
+    let mut content = RichContent::default();
+    let stickers = vec![sticker("png", 1), sticker("lottie", 3)];
+
+    mirror_stickers(&mut content, &stickers);
+
+    assert_eq!(content.embeds.len(), 1);
+    assert_eq!(
+        content.unmirrored,
+        vec![UnmirroredContent::Sticker("lottie".to_string())]
+    );
+}
+
+#[test]
+/// 投票は質問と選択肢のテキストにする
+fn test_summarize_poll() {
+    let poll: Poll = serde_json::from_value(json!({
+        "question": { "text": "Lunch?" },
+        "answers": [
+            { "answer_id": 1, "poll_media": { "text": "Ramen", "emoji": { "name": "🍜" } } },
+            { "answer_id": 2, "poll_media": { "text": "Curry" } },
+        ],
+        "expiry": null,
+        "allow_multiselect": false,
+        "layout_type": 1,
+    }))
+    .unwrap();
+
+    assert_eq!(
+        summarize_poll(&poll),
+        "\n📊 **Lunch?**\n- 🍜 Ramen\n- Curry\n"
+    );
+}
@@ -1,5 +1,22 @@
+pub mod postgres_audit_log_channel_repository;
+pub mod postgres_audit_log_repository;
+pub mod postgres_channel_webhook_repository;
+pub mod postgres_content_filter_repository;
+pub mod postgres_delivery_repository;
+pub mod postgres_footer_settings_repository;
+pub mod postgres_guild_pair_policy_repository;
 pub mod postgres_guild_repository;
+pub mod postgres_inbound_policy_repository;
+pub mod postgres_member_leave_policy_repository;
+pub mod postgres_mirror_feedback_repository;
+pub mod postgres_moderation_channel_repository;
+pub mod postgres_pending_mirror_repository;
+pub mod postgres_rate_limit_repository;
+pub mod postgres_reply_bridge_repository;
+pub mod postgres_thread_follow_repository;
+pub mod postgres_time_removal_repository;
 pub mod postgres_times_repository;
+pub mod webhook_cipher;
 
 #[cfg(test)]
 mod test_utils;
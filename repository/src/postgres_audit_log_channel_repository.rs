@@ -0,0 +1,119 @@
+use domain::models::UtAuditLogChannel;
+use domain::repository::AuditLogChannelRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresAuditLogChannelRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtAuditLogChannel {
+    guild_id: BigDecimal,
+    channel_id: BigDecimal,
+}
+
+// PostgresUtAuditLogChannelをUtAuditLogChannelに変換する
+
+impl From<PostgresUtAuditLogChannel> for UtAuditLogChannel {
+    fn from(c: PostgresUtAuditLogChannel) -> Self {
+        Self {
+            guild_id: c.guild_id.to_string().parse().unwrap(),
+            channel_id: c.channel_id.to_string().parse().unwrap(),
+        }
+    }
+}
+
+pub struct PostgresAuditLogChannelRepository {
+    pool: PgPool,
+}
+
+impl PostgresAuditLogChannelRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl AuditLogChannelRepository for PostgresAuditLogChannelRepository {
+    type Error = PostgresAuditLogChannelRepositoryError;
+
+    #[instrument(skip(self))]
+    async fn upsert_audit_log_channel(
+        &self,
+        audit_log_channel: UtAuditLogChannel,
+    ) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO auditlogchannels (guild_id, channel_id)
+            VALUES ($1, $2)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET channel_id = $2
+            "#,
+        )
+        .bind(BigDecimal::from(audit_log_channel.guild_id))
+        .bind(BigDecimal::from(audit_log_channel.channel_id))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "audit log channel upserted successfully in postgres. guild_id: {}",
+            audit_log_channel.guild_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_audit_log_channel(&self, guild_id: u64) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            DELETE FROM auditlogchannels
+            WHERE guild_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(guild_id))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "audit log channel deleted successfully from postgres. guild_id: {}",
+            guild_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_audit_log_channel(
+        &self,
+        guild_id: u64,
+    ) -> Result<Option<UtAuditLogChannel>, Self::Error> {
+        let audit_log_channel: Option<PostgresUtAuditLogChannel> = sqlx::query_as(
+            r#"
+            SELECT guild_id, channel_id
+            FROM auditlogchannels
+            WHERE guild_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(guild_id))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        info!(
+            "audit log channel fetched successfully from postgres. guild_id: {}",
+            guild_id
+        );
+        Ok(audit_log_channel.map(|c| c.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,74 @@
+use super::*;
+use domain::models::UtGuild;
+use domain::repository::GuildRepository;
+
+use crate::postgres_guild_repository::PostgresGuildRepository;
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer};
+
+async fn setup_guild(pool: &PgPool) -> u64 {
+    let guild_id = generate_random_20_digits();
+    PostgresGuildRepository::new(pool.clone())
+        .upsert_guild(UtGuild::new(guild_id, Some("test_guild".to_string())))
+        .await
+        .unwrap();
+    guild_id
+}
+
+#[tokio::test]
+async fn test_upsert_audit_log_channel() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresAuditLogChannelRepository::new(pool);
+
+    assert_eq!(
+        repository.get_audit_log_channel(guild_id).await.unwrap(),
+        None
+    );
+
+    let audit_log_channel = UtAuditLogChannel {
+        guild_id,
+        channel_id: generate_random_20_digits(),
+    };
+    repository
+        .upsert_audit_log_channel(audit_log_channel)
+        .await
+        .unwrap();
+
+    // 同じギルドは上書きする
+    let audit_log_channel = UtAuditLogChannel {
+        channel_id: generate_random_20_digits(),
+        ..audit_log_channel
+    };
+    repository
+        .upsert_audit_log_channel(audit_log_channel)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        repository.get_audit_log_channel(guild_id).await.unwrap(),
+        Some(audit_log_channel)
+    );
+}
+
+#[tokio::test]
+async fn test_delete_audit_log_channel() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresAuditLogChannelRepository::new(pool);
+
+    repository
+        .upsert_audit_log_channel(UtAuditLogChannel {
+            guild_id,
+            channel_id: generate_random_20_digits(),
+        })
+        .await
+        .unwrap();
+    repository.delete_audit_log_channel(guild_id).await.unwrap();
+
+    assert_eq!(
+        repository.get_audit_log_channel(guild_id).await.unwrap(),
+        None
+    );
+}
@@ -0,0 +1,199 @@
+use domain::models::{AuditAction, NewAuditEntry, UtAuditEntry};
+use domain::repository::AuditLogRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresAuditLogRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+    #[error("unknown audit action: {0}")]
+    UnknownAction(String),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+// entry_idはBIGSERIALなので，i64で受け取る
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtAuditEntry {
+    entry_id: i64,
+    guild_id: BigDecimal,
+    actor_id: Option<BigDecimal>,
+    action: String,
+    detail: String,
+    created_at: String,
+}
+
+// AuditActionをDBに保存する文字列に変換する
+
+fn action_to_str(action: AuditAction) -> &'static str {
+    match action {
+        AuditAction::GuildInit => "guild_init",
+        AuditAction::TimeSet => "time_set",
+        AuditAction::TimeWebhookRecreate => "time_webhook_recreate",
+        AuditAction::TimeDelete => "time_delete",
+        AuditAction::TimeRemove => "time_remove",
+        AuditAction::SettingChange => "setting_change",
+        AuditAction::Takedown => "takedown",
+        AuditAction::Retract => "retract",
+        AuditAction::ReviewApprove => "review_approve",
+        AuditAction::ReviewReject => "review_reject",
+        AuditAction::ForgetMe => "forget_me",
+    }
+}
+
+// PostgresUtAuditEntryをUtAuditEntryに変換する
+
+impl TryFrom<PostgresUtAuditEntry> for UtAuditEntry {
+    type Error = PostgresAuditLogRepositoryError;
+
+    fn try_from(e: PostgresUtAuditEntry) -> Result<Self, Self::Error> {
+        let action = match e.action.as_str() {
+            "guild_init" => AuditAction::GuildInit,
+            "time_set" => AuditAction::TimeSet,
+            "time_webhook_recreate" => AuditAction::TimeWebhookRecreate,
+            "time_delete" => AuditAction::TimeDelete,
+            "time_remove" => AuditAction::TimeRemove,
+            "setting_change" => AuditAction::SettingChange,
+            "takedown" => AuditAction::Takedown,
+            "retract" => AuditAction::Retract,
+            "review_approve" => AuditAction::ReviewApprove,
+            "review_reject" => AuditAction::ReviewReject,
+            "forget_me" => AuditAction::ForgetMe,
+            _ => return Err(PostgresAuditLogRepositoryError::UnknownAction(e.action)),
+        };
+        Ok(Self {
+            entry_id: e.entry_id as u64,
+            guild_id: e.guild_id.to_string().parse().unwrap(),
+            actor_id: e.actor_id.map(|a| a.to_string().parse().unwrap()),
+            action,
+            detail: e.detail,
+            created_at: e.created_at,
+        })
+    }
+}
+
+pub struct PostgresAuditLogRepository {
+    pool: PgPool,
+}
+
+impl PostgresAuditLogRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl AuditLogRepository for PostgresAuditLogRepository {
+    type Error = PostgresAuditLogRepositoryError;
+
+    #[instrument(skip(self))]
+    async fn insert_audit_entry(&self, entry: NewAuditEntry) -> Result<UtAuditEntry, Self::Error> {
+        let entry: PostgresUtAuditEntry = sqlx::query_as(
+            r#"
+            INSERT INTO auditentries (guild_id, actor_id, action, detail, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING entry_id, guild_id, actor_id, action, detail, created_at
+            "#,
+        )
+        .bind(BigDecimal::from(entry.guild_id))
+        .bind(entry.actor_id.map(BigDecimal::from))
+        .bind(action_to_str(entry.action))
+        .bind(&entry.detail)
+        .bind(&entry.created_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        info!(
+            "audit entry inserted successfully in postgres. guild_id: {}, entry_id: {}",
+            entry.guild_id, entry.entry_id
+        );
+        entry.try_into()
+    }
+
+    #[instrument(skip(self))]
+    async fn get_audit_entries(
+        &self,
+        guild_id: u64,
+        before: Option<u64>,
+        limit: usize,
+    ) -> Result<Vec<UtAuditEntry>, Self::Error> {
+        // BIGSERIALに収まらないidより前の項目は，すべての項目
+        let before = before.and_then(|b| i64::try_from(b).ok());
+        let entries: Vec<PostgresUtAuditEntry> = sqlx::query_as(
+            r#"
+            SELECT entry_id, guild_id, actor_id, action, detail, created_at
+            FROM auditentries
+            WHERE guild_id = $1 AND ($2::BIGINT IS NULL OR entry_id < $2)
+            ORDER BY entry_id DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(BigDecimal::from(guild_id))
+        .bind(before)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "audit entries fetched successfully from postgres. guild_id: {}, count: {}",
+            guild_id,
+            entries.len()
+        );
+        entries.into_iter().map(|e| e.try_into()).collect()
+    }
+
+    #[instrument(skip(self))]
+    async fn get_audit_entries_by_actor(
+        &self,
+        actor_id: u64,
+    ) -> Result<Vec<UtAuditEntry>, Self::Error> {
+        let entries: Vec<PostgresUtAuditEntry> = sqlx::query_as(
+            r#"
+            SELECT entry_id, guild_id, actor_id, action, detail, created_at
+            FROM auditentries
+            WHERE actor_id = $1
+            ORDER BY entry_id
+            "#,
+        )
+        .bind(BigDecimal::from(actor_id))
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "audit entries fetched successfully from postgres. actor_id: {}, count: {}",
+            actor_id,
+            entries.len()
+        );
+        entries.into_iter().map(|e| e.try_into()).collect()
+    }
+
+    #[instrument(skip(self))]
+    async fn anonymize_audit_entries_by_actor(&self, actor_id: u64) -> Result<(), Self::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE auditentries
+            SET actor_id = NULL
+            WHERE actor_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(actor_id))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "audit entries anonymized successfully in postgres. actor_id: {}, count: {}",
+            actor_id,
+            result.rows_affected()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
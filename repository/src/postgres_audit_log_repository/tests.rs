@@ -0,0 +1,184 @@
+use super::*;
+
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer};
+
+fn new_entry(guild_id: u64, actor_id: Option<u64>, detail: &str) -> NewAuditEntry {
+    NewAuditEntry {
+        guild_id,
+        actor_id,
+        action: AuditAction::SettingChange,
+        detail: detail.to_string(),
+        created_at: "2024-01-01T00:00:00Z".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_insert_audit_entry() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let repository = PostgresAuditLogRepository::new(pool);
+
+    let guild_id = generate_random_20_digits();
+    let actor_id = generate_random_20_digits();
+    let entry = repository
+        .insert_audit_entry(new_entry(guild_id, Some(actor_id), "first"))
+        .await
+        .unwrap();
+
+    assert_eq!(entry.guild_id, guild_id);
+    assert_eq!(entry.actor_id, Some(actor_id));
+    assert_eq!(entry.action, AuditAction::SettingChange);
+    assert_eq!(entry.detail, "first");
+
+    // botが自分で行った操作
+    let entry = repository
+        .insert_audit_entry(new_entry(guild_id, None, "second"))
+        .await
+        .unwrap();
+    assert_eq!(entry.actor_id, None);
+}
+
+#[tokio::test]
+async fn test_audit_actions_round_trip() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let repository = PostgresAuditLogRepository::new(pool);
+
+    let guild_id = generate_random_20_digits();
+    let actions = [
+        AuditAction::Takedown,
+        AuditAction::Retract,
+        AuditAction::ReviewApprove,
+        AuditAction::ReviewReject,
+        AuditAction::ForgetMe,
+    ];
+    for action in actions {
+        repository
+            .insert_audit_entry(NewAuditEntry {
+                action,
+                ..new_entry(guild_id, None, "detail")
+            })
+            .await
+            .unwrap();
+    }
+
+    let entries = repository
+        .get_audit_entries(guild_id, None, actions.len())
+        .await
+        .unwrap();
+    let mut read: Vec<_> = entries.iter().map(|e| e.action).collect();
+    read.reverse();
+    assert_eq!(read, actions);
+}
+
+#[tokio::test]
+async fn test_get_audit_entries_pages_newest_first() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let repository = PostgresAuditLogRepository::new(pool);
+
+    let guild_id = generate_random_20_digits();
+    for detail in ["1", "2", "3"] {
+        repository
+            .insert_audit_entry(new_entry(guild_id, None, detail))
+            .await
+            .unwrap();
+    }
+    // 他のギルドの項目は含めない
+    repository
+        .insert_audit_entry(new_entry(generate_random_20_digits(), None, "other"))
+        .await
+        .unwrap();
+
+    let first = repository
+        .get_audit_entries(guild_id, None, 2)
+        .await
+        .unwrap();
+    let details: Vec<_> = first.iter().map(|e| e.detail.as_str()).collect();
+    assert_eq!(details, vec!["3", "2"]);
+
+    let second = repository
+        .get_audit_entries(guild_id, Some(first[1].entry_id), 2)
+        .await
+        .unwrap();
+    let details: Vec<_> = second.iter().map(|e| e.detail.as_str()).collect();
+    assert_eq!(details, vec!["1"]);
+}
+
+#[tokio::test]
+async fn test_get_audit_entries_by_actor() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let repository = PostgresAuditLogRepository::new(pool);
+
+    let actor_id = generate_random_20_digits();
+    // ギルドをまたいで，古い順に取得する
+    for (guild_id, detail) in [
+        (generate_random_20_digits(), "1"),
+        (generate_random_20_digits(), "2"),
+    ] {
+        repository
+            .insert_audit_entry(new_entry(guild_id, Some(actor_id), detail))
+            .await
+            .unwrap();
+    }
+    // 他のユーザーやbotの操作は含めない
+    let guild_id = generate_random_20_digits();
+    repository
+        .insert_audit_entry(new_entry(
+            guild_id,
+            Some(generate_random_20_digits()),
+            "other",
+        ))
+        .await
+        .unwrap();
+    repository
+        .insert_audit_entry(new_entry(guild_id, None, "bot"))
+        .await
+        .unwrap();
+
+    let entries = repository
+        .get_audit_entries_by_actor(actor_id)
+        .await
+        .unwrap();
+    let details: Vec<_> = entries.iter().map(|e| e.detail.as_str()).collect();
+    assert_eq!(details, vec!["1", "2"]);
+}
+
+#[tokio::test]
+async fn test_anonymize_audit_entries_by_actor() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let repository = PostgresAuditLogRepository::new(pool);
+
+    let guild_id = generate_random_20_digits();
+    let actor_id = generate_random_20_digits();
+    let other_actor_id = generate_random_20_digits();
+    repository
+        .insert_audit_entry(new_entry(guild_id, Some(actor_id), "mine"))
+        .await
+        .unwrap();
+    repository
+        .insert_audit_entry(new_entry(guild_id, Some(other_actor_id), "other"))
+        .await
+        .unwrap();
+
+    repository
+        .anonymize_audit_entries_by_actor(actor_id)
+        .await
+        .unwrap();
+
+    assert!(repository
+        .get_audit_entries_by_actor(actor_id)
+        .await
+        .unwrap()
+        .is_empty());
+    // 項目そのものは残り，他のユーザーの項目は変えない
+    let entries = repository
+        .get_audit_entries(guild_id, None, 10)
+        .await
+        .unwrap();
+    let actors: Vec<_> = entries
+        .iter()
+        .map(|e| (e.detail.as_str(), e.actor_id))
+        .collect();
+    assert_eq!(
+        actors,
+        vec![("other", Some(other_actor_id)), ("mine", None)]
+    );
+}
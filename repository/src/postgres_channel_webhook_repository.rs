@@ -0,0 +1,218 @@
+use std::sync::Arc;
+
+use domain::models::{UtChannelWebhook, WebhookUrl};
+use domain::repository::ChannelWebhookRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use crate::webhook_cipher::{WebhookCipher, WebhookCipherError};
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresChannelWebhookRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+    #[error("webhook cipher error: {0}")]
+    WebhookCipherError(#[from] WebhookCipherError),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtChannelWebhook {
+    channel_id: BigDecimal,
+    guild_id: BigDecimal,
+    /// 暗号化したもの．暗号化する前の行では平文のURL
+    #[sqlx(try_from = "String")]
+    webhook_url: WebhookUrl,
+}
+
+// UtChannelWebhookをPostgresUtChannelWebhookに変換する
+// WebhookのURLは暗号化して保存する
+
+// WebhookのURLの暗号文を，チャンネルとサーバーの組に結びつける
+fn associated_data(channel_id: u64, guild_id: u64) -> Vec<u8> {
+    format!("channelwebhooks:{}:{}", channel_id, guild_id).into_bytes()
+}
+
+impl PostgresUtChannelWebhook {
+    fn encrypt(w: UtChannelWebhook, cipher: &WebhookCipher) -> Result<Self, WebhookCipherError> {
+        Ok(Self {
+            channel_id: BigDecimal::from(w.channel_id),
+            guild_id: BigDecimal::from(w.guild_id),
+            webhook_url: cipher
+                .encrypt(
+                    w.webhook_url.expose_secret(),
+                    &associated_data(w.channel_id, w.guild_id),
+                )?
+                .into(),
+        })
+    }
+
+    // PostgresUtChannelWebhookをUtChannelWebhookに変換する
+    fn decrypt(self, cipher: &WebhookCipher) -> Result<UtChannelWebhook, WebhookCipherError> {
+        let channel_id = self.channel_id.to_string().parse().unwrap();
+        let guild_id = self.guild_id.to_string().parse().unwrap();
+        Ok(UtChannelWebhook {
+            channel_id,
+            guild_id,
+            webhook_url: cipher
+                .decrypt(
+                    self.webhook_url.expose_secret(),
+                    &associated_data(channel_id, guild_id),
+                )?
+                .into(),
+        })
+    }
+}
+
+pub struct PostgresChannelWebhookRepository {
+    pool: PgPool,
+    cipher: Arc<WebhookCipher>,
+}
+
+impl PostgresChannelWebhookRepository {
+    pub fn new(pool: PgPool, cipher: Arc<WebhookCipher>) -> Self {
+        Self { pool, cipher }
+    }
+
+    /// 暗号化されていないWebhookのURLと，古い鍵で暗号化されたURLを，現在の鍵で暗号化しなおす
+    /// 起動時に実行する．何度実行してもよい
+    /// 暗号化しなおした行の数を返す
+    #[instrument(skip(self))]
+    pub async fn encrypt_webhook_urls(
+        &self,
+    ) -> Result<usize, PostgresChannelWebhookRepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let rows: Vec<(BigDecimal, BigDecimal, String)> = sqlx::query_as(
+            r#"
+            SELECT channel_id, guild_id, webhook_url
+            FROM channelwebhooks
+            FOR UPDATE
+            "#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut count = 0;
+        for (channel_id, guild_id, webhook_url) in rows {
+            if !self.cipher.needs_reencryption(&webhook_url) {
+                continue;
+            }
+            let webhook_url = self.cipher.reencrypt(
+                &webhook_url,
+                &associated_data(
+                    channel_id.to_string().parse().unwrap(),
+                    guild_id.to_string().parse().unwrap(),
+                ),
+            )?;
+            sqlx::query(
+                r#"
+                UPDATE channelwebhooks
+                SET webhook_url = $2
+                WHERE channel_id = $1
+                "#,
+            )
+            .bind(channel_id)
+            .bind(webhook_url)
+            .execute(&mut *tx)
+            .await?;
+            count += 1;
+        }
+
+        tx.commit().await?;
+        info!(
+            "webhook urls of channel webhooks encrypted. count: {}",
+            count
+        );
+        Ok(count)
+    }
+}
+
+impl ChannelWebhookRepository for PostgresChannelWebhookRepository {
+    type Error = PostgresChannelWebhookRepositoryError;
+
+    #[instrument(skip(self, channel_webhook))]
+    async fn upsert_channel_webhook(
+        &self,
+        channel_webhook: UtChannelWebhook,
+    ) -> Result<(), Self::Error> {
+        let postgres_channel_webhook =
+            PostgresUtChannelWebhook::encrypt(channel_webhook, &self.cipher)?;
+        sqlx::query(
+            r#"
+            INSERT INTO channelwebhooks (channel_id, guild_id, webhook_url)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (channel_id) DO UPDATE
+            SET guild_id = $2, webhook_url = $3
+            "#,
+        )
+        .bind(&postgres_channel_webhook.channel_id)
+        .bind(&postgres_channel_webhook.guild_id)
+        .bind(postgres_channel_webhook.webhook_url.expose_secret())
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "channel webhook upserted successfully in postgres. channel_id: {}",
+            postgres_channel_webhook.channel_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_channel_webhook(
+        &self,
+        channel_id: u64,
+    ) -> Result<Option<UtChannelWebhook>, Self::Error> {
+        let bigdecimal_channel_id = BigDecimal::from(channel_id);
+        let channel_webhook: Option<PostgresUtChannelWebhook> = sqlx::query_as(
+            r#"
+            SELECT channel_id, guild_id, webhook_url
+            FROM channelwebhooks
+            WHERE channel_id = $1
+            "#,
+        )
+        .bind(bigdecimal_channel_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        info!(
+            "channel webhook fetched successfully from postgres. channel_id: {}",
+            channel_id
+        );
+
+        Ok(channel_webhook
+            .map(|w| w.decrypt(&self.cipher))
+            .transpose()?)
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_channel_webhook(&self, channel_id: u64) -> Result<(), Self::Error> {
+        let bigdecimal_channel_id = BigDecimal::from(channel_id);
+        sqlx::query(
+            r#"
+            DELETE FROM channelwebhooks
+            WHERE channel_id = $1
+            "#,
+        )
+        .bind(bigdecimal_channel_id)
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "channel webhook deleted successfully from postgres. channel_id: {}",
+            channel_id
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,127 @@
+// 外部キー制約の都合，guildsテーブルにもデータを入れる必要がある
+use super::*;
+use crate::postgres_guild_repository::PostgresGuildRepository;
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer, test_cipher};
+use domain::{models::UtGuild, repository::GuildRepository};
+
+async fn setup_channel_webhook(pool: &PgPool, webhook_url: &str) -> UtChannelWebhook {
+    let guild_id = generate_random_20_digits();
+    let guild_repository = PostgresGuildRepository::new(pool.clone());
+    guild_repository
+        .upsert_guild(UtGuild::new(guild_id, Some("guild_name".to_string())))
+        .await
+        .unwrap();
+
+    UtChannelWebhook {
+        channel_id: generate_random_20_digits(),
+        guild_id,
+        webhook_url: webhook_url.into(),
+    }
+}
+
+#[tokio::test]
+/// upsert_channel_webhookとget_channel_webhookを実行し，入れた値と取り出した値が一致するかどうかを確認する
+async fn test_get_channel_webhook() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let channel_webhook = setup_channel_webhook(&pool, "webhook_url").await;
+
+    let repository = PostgresChannelWebhookRepository::new(pool, test_cipher());
+    repository
+        .upsert_channel_webhook(channel_webhook.clone())
+        .await
+        .unwrap();
+
+    let fetched = repository
+        .get_channel_webhook(channel_webhook.channel_id)
+        .await
+        .unwrap();
+    assert_eq!(fetched, Some(channel_webhook));
+}
+
+#[tokio::test]
+/// 同じチャンネルに２回登録した場合，後から登録したWebhookになることを確認する
+async fn test_upsert_channel_webhook_twice() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let channel_webhook = setup_channel_webhook(&pool, "webhook_url").await;
+
+    let repository = PostgresChannelWebhookRepository::new(pool, test_cipher());
+    repository
+        .upsert_channel_webhook(channel_webhook.clone())
+        .await
+        .unwrap();
+    let updated = UtChannelWebhook {
+        webhook_url: "new_webhook_url".into(),
+        ..channel_webhook
+    };
+    repository
+        .upsert_channel_webhook(updated.clone())
+        .await
+        .unwrap();
+
+    let fetched = repository
+        .get_channel_webhook(updated.channel_id)
+        .await
+        .unwrap();
+    assert_eq!(fetched, Some(updated));
+}
+
+#[tokio::test]
+/// delete_channel_webhookを実行し，入れた値を削除できるかどうかを確認する
+async fn test_delete_channel_webhook() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let channel_webhook = setup_channel_webhook(&pool, "webhook_url").await;
+
+    let repository = PostgresChannelWebhookRepository::new(pool, test_cipher());
+    repository
+        .upsert_channel_webhook(channel_webhook.clone())
+        .await
+        .unwrap();
+    repository
+        .delete_channel_webhook(channel_webhook.channel_id)
+        .await
+        .unwrap();
+
+    let fetched = repository
+        .get_channel_webhook(channel_webhook.channel_id)
+        .await
+        .unwrap();
+    assert_eq!(fetched, None);
+}
+
+#[tokio::test]
+/// 暗号化する前の行を，暗号化しなおせることを確認する
+async fn test_encrypt_webhook_urls() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let channel_webhook =
+        setup_channel_webhook(&pool, "https://discord.com/api/webhooks/1/secret-token").await;
+
+    sqlx::query(
+        r#"
+        INSERT INTO channelwebhooks (channel_id, guild_id, webhook_url)
+        VALUES ($1, $2, $3)
+        "#,
+    )
+    .bind(BigDecimal::from(channel_webhook.channel_id))
+    .bind(BigDecimal::from(channel_webhook.guild_id))
+    .bind(channel_webhook.webhook_url.expose_secret())
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let repository = PostgresChannelWebhookRepository::new(pool.clone(), test_cipher());
+    assert_eq!(repository.encrypt_webhook_urls().await.unwrap(), 1);
+
+    let (stored,): (String,) =
+        sqlx::query_as("SELECT webhook_url FROM channelwebhooks WHERE channel_id = $1")
+            .bind(BigDecimal::from(channel_webhook.channel_id))
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(!stored.contains("secret-token"));
+
+    let fetched = repository
+        .get_channel_webhook(channel_webhook.channel_id)
+        .await
+        .unwrap();
+    assert_eq!(fetched, Some(channel_webhook));
+}
@@ -0,0 +1,201 @@
+use domain::models::{ContentFilterRule, FilterAction, UtContentFilter};
+use domain::repository::ContentFilterRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresContentFilterRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+    #[error("unknown content filter kind: {0}")]
+    UnknownKind(String),
+    #[error("invalid content filter value: {0}")]
+    InvalidValue(String),
+    #[error("unknown filter action: {0}")]
+    UnknownAction(String),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+// filter_idはBIGSERIALなので，i64で受け取る
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtContentFilter {
+    filter_id: i64,
+    guild_id: BigDecimal,
+    kind: String,
+    value: String,
+    action: String,
+}
+
+// ContentFilterRuleをDBに保存するkindとvalueに変換する
+
+fn rule_to_kind_and_value(rule: &ContentFilterRule) -> (&'static str, String) {
+    match rule {
+        ContentFilterRule::Regex(pattern) => ("regex", pattern.clone()),
+        ContentFilterRule::Word(word) => ("word", word.clone()),
+        ContentFilterRule::MaxAttachmentSize(size) => ("max_attachment_size", size.to_string()),
+        ContentFilterRule::AllowedMimeTypes(types) => ("allowed_mime_types", types.join(",")),
+        ContentFilterRule::NoLinks => ("no_links", String::new()),
+    }
+}
+
+fn action_to_str(action: FilterAction) -> &'static str {
+    match action {
+        FilterAction::Block => "block",
+        FilterAction::Redact => "redact",
+        FilterAction::Warn => "warn",
+    }
+}
+
+// PostgresUtContentFilterをUtContentFilterに変換する
+
+impl TryFrom<PostgresUtContentFilter> for UtContentFilter {
+    type Error = PostgresContentFilterRepositoryError;
+
+    fn try_from(f: PostgresUtContentFilter) -> Result<Self, Self::Error> {
+        let rule = match f.kind.as_str() {
+            "regex" => ContentFilterRule::Regex(f.value),
+            "word" => ContentFilterRule::Word(f.value),
+            "max_attachment_size" => ContentFilterRule::MaxAttachmentSize(
+                f.value
+                    .parse()
+                    .map_err(|_| PostgresContentFilterRepositoryError::InvalidValue(f.value))?,
+            ),
+            "allowed_mime_types" => ContentFilterRule::AllowedMimeTypes(
+                f.value
+                    .split(',')
+                    .filter(|t| !t.is_empty())
+                    .map(|t| t.to_string())
+                    .collect(),
+            ),
+            "no_links" => ContentFilterRule::NoLinks,
+            _ => return Err(PostgresContentFilterRepositoryError::UnknownKind(f.kind)),
+        };
+        let action = match f.action.as_str() {
+            "block" => FilterAction::Block,
+            "redact" => FilterAction::Redact,
+            "warn" => FilterAction::Warn,
+            _ => {
+                return Err(PostgresContentFilterRepositoryError::UnknownAction(
+                    f.action,
+                ))
+            }
+        };
+        Ok(Self {
+            filter_id: f.filter_id as u64,
+            guild_id: f.guild_id.to_string().parse().unwrap(),
+            rule,
+            action,
+        })
+    }
+}
+
+pub struct PostgresContentFilterRepository {
+    pool: PgPool,
+}
+
+impl PostgresContentFilterRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl ContentFilterRepository for PostgresContentFilterRepository {
+    type Error = PostgresContentFilterRepositoryError;
+
+    #[instrument(skip(self))]
+    async fn insert_content_filter(
+        &self,
+        guild_id: u64,
+        rule: ContentFilterRule,
+        action: FilterAction,
+    ) -> Result<UtContentFilter, Self::Error> {
+        let (kind, value) = rule_to_kind_and_value(&rule);
+        let filter: PostgresUtContentFilter = sqlx::query_as(
+            r#"
+            INSERT INTO contentfilters (guild_id, kind, value, action)
+            VALUES ($1, $2, $3, $4)
+            RETURNING filter_id, guild_id, kind, value, action
+            "#,
+        )
+        .bind(BigDecimal::from(guild_id))
+        .bind(kind)
+        .bind(value)
+        .bind(action_to_str(action))
+        .fetch_one(&self.pool)
+        .await?;
+
+        info!(
+            "content filter inserted successfully in postgres. guild_id: {}, filter_id: {}",
+            guild_id, filter.filter_id
+        );
+        filter.try_into()
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_content_filter(
+        &self,
+        guild_id: u64,
+        filter_id: u64,
+    ) -> Result<bool, Self::Error> {
+        // BIGSERIALに収まらないidは，どのフィルタのidでもない
+        let Ok(filter_id) = i64::try_from(filter_id) else {
+            return Ok(false);
+        };
+        let result = sqlx::query(
+            r#"
+            DELETE FROM contentfilters
+            WHERE guild_id = $1 AND filter_id = $2
+            "#,
+        )
+        .bind(BigDecimal::from(guild_id))
+        .bind(filter_id)
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "content filter deleted from postgres. guild_id: {}, filter_id: {}, deleted: {}",
+            guild_id,
+            filter_id,
+            result.rows_affected()
+        );
+        Ok(result.rows_affected() > 0)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_content_filters(
+        &self,
+        guild_ids: Vec<u64>,
+    ) -> Result<Vec<UtContentFilter>, Self::Error> {
+        let guild_ids = guild_ids
+            .into_iter()
+            .map(BigDecimal::from)
+            .collect::<Vec<_>>();
+        let filters: Vec<PostgresUtContentFilter> = sqlx::query_as(
+            r#"
+            SELECT filter_id, guild_id, kind, value, action
+            FROM contentfilters
+            WHERE guild_id = ANY($1)
+            ORDER BY filter_id
+            "#,
+        )
+        .bind(guild_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "content filters fetched successfully from postgres. count: {}",
+            filters.len()
+        );
+        filters.into_iter().map(|f| f.try_into()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests;
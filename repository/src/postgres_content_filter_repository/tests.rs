@@ -0,0 +1,126 @@
+use super::*;
+use domain::models::UtGuild;
+use domain::repository::GuildRepository;
+
+use crate::postgres_guild_repository::PostgresGuildRepository;
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer};
+
+async fn setup_guild(pool: &PgPool) -> u64 {
+    let guild_id = generate_random_20_digits();
+    PostgresGuildRepository::new(pool.clone())
+        .upsert_guild(UtGuild::new(guild_id, Some("test_guild".to_string())))
+        .await
+        .unwrap();
+    guild_id
+}
+
+#[tokio::test]
+async fn test_insert_content_filter() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresContentFilterRepository::new(pool);
+
+    let rules = [
+        (
+            ContentFilterRule::Regex(r"spam\d+".to_string()),
+            FilterAction::Block,
+        ),
+        (
+            ContentFilterRule::Word("secret".to_string()),
+            FilterAction::Redact,
+        ),
+        (
+            ContentFilterRule::MaxAttachmentSize(8 * 1024 * 1024),
+            FilterAction::Redact,
+        ),
+        (
+            ContentFilterRule::AllowedMimeTypes(vec![
+                "image/*".to_string(),
+                "text/plain".to_string(),
+            ]),
+            FilterAction::Block,
+        ),
+        (ContentFilterRule::NoLinks, FilterAction::Warn),
+    ];
+    let mut inserted = Vec::new();
+    for (rule, action) in rules.iter().cloned() {
+        let filter = repository
+            .insert_content_filter(guild_id, rule.clone(), action)
+            .await
+            .unwrap();
+        assert_eq!(filter.guild_id, guild_id);
+        assert_eq!(filter.rule, rule);
+        assert_eq!(filter.action, action);
+        inserted.push(filter);
+    }
+
+    let filters = repository
+        .get_content_filters(vec![guild_id])
+        .await
+        .unwrap();
+    assert_eq!(filters, inserted);
+}
+
+#[tokio::test]
+async fn test_get_content_filters() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let first = setup_guild(&pool).await;
+    let second = setup_guild(&pool).await;
+    let unrelated = setup_guild(&pool).await;
+
+    let repository = PostgresContentFilterRepository::new(pool);
+
+    let mut expected = Vec::new();
+    for guild_id in [first, second] {
+        expected.push(
+            repository
+                .insert_content_filter(guild_id, ContentFilterRule::NoLinks, FilterAction::Block)
+                .await
+                .unwrap(),
+        );
+    }
+    repository
+        .insert_content_filter(unrelated, ContentFilterRule::NoLinks, FilterAction::Block)
+        .await
+        .unwrap();
+
+    let filters = repository
+        .get_content_filters(vec![first, second])
+        .await
+        .unwrap();
+    assert_eq!(filters, expected);
+}
+
+#[tokio::test]
+async fn test_delete_content_filter() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+    let other_guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresContentFilterRepository::new(pool);
+
+    let filter = repository
+        .insert_content_filter(guild_id, ContentFilterRule::NoLinks, FilterAction::Warn)
+        .await
+        .unwrap();
+
+    // 他のギルドのフィルタは削除できない
+    let deleted = repository
+        .delete_content_filter(other_guild_id, filter.filter_id)
+        .await
+        .unwrap();
+    assert!(!deleted);
+
+    let deleted = repository
+        .delete_content_filter(guild_id, filter.filter_id)
+        .await
+        .unwrap();
+    assert!(deleted);
+
+    let filters = repository
+        .get_content_filters(vec![guild_id])
+        .await
+        .unwrap();
+    assert!(filters.is_empty());
+}
@@ -0,0 +1,312 @@
+use domain::models::UtDelivery;
+use domain::repository::DeliveryRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresDeliveryRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtDelivery {
+    mirror_message_id: BigDecimal,
+    source_message_id: BigDecimal,
+    source_guild_id: BigDecimal,
+    source_channel_id: BigDecimal,
+    user_id: BigDecimal,
+    target_guild_id: BigDecimal,
+    target_channel_id: BigDecimal,
+    part: i32,
+}
+
+// UtDeliveryをPostgresUtDeliveryに変換する
+
+impl From<UtDelivery> for PostgresUtDelivery {
+    fn from(d: UtDelivery) -> Self {
+        Self {
+            mirror_message_id: BigDecimal::from(d.mirror_message_id),
+            source_message_id: BigDecimal::from(d.source_message_id),
+            source_guild_id: BigDecimal::from(d.source_guild_id),
+            source_channel_id: BigDecimal::from(d.source_channel_id),
+            user_id: BigDecimal::from(d.user_id),
+            target_guild_id: BigDecimal::from(d.target_guild_id),
+            target_channel_id: BigDecimal::from(d.target_channel_id),
+            part: d.part as i32,
+        }
+    }
+}
+
+// PostgresUtDeliveryをUtDeliveryに変換する
+
+impl From<PostgresUtDelivery> for UtDelivery {
+    fn from(p: PostgresUtDelivery) -> Self {
+        Self {
+            mirror_message_id: p.mirror_message_id.to_string().parse().unwrap(),
+            source_message_id: p.source_message_id.to_string().parse().unwrap(),
+            source_guild_id: p.source_guild_id.to_string().parse().unwrap(),
+            source_channel_id: p.source_channel_id.to_string().parse().unwrap(),
+            user_id: p.user_id.to_string().parse().unwrap(),
+            target_guild_id: p.target_guild_id.to_string().parse().unwrap(),
+            target_channel_id: p.target_channel_id.to_string().parse().unwrap(),
+            part: p.part as u32,
+        }
+    }
+}
+
+pub struct PostgresDeliveryRepository {
+    pool: PgPool,
+}
+
+impl PostgresDeliveryRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl DeliveryRepository for PostgresDeliveryRepository {
+    type Error = PostgresDeliveryRepositoryError;
+
+    #[instrument(skip(self, deliveries))]
+    async fn insert_deliveries(&self, deliveries: Vec<UtDelivery>) -> Result<(), Self::Error> {
+        // 分割送信したものは，まとめて記録されるか，まったく記録されないかのどちらかにする
+        let mut tx = self.pool.begin().await?;
+
+        let count = deliveries.len();
+        for delivery in deliveries {
+            let postgres_delivery = PostgresUtDelivery::from(delivery);
+            sqlx::query(
+                r#"
+                INSERT INTO deliveries (mirror_message_id, source_message_id, source_guild_id,
+                    source_channel_id, user_id, target_guild_id, target_channel_id, part)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT (mirror_message_id) DO NOTHING
+                "#,
+            )
+            .bind(&postgres_delivery.mirror_message_id)
+            .bind(&postgres_delivery.source_message_id)
+            .bind(&postgres_delivery.source_guild_id)
+            .bind(&postgres_delivery.source_channel_id)
+            .bind(&postgres_delivery.user_id)
+            .bind(&postgres_delivery.target_guild_id)
+            .bind(&postgres_delivery.target_channel_id)
+            .bind(postgres_delivery.part)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        info!(
+            "deliveries inserted successfully in postgres. count: {}",
+            count
+        );
+        Ok(())
+    }
+
+    /// source_message_idと一致する記録をすべて取得する
+    /// 送信先ギルドごと，分割した順に並べて返す
+    #[instrument(skip(self))]
+    async fn get_deliveries_by_source(
+        &self,
+        source_message_id: u64,
+    ) -> Result<Vec<UtDelivery>, Self::Error> {
+        let bigdecimal_source_message_id = BigDecimal::from(source_message_id);
+        let deliveries: Vec<PostgresUtDelivery> = sqlx::query_as(
+            r#"
+            SELECT mirror_message_id, source_message_id, source_guild_id, source_channel_id,
+                user_id, target_guild_id, target_channel_id, part
+            FROM deliveries
+            WHERE source_message_id = $1
+            ORDER BY target_guild_id, part
+            "#,
+        )
+        .bind(bigdecimal_source_message_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "deliveries fetched successfully from postgres. source_message_id: {}",
+            source_message_id
+        );
+
+        let deliveries = deliveries.into_iter().map(|d| d.into()).collect();
+
+        Ok(deliveries)
+    }
+
+    /// user_idと一致する記録をすべて取得する
+    /// 発信元メッセージごと，送信先ギルドごと，分割した順に並べて返す
+    #[instrument(skip(self))]
+    async fn get_deliveries_by_user(&self, user_id: u64) -> Result<Vec<UtDelivery>, Self::Error> {
+        let bigdecimal_user_id = BigDecimal::from(user_id);
+        let deliveries: Vec<PostgresUtDelivery> = sqlx::query_as(
+            r#"
+            SELECT mirror_message_id, source_message_id, source_guild_id, source_channel_id,
+                user_id, target_guild_id, target_channel_id, part
+            FROM deliveries
+            WHERE user_id = $1
+            ORDER BY source_message_id, target_guild_id, part
+            "#,
+        )
+        .bind(bigdecimal_user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "deliveries fetched successfully from postgres. user_id: {}",
+            user_id
+        );
+
+        let deliveries = deliveries.into_iter().map(|d| d.into()).collect();
+
+        Ok(deliveries)
+    }
+
+    #[instrument(skip(self, mirror_message_ids))]
+    async fn delete_deliveries(&self, mirror_message_ids: Vec<u64>) -> Result<(), Self::Error> {
+        let count = mirror_message_ids.len();
+        let bigdecimal_mirror_message_ids = mirror_message_ids
+            .into_iter()
+            .map(BigDecimal::from)
+            .collect::<Vec<_>>();
+
+        sqlx::query(
+            r#"
+            DELETE FROM deliveries
+            WHERE mirror_message_id = ANY($1)
+            "#,
+        )
+        .bind(bigdecimal_mirror_message_ids)
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "deliveries deleted successfully from postgres. count: {}",
+            count
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_delivery_by_mirror(
+        &self,
+        mirror_message_id: u64,
+    ) -> Result<Option<UtDelivery>, Self::Error> {
+        let bigdecimal_mirror_message_id = BigDecimal::from(mirror_message_id);
+        let delivery: Option<PostgresUtDelivery> = sqlx::query_as(
+            r#"
+            SELECT mirror_message_id, source_message_id, source_guild_id, source_channel_id,
+                user_id, target_guild_id, target_channel_id, part
+            FROM deliveries
+            WHERE mirror_message_id = $1
+            "#,
+        )
+        .bind(bigdecimal_mirror_message_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        info!(
+            "delivery fetched successfully from postgres. mirror_message_id: {}",
+            mirror_message_id
+        );
+
+        Ok(delivery.map(|d| d.into()))
+    }
+
+    #[instrument(skip(self))]
+    async fn mark_released(&self, source_message_id: u64, user_id: u64) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO releasedmessages (source_message_id, user_id)
+            VALUES ($1, $2)
+            ON CONFLICT (source_message_id) DO NOTHING
+            "#,
+        )
+        .bind(BigDecimal::from(source_message_id))
+        .bind(BigDecimal::from(user_id))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "message marked as released in postgres. source_message_id: {}",
+            source_message_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn is_released(&self, source_message_id: u64) -> Result<bool, Self::Error> {
+        // 記録を始める前に拡散したメッセージは，配信ログにだけある
+        let released: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (SELECT 1 FROM releasedmessages WHERE source_message_id = $1)
+                OR EXISTS (SELECT 1 FROM deliveries WHERE source_message_id = $1)
+            "#,
+        )
+        .bind(BigDecimal::from(source_message_id))
+        .fetch_one(&self.pool)
+        .await?;
+
+        info!(
+            "released mark fetched successfully from postgres. source_message_id: {}",
+            source_message_id
+        );
+        Ok(released)
+    }
+
+    #[instrument(skip(self))]
+    async fn get_released_by_user(&self, user_id: u64) -> Result<Vec<u64>, Self::Error> {
+        let released: Vec<BigDecimal> = sqlx::query_scalar(
+            r#"
+            SELECT source_message_id
+            FROM releasedmessages
+            WHERE user_id = $1
+            ORDER BY source_message_id
+            "#,
+        )
+        .bind(BigDecimal::from(user_id))
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "released marks fetched successfully from postgres. count: {}",
+            released.len()
+        );
+        Ok(released
+            .into_iter()
+            .map(|id| id.to_string().parse().unwrap())
+            .collect())
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_released_by_user(&self, user_id: u64) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            DELETE FROM releasedmessages
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(user_id))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "released marks deleted successfully from postgres. user_id: {}",
+            user_id
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,232 @@
+use super::*;
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer};
+
+/// 同じ発信元メッセージを，指定したギルドへpart_count個に分割して送ったときの記録を作る
+fn deliveries_for(
+    source_message_id: u64,
+    user_id: u64,
+    target_guild_id: u64,
+    part_count: u32,
+) -> Vec<UtDelivery> {
+    let target_channel_id = generate_random_20_digits();
+    (0..part_count)
+        .map(|part| UtDelivery {
+            source_message_id,
+            source_guild_id: 1,
+            source_channel_id: 2,
+            user_id,
+            target_guild_id,
+            target_channel_id,
+            mirror_message_id: generate_random_20_digits(),
+            part,
+        })
+        .collect()
+}
+
+#[tokio::test]
+/// insert_deliveriesとget_deliveries_by_sourceを実行し，入れた値と取り出した値が一致するかどうかを確認する
+async fn test_get_deliveries_by_source() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let repository = PostgresDeliveryRepository::new(pool);
+
+    let source_message_id = generate_random_20_digits();
+    let user_id = generate_random_20_digits();
+    let mut deliveries = deliveries_for(source_message_id, user_id, 10, 3);
+    deliveries.extend(deliveries_for(source_message_id, user_id, 20, 1));
+
+    repository
+        .insert_deliveries(deliveries.clone())
+        .await
+        .unwrap();
+
+    let fetched = repository
+        .get_deliveries_by_source(source_message_id)
+        .await
+        .unwrap();
+
+    // ギルドごと，partの順に並んでいることを確認する
+    assert_eq!(fetched, deliveries);
+}
+
+#[tokio::test]
+/// 関係のない発信元メッセージの記録は取得されないことを確認する
+async fn test_get_deliveries_by_other_source() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let repository = PostgresDeliveryRepository::new(pool);
+
+    let user_id = generate_random_20_digits();
+    let deliveries = deliveries_for(generate_random_20_digits(), user_id, 10, 2);
+
+    repository.insert_deliveries(deliveries).await.unwrap();
+
+    let fetched = repository
+        .get_deliveries_by_source(generate_random_20_digits())
+        .await
+        .unwrap();
+    assert!(fetched.is_empty());
+}
+
+#[tokio::test]
+/// 拡散先のメッセージから記録を取得できるかどうかを確認する
+async fn test_get_delivery_by_mirror() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let repository = PostgresDeliveryRepository::new(pool);
+
+    let deliveries = deliveries_for(
+        generate_random_20_digits(),
+        generate_random_20_digits(),
+        10,
+        2,
+    );
+
+    repository
+        .insert_deliveries(deliveries.clone())
+        .await
+        .unwrap();
+
+    let fetched = repository
+        .get_delivery_by_mirror(deliveries[1].mirror_message_id)
+        .await
+        .unwrap();
+    assert_eq!(fetched, Some(deliveries[1].clone()));
+
+    let not_found = repository
+        .get_delivery_by_mirror(generate_random_20_digits())
+        .await
+        .unwrap();
+    assert_eq!(not_found, None);
+}
+
+#[tokio::test]
+/// そのユーザーの記録だけを，発信元メッセージの順に取得できるかどうかを確認する
+async fn test_get_deliveries_by_user() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let repository = PostgresDeliveryRepository::new(pool);
+
+    let user_id = generate_random_20_digits();
+    let mut deliveries = deliveries_for(generate_random_20_digits(), user_id, 10, 2);
+    deliveries.extend(deliveries_for(generate_random_20_digits(), user_id, 20, 1));
+    let others = deliveries_for(
+        generate_random_20_digits(),
+        generate_random_20_digits(),
+        10,
+        1,
+    );
+
+    repository
+        .insert_deliveries(deliveries.clone())
+        .await
+        .unwrap();
+    repository.insert_deliveries(others).await.unwrap();
+
+    let fetched = repository.get_deliveries_by_user(user_id).await.unwrap();
+
+    deliveries.sort_by_key(|d| (d.source_message_id, d.target_guild_id, d.part));
+    assert_eq!(fetched, deliveries);
+}
+
+#[tokio::test]
+/// 指定した拡散先のメッセージの記録だけを削除できるかどうかを確認する
+async fn test_delete_deliveries() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let repository = PostgresDeliveryRepository::new(pool);
+
+    let source_message_id = generate_random_20_digits();
+    let deliveries = deliveries_for(source_message_id, generate_random_20_digits(), 10, 3);
+
+    repository
+        .insert_deliveries(deliveries.clone())
+        .await
+        .unwrap();
+    repository
+        .delete_deliveries(vec![
+            deliveries[0].mirror_message_id,
+            deliveries[2].mirror_message_id,
+        ])
+        .await
+        .unwrap();
+
+    let fetched = repository
+        .get_deliveries_by_source(source_message_id)
+        .await
+        .unwrap();
+    assert_eq!(fetched, vec![deliveries[1].clone()]);
+
+    // もう一度削除しても失敗しない
+    repository
+        .delete_deliveries(vec![deliveries[0].mirror_message_id])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+/// mark_releasedで記録したメッセージと，配信ログにあるメッセージが拡散済みになることを確認する
+async fn test_is_released() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let repository = PostgresDeliveryRepository::new(pool);
+
+    let user_id = generate_random_20_digits();
+    let marked = generate_random_20_digits();
+    let delivered = generate_random_20_digits();
+    let other = generate_random_20_digits();
+
+    repository.mark_released(marked, user_id).await.unwrap();
+    // 同じメッセージを何度記録してもよい
+    repository.mark_released(marked, user_id).await.unwrap();
+    repository
+        .insert_deliveries(deliveries_for(
+            delivered,
+            user_id,
+            generate_random_20_digits(),
+            1,
+        ))
+        .await
+        .unwrap();
+
+    assert!(repository.is_released(marked).await.unwrap());
+    assert!(repository.is_released(delivered).await.unwrap());
+    assert!(!repository.is_released(other).await.unwrap());
+}
+
+#[tokio::test]
+/// ユーザーの拡散済みの記録を取得して，削除できることを確認する
+async fn test_released_by_user() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let repository = PostgresDeliveryRepository::new(pool);
+
+    let user_id = generate_random_20_digits();
+    let other_user_id = generate_random_20_digits();
+    let mut released = vec![generate_random_20_digits(), generate_random_20_digits()];
+    released.sort();
+    for source_message_id in released.iter() {
+        repository
+            .mark_released(*source_message_id, user_id)
+            .await
+            .unwrap();
+    }
+    let other = generate_random_20_digits();
+    repository
+        .mark_released(other, other_user_id)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        repository.get_released_by_user(user_id).await.unwrap(),
+        released
+    );
+
+    repository.delete_released_by_user(user_id).await.unwrap();
+    assert!(repository
+        .get_released_by_user(user_id)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(repository.is_released(other).await.unwrap());
+}
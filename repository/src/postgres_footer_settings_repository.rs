@@ -0,0 +1,209 @@
+use domain::models::{CommunityAccess, UtGuildFooterSettings, UtUserFooterSetting};
+use domain::repository::FooterSettingsRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresFooterSettingsRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+    #[error("unknown community access: {0}")]
+    UnknownAccess(String),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtGuildFooterSettings {
+    guild_id: BigDecimal,
+    enabled: bool,
+    access: String,
+}
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtUserFooterSetting {
+    user_id: BigDecimal,
+    enabled: bool,
+}
+
+// CommunityAccessをDBに保存する文字列に変換する
+
+fn access_to_str(access: CommunityAccess) -> &'static str {
+    match access {
+        CommunityAccess::Open => "open",
+        CommunityAccess::Closed => "closed",
+    }
+}
+
+// PostgresUtGuildFooterSettingsをUtGuildFooterSettingsに変換する
+
+impl TryFrom<PostgresUtGuildFooterSettings> for UtGuildFooterSettings {
+    type Error = PostgresFooterSettingsRepositoryError;
+
+    fn try_from(s: PostgresUtGuildFooterSettings) -> Result<Self, Self::Error> {
+        let access = match s.access.as_str() {
+            "open" => CommunityAccess::Open,
+            "closed" => CommunityAccess::Closed,
+            _ => {
+                return Err(PostgresFooterSettingsRepositoryError::UnknownAccess(
+                    s.access,
+                ))
+            }
+        };
+        Ok(Self {
+            guild_id: s.guild_id.to_string().parse().unwrap(),
+            enabled: s.enabled,
+            access,
+        })
+    }
+}
+
+// PostgresUtUserFooterSettingをUtUserFooterSettingに変換する
+
+impl From<PostgresUtUserFooterSetting> for UtUserFooterSetting {
+    fn from(s: PostgresUtUserFooterSetting) -> Self {
+        Self {
+            user_id: s.user_id.to_string().parse().unwrap(),
+            enabled: s.enabled,
+        }
+    }
+}
+
+pub struct PostgresFooterSettingsRepository {
+    pool: PgPool,
+}
+
+impl PostgresFooterSettingsRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl FooterSettingsRepository for PostgresFooterSettingsRepository {
+    type Error = PostgresFooterSettingsRepositoryError;
+
+    #[instrument(skip(self))]
+    async fn upsert_guild_footer_settings(
+        &self,
+        settings: UtGuildFooterSettings,
+    ) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO guildfootersettings (guild_id, enabled, access)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET enabled = $2, access = $3
+            "#,
+        )
+        .bind(BigDecimal::from(settings.guild_id))
+        .bind(settings.enabled)
+        .bind(access_to_str(settings.access))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "guild footer settings upserted successfully in postgres. guild_id: {}",
+            settings.guild_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_guild_footer_settings(
+        &self,
+        guild_id: u64,
+    ) -> Result<Option<UtGuildFooterSettings>, Self::Error> {
+        let settings: Option<PostgresUtGuildFooterSettings> = sqlx::query_as(
+            r#"
+            SELECT guild_id, enabled, access
+            FROM guildfootersettings
+            WHERE guild_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(guild_id))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        info!(
+            "guild footer settings fetched successfully from postgres. guild_id: {}",
+            guild_id
+        );
+        settings.map(|s| s.try_into()).transpose()
+    }
+
+    #[instrument(skip(self))]
+    async fn upsert_user_footer_setting(
+        &self,
+        setting: UtUserFooterSetting,
+    ) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO userfootersettings (user_id, enabled)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE
+            SET enabled = $2
+            "#,
+        )
+        .bind(BigDecimal::from(setting.user_id))
+        .bind(setting.enabled)
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "user footer setting upserted successfully in postgres. user_id: {}",
+            setting.user_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_user_footer_setting(&self, user_id: u64) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            DELETE FROM userfootersettings
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(user_id))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "user footer setting deleted successfully from postgres. user_id: {}",
+            user_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_user_footer_setting(
+        &self,
+        user_id: u64,
+    ) -> Result<Option<UtUserFooterSetting>, Self::Error> {
+        let setting: Option<PostgresUtUserFooterSetting> = sqlx::query_as(
+            r#"
+            SELECT user_id, enabled
+            FROM userfootersettings
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(user_id))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        info!(
+            "user footer setting fetched successfully from postgres. user_id: {}",
+            user_id
+        );
+        Ok(setting.map(|s| s.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,93 @@
+use super::*;
+use domain::models::UtGuild;
+use domain::repository::GuildRepository;
+
+use crate::postgres_guild_repository::PostgresGuildRepository;
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer};
+
+async fn setup_guild(pool: &PgPool) -> u64 {
+    let guild_id = generate_random_20_digits();
+    PostgresGuildRepository::new(pool.clone())
+        .upsert_guild(UtGuild::new(guild_id, Some("test_guild".to_string())))
+        .await
+        .unwrap();
+    guild_id
+}
+
+#[tokio::test]
+async fn test_upsert_guild_footer_settings() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresFooterSettingsRepository::new(pool);
+
+    assert_eq!(
+        repository
+            .get_guild_footer_settings(guild_id)
+            .await
+            .unwrap(),
+        None
+    );
+
+    repository
+        .upsert_guild_footer_settings(UtGuildFooterSettings {
+            guild_id,
+            enabled: true,
+            access: CommunityAccess::Closed,
+        })
+        .await
+        .unwrap();
+
+    // 同じギルドは上書きする
+    let settings = UtGuildFooterSettings {
+        guild_id,
+        enabled: false,
+        access: CommunityAccess::Open,
+    };
+    repository
+        .upsert_guild_footer_settings(settings)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        repository
+            .get_guild_footer_settings(guild_id)
+            .await
+            .unwrap(),
+        Some(settings)
+    );
+}
+
+#[tokio::test]
+async fn test_user_footer_setting() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let repository = PostgresFooterSettingsRepository::new(pool);
+
+    let user_id = generate_random_20_digits();
+    assert_eq!(
+        repository.get_user_footer_setting(user_id).await.unwrap(),
+        None
+    );
+
+    let setting = UtUserFooterSetting {
+        user_id,
+        enabled: true,
+    };
+    repository
+        .upsert_user_footer_setting(setting)
+        .await
+        .unwrap();
+    assert_eq!(
+        repository.get_user_footer_setting(user_id).await.unwrap(),
+        Some(setting)
+    );
+
+    repository
+        .delete_user_footer_setting(user_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        repository.get_user_footer_setting(user_id).await.unwrap(),
+        None
+    );
+}
@@ -0,0 +1,151 @@
+use domain::models::{GuildPairAction, UtGuildPairPolicy};
+use domain::repository::GuildPairPolicyRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresGuildPairPolicyRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+    #[error("unknown guild pair action: {0}")]
+    UnknownAction(String),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtGuildPairPolicy {
+    guild_id: BigDecimal,
+    other_guild_id: BigDecimal,
+    action: String,
+}
+
+// GuildPairActionをDBに保存する文字列に変換する
+
+fn action_to_str(action: GuildPairAction) -> &'static str {
+    match action {
+        GuildPairAction::Block => "block",
+        GuildPairAction::Allow => "allow",
+    }
+}
+
+// PostgresUtGuildPairPolicyをUtGuildPairPolicyに変換する
+
+impl TryFrom<PostgresUtGuildPairPolicy> for UtGuildPairPolicy {
+    type Error = PostgresGuildPairPolicyRepositoryError;
+
+    fn try_from(p: PostgresUtGuildPairPolicy) -> Result<Self, Self::Error> {
+        let action = match p.action.as_str() {
+            "block" => GuildPairAction::Block,
+            "allow" => GuildPairAction::Allow,
+            _ => {
+                return Err(PostgresGuildPairPolicyRepositoryError::UnknownAction(
+                    p.action,
+                ))
+            }
+        };
+        Ok(Self {
+            guild_id: p.guild_id.to_string().parse().unwrap(),
+            other_guild_id: p.other_guild_id.to_string().parse().unwrap(),
+            action,
+        })
+    }
+}
+
+pub struct PostgresGuildPairPolicyRepository {
+    pool: PgPool,
+}
+
+impl PostgresGuildPairPolicyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl GuildPairPolicyRepository for PostgresGuildPairPolicyRepository {
+    type Error = PostgresGuildPairPolicyRepositoryError;
+
+    #[instrument(skip(self))]
+    async fn upsert_guild_pair_policy(&self, policy: UtGuildPairPolicy) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO guildpairpolicies (guild_id, other_guild_id, action)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (guild_id, other_guild_id) DO UPDATE
+            SET action = $3
+            "#,
+        )
+        .bind(BigDecimal::from(policy.guild_id))
+        .bind(BigDecimal::from(policy.other_guild_id))
+        .bind(action_to_str(policy.action))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "guild pair policy upserted successfully in postgres. guild_id: {}, other_guild_id: {}, action: {:?}",
+            policy.guild_id, policy.other_guild_id, policy.action
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_guild_pair_policy(
+        &self,
+        guild_id: u64,
+        other_guild_id: u64,
+    ) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            DELETE FROM guildpairpolicies
+            WHERE guild_id = $1 AND other_guild_id = $2
+            "#,
+        )
+        .bind(BigDecimal::from(guild_id))
+        .bind(BigDecimal::from(other_guild_id))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "guild pair policy deleted successfully from postgres. guild_id: {}, other_guild_id: {}",
+            guild_id, other_guild_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_guild_pair_policies(
+        &self,
+        guild_ids: Vec<u64>,
+    ) -> Result<Vec<UtGuildPairPolicy>, Self::Error> {
+        let guild_ids = guild_ids
+            .into_iter()
+            .map(BigDecimal::from)
+            .collect::<Vec<_>>();
+        let policies: Vec<PostgresUtGuildPairPolicy> = sqlx::query_as(
+            r#"
+            SELECT guild_id, other_guild_id, action
+            FROM guildpairpolicies
+            WHERE guild_id = ANY($1)
+            ORDER BY guild_id, other_guild_id
+            "#,
+        )
+        .bind(guild_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "guild pair policies fetched successfully from postgres. count: {}",
+            policies.len()
+        );
+        policies.into_iter().map(|p| p.try_into()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,115 @@
+use super::*;
+use domain::models::UtGuild;
+use domain::repository::GuildRepository;
+
+use crate::postgres_guild_repository::PostgresGuildRepository;
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer};
+
+async fn setup_guild(pool: &PgPool) -> u64 {
+    let guild_id = generate_random_20_digits();
+    PostgresGuildRepository::new(pool.clone())
+        .upsert_guild(UtGuild::new(guild_id, Some("test_guild".to_string())))
+        .await
+        .unwrap();
+    guild_id
+}
+
+#[tokio::test]
+async fn test_upsert_guild_pair_policy() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresGuildPairPolicyRepository::new(pool);
+
+    let policy = UtGuildPairPolicy {
+        guild_id,
+        other_guild_id: generate_random_20_digits(),
+        action: GuildPairAction::Block,
+    };
+    repository
+        .upsert_guild_pair_policy(policy.clone())
+        .await
+        .unwrap();
+
+    // 同じ組は上書きする
+    let policy = UtGuildPairPolicy {
+        action: GuildPairAction::Allow,
+        ..policy
+    };
+    repository
+        .upsert_guild_pair_policy(policy.clone())
+        .await
+        .unwrap();
+
+    let policies = repository
+        .get_guild_pair_policies(vec![guild_id])
+        .await
+        .unwrap();
+    assert_eq!(policies, vec![policy]);
+}
+
+#[tokio::test]
+async fn test_get_guild_pair_policies() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let first = setup_guild(&pool).await;
+    let second = setup_guild(&pool).await;
+    let unrelated = setup_guild(&pool).await;
+
+    let repository = PostgresGuildPairPolicyRepository::new(pool);
+
+    let mut policies = vec![
+        UtGuildPairPolicy {
+            guild_id: first,
+            other_guild_id: second,
+            action: GuildPairAction::Block,
+        },
+        UtGuildPairPolicy {
+            guild_id: second,
+            other_guild_id: first,
+            action: GuildPairAction::Allow,
+        },
+    ];
+    for policy in policies.iter().cloned().chain([UtGuildPairPolicy {
+        guild_id: unrelated,
+        other_guild_id: first,
+        action: GuildPairAction::Block,
+    }]) {
+        repository.upsert_guild_pair_policy(policy).await.unwrap();
+    }
+
+    let mut fetched = repository
+        .get_guild_pair_policies(vec![first, second])
+        .await
+        .unwrap();
+    fetched.sort_by_key(|p| p.guild_id);
+    policies.sort_by_key(|p| p.guild_id);
+    assert_eq!(fetched, policies);
+}
+
+#[tokio::test]
+async fn test_delete_guild_pair_policy() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresGuildPairPolicyRepository::new(pool);
+
+    let policy = UtGuildPairPolicy {
+        guild_id,
+        other_guild_id: generate_random_20_digits(),
+        action: GuildPairAction::Block,
+    };
+    repository
+        .upsert_guild_pair_policy(policy.clone())
+        .await
+        .unwrap();
+    repository
+        .delete_guild_pair_policy(policy.guild_id, policy.other_guild_id)
+        .await
+        .unwrap();
+
+    let policies = repository
+        .get_guild_pair_policies(vec![guild_id])
+        .await
+        .unwrap();
+    assert!(policies.is_empty());
+}
@@ -125,6 +125,36 @@ impl GuildRepository for PostgresGuildRepository {
         );
         Ok(())
     }
+
+    #[instrument(skip(self))]
+    async fn search_guilds(
+        &self,
+        partial: String,
+        limit: usize,
+    ) -> Result<Vec<UtGuild>, Self::Error> {
+        // LIKEだと%や_をエスケープする必要があるので，strposで探す
+        let guilds: Vec<PostgresUtGuild> = sqlx::query_as(
+            r#"
+            SELECT guild_id, guild_name
+            FROM guilds
+            WHERE strpos(lower(coalesce(guild_name, '')), lower($1)) > 0
+                OR strpos(guild_id::text, $1) = 1
+            ORDER BY guild_name, guild_id
+            LIMIT $2
+            "#,
+        )
+        .bind(&partial)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "guilds searched successfully from postgres. partial: {}, count: {}",
+            partial,
+            guilds.len()
+        );
+        Ok(guilds.into_iter().map(|g| g.into()).collect())
+    }
 }
 
 #[cfg(test)]
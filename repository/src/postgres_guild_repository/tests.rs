@@ -58,3 +58,38 @@ async fn test_delete_guild() {
 
     repository.delete_guild(guild.guild_id).await.unwrap();
 }
+
+#[tokio::test]
+async fn test_search_guilds() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let repository = PostgresGuildRepository::new(pool);
+
+    let guilds = [
+        UtGuild::new(generate_random_20_digits(), Some("Rust 100%".to_string())),
+        UtGuild::new(generate_random_20_digits(), Some("rustacean".to_string())),
+        UtGuild::new(generate_random_20_digits(), Some("python".to_string())),
+        UtGuild::new(generate_random_20_digits(), None),
+    ];
+    for guild in guilds.iter() {
+        repository.upsert_guild(guild.clone()).await.unwrap();
+    }
+
+    let found = repository
+        .search_guilds("RUST".to_string(), 25)
+        .await
+        .unwrap();
+    assert_eq!(found, vec![guilds[0].clone(), guilds[1].clone()]);
+
+    // %はワイルドカードとして扱わない
+    let found = repository.search_guilds("%".to_string(), 25).await.unwrap();
+    assert_eq!(found, vec![guilds[0].clone()]);
+
+    // idは前方一致で探す
+    let partial = guilds[3].guild_id.to_string()[..10].to_string();
+    let found = repository.search_guilds(partial, 25).await.unwrap();
+    assert!(found.contains(&guilds[3]));
+
+    let found = repository.search_guilds(String::new(), 2).await.unwrap();
+    assert_eq!(found.len(), 2);
+}
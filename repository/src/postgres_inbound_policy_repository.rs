@@ -0,0 +1,223 @@
+use domain::models::{InboundMode, UtInboundPolicy};
+use domain::repository::InboundPolicyRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresInboundPolicyRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+    #[error("unknown inbound mode: {0}")]
+    UnknownMode(String),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtInboundPolicy {
+    guild_id: BigDecimal,
+    mode: String,
+    approval_channel_id: Option<BigDecimal>,
+}
+
+// InboundModeをDBに保存する文字列に変換する
+
+fn mode_to_str(mode: InboundMode) -> &'static str {
+    match mode {
+        InboundMode::Open => "open",
+        InboundMode::AllowList => "allow_list",
+        InboundMode::ApprovalQueue => "approval_queue",
+    }
+}
+
+fn mode_from_str(mode: &str) -> Result<InboundMode, PostgresInboundPolicyRepositoryError> {
+    match mode {
+        "open" => Ok(InboundMode::Open),
+        "allow_list" => Ok(InboundMode::AllowList),
+        "approval_queue" => Ok(InboundMode::ApprovalQueue),
+        _ => Err(PostgresInboundPolicyRepositoryError::UnknownMode(
+            mode.to_string(),
+        )),
+    }
+}
+
+pub struct PostgresInboundPolicyRepository {
+    pool: PgPool,
+}
+
+impl PostgresInboundPolicyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl InboundPolicyRepository for PostgresInboundPolicyRepository {
+    type Error = PostgresInboundPolicyRepositoryError;
+
+    #[instrument(skip(self))]
+    async fn upsert_inbound_policy(&self, policy: UtInboundPolicy) -> Result<(), Self::Error> {
+        let guild_id = BigDecimal::from(policy.guild_id);
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO inboundpolicies (guild_id, mode, approval_channel_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET mode = $2, approval_channel_id = $3
+            "#,
+        )
+        .bind(&guild_id)
+        .bind(mode_to_str(policy.mode))
+        .bind(policy.approval_channel_id.map(BigDecimal::from))
+        .execute(&mut *tx)
+        .await?;
+
+        // 許可したギルドは，まとめて置き換える
+        sqlx::query(
+            r#"
+            DELETE FROM inboundallowedguilds
+            WHERE guild_id = $1
+            "#,
+        )
+        .bind(&guild_id)
+        .execute(&mut *tx)
+        .await?;
+
+        for origin_guild_id in policy.allowed_guild_ids.iter() {
+            sqlx::query(
+                r#"
+                INSERT INTO inboundallowedguilds (guild_id, origin_guild_id)
+                VALUES ($1, $2)
+                ON CONFLICT DO NOTHING
+                "#,
+            )
+            .bind(&guild_id)
+            .bind(BigDecimal::from(*origin_guild_id))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        info!(
+            "inbound policy upserted successfully in postgres. guild_id: {}, mode: {:?}, allowed: {}",
+            policy.guild_id,
+            policy.mode,
+            policy.allowed_guild_ids.len()
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_inbound_policy(
+        &self,
+        guild_id: u64,
+    ) -> Result<Option<UtInboundPolicy>, Self::Error> {
+        let bigdecimal_guild_id = BigDecimal::from(guild_id);
+        let policy: Option<PostgresUtInboundPolicy> = sqlx::query_as(
+            r#"
+            SELECT guild_id, mode, approval_channel_id
+            FROM inboundpolicies
+            WHERE guild_id = $1
+            "#,
+        )
+        .bind(&bigdecimal_guild_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let Some(policy) = policy else {
+            return Ok(None);
+        };
+
+        let allowed: Vec<(BigDecimal,)> = sqlx::query_as(
+            r#"
+            SELECT origin_guild_id
+            FROM inboundallowedguilds
+            WHERE guild_id = $1
+            ORDER BY origin_guild_id
+            "#,
+        )
+        .bind(&bigdecimal_guild_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(Some(UtInboundPolicy {
+            guild_id: policy.guild_id.to_string().parse().unwrap(),
+            mode: mode_from_str(&policy.mode)?,
+            approval_channel_id: policy
+                .approval_channel_id
+                .map(|c| c.to_string().parse().unwrap()),
+            allowed_guild_ids: allowed
+                .into_iter()
+                .map(|(g,)| g.to_string().parse().unwrap())
+                .collect(),
+        }))
+    }
+
+    #[instrument(skip(self))]
+    async fn get_inbound_policies(
+        &self,
+        guild_ids: Vec<u64>,
+    ) -> Result<Vec<UtInboundPolicy>, Self::Error> {
+        let guild_ids = guild_ids
+            .into_iter()
+            .map(BigDecimal::from)
+            .collect::<Vec<_>>();
+        let policies: Vec<PostgresUtInboundPolicy> = sqlx::query_as(
+            r#"
+            SELECT guild_id, mode, approval_channel_id
+            FROM inboundpolicies
+            WHERE guild_id = ANY($1)
+            ORDER BY guild_id
+            "#,
+        )
+        .bind(&guild_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let allowed: Vec<(BigDecimal, BigDecimal)> = sqlx::query_as(
+            r#"
+            SELECT guild_id, origin_guild_id
+            FROM inboundallowedguilds
+            WHERE guild_id = ANY($1)
+            ORDER BY guild_id, origin_guild_id
+            "#,
+        )
+        .bind(&guild_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "inbound policies fetched successfully from postgres. count: {}",
+            policies.len()
+        );
+        policies
+            .into_iter()
+            .map(|policy| {
+                Ok(UtInboundPolicy {
+                    guild_id: policy.guild_id.to_string().parse().unwrap(),
+                    mode: mode_from_str(&policy.mode)?,
+                    approval_channel_id: policy
+                        .approval_channel_id
+                        .map(|c| c.to_string().parse().unwrap()),
+                    allowed_guild_ids: allowed
+                        .iter()
+                        .filter(|(g, _)| *g == policy.guild_id)
+                        .map(|(_, o)| o.to_string().parse().unwrap())
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,129 @@
+use super::*;
+use domain::models::UtGuild;
+use domain::repository::GuildRepository;
+
+use crate::postgres_guild_repository::PostgresGuildRepository;
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer};
+
+async fn setup_guild(pool: &PgPool) -> u64 {
+    let guild_id = generate_random_20_digits();
+    PostgresGuildRepository::new(pool.clone())
+        .upsert_guild(UtGuild::new(guild_id, Some("test_guild".to_string())))
+        .await
+        .unwrap();
+    guild_id
+}
+
+#[tokio::test]
+async fn test_get_inbound_policy_not_set() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresInboundPolicyRepository::new(pool);
+
+    assert_eq!(repository.get_inbound_policy(guild_id).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_upsert_inbound_policy() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresInboundPolicyRepository::new(pool);
+
+    let mut allowed_guild_ids = vec![generate_random_20_digits(), generate_random_20_digits()];
+    allowed_guild_ids.sort();
+    let policy = UtInboundPolicy {
+        guild_id,
+        mode: InboundMode::ApprovalQueue,
+        approval_channel_id: Some(generate_random_20_digits()),
+        allowed_guild_ids,
+    };
+    repository
+        .upsert_inbound_policy(policy.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        repository.get_inbound_policy(guild_id).await.unwrap(),
+        Some(policy)
+    );
+}
+
+#[tokio::test]
+async fn test_upsert_inbound_policy_replaces_allowed_guilds() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresInboundPolicyRepository::new(pool);
+
+    let policy = UtInboundPolicy {
+        guild_id,
+        mode: InboundMode::AllowList,
+        approval_channel_id: None,
+        allowed_guild_ids: vec![generate_random_20_digits()],
+    };
+    repository
+        .upsert_inbound_policy(policy.clone())
+        .await
+        .unwrap();
+
+    let policy = UtInboundPolicy {
+        mode: InboundMode::Open,
+        allowed_guild_ids: vec![],
+        ..policy
+    };
+    repository
+        .upsert_inbound_policy(policy.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        repository.get_inbound_policy(guild_id).await.unwrap(),
+        Some(policy)
+    );
+}
+
+#[tokio::test]
+async fn test_get_inbound_policies() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let open_guild_id = setup_guild(&pool).await;
+    let allow_list_guild_id = setup_guild(&pool).await;
+    let not_set_guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresInboundPolicyRepository::new(pool);
+
+    let open = UtInboundPolicy {
+        guild_id: open_guild_id,
+        mode: InboundMode::Open,
+        approval_channel_id: None,
+        allowed_guild_ids: vec![],
+    };
+    let mut allowed_guild_ids = vec![generate_random_20_digits(), generate_random_20_digits()];
+    allowed_guild_ids.sort();
+    let allow_list = UtInboundPolicy {
+        guild_id: allow_list_guild_id,
+        mode: InboundMode::AllowList,
+        approval_channel_id: None,
+        allowed_guild_ids,
+    };
+    repository
+        .upsert_inbound_policy(open.clone())
+        .await
+        .unwrap();
+    repository
+        .upsert_inbound_policy(allow_list.clone())
+        .await
+        .unwrap();
+
+    let mut policies = repository
+        .get_inbound_policies(vec![open_guild_id, allow_list_guild_id, not_set_guild_id])
+        .await
+        .unwrap();
+    policies.sort_by_key(|p| p.guild_id);
+    let mut expected = vec![open, allow_list];
+    expected.sort_by_key(|p| p.guild_id);
+
+    // 設定していないギルドは含まれず，許可したギルドはそれぞれのギルドに分かれる
+    assert_eq!(policies, expected);
+}
@@ -0,0 +1,122 @@
+use domain::models::{MemberLeaveAction, UtMemberLeavePolicy};
+use domain::repository::MemberLeavePolicyRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresMemberLeavePolicyRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+    #[error("unknown member leave action: {0}")]
+    UnknownAction(String),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtMemberLeavePolicy {
+    guild_id: BigDecimal,
+    action: String,
+}
+
+// MemberLeaveActionをDBに保存する文字列に変換する
+
+fn action_to_str(action: MemberLeaveAction) -> &'static str {
+    match action {
+        MemberLeaveAction::Keep => "keep",
+        MemberLeaveAction::Unregister => "unregister",
+    }
+}
+
+// PostgresUtMemberLeavePolicyをUtMemberLeavePolicyに変換する
+
+impl TryFrom<PostgresUtMemberLeavePolicy> for UtMemberLeavePolicy {
+    type Error = PostgresMemberLeavePolicyRepositoryError;
+
+    fn try_from(p: PostgresUtMemberLeavePolicy) -> Result<Self, Self::Error> {
+        let action = match p.action.as_str() {
+            "keep" => MemberLeaveAction::Keep,
+            "unregister" => MemberLeaveAction::Unregister,
+            _ => {
+                return Err(PostgresMemberLeavePolicyRepositoryError::UnknownAction(
+                    p.action,
+                ))
+            }
+        };
+        Ok(Self {
+            guild_id: p.guild_id.to_string().parse().unwrap(),
+            action,
+        })
+    }
+}
+
+pub struct PostgresMemberLeavePolicyRepository {
+    pool: PgPool,
+}
+
+impl PostgresMemberLeavePolicyRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl MemberLeavePolicyRepository for PostgresMemberLeavePolicyRepository {
+    type Error = PostgresMemberLeavePolicyRepositoryError;
+
+    #[instrument(skip(self))]
+    async fn upsert_member_leave_policy(
+        &self,
+        policy: UtMemberLeavePolicy,
+    ) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO memberleavepolicies (guild_id, action)
+            VALUES ($1, $2)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET action = $2
+            "#,
+        )
+        .bind(BigDecimal::from(policy.guild_id))
+        .bind(action_to_str(policy.action))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "member leave policy upserted successfully in postgres. guild_id: {}",
+            policy.guild_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_member_leave_policy(
+        &self,
+        guild_id: u64,
+    ) -> Result<Option<UtMemberLeavePolicy>, Self::Error> {
+        let policy: Option<PostgresUtMemberLeavePolicy> = sqlx::query_as(
+            r#"
+            SELECT guild_id, action
+            FROM memberleavepolicies
+            WHERE guild_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(guild_id))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        info!(
+            "member leave policy fetched successfully from postgres. guild_id: {}",
+            guild_id
+        );
+        policy.map(|p| p.try_into()).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests;
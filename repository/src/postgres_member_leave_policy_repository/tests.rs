@@ -0,0 +1,48 @@
+use super::*;
+use domain::models::UtGuild;
+use domain::repository::GuildRepository;
+
+use crate::postgres_guild_repository::PostgresGuildRepository;
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer};
+
+async fn setup_guild(pool: &PgPool) -> u64 {
+    let guild_id = generate_random_20_digits();
+    PostgresGuildRepository::new(pool.clone())
+        .upsert_guild(UtGuild::new(guild_id, Some("test_guild".to_string())))
+        .await
+        .unwrap();
+    guild_id
+}
+
+#[tokio::test]
+async fn test_upsert_member_leave_policy() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresMemberLeavePolicyRepository::new(pool);
+
+    assert_eq!(
+        repository.get_member_leave_policy(guild_id).await.unwrap(),
+        None
+    );
+
+    repository
+        .upsert_member_leave_policy(UtMemberLeavePolicy {
+            guild_id,
+            action: MemberLeaveAction::Unregister,
+        })
+        .await
+        .unwrap();
+
+    // 同じギルドは上書きする
+    let policy = UtMemberLeavePolicy {
+        guild_id,
+        action: MemberLeaveAction::Keep,
+    };
+    repository.upsert_member_leave_policy(policy).await.unwrap();
+
+    assert_eq!(
+        repository.get_member_leave_policy(guild_id).await.unwrap(),
+        Some(policy)
+    );
+}
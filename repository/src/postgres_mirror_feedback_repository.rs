@@ -0,0 +1,262 @@
+use domain::models::{UtMirrorReaction, UtMirrorReply};
+use domain::repository::MirrorFeedbackRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresMirrorFeedbackRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtMirrorReaction {
+    mirror_message_id: BigDecimal,
+    emoji: String,
+    count: i32,
+}
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtMirrorReply {
+    reply_message_id: BigDecimal,
+    mirror_message_id: BigDecimal,
+    guild_id: BigDecimal,
+    channel_id: BigDecimal,
+}
+
+// PostgresUtMirrorReactionをUtMirrorReactionに変換する
+
+impl From<PostgresUtMirrorReaction> for UtMirrorReaction {
+    fn from(r: PostgresUtMirrorReaction) -> Self {
+        Self {
+            mirror_message_id: r.mirror_message_id.to_string().parse().unwrap(),
+            emoji: r.emoji,
+            count: r.count.max(0) as u32,
+        }
+    }
+}
+
+// PostgresUtMirrorReplyをUtMirrorReplyに変換する
+
+impl From<PostgresUtMirrorReply> for UtMirrorReply {
+    fn from(r: PostgresUtMirrorReply) -> Self {
+        Self {
+            reply_message_id: r.reply_message_id.to_string().parse().unwrap(),
+            mirror_message_id: r.mirror_message_id.to_string().parse().unwrap(),
+            guild_id: r.guild_id.to_string().parse().unwrap(),
+            channel_id: r.channel_id.to_string().parse().unwrap(),
+        }
+    }
+}
+
+pub struct PostgresMirrorFeedbackRepository {
+    pool: PgPool,
+}
+
+impl PostgresMirrorFeedbackRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl MirrorFeedbackRepository for PostgresMirrorFeedbackRepository {
+    type Error = PostgresMirrorFeedbackRepositoryError;
+
+    #[instrument(skip(self))]
+    async fn add_mirror_reaction(
+        &self,
+        mirror_message_id: u64,
+        emoji: String,
+    ) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO mirrorreactions (mirror_message_id, emoji, count)
+            VALUES ($1, $2, 1)
+            ON CONFLICT (mirror_message_id, emoji) DO UPDATE
+            SET count = mirrorreactions.count + 1
+            "#,
+        )
+        .bind(BigDecimal::from(mirror_message_id))
+        .bind(&emoji)
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "mirror reaction added successfully in postgres. mirror_message_id: {}",
+            mirror_message_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn remove_mirror_reaction(
+        &self,
+        mirror_message_id: u64,
+        emoji: String,
+    ) -> Result<(), Self::Error> {
+        // botが止まっている間に付いたリアクションは数えていないので，0より小さくしない
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            UPDATE mirrorreactions
+            SET count = count - 1
+            WHERE mirror_message_id = $1 AND emoji = $2
+            "#,
+        )
+        .bind(BigDecimal::from(mirror_message_id))
+        .bind(&emoji)
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query(
+            r#"
+            DELETE FROM mirrorreactions
+            WHERE mirror_message_id = $1 AND emoji = $2 AND count <= 0
+            "#,
+        )
+        .bind(BigDecimal::from(mirror_message_id))
+        .bind(&emoji)
+        .execute(&mut *transaction)
+        .await?;
+        transaction.commit().await?;
+
+        info!(
+            "mirror reaction removed successfully in postgres. mirror_message_id: {}",
+            mirror_message_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn clear_mirror_reactions(
+        &self,
+        mirror_message_id: u64,
+        emoji: Option<String>,
+    ) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            DELETE FROM mirrorreactions
+            WHERE mirror_message_id = $1 AND ($2::VARCHAR IS NULL OR emoji = $2)
+            "#,
+        )
+        .bind(BigDecimal::from(mirror_message_id))
+        .bind(&emoji)
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "mirror reactions cleared successfully from postgres. mirror_message_id: {}",
+            mirror_message_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self, mirror_message_ids))]
+    async fn get_mirror_reactions(
+        &self,
+        mirror_message_ids: Vec<u64>,
+    ) -> Result<Vec<UtMirrorReaction>, Self::Error> {
+        let bigdecimal_mirror_message_ids = mirror_message_ids
+            .into_iter()
+            .map(BigDecimal::from)
+            .collect::<Vec<_>>();
+        let reactions: Vec<PostgresUtMirrorReaction> = sqlx::query_as(
+            r#"
+            SELECT mirror_message_id, emoji, count
+            FROM mirrorreactions
+            WHERE mirror_message_id = ANY($1)
+            ORDER BY mirror_message_id, count DESC, emoji
+            "#,
+        )
+        .bind(bigdecimal_mirror_message_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "mirror reactions fetched successfully from postgres. count: {}",
+            reactions.len()
+        );
+        Ok(reactions.into_iter().map(|r| r.into()).collect())
+    }
+
+    #[instrument(skip(self))]
+    async fn insert_mirror_reply(&self, reply: UtMirrorReply) -> Result<(), Self::Error> {
+        // 編集でもう一度届いても，記録は1つにする
+        sqlx::query(
+            r#"
+            INSERT INTO mirrorreplies (reply_message_id, mirror_message_id, guild_id, channel_id)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (reply_message_id) DO NOTHING
+            "#,
+        )
+        .bind(BigDecimal::from(reply.reply_message_id))
+        .bind(BigDecimal::from(reply.mirror_message_id))
+        .bind(BigDecimal::from(reply.guild_id))
+        .bind(BigDecimal::from(reply.channel_id))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "mirror reply inserted successfully in postgres. reply_message_id: {}",
+            reply.reply_message_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_mirror_reply(&self, reply_message_id: u64) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            DELETE FROM mirrorreplies
+            WHERE reply_message_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(reply_message_id))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "mirror reply deleted successfully from postgres. reply_message_id: {}",
+            reply_message_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self, mirror_message_ids))]
+    async fn get_mirror_replies(
+        &self,
+        mirror_message_ids: Vec<u64>,
+    ) -> Result<Vec<UtMirrorReply>, Self::Error> {
+        let bigdecimal_mirror_message_ids = mirror_message_ids
+            .into_iter()
+            .map(BigDecimal::from)
+            .collect::<Vec<_>>();
+        let replies: Vec<PostgresUtMirrorReply> = sqlx::query_as(
+            r#"
+            SELECT reply_message_id, mirror_message_id, guild_id, channel_id
+            FROM mirrorreplies
+            WHERE mirror_message_id = ANY($1)
+            ORDER BY reply_message_id
+            "#,
+        )
+        .bind(bigdecimal_mirror_message_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "mirror replies fetched successfully from postgres. count: {}",
+            replies.len()
+        );
+        Ok(replies.into_iter().map(|r| r.into()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,181 @@
+use super::*;
+use domain::models::UtDelivery;
+use domain::repository::DeliveryRepository;
+
+use crate::postgres_delivery_repository::PostgresDeliveryRepository;
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer};
+
+/// 配信ログにある拡散先のメッセージしか記録できないので，先に記録しておく
+async fn setup_delivery(pool: &PgPool) -> UtDelivery {
+    let delivery = UtDelivery {
+        source_message_id: generate_random_20_digits(),
+        source_guild_id: 1,
+        source_channel_id: 2,
+        user_id: generate_random_20_digits(),
+        target_guild_id: generate_random_20_digits(),
+        target_channel_id: generate_random_20_digits(),
+        mirror_message_id: generate_random_20_digits(),
+        part: 0,
+    };
+    PostgresDeliveryRepository::new(pool.clone())
+        .insert_deliveries(vec![delivery.clone()])
+        .await
+        .unwrap();
+    delivery
+}
+
+fn reaction(mirror_message_id: u64, emoji: &str, count: u32) -> UtMirrorReaction {
+    UtMirrorReaction {
+        mirror_message_id,
+        emoji: emoji.to_string(),
+        count,
+    }
+}
+
+#[tokio::test]
+async fn test_add_and_remove_mirror_reaction() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let mirror_message_id = setup_delivery(&pool).await.mirror_message_id;
+
+    let repository = PostgresMirrorFeedbackRepository::new(pool);
+
+    for emoji in ["👍", "👍", "🎉"] {
+        repository
+            .add_mirror_reaction(mirror_message_id, emoji.to_string())
+            .await
+            .unwrap();
+    }
+    assert_eq!(
+        repository
+            .get_mirror_reactions(vec![mirror_message_id])
+            .await
+            .unwrap(),
+        vec![
+            reaction(mirror_message_id, "👍", 2),
+            reaction(mirror_message_id, "🎉", 1)
+        ]
+    );
+
+    // 0になった絵文字は消える．数えていない絵文字を減らしても何も起きない
+    for emoji in ["🎉", "🎉", "👀"] {
+        repository
+            .remove_mirror_reaction(mirror_message_id, emoji.to_string())
+            .await
+            .unwrap();
+    }
+    assert_eq!(
+        repository
+            .get_mirror_reactions(vec![mirror_message_id])
+            .await
+            .unwrap(),
+        vec![reaction(mirror_message_id, "👍", 2)]
+    );
+}
+
+#[tokio::test]
+async fn test_clear_mirror_reactions() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let mirror_message_id = setup_delivery(&pool).await.mirror_message_id;
+
+    let repository = PostgresMirrorFeedbackRepository::new(pool);
+    for emoji in ["👍", "🎉", "👀"] {
+        repository
+            .add_mirror_reaction(mirror_message_id, emoji.to_string())
+            .await
+            .unwrap();
+    }
+
+    repository
+        .clear_mirror_reactions(mirror_message_id, Some("🎉".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(
+        repository
+            .get_mirror_reactions(vec![mirror_message_id])
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+
+    repository
+        .clear_mirror_reactions(mirror_message_id, None)
+        .await
+        .unwrap();
+    assert!(repository
+        .get_mirror_reactions(vec![mirror_message_id])
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_mirror_replies() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let delivery = setup_delivery(&pool).await;
+
+    let repository = PostgresMirrorFeedbackRepository::new(pool.clone());
+    let reply = UtMirrorReply {
+        reply_message_id: generate_random_20_digits(),
+        mirror_message_id: delivery.mirror_message_id,
+        guild_id: delivery.target_guild_id,
+        channel_id: delivery.target_channel_id,
+    };
+    // 同じ返信は1つだけ記録する
+    repository.insert_mirror_reply(reply.clone()).await.unwrap();
+    repository.insert_mirror_reply(reply.clone()).await.unwrap();
+    assert_eq!(
+        repository
+            .get_mirror_replies(vec![delivery.mirror_message_id])
+            .await
+            .unwrap(),
+        vec![reply.clone()]
+    );
+
+    repository
+        .delete_mirror_reply(reply.reply_message_id)
+        .await
+        .unwrap();
+    assert!(repository
+        .get_mirror_replies(vec![delivery.mirror_message_id])
+        .await
+        .unwrap()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_feedback_is_deleted_with_delivery() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let delivery = setup_delivery(&pool).await;
+
+    let repository = PostgresMirrorFeedbackRepository::new(pool.clone());
+    repository
+        .add_mirror_reaction(delivery.mirror_message_id, "👍".to_string())
+        .await
+        .unwrap();
+    repository
+        .insert_mirror_reply(UtMirrorReply {
+            reply_message_id: generate_random_20_digits(),
+            mirror_message_id: delivery.mirror_message_id,
+            guild_id: delivery.target_guild_id,
+            channel_id: delivery.target_channel_id,
+        })
+        .await
+        .unwrap();
+
+    PostgresDeliveryRepository::new(pool)
+        .delete_deliveries(vec![delivery.mirror_message_id])
+        .await
+        .unwrap();
+
+    assert!(repository
+        .get_mirror_reactions(vec![delivery.mirror_message_id])
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(repository
+        .get_mirror_replies(vec![delivery.mirror_message_id])
+        .await
+        .unwrap()
+        .is_empty());
+}
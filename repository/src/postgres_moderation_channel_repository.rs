@@ -0,0 +1,119 @@
+use domain::models::UtModerationChannel;
+use domain::repository::ModerationChannelRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresModerationChannelRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtModerationChannel {
+    guild_id: BigDecimal,
+    channel_id: BigDecimal,
+}
+
+// PostgresUtModerationChannelをUtModerationChannelに変換する
+
+impl From<PostgresUtModerationChannel> for UtModerationChannel {
+    fn from(c: PostgresUtModerationChannel) -> Self {
+        Self {
+            guild_id: c.guild_id.to_string().parse().unwrap(),
+            channel_id: c.channel_id.to_string().parse().unwrap(),
+        }
+    }
+}
+
+pub struct PostgresModerationChannelRepository {
+    pool: PgPool,
+}
+
+impl PostgresModerationChannelRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl ModerationChannelRepository for PostgresModerationChannelRepository {
+    type Error = PostgresModerationChannelRepositoryError;
+
+    #[instrument(skip(self))]
+    async fn upsert_moderation_channel(
+        &self,
+        moderation_channel: UtModerationChannel,
+    ) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO moderationchannels (guild_id, channel_id)
+            VALUES ($1, $2)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET channel_id = $2
+            "#,
+        )
+        .bind(BigDecimal::from(moderation_channel.guild_id))
+        .bind(BigDecimal::from(moderation_channel.channel_id))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "moderation channel upserted successfully in postgres. guild_id: {}",
+            moderation_channel.guild_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_moderation_channel(&self, guild_id: u64) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            DELETE FROM moderationchannels
+            WHERE guild_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(guild_id))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "moderation channel deleted successfully from postgres. guild_id: {}",
+            guild_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_moderation_channel(
+        &self,
+        guild_id: u64,
+    ) -> Result<Option<UtModerationChannel>, Self::Error> {
+        let moderation_channel: Option<PostgresUtModerationChannel> = sqlx::query_as(
+            r#"
+            SELECT guild_id, channel_id
+            FROM moderationchannels
+            WHERE guild_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(guild_id))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        info!(
+            "moderation channel fetched successfully from postgres. guild_id: {}",
+            guild_id
+        );
+        Ok(moderation_channel.map(|c| c.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests;
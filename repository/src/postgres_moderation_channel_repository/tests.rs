@@ -0,0 +1,77 @@
+use super::*;
+use domain::models::UtGuild;
+use domain::repository::GuildRepository;
+
+use crate::postgres_guild_repository::PostgresGuildRepository;
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer};
+
+async fn setup_guild(pool: &PgPool) -> u64 {
+    let guild_id = generate_random_20_digits();
+    PostgresGuildRepository::new(pool.clone())
+        .upsert_guild(UtGuild::new(guild_id, Some("test_guild".to_string())))
+        .await
+        .unwrap();
+    guild_id
+}
+
+#[tokio::test]
+async fn test_upsert_moderation_channel() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresModerationChannelRepository::new(pool);
+
+    assert_eq!(
+        repository.get_moderation_channel(guild_id).await.unwrap(),
+        None
+    );
+
+    let moderation_channel = UtModerationChannel {
+        guild_id,
+        channel_id: generate_random_20_digits(),
+    };
+    repository
+        .upsert_moderation_channel(moderation_channel)
+        .await
+        .unwrap();
+
+    // 同じギルドは上書きする
+    let moderation_channel = UtModerationChannel {
+        channel_id: generate_random_20_digits(),
+        ..moderation_channel
+    };
+    repository
+        .upsert_moderation_channel(moderation_channel)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        repository.get_moderation_channel(guild_id).await.unwrap(),
+        Some(moderation_channel)
+    );
+}
+
+#[tokio::test]
+async fn test_delete_moderation_channel() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresModerationChannelRepository::new(pool);
+
+    repository
+        .upsert_moderation_channel(UtModerationChannel {
+            guild_id,
+            channel_id: generate_random_20_digits(),
+        })
+        .await
+        .unwrap();
+    repository
+        .delete_moderation_channel(guild_id)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        repository.get_moderation_channel(guild_id).await.unwrap(),
+        None
+    );
+}
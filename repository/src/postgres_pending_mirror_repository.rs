@@ -0,0 +1,214 @@
+use domain::models::UtPendingMirror;
+use domain::repository::PendingMirrorRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresPendingMirrorRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtPendingMirror {
+    review_message_id: BigDecimal,
+    review_channel_id: BigDecimal,
+    source_message_id: BigDecimal,
+    source_guild_id: BigDecimal,
+    source_channel_id: BigDecimal,
+    user_id: BigDecimal,
+    target_guild_id: BigDecimal,
+    content: String,
+}
+
+// UtPendingMirrorをPostgresUtPendingMirrorに変換する
+
+impl From<UtPendingMirror> for PostgresUtPendingMirror {
+    fn from(p: UtPendingMirror) -> Self {
+        Self {
+            review_message_id: BigDecimal::from(p.review_message_id),
+            review_channel_id: BigDecimal::from(p.review_channel_id),
+            source_message_id: BigDecimal::from(p.source_message_id),
+            source_guild_id: BigDecimal::from(p.source_guild_id),
+            source_channel_id: BigDecimal::from(p.source_channel_id),
+            user_id: BigDecimal::from(p.user_id),
+            target_guild_id: BigDecimal::from(p.target_guild_id),
+            content: p.content,
+        }
+    }
+}
+
+// PostgresUtPendingMirrorをUtPendingMirrorに変換する
+
+impl From<PostgresUtPendingMirror> for UtPendingMirror {
+    fn from(p: PostgresUtPendingMirror) -> Self {
+        Self {
+            review_message_id: p.review_message_id.to_string().parse().unwrap(),
+            review_channel_id: p.review_channel_id.to_string().parse().unwrap(),
+            source_message_id: p.source_message_id.to_string().parse().unwrap(),
+            source_guild_id: p.source_guild_id.to_string().parse().unwrap(),
+            source_channel_id: p.source_channel_id.to_string().parse().unwrap(),
+            user_id: p.user_id.to_string().parse().unwrap(),
+            target_guild_id: p.target_guild_id.to_string().parse().unwrap(),
+            content: p.content,
+        }
+    }
+}
+
+pub struct PostgresPendingMirrorRepository {
+    pool: PgPool,
+}
+
+impl PostgresPendingMirrorRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl PendingMirrorRepository for PostgresPendingMirrorRepository {
+    type Error = PostgresPendingMirrorRepositoryError;
+
+    #[instrument(skip(self, pending))]
+    async fn insert_pending_mirror(&self, pending: UtPendingMirror) -> Result<(), Self::Error> {
+        let p = PostgresUtPendingMirror::from(pending);
+
+        sqlx::query(
+            r#"
+            INSERT INTO pendingmirrors (
+                review_message_id, review_channel_id, source_message_id, source_guild_id,
+                source_channel_id, user_id, target_guild_id, content
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(&p.review_message_id)
+        .bind(&p.review_channel_id)
+        .bind(&p.source_message_id)
+        .bind(&p.source_guild_id)
+        .bind(&p.source_channel_id)
+        .bind(&p.user_id)
+        .bind(&p.target_guild_id)
+        .bind(&p.content)
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "pending mirror inserted successfully in postgres. review_message_id: {}, target_guild_id: {}",
+            p.review_message_id, p.target_guild_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn take_pending_mirror(
+        &self,
+        review_message_id: u64,
+    ) -> Result<Option<UtPendingMirror>, Self::Error> {
+        // 削除した行を返すので，同時に取り出そうとしても片方にしか返らない
+        let pending: Option<PostgresUtPendingMirror> = sqlx::query_as(
+            r#"
+            DELETE FROM pendingmirrors
+            WHERE review_message_id = $1
+            RETURNING review_message_id, review_channel_id, source_message_id, source_guild_id,
+                source_channel_id, user_id, target_guild_id, content
+            "#,
+        )
+        .bind(BigDecimal::from(review_message_id))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        info!(
+            "pending mirror taken from postgres. review_message_id: {}, found: {}",
+            review_message_id,
+            pending.is_some()
+        );
+        Ok(pending.map(|p| p.into()))
+    }
+
+    #[instrument(skip(self))]
+    async fn get_pending_mirror(
+        &self,
+        review_message_id: u64,
+    ) -> Result<Option<UtPendingMirror>, Self::Error> {
+        let pending: Option<PostgresUtPendingMirror> = sqlx::query_as(
+            r#"
+            SELECT review_message_id, review_channel_id, source_message_id, source_guild_id,
+                source_channel_id, user_id, target_guild_id, content
+            FROM pendingmirrors
+            WHERE review_message_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(review_message_id))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        info!(
+            "pending mirror fetched from postgres. review_message_id: {}, found: {}",
+            review_message_id,
+            pending.is_some()
+        );
+        Ok(pending.map(|p| p.into()))
+    }
+
+    #[instrument(skip(self))]
+    async fn get_pending_mirrors_by_user(
+        &self,
+        user_id: u64,
+    ) -> Result<Vec<UtPendingMirror>, Self::Error> {
+        let pendings: Vec<PostgresUtPendingMirror> = sqlx::query_as(
+            r#"
+            SELECT review_message_id, review_channel_id, source_message_id, source_guild_id,
+                source_channel_id, user_id, target_guild_id, content
+            FROM pendingmirrors
+            WHERE user_id = $1
+            ORDER BY review_message_id
+            "#,
+        )
+        .bind(BigDecimal::from(user_id))
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "pending mirrors fetched successfully from postgres. user_id: {}, count: {}",
+            user_id,
+            pendings.len()
+        );
+        Ok(pendings.into_iter().map(|p| p.into()).collect())
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_pending_mirrors_by_user(
+        &self,
+        user_id: u64,
+    ) -> Result<Vec<UtPendingMirror>, Self::Error> {
+        let pendings: Vec<PostgresUtPendingMirror> = sqlx::query_as(
+            r#"
+            DELETE FROM pendingmirrors
+            WHERE user_id = $1
+            RETURNING review_message_id, review_channel_id, source_message_id, source_guild_id,
+                source_channel_id, user_id, target_guild_id, content
+            "#,
+        )
+        .bind(BigDecimal::from(user_id))
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "pending mirrors deleted successfully from postgres. user_id: {}, count: {}",
+            user_id,
+            pendings.len()
+        );
+        Ok(pendings.into_iter().map(|p| p.into()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests;
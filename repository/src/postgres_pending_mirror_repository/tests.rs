@@ -0,0 +1,130 @@
+use super::*;
+
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer};
+
+fn pending_mirror() -> UtPendingMirror {
+    UtPendingMirror {
+        review_message_id: generate_random_20_digits(),
+        review_channel_id: generate_random_20_digits(),
+        source_message_id: generate_random_20_digits(),
+        source_guild_id: generate_random_20_digits(),
+        source_channel_id: generate_random_20_digits(),
+        user_id: generate_random_20_digits(),
+        target_guild_id: generate_random_20_digits(),
+        content: "content".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_take_pending_mirror() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let repository = PostgresPendingMirrorRepository::new(pool);
+
+    let pending = pending_mirror();
+    repository
+        .insert_pending_mirror(pending.clone())
+        .await
+        .unwrap();
+
+    let taken = repository
+        .take_pending_mirror(pending.review_message_id)
+        .await
+        .unwrap();
+    assert_eq!(taken, Some(pending.clone()));
+
+    // 一度取り出したら，もう取り出せない
+    let taken = repository
+        .take_pending_mirror(pending.review_message_id)
+        .await
+        .unwrap();
+    assert_eq!(taken, None);
+}
+
+#[tokio::test]
+async fn test_take_pending_mirror_not_found() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let repository = PostgresPendingMirrorRepository::new(pool);
+
+    let taken = repository
+        .take_pending_mirror(generate_random_20_digits())
+        .await
+        .unwrap();
+    assert_eq!(taken, None);
+}
+
+#[tokio::test]
+async fn test_get_pending_mirror_keeps_it() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let repository = PostgresPendingMirrorRepository::new(pool);
+
+    let pending = pending_mirror();
+    repository
+        .insert_pending_mirror(pending.clone())
+        .await
+        .unwrap();
+
+    let fetched = repository
+        .get_pending_mirror(pending.review_message_id)
+        .await
+        .unwrap();
+    assert_eq!(fetched, Some(pending.clone()));
+
+    // 取得しただけなので，まだ取り出せる
+    let taken = repository
+        .take_pending_mirror(pending.review_message_id)
+        .await
+        .unwrap();
+    assert_eq!(taken, Some(pending));
+}
+
+#[tokio::test]
+async fn test_pending_mirrors_by_user() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let repository = PostgresPendingMirrorRepository::new(pool);
+
+    let pending = pending_mirror();
+    let same_user = UtPendingMirror {
+        review_message_id: generate_random_20_digits(),
+        ..pending.clone()
+    };
+    let other_user = pending_mirror();
+    for p in [&pending, &same_user, &other_user] {
+        repository.insert_pending_mirror(p.clone()).await.unwrap();
+    }
+
+    let mut expected = vec![pending.clone(), same_user.clone()];
+    expected.sort_by_key(|p| p.review_message_id);
+    assert_eq!(
+        repository
+            .get_pending_mirrors_by_user(pending.user_id)
+            .await
+            .unwrap(),
+        expected
+    );
+
+    let mut deleted = repository
+        .delete_pending_mirrors_by_user(pending.user_id)
+        .await
+        .unwrap();
+    deleted.sort_by_key(|p| p.review_message_id);
+    assert_eq!(deleted, expected);
+
+    assert_eq!(
+        repository
+            .get_pending_mirror(pending.review_message_id)
+            .await
+            .unwrap(),
+        None
+    );
+    assert_eq!(
+        repository
+            .get_pending_mirror(other_user.review_message_id)
+            .await
+            .unwrap(),
+        Some(other_user)
+    );
+}
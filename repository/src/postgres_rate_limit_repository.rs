@@ -0,0 +1,138 @@
+use domain::models::{RateLimit, UtRateLimitSettings};
+use domain::repository::RateLimitRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresRateLimitRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+// 回数と秒数はINTEGERに収まる範囲で使う
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtRateLimitSettings {
+    guild_id: BigDecimal,
+    user_capacity: i32,
+    user_period_secs: i32,
+    guild_capacity: i32,
+    guild_period_secs: i32,
+}
+
+// UtRateLimitSettingsをPostgresUtRateLimitSettingsに変換する
+
+impl From<UtRateLimitSettings> for PostgresUtRateLimitSettings {
+    fn from(s: UtRateLimitSettings) -> Self {
+        Self {
+            guild_id: BigDecimal::from(s.guild_id),
+            user_capacity: to_i32(s.user_limit.capacity),
+            user_period_secs: to_i32(s.user_limit.period_secs),
+            guild_capacity: to_i32(s.guild_limit.capacity),
+            guild_period_secs: to_i32(s.guild_limit.period_secs),
+        }
+    }
+}
+
+fn to_i32(value: u32) -> i32 {
+    i32::try_from(value).unwrap_or(i32::MAX)
+}
+
+// PostgresUtRateLimitSettingsをUtRateLimitSettingsに変換する
+
+impl From<PostgresUtRateLimitSettings> for UtRateLimitSettings {
+    fn from(s: PostgresUtRateLimitSettings) -> Self {
+        Self {
+            guild_id: s.guild_id.to_string().parse().unwrap(),
+            user_limit: RateLimit {
+                capacity: s.user_capacity.max(0) as u32,
+                period_secs: s.user_period_secs.max(0) as u32,
+            },
+            guild_limit: RateLimit {
+                capacity: s.guild_capacity.max(0) as u32,
+                period_secs: s.guild_period_secs.max(0) as u32,
+            },
+        }
+    }
+}
+
+pub struct PostgresRateLimitRepository {
+    pool: PgPool,
+}
+
+impl PostgresRateLimitRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl RateLimitRepository for PostgresRateLimitRepository {
+    type Error = PostgresRateLimitRepositoryError;
+
+    #[instrument(skip(self))]
+    async fn upsert_rate_limit_settings(
+        &self,
+        settings: UtRateLimitSettings,
+    ) -> Result<(), Self::Error> {
+        let postgres_settings = PostgresUtRateLimitSettings::from(settings);
+        sqlx::query(
+            r#"
+            INSERT INTO releaseratelimits (guild_id, user_capacity, user_period_secs, guild_capacity, guild_period_secs)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET user_capacity = $2, user_period_secs = $3, guild_capacity = $4, guild_period_secs = $5
+            "#,
+        )
+        .bind(&postgres_settings.guild_id)
+        .bind(postgres_settings.user_capacity)
+        .bind(postgres_settings.user_period_secs)
+        .bind(postgres_settings.guild_capacity)
+        .bind(postgres_settings.guild_period_secs)
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "rate limit settings upserted successfully in postgres. guild_id: {}",
+            settings.guild_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_rate_limit_settings(
+        &self,
+        guild_ids: Vec<u64>,
+    ) -> Result<Vec<UtRateLimitSettings>, Self::Error> {
+        let guild_ids = guild_ids
+            .into_iter()
+            .map(BigDecimal::from)
+            .collect::<Vec<_>>();
+        let settings: Vec<PostgresUtRateLimitSettings> = sqlx::query_as(
+            r#"
+            SELECT guild_id, user_capacity, user_period_secs, guild_capacity, guild_period_secs
+            FROM releaseratelimits
+            WHERE guild_id = ANY($1)
+            ORDER BY guild_id
+            "#,
+        )
+        .bind(guild_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "rate limit settings fetched successfully from postgres. count: {}",
+            settings.len()
+        );
+        Ok(settings.into_iter().map(|s| s.into()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests;
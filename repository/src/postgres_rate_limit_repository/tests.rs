@@ -0,0 +1,73 @@
+use super::*;
+use domain::models::UtGuild;
+use domain::repository::GuildRepository;
+
+use crate::postgres_guild_repository::PostgresGuildRepository;
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer};
+
+async fn setup_guild(pool: &PgPool) -> u64 {
+    let guild_id = generate_random_20_digits();
+    PostgresGuildRepository::new(pool.clone())
+        .upsert_guild(UtGuild::new(guild_id, Some("test_guild".to_string())))
+        .await
+        .unwrap();
+    guild_id
+}
+
+#[tokio::test]
+async fn test_upsert_rate_limit_settings() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresRateLimitRepository::new(pool);
+
+    let settings = UtRateLimitSettings::default_for(guild_id);
+    repository
+        .upsert_rate_limit_settings(settings)
+        .await
+        .unwrap();
+
+    // 同じギルドは上書きする
+    let settings = UtRateLimitSettings {
+        user_limit: RateLimit {
+            capacity: 2,
+            period_secs: 30,
+        },
+        guild_limit: RateLimit {
+            capacity: 100,
+            period_secs: 3600,
+        },
+        ..settings
+    };
+    repository
+        .upsert_rate_limit_settings(settings)
+        .await
+        .unwrap();
+
+    let fetched = repository
+        .get_rate_limit_settings(vec![guild_id])
+        .await
+        .unwrap();
+    assert_eq!(fetched, vec![settings]);
+}
+
+#[tokio::test]
+async fn test_get_rate_limit_settings_only_for_configured_guilds() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let configured = setup_guild(&pool).await;
+    let unconfigured = setup_guild(&pool).await;
+
+    let repository = PostgresRateLimitRepository::new(pool);
+
+    let settings = UtRateLimitSettings::default_for(configured);
+    repository
+        .upsert_rate_limit_settings(settings)
+        .await
+        .unwrap();
+
+    let fetched = repository
+        .get_rate_limit_settings(vec![configured, unconfigured])
+        .await
+        .unwrap();
+    assert_eq!(fetched, vec![settings]);
+}
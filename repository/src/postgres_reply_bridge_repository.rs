@@ -0,0 +1,253 @@
+use domain::models::{UtRelayedReply, UtReplyBridgeSettings};
+use domain::repository::ReplyBridgeRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresReplyBridgeRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtReplyBridgeSettings {
+    guild_id: BigDecimal,
+    send_replies: bool,
+    receive_replies: bool,
+}
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtRelayedReply {
+    reply_message_id: BigDecimal,
+    author_id: BigDecimal,
+    user_id: BigDecimal,
+    guild_id: BigDecimal,
+    channel_id: BigDecimal,
+    thread_id: Option<BigDecimal>,
+    relayed_message_id: BigDecimal,
+}
+
+// PostgresUtReplyBridgeSettingsをUtReplyBridgeSettingsに変換する
+
+impl From<PostgresUtReplyBridgeSettings> for UtReplyBridgeSettings {
+    fn from(s: PostgresUtReplyBridgeSettings) -> Self {
+        Self {
+            guild_id: s.guild_id.to_string().parse().unwrap(),
+            send_replies: s.send_replies,
+            receive_replies: s.receive_replies,
+        }
+    }
+}
+
+// PostgresUtRelayedReplyをUtRelayedReplyに変換する
+
+impl From<PostgresUtRelayedReply> for UtRelayedReply {
+    fn from(r: PostgresUtRelayedReply) -> Self {
+        Self {
+            reply_message_id: r.reply_message_id.to_string().parse().unwrap(),
+            author_id: r.author_id.to_string().parse().unwrap(),
+            user_id: r.user_id.to_string().parse().unwrap(),
+            guild_id: r.guild_id.to_string().parse().unwrap(),
+            channel_id: r.channel_id.to_string().parse().unwrap(),
+            thread_id: r.thread_id.map(|t| t.to_string().parse().unwrap()),
+            relayed_message_id: r.relayed_message_id.to_string().parse().unwrap(),
+        }
+    }
+}
+
+pub struct PostgresReplyBridgeRepository {
+    pool: PgPool,
+}
+
+impl PostgresReplyBridgeRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl ReplyBridgeRepository for PostgresReplyBridgeRepository {
+    type Error = PostgresReplyBridgeRepositoryError;
+
+    #[instrument(skip(self))]
+    async fn upsert_reply_bridge_settings(
+        &self,
+        settings: UtReplyBridgeSettings,
+    ) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO replybridgesettings (guild_id, send_replies, receive_replies)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET send_replies = $2, receive_replies = $3
+            "#,
+        )
+        .bind(BigDecimal::from(settings.guild_id))
+        .bind(settings.send_replies)
+        .bind(settings.receive_replies)
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "reply bridge settings upserted successfully in postgres. guild_id: {}",
+            settings.guild_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_reply_bridge_settings(
+        &self,
+        guild_id: u64,
+    ) -> Result<Option<UtReplyBridgeSettings>, Self::Error> {
+        let settings: Option<PostgresUtReplyBridgeSettings> = sqlx::query_as(
+            r#"
+            SELECT guild_id, send_replies, receive_replies
+            FROM replybridgesettings
+            WHERE guild_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(guild_id))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        info!(
+            "reply bridge settings fetched successfully from postgres. guild_id: {}",
+            guild_id
+        );
+        Ok(settings.map(|s| s.into()))
+    }
+
+    #[instrument(skip(self))]
+    async fn insert_relayed_reply(&self, relayed: UtRelayedReply) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO relayedreplies (
+                reply_message_id, author_id, user_id, guild_id, channel_id, thread_id,
+                relayed_message_id
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (reply_message_id) DO UPDATE
+            SET author_id = $2, user_id = $3, guild_id = $4, channel_id = $5, thread_id = $6,
+                relayed_message_id = $7
+            "#,
+        )
+        .bind(BigDecimal::from(relayed.reply_message_id))
+        .bind(BigDecimal::from(relayed.author_id))
+        .bind(BigDecimal::from(relayed.user_id))
+        .bind(BigDecimal::from(relayed.guild_id))
+        .bind(BigDecimal::from(relayed.channel_id))
+        .bind(relayed.thread_id.map(BigDecimal::from))
+        .bind(BigDecimal::from(relayed.relayed_message_id))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "relayed reply inserted successfully in postgres. reply_message_id: {}",
+            relayed.reply_message_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_relayed_reply(
+        &self,
+        reply_message_id: u64,
+    ) -> Result<Option<UtRelayedReply>, Self::Error> {
+        let relayed: Option<PostgresUtRelayedReply> = sqlx::query_as(
+            r#"
+            SELECT reply_message_id, author_id, user_id, guild_id, channel_id, thread_id,
+                relayed_message_id
+            FROM relayedreplies
+            WHERE reply_message_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(reply_message_id))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        info!(
+            "relayed reply fetched successfully from postgres. reply_message_id: {}",
+            reply_message_id
+        );
+        Ok(relayed.map(|r| r.into()))
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_relayed_reply(&self, reply_message_id: u64) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            DELETE FROM relayedreplies
+            WHERE reply_message_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(reply_message_id))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "relayed reply deleted successfully from postgres. reply_message_id: {}",
+            reply_message_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_relayed_replies_by_user(
+        &self,
+        user_id: u64,
+    ) -> Result<Vec<UtRelayedReply>, Self::Error> {
+        let relayed: Vec<PostgresUtRelayedReply> = sqlx::query_as(
+            r#"
+            SELECT reply_message_id, author_id, user_id, guild_id, channel_id, thread_id,
+                relayed_message_id
+            FROM relayedreplies
+            WHERE author_id = $1 OR user_id = $1
+            ORDER BY reply_message_id
+            "#,
+        )
+        .bind(BigDecimal::from(user_id))
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "relayed replies fetched successfully from postgres. user_id: {}, count: {}",
+            user_id,
+            relayed.len()
+        );
+        Ok(relayed.into_iter().map(|r| r.into()).collect())
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_relayed_replies(&self, reply_message_ids: Vec<u64>) -> Result<(), Self::Error> {
+        let reply_message_ids = reply_message_ids
+            .into_iter()
+            .map(BigDecimal::from)
+            .collect::<Vec<_>>();
+        sqlx::query(
+            r#"
+            DELETE FROM relayedreplies
+            WHERE reply_message_id = ANY($1)
+            "#,
+        )
+        .bind(&reply_message_ids)
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "relayed replies deleted successfully from postgres. count: {}",
+            reply_message_ids.len()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
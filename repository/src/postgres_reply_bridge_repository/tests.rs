@@ -0,0 +1,178 @@
+use super::*;
+use domain::models::UtGuild;
+use domain::repository::GuildRepository;
+
+use crate::postgres_guild_repository::PostgresGuildRepository;
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer};
+
+async fn setup_guild(pool: &PgPool) -> u64 {
+    let guild_id = generate_random_20_digits();
+    PostgresGuildRepository::new(pool.clone())
+        .upsert_guild(UtGuild::new(guild_id, Some("test_guild".to_string())))
+        .await
+        .unwrap();
+    guild_id
+}
+
+#[tokio::test]
+async fn test_upsert_reply_bridge_settings() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresReplyBridgeRepository::new(pool);
+
+    assert_eq!(
+        repository
+            .get_reply_bridge_settings(guild_id)
+            .await
+            .unwrap(),
+        None
+    );
+
+    let settings = UtReplyBridgeSettings {
+        guild_id,
+        send_replies: true,
+        receive_replies: false,
+    };
+    repository
+        .upsert_reply_bridge_settings(settings)
+        .await
+        .unwrap();
+    assert_eq!(
+        repository
+            .get_reply_bridge_settings(guild_id)
+            .await
+            .unwrap(),
+        Some(settings)
+    );
+
+    let updated = UtReplyBridgeSettings {
+        send_replies: false,
+        receive_replies: true,
+        ..settings
+    };
+    repository
+        .upsert_reply_bridge_settings(updated)
+        .await
+        .unwrap();
+    assert_eq!(
+        repository
+            .get_reply_bridge_settings(guild_id)
+            .await
+            .unwrap(),
+        Some(updated)
+    );
+}
+
+#[tokio::test]
+async fn test_relayed_reply() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let repository = PostgresReplyBridgeRepository::new(pool);
+
+    let in_thread = UtRelayedReply {
+        reply_message_id: generate_random_20_digits(),
+        author_id: generate_random_20_digits(),
+        user_id: generate_random_20_digits(),
+        guild_id: generate_random_20_digits(),
+        channel_id: generate_random_20_digits(),
+        thread_id: Some(generate_random_20_digits()),
+        relayed_message_id: generate_random_20_digits(),
+    };
+    let in_channel = UtRelayedReply {
+        reply_message_id: generate_random_20_digits(),
+        thread_id: None,
+        ..in_thread.clone()
+    };
+    for relayed in [&in_thread, &in_channel] {
+        repository
+            .insert_relayed_reply(relayed.clone())
+            .await
+            .unwrap();
+    }
+
+    assert_eq!(
+        repository
+            .get_relayed_reply(in_thread.reply_message_id)
+            .await
+            .unwrap(),
+        Some(in_thread.clone())
+    );
+    assert_eq!(
+        repository
+            .get_relayed_reply(in_channel.reply_message_id)
+            .await
+            .unwrap(),
+        Some(in_channel.clone())
+    );
+
+    repository
+        .delete_relayed_reply(in_thread.reply_message_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        repository
+            .get_relayed_reply(in_thread.reply_message_id)
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+fn relayed_reply(author_id: u64, user_id: u64) -> UtRelayedReply {
+    UtRelayedReply {
+        reply_message_id: generate_random_20_digits(),
+        author_id,
+        user_id,
+        guild_id: generate_random_20_digits(),
+        channel_id: generate_random_20_digits(),
+        thread_id: None,
+        relayed_message_id: generate_random_20_digits(),
+    }
+}
+
+#[tokio::test]
+async fn test_relayed_replies_by_user() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let repository = PostgresReplyBridgeRepository::new(pool);
+
+    let user_id = generate_random_20_digits();
+    // 返信したものと，Timesへ届けられたもの
+    let written = relayed_reply(user_id, generate_random_20_digits());
+    let received = relayed_reply(generate_random_20_digits(), user_id);
+    let unrelated = relayed_reply(generate_random_20_digits(), generate_random_20_digits());
+    for relayed in [&written, &received, &unrelated] {
+        repository
+            .insert_relayed_reply(relayed.clone())
+            .await
+            .unwrap();
+    }
+
+    let mut expected = vec![written.clone(), received.clone()];
+    expected.sort_by_key(|r| r.reply_message_id);
+    assert_eq!(
+        repository
+            .get_relayed_replies_by_user(user_id)
+            .await
+            .unwrap(),
+        expected
+    );
+
+    repository
+        .delete_relayed_replies(vec![written.reply_message_id, received.reply_message_id])
+        .await
+        .unwrap();
+    assert!(repository
+        .get_relayed_replies_by_user(user_id)
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        repository
+            .get_relayed_reply(unrelated.reply_message_id)
+            .await
+            .unwrap(),
+        Some(unrelated)
+    );
+}
@@ -0,0 +1,104 @@
+use domain::models::UtThreadFollowSetting;
+use domain::repository::ThreadFollowRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresThreadFollowRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtThreadFollowSetting {
+    guild_id: BigDecimal,
+    follow_threads: bool,
+}
+
+// PostgresUtThreadFollowSettingをUtThreadFollowSettingに変換する
+
+impl From<PostgresUtThreadFollowSetting> for UtThreadFollowSetting {
+    fn from(s: PostgresUtThreadFollowSetting) -> Self {
+        Self {
+            guild_id: s.guild_id.to_string().parse().unwrap(),
+            follow_threads: s.follow_threads,
+        }
+    }
+}
+
+pub struct PostgresThreadFollowRepository {
+    pool: PgPool,
+}
+
+impl PostgresThreadFollowRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl ThreadFollowRepository for PostgresThreadFollowRepository {
+    type Error = PostgresThreadFollowRepositoryError;
+
+    #[instrument(skip(self))]
+    async fn upsert_thread_follow_setting(
+        &self,
+        setting: UtThreadFollowSetting,
+    ) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO threadfollowsettings (guild_id, follow_threads)
+            VALUES ($1, $2)
+            ON CONFLICT (guild_id) DO UPDATE
+            SET follow_threads = $2
+            "#,
+        )
+        .bind(BigDecimal::from(setting.guild_id))
+        .bind(setting.follow_threads)
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "thread follow setting upserted successfully in postgres. guild_id: {}",
+            setting.guild_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_thread_follow_settings(
+        &self,
+        guild_ids: Vec<u64>,
+    ) -> Result<Vec<UtThreadFollowSetting>, Self::Error> {
+        let bigdecimal_guild_ids = guild_ids
+            .into_iter()
+            .map(BigDecimal::from)
+            .collect::<Vec<_>>();
+        let settings: Vec<PostgresUtThreadFollowSetting> = sqlx::query_as(
+            r#"
+            SELECT guild_id, follow_threads
+            FROM threadfollowsettings
+            WHERE guild_id = ANY($1)
+            "#,
+        )
+        .bind(bigdecimal_guild_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "thread follow settings fetched successfully from postgres. count: {}",
+            settings.len()
+        );
+        Ok(settings.into_iter().map(|s| s.into()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,80 @@
+use super::*;
+use domain::models::UtGuild;
+use domain::repository::GuildRepository;
+
+use crate::postgres_guild_repository::PostgresGuildRepository;
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer};
+
+async fn setup_guild(pool: &PgPool) -> u64 {
+    let guild_id = generate_random_20_digits();
+    PostgresGuildRepository::new(pool.clone())
+        .upsert_guild(UtGuild::new(guild_id, Some("test_guild".to_string())))
+        .await
+        .unwrap();
+    guild_id
+}
+
+#[tokio::test]
+/// スレッドの設定を保存して，更新できることを確認する
+async fn test_upsert_thread_follow_setting() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id = setup_guild(&pool).await;
+
+    let repository = PostgresThreadFollowRepository::new(pool);
+
+    assert!(repository
+        .get_thread_follow_settings(vec![guild_id])
+        .await
+        .unwrap()
+        .is_empty());
+
+    for follow_threads in [false, true] {
+        let setting = UtThreadFollowSetting {
+            guild_id,
+            follow_threads,
+        };
+        repository
+            .upsert_thread_follow_setting(setting)
+            .await
+            .unwrap();
+        assert_eq!(
+            repository
+                .get_thread_follow_settings(vec![guild_id])
+                .await
+                .unwrap(),
+            vec![setting]
+        );
+    }
+}
+
+#[tokio::test]
+/// 指定したギルドの設定だけを取得することを確認する
+async fn test_get_thread_follow_settings() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let guild_id_1 = setup_guild(&pool).await;
+    let guild_id_2 = setup_guild(&pool).await;
+    let guild_id_3 = setup_guild(&pool).await;
+
+    let repository = PostgresThreadFollowRepository::new(pool);
+    for guild_id in [guild_id_1, guild_id_2, guild_id_3] {
+        repository
+            .upsert_thread_follow_setting(UtThreadFollowSetting {
+                guild_id,
+                follow_threads: false,
+            })
+            .await
+            .unwrap();
+    }
+
+    let mut guild_ids = repository
+        .get_thread_follow_settings(vec![guild_id_1, guild_id_3])
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|s| s.guild_id)
+        .collect::<Vec<_>>();
+    guild_ids.sort();
+    let mut expected = vec![guild_id_1, guild_id_3];
+    expected.sort();
+    assert_eq!(guild_ids, expected);
+}
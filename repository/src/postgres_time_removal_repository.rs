@@ -0,0 +1,141 @@
+use domain::models::{TimeRemovalReason, UtTimeRemoval};
+use domain::repository::TimeRemovalRepository;
+
+use thiserror::Error;
+
+use sqlx::{types::BigDecimal, FromRow, PgPool};
+
+use sqlx::Error as SqlxError;
+
+use tracing::{info, instrument};
+
+#[derive(Error, Debug)]
+pub enum PostgresTimeRemovalRepositoryError {
+    #[error("sqlx error: {0}")]
+    SqlxError(#[from] SqlxError),
+    #[error("unknown time removal reason: {0}")]
+    UnknownReason(String),
+}
+
+// postgresではu64を格納できないので，Bigdecimalに変換して格納する
+
+#[derive(Debug, Clone, FromRow)]
+struct PostgresUtTimeRemoval {
+    user_id: BigDecimal,
+    guild_id: BigDecimal,
+    channel_id: BigDecimal,
+    reason: String,
+    removed_at: String,
+}
+
+// TimeRemovalReasonをDBに保存する文字列に変換する
+
+fn reason_to_str(reason: TimeRemovalReason) -> &'static str {
+    match reason {
+        TimeRemovalReason::Banned => "banned",
+        TimeRemovalReason::Left => "left",
+    }
+}
+
+// PostgresUtTimeRemovalをUtTimeRemovalに変換する
+
+impl TryFrom<PostgresUtTimeRemoval> for UtTimeRemoval {
+    type Error = PostgresTimeRemovalRepositoryError;
+
+    fn try_from(r: PostgresUtTimeRemoval) -> Result<Self, Self::Error> {
+        let reason = match r.reason.as_str() {
+            "banned" => TimeRemovalReason::Banned,
+            "left" => TimeRemovalReason::Left,
+            _ => return Err(PostgresTimeRemovalRepositoryError::UnknownReason(r.reason)),
+        };
+        Ok(Self {
+            user_id: r.user_id.to_string().parse().unwrap(),
+            guild_id: r.guild_id.to_string().parse().unwrap(),
+            channel_id: r.channel_id.to_string().parse().unwrap(),
+            reason,
+            removed_at: r.removed_at,
+        })
+    }
+}
+
+pub struct PostgresTimeRemovalRepository {
+    pool: PgPool,
+}
+
+impl PostgresTimeRemovalRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+impl TimeRemovalRepository for PostgresTimeRemovalRepository {
+    type Error = PostgresTimeRemovalRepositoryError;
+
+    #[instrument(skip(self))]
+    async fn upsert_time_removal(&self, removal: UtTimeRemoval) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO timeremovals (user_id, guild_id, channel_id, reason, removed_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (user_id, guild_id) DO UPDATE
+            SET channel_id = $3, reason = $4, removed_at = $5
+            "#,
+        )
+        .bind(BigDecimal::from(removal.user_id))
+        .bind(BigDecimal::from(removal.guild_id))
+        .bind(BigDecimal::from(removal.channel_id))
+        .bind(reason_to_str(removal.reason))
+        .bind(&removal.removed_at)
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "time removal upserted successfully in postgres. user_id: {}, guild_id: {}",
+            removal.user_id, removal.guild_id
+        );
+        Ok(())
+    }
+
+    #[instrument(skip(self))]
+    async fn get_time_removals(&self, user_id: u64) -> Result<Vec<UtTimeRemoval>, Self::Error> {
+        let removals: Vec<PostgresUtTimeRemoval> = sqlx::query_as(
+            r#"
+            SELECT user_id, guild_id, channel_id, reason, removed_at
+            FROM timeremovals
+            WHERE user_id = $1
+            ORDER BY guild_id
+            "#,
+        )
+        .bind(BigDecimal::from(user_id))
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "time removals fetched successfully from postgres. user_id: {}",
+            user_id
+        );
+        removals.into_iter().map(|r| r.try_into()).collect()
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_time_removals(&self, user_id: u64) -> Result<(), Self::Error> {
+        sqlx::query(
+            r#"
+            DELETE FROM timeremovals
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(BigDecimal::from(user_id))
+        .execute(&self.pool)
+        .await?;
+
+        info!(
+            "time removals deleted successfully from postgres. user_id: {}",
+            user_id
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,73 @@
+use super::*;
+
+use crate::test_utils::{generate_random_20_digits, setup_postgres_testcontainer};
+
+fn removal(user_id: u64, guild_id: u64, reason: TimeRemovalReason) -> UtTimeRemoval {
+    UtTimeRemoval {
+        user_id,
+        guild_id,
+        channel_id: generate_random_20_digits(),
+        reason,
+        removed_at: "2024-01-01T00:00:00Z".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_upsert_time_removal() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let repository = PostgresTimeRemovalRepository::new(pool);
+
+    let user_id = generate_random_20_digits();
+    let guild_id = generate_random_20_digits();
+    repository
+        .upsert_time_removal(removal(user_id, guild_id, TimeRemovalReason::Left))
+        .await
+        .unwrap();
+
+    // 同じユーザーとギルドは新しい記録で置き換える
+    let banned = removal(user_id, guild_id, TimeRemovalReason::Banned);
+    repository
+        .upsert_time_removal(banned.clone())
+        .await
+        .unwrap();
+
+    // 他のユーザーの記録は含めない
+    repository
+        .upsert_time_removal(removal(
+            generate_random_20_digits(),
+            guild_id,
+            TimeRemovalReason::Left,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        repository.get_time_removals(user_id).await.unwrap(),
+        vec![banned]
+    );
+}
+
+#[tokio::test]
+async fn test_delete_time_removals() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+    let repository = PostgresTimeRemovalRepository::new(pool);
+
+    let user_id = generate_random_20_digits();
+    for _ in 0..2 {
+        repository
+            .upsert_time_removal(removal(
+                user_id,
+                generate_random_20_digits(),
+                TimeRemovalReason::Banned,
+            ))
+            .await
+            .unwrap();
+    }
+    repository.delete_time_removals(user_id).await.unwrap();
+
+    assert!(repository
+        .get_time_removals(user_id)
+        .await
+        .unwrap()
+        .is_empty());
+}
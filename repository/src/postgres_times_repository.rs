@@ -1,4 +1,6 @@
-use domain::models::UtTime;
+use std::sync::Arc;
+
+use domain::models::{UtTime, WebhookUrl};
 use domain::repository::TimesRepository;
 
 use thiserror::Error;
@@ -7,6 +9,8 @@ use sqlx::{types::BigDecimal, FromRow, PgPool};
 
 use sqlx::Error as SqlxError;
 
+use crate::webhook_cipher::{WebhookCipher, WebhookCipherError};
+
 // tracingもロギングも全く理解していないことだらけだが，とりあえず使ってみる
 use tracing::{info, instrument};
 
@@ -14,6 +18,8 @@ use tracing::{info, instrument};
 pub enum PostgresTimesRepositoryError {
     #[error("sqlx error: {0}")]
     SqlxError(#[from] SqlxError),
+    #[error("webhook cipher error: {0}")]
+    WebhookCipherError(#[from] WebhookCipherError),
 }
 
 // postgresではu64を格納できないので，Bigdecimalに変換して格納する
@@ -24,44 +30,111 @@ struct PostgresUtTime {
     guild_id: BigDecimal,
     user_name: String,
     channel_id: BigDecimal,
-    webhook_url: String,
+    /// 暗号化したもの．暗号化する前の行では平文のURL
+    #[sqlx(try_from = "String")]
+    webhook_url: WebhookUrl,
 }
 
 // UtTimeをPostgresUtTimeに変換する
+// WebhookのURLは暗号化して保存する
 
-impl From<UtTime> for PostgresUtTime {
-    fn from(u: UtTime) -> Self {
-        Self {
+// WebhookのURLの暗号文を，ユーザーとサーバーの組に結びつける
+fn associated_data(user_id: u64, guild_id: u64) -> Vec<u8> {
+    format!("times:{}:{}", user_id, guild_id).into_bytes()
+}
+
+impl PostgresUtTime {
+    fn encrypt(u: UtTime, cipher: &WebhookCipher) -> Result<Self, WebhookCipherError> {
+        Ok(Self {
             user_id: BigDecimal::from(u.user_id),
             guild_id: BigDecimal::from(u.guild_id),
             user_name: u.user_name,
             channel_id: BigDecimal::from(u.channel_id),
-            webhook_url: u.webhook_url,
-        }
+            webhook_url: cipher
+                .encrypt(
+                    u.webhook_url.expose_secret(),
+                    &associated_data(u.user_id, u.guild_id),
+                )?
+                .into(),
+        })
     }
-}
-
-// PostgresUtTimeをUtTimeに変換する
 
-impl From<PostgresUtTime> for UtTime {
-    fn from(p: PostgresUtTime) -> Self {
-        Self {
-            user_id: p.user_id.to_string().parse().unwrap(),
-            guild_id: p.guild_id.to_string().parse().unwrap(),
-            user_name: p.user_name,
-            channel_id: p.channel_id.to_string().parse().unwrap(),
-            webhook_url: p.webhook_url,
-        }
+    // PostgresUtTimeをUtTimeに変換する
+    fn decrypt(self, cipher: &WebhookCipher) -> Result<UtTime, WebhookCipherError> {
+        let user_id = self.user_id.to_string().parse().unwrap();
+        let guild_id = self.guild_id.to_string().parse().unwrap();
+        Ok(UtTime {
+            user_id,
+            guild_id,
+            user_name: self.user_name,
+            channel_id: self.channel_id.to_string().parse().unwrap(),
+            webhook_url: cipher
+                .decrypt(
+                    self.webhook_url.expose_secret(),
+                    &associated_data(user_id, guild_id),
+                )?
+                .into(),
+        })
     }
 }
 
 pub struct PostgresTimesRepository {
     pool: PgPool,
+    cipher: Arc<WebhookCipher>,
 }
 
 impl PostgresTimesRepository {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pool: PgPool, cipher: Arc<WebhookCipher>) -> Self {
+        Self { pool, cipher }
+    }
+
+    /// 暗号化されていないWebhookのURLと，古い鍵で暗号化されたURLを，現在の鍵で暗号化しなおす
+    /// 起動時に実行する．何度実行してもよい
+    /// 暗号化しなおした行の数を返す
+    #[instrument(skip(self))]
+    pub async fn encrypt_webhook_urls(&self) -> Result<usize, PostgresTimesRepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let rows: Vec<(BigDecimal, BigDecimal, String)> = sqlx::query_as(
+            r#"
+            SELECT user_id, guild_id, webhook_url
+            FROM times
+            FOR UPDATE
+            "#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut count = 0;
+        for (user_id, guild_id, webhook_url) in rows {
+            if !self.cipher.needs_reencryption(&webhook_url) {
+                continue;
+            }
+            let webhook_url = self.cipher.reencrypt(
+                &webhook_url,
+                &associated_data(
+                    user_id.to_string().parse().unwrap(),
+                    guild_id.to_string().parse().unwrap(),
+                ),
+            )?;
+            sqlx::query(
+                r#"
+                UPDATE times
+                SET webhook_url = $3
+                WHERE user_id = $1 AND guild_id = $2
+                "#,
+            )
+            .bind(user_id)
+            .bind(guild_id)
+            .bind(webhook_url)
+            .execute(&mut *tx)
+            .await?;
+            count += 1;
+        }
+
+        tx.commit().await?;
+        info!("webhook urls of times encrypted. count: {}", count);
+        Ok(count)
     }
 }
 
@@ -73,7 +146,7 @@ impl TimesRepository for PostgresTimesRepository {
         &self,
         time: UtTime,
     ) -> Result<Option<UtTime>, Self::Error> {
-        let postgres_time = PostgresUtTime::from(time);
+        let postgres_time = PostgresUtTime::encrypt(time, &self.cipher)?;
         // 現在の値を取得する
         // ない場合はNoneを返す
         // ある場合はその値を返す
@@ -110,7 +183,7 @@ impl TimesRepository for PostgresTimesRepository {
         .bind(&postgres_time.guild_id)
         .bind(&postgres_time.user_name)
         .bind(&postgres_time.channel_id)
-        .bind(&postgres_time.webhook_url)
+        .bind(postgres_time.webhook_url.expose_secret())
         .execute(&mut *tx)
         .await?;
 
@@ -121,7 +194,7 @@ impl TimesRepository for PostgresTimesRepository {
         );
 
         if let Some(old_time) = old_time {
-            Ok(Some(old_time.into()))
+            Ok(Some(old_time.decrypt(&self.cipher)?))
         } else {
             Ok(None)
         }
@@ -146,7 +219,38 @@ impl TimesRepository for PostgresTimesRepository {
             user_id
         );
 
-        let times = times.into_iter().map(|t| t.into()).collect();
+        let times = times
+            .into_iter()
+            .map(|t| t.decrypt(&self.cipher))
+            .collect::<Result<_, _>>()?;
+
+        Ok(times)
+    }
+
+    /// channel_idと一致するTimeをすべて取得する
+    #[instrument(skip(self))]
+    async fn get_times_by_channel(&self, channel_id: u64) -> Result<Vec<UtTime>, Self::Error> {
+        let bigdecimal_channel_id = BigDecimal::from(channel_id);
+        let times: Vec<PostgresUtTime> = sqlx::query_as(
+            r#"
+            SELECT user_id, guild_id, user_name, channel_id, webhook_url
+            FROM times
+            WHERE channel_id = $1
+            "#,
+        )
+        .bind(bigdecimal_channel_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        info!(
+            "times fetched successfully from postgres. channel_id: {}",
+            channel_id
+        );
+
+        let times = times
+            .into_iter()
+            .map(|t| t.decrypt(&self.cipher))
+            .collect::<Result<_, _>>()?;
 
         Ok(times)
     }
@@ -195,7 +299,7 @@ impl TimesRepository for PostgresTimesRepository {
             user_id, guild_id
         );
 
-        Ok(time.into())
+        Ok(time.decrypt(&self.cipher)?)
     }
 }
 
@@ -1,12 +1,13 @@
 // 外部キー制約の都合，guildsテーブルにもデータを入れる必要がある
 use super::*;
+use crate::webhook_cipher::WebhookCipher;
 use crate::{
     postgres_guild_repository::PostgresGuildRepository, test_utils::setup_postgres_testcontainer,
 };
 use domain::{models::UtGuild, repository::GuildRepository};
 use sqlx::PgPool;
 
-use crate::test_utils::generate_random_20_digits;
+use crate::test_utils::{generate_random_20_digits, test_cipher};
 
 async fn setup_guilds_from_times(pool: &PgPool, times: Vec<UtTime>) {
     let guild_repository = PostgresGuildRepository::new(pool.clone());
@@ -36,7 +37,7 @@ async fn test_upsert_and_return_old_time() {
         guild_id,
         user_name: "user_name".to_string(),
         channel_id,
-        webhook_url: "webhook_url".to_string(),
+        webhook_url: "webhook_url".into(),
     };
 
     let times = vec![time.clone()];
@@ -45,7 +46,7 @@ async fn test_upsert_and_return_old_time() {
     // そのための処理
     setup_guilds_from_times(&pool, times).await;
 
-    let repository = PostgresTimesRepository::new(pool);
+    let repository = PostgresTimesRepository::new(pool, test_cipher());
 
     repository
         .upsert_and_return_old_time(time.clone())
@@ -68,7 +69,7 @@ async fn test_get_time() {
         guild_id,
         user_name: "user_name".to_string(),
         channel_id,
-        webhook_url: "webhook_url".to_string(),
+        webhook_url: "webhook_url".into(),
     };
 
     let times = vec![time.clone()];
@@ -77,7 +78,7 @@ async fn test_get_time() {
     // そのための処理
     setup_guilds_from_times(&pool, times).await;
 
-    let repository = PostgresTimesRepository::new(pool);
+    let repository = PostgresTimesRepository::new(pool, test_cipher());
 
     repository
         .upsert_and_return_old_time(time.clone())
@@ -103,7 +104,7 @@ async fn test_gets_times() {
         guild_id: guild_id_1,
         user_name: "user_name".to_string(),
         channel_id: channel_id_1,
-        webhook_url: "webhook_url".to_string(),
+        webhook_url: "webhook_url".into(),
     };
 
     let guild_id_2 = generate_random_20_digits();
@@ -114,7 +115,7 @@ async fn test_gets_times() {
         guild_id: guild_id_2,
         user_name: "user_name_2".to_string(),
         channel_id: channel_id_2,
-        webhook_url: "webhook_url_2".to_string(),
+        webhook_url: "webhook_url_2".into(),
     };
 
     let times = vec![time_1.clone(), time_2.clone()];
@@ -123,7 +124,7 @@ async fn test_gets_times() {
     // そのための処理
     setup_guilds_from_times(&pool, times).await;
 
-    let repository = PostgresTimesRepository::new(pool);
+    let repository = PostgresTimesRepository::new(pool, test_cipher());
 
     repository
         .upsert_and_return_old_time(time_1.clone())
@@ -138,10 +139,10 @@ async fn test_gets_times() {
 
     // ２つのベクタを順序に依存せずに比較するためにソートする
     let mut times = times;
-    times.sort_by(|a, b| a.guild_id.cmp(&b.guild_id));
+    times.sort_by_key(|a| a.guild_id);
 
     let mut expected_times = vec![time_1, time_2];
-    expected_times.sort_by(|a, b| a.guild_id.cmp(&b.guild_id));
+    expected_times.sort_by_key(|a| a.guild_id);
 
     assert_eq!(times, expected_times);
 }
@@ -161,7 +162,7 @@ async fn test_upsert_and_return_old_time_twice() {
         guild_id,
         user_name: "user_name".to_string(),
         channel_id,
-        webhook_url: "webhook_url".to_string(),
+        webhook_url: "webhook_url".into(),
     };
 
     let times = vec![time_1.clone()];
@@ -170,7 +171,7 @@ async fn test_upsert_and_return_old_time_twice() {
     // そのための処理
     setup_guilds_from_times(&pool, times).await;
 
-    let repository = PostgresTimesRepository::new(pool);
+    let repository = PostgresTimesRepository::new(pool, test_cipher());
 
     repository
         .upsert_and_return_old_time(time_1.clone())
@@ -187,7 +188,7 @@ async fn test_upsert_and_return_old_time_twice() {
         guild_id,
         user_name: "user_name_2".to_string(),
         channel_id,
-        webhook_url: "webhook_url_2".to_string(),
+        webhook_url: "webhook_url_2".into(),
     };
     repository
         .upsert_and_return_old_time(time_2.clone())
@@ -215,7 +216,7 @@ async fn test_delete_time() {
         guild_id,
         user_name: "user_name".to_string(),
         channel_id,
-        webhook_url: "webhook_url".to_string(),
+        webhook_url: "webhook_url".into(),
     };
 
     let times = vec![time.clone()];
@@ -224,7 +225,7 @@ async fn test_delete_time() {
     // そのための処理
     setup_guilds_from_times(&pool, times).await;
 
-    let repository = PostgresTimesRepository::new(pool);
+    let repository = PostgresTimesRepository::new(pool, test_cipher());
 
     repository
         .upsert_and_return_old_time(time.clone())
@@ -253,7 +254,7 @@ async fn test_upsert_conflict() {
         guild_id,
         user_name: "user_name".to_string(),
         channel_id,
-        webhook_url: "webhook_url".to_string(),
+        webhook_url: "webhook_url".into(),
     };
 
     let times = vec![time_1.clone()];
@@ -262,7 +263,7 @@ async fn test_upsert_conflict() {
     // そのための処理
     setup_guilds_from_times(&pool, times).await;
 
-    let repository = PostgresTimesRepository::new(pool);
+    let repository = PostgresTimesRepository::new(pool, test_cipher());
 
     repository
         .upsert_and_return_old_time(time_1.clone())
@@ -273,7 +274,7 @@ async fn test_upsert_conflict() {
         guild_id,
         user_name: "user_name_2".to_string(),
         channel_id,
-        webhook_url: "webhook_url_2".to_string(),
+        webhook_url: "webhook_url_2".into(),
     };
 
     let returned_time = repository
@@ -282,3 +283,156 @@ async fn test_upsert_conflict() {
         .unwrap();
     assert_eq!(returned_time, Some(time_1));
 }
+
+#[tokio::test]
+/// get_times_by_channelで，同じチャンネルをTimesにしている登録だけを取得できるかどうかを確認する
+async fn test_get_times_by_channel() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let guild_id = generate_random_20_digits();
+    let channel_id = generate_random_20_digits();
+
+    let times = (0..2)
+        .map(|_| UtTime {
+            user_id: generate_random_20_digits(),
+            guild_id,
+            user_name: "user_name".to_string(),
+            channel_id,
+            webhook_url: "webhook_url".into(),
+        })
+        .collect::<Vec<_>>();
+    let other = UtTime {
+        user_id: generate_random_20_digits(),
+        guild_id,
+        user_name: "user_name".to_string(),
+        channel_id: generate_random_20_digits(),
+        webhook_url: "other_webhook_url".into(),
+    };
+
+    setup_guilds_from_times(&pool, vec![other.clone()]).await;
+
+    let repository = PostgresTimesRepository::new(pool, test_cipher());
+
+    for time in times.iter().chain([&other]) {
+        repository
+            .upsert_and_return_old_time(time.clone())
+            .await
+            .unwrap();
+    }
+
+    let mut fetched = repository.get_times_by_channel(channel_id).await.unwrap();
+    fetched.sort_by_key(|t| t.user_id);
+    let mut expected = times;
+    expected.sort_by_key(|t| t.user_id);
+
+    assert_eq!(fetched, expected);
+}
+
+async fn stored_webhook_url(pool: &PgPool, time: &UtTime) -> String {
+    let (webhook_url,): (String,) = sqlx::query_as(
+        r#"
+        SELECT webhook_url FROM times
+        WHERE user_id = $1 AND guild_id = $2
+        "#,
+    )
+    .bind(BigDecimal::from(time.user_id))
+    .bind(BigDecimal::from(time.guild_id))
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    webhook_url
+}
+
+#[tokio::test]
+/// WebhookのURLが暗号化して保存され，取り出すときに復号されることを確認する
+async fn test_webhook_url_encrypted_at_rest() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let time = UtTime {
+        user_id: generate_random_20_digits(),
+        guild_id: generate_random_20_digits(),
+        user_name: "user_name".to_string(),
+        channel_id: generate_random_20_digits(),
+        webhook_url: "https://discord.com/api/webhooks/1/secret-token".into(),
+    };
+    setup_guilds_from_times(&pool, vec![time.clone()]).await;
+
+    let repository = PostgresTimesRepository::new(pool.clone(), test_cipher());
+    repository
+        .upsert_and_return_old_time(time.clone())
+        .await
+        .unwrap();
+
+    let stored = stored_webhook_url(&pool, &time).await;
+    assert!(!stored.contains("secret-token"));
+
+    let fetched = repository
+        .get_time(time.user_id, time.guild_id)
+        .await
+        .unwrap();
+    assert_eq!(fetched, time);
+}
+
+#[tokio::test]
+/// 暗号化する前の行と古い鍵で暗号化した行を，現在の鍵で暗号化しなおせることを確認する
+async fn test_encrypt_webhook_urls() {
+    let (_container, pool) = setup_postgres_testcontainer().await;
+
+    let times = (0..2)
+        .map(|i| UtTime {
+            user_id: generate_random_20_digits(),
+            guild_id: generate_random_20_digits(),
+            user_name: "user_name".to_string(),
+            channel_id: generate_random_20_digits(),
+            webhook_url: format!("https://discord.com/api/webhooks/{}/secret-token", i).into(),
+        })
+        .collect::<Vec<_>>();
+    setup_guilds_from_times(&pool, times.clone()).await;
+
+    // 1つ目は暗号化する前の行として，直接入れる
+    sqlx::query(
+        r#"
+        INSERT INTO times (user_id, guild_id, user_name, channel_id, webhook_url)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(BigDecimal::from(times[0].user_id))
+    .bind(BigDecimal::from(times[0].guild_id))
+    .bind(&times[0].user_name)
+    .bind(BigDecimal::from(times[0].channel_id))
+    .bind(times[0].webhook_url.expose_secret())
+    .execute(&pool)
+    .await
+    .unwrap();
+    // 2つ目は古い鍵で暗号化する
+    let old_repository = PostgresTimesRepository::new(pool.clone(), test_cipher());
+    old_repository
+        .upsert_and_return_old_time(times[1].clone())
+        .await
+        .unwrap();
+
+    let rotated_cipher = Arc::new(
+        WebhookCipher::new(vec![
+            ("rotated".to_string(), [8; 32]),
+            ("test".to_string(), [7; 32]),
+        ])
+        .unwrap(),
+    );
+    let repository = PostgresTimesRepository::new(pool.clone(), rotated_cipher.clone());
+
+    assert_eq!(repository.encrypt_webhook_urls().await.unwrap(), 2);
+    // 何度実行してもよい
+    assert_eq!(repository.encrypt_webhook_urls().await.unwrap(), 0);
+
+    for time in times.iter() {
+        let stored = stored_webhook_url(&pool, time).await;
+        assert!(stored.starts_with("enc:v1:rotated:"));
+        assert!(!rotated_cipher.needs_reencryption(&stored));
+
+        let fetched = repository
+            .get_time(time.user_id, time.guild_id)
+            .await
+            .unwrap();
+        assert_eq!(&fetched, time);
+    }
+}
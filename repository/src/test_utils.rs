@@ -1,9 +1,13 @@
+use std::sync::Arc;
+
 use rand::Rng;
 use sqlx::{Executor as _, PgPool};
 use testcontainers::ContainerAsync;
 use testcontainers_modules::postgres::{self, Postgres};
 use testcontainers_modules::testcontainers::runners::AsyncRunner;
 
+use crate::webhook_cipher::WebhookCipher;
+
 // ランダムな20桁の数値を生成する
 // discordの各種idが20桁の数値であるため，それに合わせる
 #[allow(dead_code)]
@@ -27,3 +31,9 @@ pub async fn setup_postgres_testcontainer() -> (ContainerAsync<Postgres>, PgPool
 
     (container, pool)
 }
+
+/// テスト用の鍵で作った暗号
+#[allow(dead_code)]
+pub(crate) fn test_cipher() -> Arc<WebhookCipher> {
+    Arc::new(WebhookCipher::new(vec![("test".to_string(), [7; 32])]).unwrap())
+}
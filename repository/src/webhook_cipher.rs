@@ -0,0 +1,291 @@
+//! WebhookのURLを暗号化して保存するための暗号
+//!
+//! URLにはトークンが含まれ，それだけでTimesへ投稿できてしまうので，DBには暗号化して保存する
+//! エンベロープ暗号化を使う．値ごとにデータ鍵を作ってURLを暗号化し，データ鍵を鍵(マスター鍵)で暗号化する
+//! 鍵を替えるときは，データ鍵を暗号化しなおすだけでよい
+//!
+//! 保存する形式は enc:v2:{鍵のid}:{データ鍵のnonceと暗号文}:{URLのnonceと暗号文}
+//! 暗号文はbase64で表す
+//!
+//! URLは行を表す値(関連データ)と結びつけて暗号化する．別の行にコピーされた暗号文は復号できない
+//! enc:v1: は関連データなしで暗号化した古い形式．読めるが，起動時にenc:v2:へ暗号化しなおす
+
+use std::collections::HashMap;
+use std::fmt;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use thiserror::Error;
+
+// 暗号化した値の先頭に付ける．これがない値は暗号化する前の平文として扱う
+const ENCRYPTED_PREFIX: &str = "enc:v2:";
+// 関連データなしで暗号化した古い形式
+const LEGACY_ENCRYPTED_PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum WebhookCipherError {
+    #[error("invalid key config: {0}")]
+    InvalidKeyConfig(String),
+    #[error("unknown key id: {0}")]
+    UnknownKey(String),
+    #[error("malformed encrypted value")]
+    Malformed,
+    #[error("failed to encrypt or decrypt")]
+    Crypto,
+}
+
+/// WebhookのURLの暗号化と復号
+///
+/// 鍵は複数持てる．暗号化には最初の鍵(現在の鍵)を使い，復号には鍵のidが一致するものを使う
+pub struct WebhookCipher {
+    current_key_id: String,
+    keys: HashMap<String, Aes256Gcm>,
+}
+
+// 鍵を出力しないように，Debugは自分で実装する
+impl fmt::Debug for WebhookCipher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut key_ids = self.keys.keys().collect::<Vec<_>>();
+        key_ids.sort();
+        f.debug_struct("WebhookCipher")
+            .field("current_key_id", &self.current_key_id)
+            .field("key_ids", &key_ids)
+            .finish()
+    }
+}
+
+impl WebhookCipher {
+    /// keysの最初の鍵を現在の鍵とする
+    pub fn new(keys: Vec<(String, [u8; KEY_LEN])>) -> Result<Self, WebhookCipherError> {
+        let Some((current_key_id, _)) = keys.first() else {
+            return Err(WebhookCipherError::InvalidKeyConfig("no keys".to_string()));
+        };
+        let current_key_id = current_key_id.clone();
+
+        let mut ciphers = HashMap::new();
+        for (key_id, key) in keys {
+            if key_id.is_empty() || key_id.contains(':') {
+                return Err(WebhookCipherError::InvalidKeyConfig(format!(
+                    "key id must be non-empty and must not contain ':': {:?}",
+                    key_id
+                )));
+            }
+            let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+            if ciphers.insert(key_id.clone(), cipher).is_some() {
+                return Err(WebhookCipherError::InvalidKeyConfig(format!(
+                    "duplicate key id: {}",
+                    key_id
+                )));
+            }
+        }
+
+        Ok(Self {
+            current_key_id,
+            keys: ciphers,
+        })
+    }
+
+    /// {鍵のid}:{base64の32バイトの鍵}をカンマでつないだ設定から作る
+    /// 最初の鍵を現在の鍵とする．古い鍵は復号と鍵の入れ替えのために残しておく
+    ///
+    /// 例: 2024-06:base64key,2024-01:base64oldkey
+    pub fn from_config(config: &str) -> Result<Self, WebhookCipherError> {
+        let mut keys = Vec::new();
+        for entry in config.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((key_id, encoded)) = entry.split_once(':') else {
+                return Err(WebhookCipherError::InvalidKeyConfig(
+                    "each key must be {key_id}:{base64 key}".to_string(),
+                ));
+            };
+            let key = BASE64.decode(encoded.trim()).map_err(|_| {
+                WebhookCipherError::InvalidKeyConfig(format!("key {} is not base64", key_id))
+            })?;
+            let key: [u8; KEY_LEN] = key.try_into().map_err(|_| {
+                WebhookCipherError::InvalidKeyConfig(format!(
+                    "key {} must be {} bytes",
+                    key_id, KEY_LEN
+                ))
+            })?;
+            keys.push((key_id.trim().to_string(), key));
+        }
+        Self::new(keys)
+    }
+
+    /// associated_dataには，値を保存する行を表すものを渡す．復号するときも同じものを渡す
+    pub fn encrypt(
+        &self,
+        plaintext: &str,
+        associated_data: &[u8],
+    ) -> Result<String, WebhookCipherError> {
+        let data_key = Aes256Gcm::generate_key(OsRng);
+        let data = seal(
+            &Aes256Gcm::new(&data_key),
+            plaintext.as_bytes(),
+            associated_data,
+        )?;
+        let wrapped_key = seal(self.current_cipher(), data_key.as_slice(), &[])?;
+
+        Ok(format!(
+            "{}{}:{}:{}",
+            ENCRYPTED_PREFIX,
+            self.current_key_id,
+            BASE64.encode(wrapped_key),
+            BASE64.encode(data)
+        ))
+    }
+
+    /// 暗号化されていない値は，そのまま返す
+    /// 暗号化を導入する前の行を，移行するまでの間も読めるようにするため
+    /// enc:v1:の値は関連データを確かめずに復号する
+    pub fn decrypt(
+        &self,
+        stored: &str,
+        associated_data: &[u8],
+    ) -> Result<String, WebhookCipherError> {
+        let Some(envelope) = Envelope::parse(stored)? else {
+            return Ok(stored.to_string());
+        };
+
+        let data_key = self.unwrap_key(&envelope)?;
+        let data = BASE64
+            .decode(envelope.data)
+            .map_err(|_| WebhookCipherError::Malformed)?;
+        let associated_data = if envelope.legacy {
+            &[]
+        } else {
+            associated_data
+        };
+        let plaintext = open(&Aes256Gcm::new(&data_key), &data, associated_data)?;
+
+        String::from_utf8(plaintext).map_err(|_| WebhookCipherError::Malformed)
+    }
+
+    /// 現在の鍵で暗号化しなおす必要があるか
+    /// 暗号化されていない値，古い形式の値，古い鍵で暗号化された値が対象
+    pub fn needs_reencryption(&self, stored: &str) -> bool {
+        match Envelope::parse(stored) {
+            Ok(Some(envelope)) => envelope.legacy || envelope.key_id != self.current_key_id,
+            Ok(None) => true,
+            // 壊れた値は暗号化しなおせない
+            Err(_) => false,
+        }
+    }
+
+    /// 現在の鍵で暗号化しなおす
+    /// enc:v2:の値は，データ鍵だけを暗号化しなおす
+    /// 暗号化されていない値と古い形式の値は，関連データと結びつけて暗号化しなおす
+    pub fn reencrypt(
+        &self,
+        stored: &str,
+        associated_data: &[u8],
+    ) -> Result<String, WebhookCipherError> {
+        let Some(envelope) = Envelope::parse(stored)? else {
+            return self.encrypt(stored, associated_data);
+        };
+        if envelope.legacy {
+            let plaintext = self.decrypt(stored, associated_data)?;
+            return self.encrypt(&plaintext, associated_data);
+        }
+
+        let data_key = self.unwrap_key(&envelope)?;
+        let wrapped_key = seal(self.current_cipher(), data_key.as_slice(), &[])?;
+
+        Ok(format!(
+            "{}{}:{}:{}",
+            ENCRYPTED_PREFIX,
+            self.current_key_id,
+            BASE64.encode(wrapped_key),
+            envelope.data
+        ))
+    }
+
+    fn current_cipher(&self) -> &Aes256Gcm {
+        &self.keys[&self.current_key_id]
+    }
+
+    fn unwrap_key(&self, envelope: &Envelope<'_>) -> Result<Key<Aes256Gcm>, WebhookCipherError> {
+        let cipher = self
+            .keys
+            .get(envelope.key_id)
+            .ok_or_else(|| WebhookCipherError::UnknownKey(envelope.key_id.to_string()))?;
+        let wrapped_key = BASE64
+            .decode(envelope.wrapped_key)
+            .map_err(|_| WebhookCipherError::Malformed)?;
+        let data_key = open(cipher, &wrapped_key, &[])?;
+        if data_key.len() != KEY_LEN {
+            return Err(WebhookCipherError::Malformed);
+        }
+        Ok(*Key::<Aes256Gcm>::from_slice(&data_key))
+    }
+}
+
+/// 保存されている暗号化した値を分解したもの
+struct Envelope<'a> {
+    /// 関連データなしで暗号化した古い形式か
+    legacy: bool,
+    key_id: &'a str,
+    wrapped_key: &'a str,
+    data: &'a str,
+}
+
+impl<'a> Envelope<'a> {
+    /// 暗号化されていない値ならNone
+    fn parse(stored: &'a str) -> Result<Option<Self>, WebhookCipherError> {
+        let (legacy, rest) = if let Some(rest) = stored.strip_prefix(ENCRYPTED_PREFIX) {
+            (false, rest)
+        } else if let Some(rest) = stored.strip_prefix(LEGACY_ENCRYPTED_PREFIX) {
+            (true, rest)
+        } else {
+            return Ok(None);
+        };
+        let mut parts = rest.split(':');
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(key_id), Some(wrapped_key), Some(data), None) => Ok(Some(Self {
+                legacy,
+                key_id,
+                wrapped_key,
+                data,
+            })),
+            _ => Err(WebhookCipherError::Malformed),
+        }
+    }
+}
+
+/// nonceを作って暗号化し，nonceと暗号文をつなげて返す
+fn seal(cipher: &Aes256Gcm, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, WebhookCipherError> {
+    let nonce = Aes256Gcm::generate_nonce(OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| WebhookCipherError::Crypto)?;
+    Ok([nonce.as_slice(), &ciphertext].concat())
+}
+
+/// sealで作ったものを復号する
+fn open(cipher: &Aes256Gcm, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, WebhookCipherError> {
+    if sealed.len() < NONCE_LEN {
+        return Err(WebhookCipherError::Malformed);
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| WebhookCipherError::Crypto)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,187 @@
+use super::*;
+
+const WEBHOOK_URL: &str = "https://discord.com/api/webhooks/1234567890/secret-token";
+const AAD: &[u8] = b"times:1:2";
+
+fn cipher(keys: &[(&str, u8)]) -> WebhookCipher {
+    WebhookCipher::new(
+        keys.iter()
+            .map(|(id, byte)| (id.to_string(), [*byte; KEY_LEN]))
+            .collect(),
+    )
+    .unwrap()
+}
+
+#[test]
+/// 暗号化したものを復号できる
+fn test_encrypt_and_decrypt() {
+    let cipher = cipher(&[("new", 1)]);
+
+    let encrypted = cipher.encrypt(WEBHOOK_URL, AAD).unwrap();
+
+    assert!(encrypted.starts_with("enc:v2:new:"));
+    assert!(!encrypted.contains("secret-token"));
+    assert_eq!(cipher.decrypt(&encrypted, AAD).unwrap(), WEBHOOK_URL);
+}
+
+#[test]
+/// 同じURLでも暗号化するたびに違うものになる
+fn test_same_url_encrypts_differently() {
+    let cipher = cipher(&[("new", 1)]);
+
+    assert_ne!(
+        cipher.encrypt(WEBHOOK_URL, AAD).unwrap(),
+        cipher.encrypt(WEBHOOK_URL, AAD).unwrap()
+    );
+}
+
+#[test]
+/// 暗号化していないものはそのまま読み，暗号化し直すものとする
+fn test_plaintext_is_read_as_is() {
+    let cipher = cipher(&[("new", 1)]);
+
+    assert_eq!(cipher.decrypt(WEBHOOK_URL, AAD).unwrap(), WEBHOOK_URL);
+    assert!(cipher.needs_reencryption(WEBHOOK_URL));
+}
+
+#[test]
+/// 鍵を替えても古い鍵で暗号化したものを読める
+fn test_rotation_keeps_old_values_readable() {
+    let old = cipher(&[("old", 1)]);
+    let encrypted = old.encrypt(WEBHOOK_URL, AAD).unwrap();
+
+    let rotated = cipher(&[("new", 2), ("old", 1)]);
+    assert_eq!(rotated.decrypt(&encrypted, AAD).unwrap(), WEBHOOK_URL);
+    assert!(rotated.needs_reencryption(&encrypted));
+
+    let reencrypted = rotated.reencrypt(&encrypted, AAD).unwrap();
+    assert!(reencrypted.starts_with("enc:v2:new:"));
+    assert!(!rotated.needs_reencryption(&reencrypted));
+
+    // 古い鍵を外しても読める
+    let new_only = cipher(&[("new", 2)]);
+    assert_eq!(new_only.decrypt(&reencrypted, AAD).unwrap(), WEBHOOK_URL);
+    assert_eq!(
+        new_only.decrypt(&encrypted, AAD),
+        Err(WebhookCipherError::UnknownKey("old".to_string()))
+    );
+}
+
+#[test]
+/// 暗号化していないものを暗号化し直せる
+fn test_reencrypt_plaintext() {
+    let cipher = cipher(&[("new", 1)]);
+
+    let encrypted = cipher.reencrypt(WEBHOOK_URL, AAD).unwrap();
+
+    assert_eq!(cipher.decrypt(&encrypted, AAD).unwrap(), WEBHOOK_URL);
+}
+
+#[test]
+/// 違う鍵では復号できない
+fn test_wrong_key_fails() {
+    let encrypted = cipher(&[("new", 1)]).encrypt(WEBHOOK_URL, AAD).unwrap();
+
+    assert_eq!(
+        cipher(&[("new", 2)]).decrypt(&encrypted, AAD),
+        Err(WebhookCipherError::Crypto)
+    );
+}
+
+#[test]
+/// 書き換えられたものは復号できない
+fn test_tampered_value_fails() {
+    let cipher = cipher(&[("new", 1)]);
+    let encrypted = cipher.encrypt(WEBHOOK_URL, AAD).unwrap();
+
+    let (head, data) = encrypted.rsplit_once(':').unwrap();
+    let mut data = BASE64.decode(data).unwrap();
+    *data.last_mut().unwrap() ^= 1;
+    let tampered = format!("{}:{}", head, BASE64.encode(data));
+
+    assert_eq!(
+        cipher.decrypt(&tampered, AAD),
+        Err(WebhookCipherError::Crypto)
+    );
+    assert_eq!(
+        cipher.decrypt("enc:v2:new:abc", AAD),
+        Err(WebhookCipherError::Malformed)
+    );
+}
+
+#[test]
+/// 設定から鍵を読み取り，最初の鍵で暗号化する
+fn test_from_config() {
+    let new_key = BASE64.encode([2; KEY_LEN]);
+    let old_key = BASE64.encode([1; KEY_LEN]);
+    let config = format!("new:{}, old:{}", new_key, old_key);
+
+    let cipher = WebhookCipher::from_config(&config).unwrap();
+    let encrypted = cipher.encrypt(WEBHOOK_URL, AAD).unwrap();
+
+    assert!(encrypted.starts_with("enc:v2:new:"));
+    assert_eq!(
+        format!("{:?}", cipher),
+        r#"WebhookCipher { current_key_id: "new", key_ids: ["new", "old"] }"#
+    );
+}
+
+#[test]
+/// 正しくない設定はエラーにする
+fn test_invalid_config() {
+    let key = BASE64.encode([1; KEY_LEN]);
+    for config in [
+        String::new(),
+        key.clone(),
+        "short:AAAA".to_string(),
+        "id:not base64".to_string(),
+        format!("same:{},same:{}", key, key),
+    ] {
+        assert!(
+            matches!(
+                WebhookCipher::from_config(&config),
+                Err(WebhookCipherError::InvalidKeyConfig(_))
+            ),
+            "{}",
+            config
+        );
+    }
+}
+
+#[test]
+/// 別の行の関連データでは復号できない
+fn test_other_row_fails() {
+    let cipher = cipher(&[("new", 1)]);
+    let encrypted = cipher.encrypt(WEBHOOK_URL, AAD).unwrap();
+
+    assert_eq!(
+        cipher.decrypt(&encrypted, b"times:3:2"),
+        Err(WebhookCipherError::Crypto)
+    );
+}
+
+#[test]
+/// 関連データなしの古い形式を読め，関連データと結びつけて暗号化しなおせる
+fn test_legacy_value_is_reencrypted_with_associated_data() {
+    let cipher = cipher(&[("new", 1)]);
+    let data_key = Aes256Gcm::generate_key(OsRng);
+    let data = seal(&Aes256Gcm::new(&data_key), WEBHOOK_URL.as_bytes(), &[]).unwrap();
+    let wrapped_key = seal(cipher.current_cipher(), data_key.as_slice(), &[]).unwrap();
+    let legacy = format!(
+        "enc:v1:new:{}:{}",
+        BASE64.encode(wrapped_key),
+        BASE64.encode(data)
+    );
+
+    assert_eq!(cipher.decrypt(&legacy, AAD).unwrap(), WEBHOOK_URL);
+    assert!(cipher.needs_reencryption(&legacy));
+
+    let reencrypted = cipher.reencrypt(&legacy, AAD).unwrap();
+    assert!(reencrypted.starts_with("enc:v2:new:"));
+    assert!(!cipher.needs_reencryption(&reencrypted));
+    assert_eq!(cipher.decrypt(&reencrypted, AAD).unwrap(), WEBHOOK_URL);
+    assert_eq!(
+        cipher.decrypt(&reencrypted, b"times:3:2"),
+        Err(WebhookCipherError::Crypto)
+    );
+}